@@ -10,6 +10,7 @@ use solana_sdk::{
     bs58,
 };
 use spl_token::instruction::initialize_mint;
+use favorites_client::decode::describe_client_error;
 
 fn main() {
     dotenv().ok();
@@ -66,14 +67,22 @@ fn main() {
 
     let blockhash = client.get_latest_blockhash().unwrap();
 
+    let instructions = [create_account_ix, init_mint_ix];
     let tx = Transaction::new_signed_with_payer(
-        &[create_account_ix, init_mint_ix],
+        &instructions,
         Some(&payer.pubkey()),
         &[&payer, &mint],
         blockhash,
     );
 
-    let signature = client.send_and_confirm_transaction(&tx).expect("Failed to send transaction");
+    // A payer short on SOL fails the system program's create_account
+    let signature = match client.send_and_confirm_transaction(&tx) {
+        Ok(signature) => signature,
+        Err(err) => {
+            eprintln!("❌ Error: \x1b[91m{}\x1b[0m", describe_client_error(&err, &instructions));
+            return;
+        }
+    };
 
     println!("Mint account created! Address: \x1b[32m{}\x1b[0m", mint.pubkey());
     println!("Signature: \x1b[35m{}\x1b[0m", signature);
@@ -1,4 +1,5 @@
 use dotenv::dotenv;
+use favorites_client::{decode::describe_client_error, handles::resolve};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::bs58;
 use solana_sdk::{
@@ -46,7 +47,17 @@ fn main() {
             return;
         }
     };
-    let recipient_pk: Pubkey = Pubkey::from_str_const(&recipient_pk_str);
+    let rpc_url: String = "https://api.devnet.solana.com".to_string();
+    let client: RpcClient = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+    // Either a pubkey or an @handle registered with the favorites program
+    let recipient_pk: Pubkey = match resolve(&client, &recipient_pk_str) {
+        Ok(pk) => pk,
+        Err(err) => {
+            eprintln!("❌ Error: \x1b[91m{}\x1b[0m", err);
+            return;
+        }
+    };
     let lamports: u64 = 50_000_000;
     let memo_program: Pubkey = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr".parse::<Pubkey>().unwrap();
     let memo_text: String = "Transfer for goods".to_string();
@@ -56,12 +67,13 @@ fn main() {
     println!("\x1b[32m----------------------------\x1b[0m");
     println!("\x1b[32m{} - {} lamports\x1b[0m", memo_text, lamports);
     println!("🔑 from: \x1b[95m{}\x1b[0m", sender_kp.pubkey());
-    println!("🔑 into: \x1b[95m{}\x1b[0m", recipient_pk_str);
+    if recipient_pk_str.starts_with('@') {
+        println!("🔑 into: \x1b[95m{}\x1b[0m ({})", recipient_pk, recipient_pk_str);
+    } else {
+        println!("🔑 into: \x1b[95m{}\x1b[0m", recipient_pk);
+    }
     println!("\x1b[33m----------------------------\x1b[0m");
 
-    let rpc_url: String = "https://api.devnet.solana.com".to_string();
-    let client: RpcClient = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
-
     // Instructions
     let transfer_instruction = system_instruction::transfer(&sender_kp.pubkey(), &recipient_pk, lamports);
     let memo_instruction = Instruction {
@@ -74,16 +86,22 @@ fn main() {
       .expect("Failed to get blockhash");
 
     // Create transaction
+    let instructions = [transfer_instruction, memo_instruction];
     let tx = Transaction::new_signed_with_payer(
-      &[transfer_instruction, memo_instruction],
+      &instructions,
       Some(&sender_kp.pubkey()),
       &[&sender_kp],
       recent_blockhash,
     );
 
     // Send transaction
-    let signature = client.send_and_confirm_transaction(&tx)
-        .expect("Failed to send transaction");
+    let signature = match client.send_and_confirm_transaction(&tx) {
+        Ok(signature) => signature,
+        Err(err) => {
+            eprintln!("❌ Error: \x1b[91m{}\x1b[0m", describe_client_error(&err, &instructions));
+            return;
+        }
+    };
     println!("\x1b[32m----------------------------\x1b[0m");
     println!("✅ Transaction with MEMO sent!\nSignature: \x1b[35m{}\x1b[0m", signature);
 }
@@ -0,0 +1,1286 @@
+mod output;
+
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Parser, Subcommand};
+use favorites_client::{
+    accounts::{Collection, NftFavorites, Proposal, Recovery, Referrals, Tally},
+    decode::describe_client_error,
+    fees::{fee_accounts, FeeAccounts},
+    gate::{
+        associated_token_address, create_associated_token_account, fetch_config, gate_token_account,
+    },
+    handles::{fetch_handle, resolve},
+    instructions, keypair,
+    logs::{watch, websocket_url, LogFilter},
+    pda::{
+        collection_address, config_address, favorites_address, nft_favorites_address,
+        proposal_address, recovery_address, referrals_address, tally_address, treasury_address,
+    },
+    premium::{fetch_profiles, premium_token_accounts},
+    referrals::referrers,
+    registry::{open_page, registry_users},
+    scan::{list_favorites, DelegateFilter},
+    signed::{signed_update_instructions, signed_update_message},
+    stats::fetch_stats,
+    Favorites,
+};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionLogsConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+use output::{
+    print_collection, print_config, print_error, print_favorites, print_handle,
+    print_nft_favorites, print_notification, print_profiles, print_proposal, print_recovery,
+    print_referrals, print_rows, print_signature, print_stats, print_users, CollectionView,
+    ConfigView, FavoritesRow, FavoritesView, HandleView, NftFavoritesView, NotificationView,
+    ProfileRow, ProposalView, RecoveryView, ReferralsView, SignedUpdateView, StatsView,
+};
+
+#[derive(Parser)]
+#[command(name = "favorites", version, about = "Talk to the favorites program")]
+struct Cli {
+    /// Cluster URL, or one of mainnet-beta, devnet, testnet, localhost
+    #[arg(short, long, global = true, default_value = "devnet")]
+    url: String,
+
+    /// Env variable holding the wallet's base58 secret key
+    #[arg(long, global = true, default_value = "SECRET_KEY_S1")]
+    keypair_env: String,
+
+    /// Print JSON instead of the human readable output
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the wallet's favorites account
+    Init {
+        number: u64,
+        color: String,
+        /// Pubkey or @handle of the user who brought the wallet
+        #[arg(long)]
+        referrer: Option<String>,
+    },
+    /// Change a user's favorite number and color, the wallet's by default
+    Update {
+        number: u64,
+        color: String,
+        /// Owner of the favorites, when the wallet is their delegate or session key
+        #[arg(long)]
+        owner: Option<Pubkey>,
+        /// The wallet is a session key of `owner`
+        #[arg(long, requires = "owner")]
+        session: bool,
+    },
+    /// Sign an update off-chain, for a relayer to submit and pay for
+    SignUpdate {
+        number: u64,
+        color: String,
+        /// Seconds the signed update stays valid
+        #[arg(long, default_value_t = 3600)]
+        expires_in: i64,
+    },
+    /// Submit an update signed with sign-update, paying its fees
+    Relay {
+        /// JSON file printed by sign-update
+        file: PathBuf,
+    },
+    /// Print a user's favorites, the wallet's by default
+    Show {
+        /// Pubkey or @handle
+        user: Option<String>,
+    },
+    /// Print how many users a user referred, the wallet by default
+    Referrals {
+        /// Pubkey or @handle
+        user: Option<String>,
+    },
+    /// List every favorites account on the cluster
+    List {
+        /// Only accounts delegated to this key
+        #[arg(long, conflicts_with = "no_delegate")]
+        delegate: Option<Pubkey>,
+        /// Only accounts without a delegate
+        #[arg(long)]
+        no_delegate: bool,
+        /// Print CSV instead of the human readable output
+        #[arg(long, conflicts_with = "json")]
+        csv: bool,
+    },
+    /// List registered users by walking the on-chain registry, for RPC
+    /// providers without getProgramAccounts
+    Users,
+    /// Print totals over every user's favorites
+    Stats,
+    /// Follow the program's logs live, decoded, until the connection closes
+    Watch {
+        /// Only what concerns this user, pubkey or @handle
+        #[arg(long)]
+        user: Option<String>,
+        /// Only events, no msg! lines
+        #[arg(long)]
+        events_only: bool,
+        /// Show failed transactions too
+        #[arg(long)]
+        include_failed: bool,
+        /// Websocket URL, derived from the cluster URL by default
+        #[arg(long)]
+        ws_url: Option<String>,
+    },
+    /// Manage the delegate allowed to update the wallet's favorites
+    Delegate {
+        #[command(subcommand)]
+        command: DelegateCommand,
+    },
+    /// Manage session keys allowed a limited number of updates
+    Session {
+        #[command(subcommand)]
+        command: SessionCommand,
+    },
+    /// Tip a user for their favorites
+    Tip {
+        /// Pubkey or @handle
+        user: String,
+        /// Amount to send, in lamports
+        lamports: u64,
+    },
+    /// Move the tips sent to the wallet out of its tip vault
+    WithdrawTips,
+    /// Mint a 1-of-1 NFT showing the wallet's current favorites
+    Badge,
+    /// Favorites bound to an NFT, controlled by whoever holds it
+    Nft {
+        #[command(subcommand)]
+        command: NftCommand,
+    },
+    /// Manage @handles pointing at users and their favorites
+    Handle {
+        #[command(subcommand)]
+        command: HandleCommand,
+    },
+    /// Vote on the community color with the config's vote mint
+    Proposal {
+        #[command(subcommand)]
+        command: ProposalCommand,
+    },
+    /// Buy or cancel premium: longer colors and more profiles
+    Premium {
+        #[command(subcommand)]
+        command: PremiumCommand,
+    },
+    /// Manage the wallet's extra profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Manage the wallet's collection: up to 400 more favorites in one account
+    Collection {
+        #[command(subcommand)]
+        command: CollectionCommand,
+    },
+    /// Manage the program config: token gate, vote mint, fees and premium
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Move the wallet's favorites to a new wallet, which signs too
+    Transfer {
+        /// Env variable holding the new wallet's base58 secret key
+        #[arg(long)]
+        new_owner_env: String,
+        /// Session key of the wallet to revoke along the way, repeatable
+        #[arg(long = "session")]
+        session_keys: Vec<Pubkey>,
+    },
+    /// Manage the key that can move the wallet's favorites if it is lost
+    Recovery {
+        #[command(subcommand)]
+        command: RecoveryCommand,
+    },
+    /// Bring the wallet's favorites from before the version byte to the
+    /// current layout
+    Migrate,
+    /// Close the wallet's favorites account and reclaim its rent
+    Close {
+        /// Session key of the wallet to revoke along the way, repeatable
+        #[arg(long = "session")]
+        session_keys: Vec<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DelegateCommand {
+    /// Allow `delegate` to update the wallet's favorites
+    Set { delegate: Pubkey },
+    /// Remove the current delegate
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum SessionCommand {
+    /// Allow `key` to update the wallet's favorites `uses` times
+    Create {
+        key: Pubkey,
+        #[arg(long, default_value_t = 10)]
+        uses: u32,
+        /// Seconds the session key stays valid
+        #[arg(long, default_value_t = 3600)]
+        expires_in: i64,
+    },
+    /// Revoke `key` and reclaim its session's rent
+    Revoke { key: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum RecoveryCommand {
+    /// Allow `key` to move the wallet's favorites to a new wallet, a week
+    /// after starting a recovery
+    Set { key: Pubkey },
+    /// Remove the wallet's recovery key and reclaim its rent
+    Remove,
+    /// As a user's recovery key, start moving their favorites to `new_owner`
+    Start {
+        /// Pubkey or @handle of the user who lost their key
+        user: String,
+        new_owner: Pubkey,
+    },
+    /// Cancel the recovery in progress on the wallet's favorites
+    Cancel,
+    /// As the new owner, move a user's favorites to the wallet once the
+    /// recovery's timelock passed
+    Complete {
+        /// Pubkey or @handle of the user who lost their key
+        user: String,
+    },
+    /// Print a user's recovery key and any recovery in progress, the
+    /// wallet's by default
+    Show {
+        /// Pubkey or @handle
+        user: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum NftCommand {
+    /// Create the favorites of an NFT the wallet holds
+    Init {
+        mint: Pubkey,
+        number: u64,
+        color: String,
+        /// Token account holding the NFT, the wallet's associated one by default
+        #[arg(long)]
+        token_account: Option<Pubkey>,
+    },
+    /// Change the favorites of an NFT the wallet holds
+    Update {
+        mint: Pubkey,
+        number: u64,
+        color: String,
+        /// Token account holding the NFT, the wallet's associated one by default
+        #[arg(long)]
+        token_account: Option<Pubkey>,
+    },
+    /// Print the favorites of an NFT
+    Show { mint: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum HandleCommand {
+    /// Claim a handle for the wallet, which needs favorites
+    Register { handle: String },
+    /// Hand a handle over to another user with favorites
+    Transfer {
+        handle: String,
+        /// Pubkey or @handle of the new owner
+        new_owner: String,
+    },
+    /// Free a handle and reclaim its rent
+    Release { handle: String },
+    /// Print who holds a handle
+    Show { handle: String },
+}
+
+#[derive(Subcommand)]
+enum ProposalCommand {
+    /// Open proposal `id` of the wallet between 2 to 4 colors
+    Create {
+        id: u64,
+        #[arg(required = true, num_args = 2..=4)]
+        colors: Vec<String>,
+        /// Seconds the proposal stays open for votes, at least a day
+        #[arg(long, default_value_t = 86_400)]
+        voting_period: i64,
+    },
+    /// Vote for color number `choice` with the wallet's whole balance, locked
+    /// until voting ends
+    Vote {
+        proposer: Pubkey,
+        id: u64,
+        choice: u8,
+        /// Token account of the vote mint, the wallet's associated one by default
+        #[arg(long)]
+        token_account: Option<Pubkey>,
+    },
+    /// Record the winning color once voting has ended
+    Finalize { proposer: Pubkey, id: u64 },
+    /// Take back the tokens the wallet voted with once voting has ended
+    Reclaim {
+        proposer: Pubkey,
+        id: u64,
+        #[arg(long)]
+        token_account: Option<Pubkey>,
+    },
+    /// Print a proposal and its tally
+    Show { proposer: Pubkey, id: u64 },
+}
+
+#[derive(Subcommand)]
+enum PremiumCommand {
+    /// Pay for MONTHS more of premium with the wallet's premium tokens
+    Renew {
+        #[arg(default_value_t = 1)]
+        months: u8,
+    },
+    /// End premium now. The time left is not refunded.
+    Cancel,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Set the number and color of profile INDEX, creating it if needed
+    Set {
+        index: u8,
+        number: u64,
+        color: String,
+    },
+    /// Close profile INDEX and reclaim its rent
+    Close { index: u8 },
+    /// Print a user's profiles, the wallet's by default
+    List {
+        /// Pubkey or @handle
+        user: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollectionCommand {
+    /// Create the wallet's empty collection
+    Create,
+    /// Add an item after the last one
+    Add { number: u64, color: String },
+    /// Replace item INDEX
+    Update {
+        index: u32,
+        number: u64,
+        color: String,
+    },
+    /// Remove item INDEX, moving the items after it down one index
+    Remove { index: u32 },
+    /// Close the collection, items and all, and reclaim its rent
+    Close,
+    /// Print a user's collection, the wallet's by default
+    Show {
+        /// Pubkey or @handle
+        user: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Create the config, making the wallet its admin; the wallet has to be the
+    /// program's upgrade authority
+    Init {
+        /// Only holders of this mint may create and update favorites
+        #[arg(long)]
+        gate_mint: Option<Pubkey>,
+        /// Smallest balance of the gate mint, in base units
+        #[arg(long, default_value_t = 1, requires = "gate_mint")]
+        min_amount: u64,
+    },
+    /// Change the token gate, or turn it off without --gate-mint
+    SetGate {
+        #[arg(long)]
+        gate_mint: Option<Pubkey>,
+        #[arg(long, default_value_t = 1, requires = "gate_mint")]
+        min_amount: u64,
+    },
+    /// Set the mint weighing community color votes, or stop new proposals
+    /// without --vote-mint
+    SetVoteMint {
+        /// The practice-2 token is 9pwYcFs57WQm4SkNmQpm6r8XQSA7YjtpUrnURv7mDb9Y
+        #[arg(long)]
+        vote_mint: Option<Pubkey>,
+    },
+    /// Charge AMOUNT lamports, or base units of --fee-mint, on every init and
+    /// update. 0 makes them free.
+    SetFee {
+        amount: u64,
+        #[arg(long)]
+        fee_mint: Option<Pubkey>,
+    },
+    /// Stop charging fees to a user
+    Exempt {
+        /// Pubkey or @handle
+        user: String,
+    },
+    /// Charge fees to an exempted user again
+    Unexempt {
+        /// Pubkey or @handle
+        user: String,
+    },
+    /// Sell premium for PRICE base units of --premium-mint a month, or stop
+    /// selling it without --premium-mint
+    SetPremium {
+        price: u64,
+        #[arg(long)]
+        premium_mint: Option<Pubkey>,
+    },
+    /// Move the collected lamport fees, or the fees and premium paid in
+    /// --mint, to the wallet
+    WithdrawFees {
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Print the config
+    Show,
+}
+
+fn unix_now() -> Result<i64, String> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .map_err(|err| err.to_string())
+}
+
+fn cluster_url(url: &str) -> String {
+    match url {
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "localhost" | "l" => "http://localhost:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+fn send(
+    client: &RpcClient,
+    instruction: Instruction,
+    signer: &Keypair,
+) -> Result<Signature, String> {
+    send_all(client, &[instruction], &[signer])
+}
+
+// The first signer pays the fees
+fn send_all(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<Signature, String> {
+    let recent_blockhash = client
+        .get_latest_blockhash()
+        .map_err(|err| err.to_string())?;
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        recent_blockhash,
+    );
+    client
+        .send_and_confirm_transaction(&tx)
+        .map_err(|err| describe_client_error(&err, instructions))
+}
+
+fn fetch_favorites(client: &RpcClient, user: &Pubkey) -> Result<(Pubkey, Favorites), String> {
+    let address = favorites_address(user).0;
+    let account = client
+        .get_account_with_commitment(&address, client.commitment())
+        .map_err(|err| err.to_string())?
+        .value
+        .ok_or_else(|| format!("No favorites found for {user}"))?;
+    let favorites = Favorites::try_from_bytes(&account.data).map_err(|err| err.to_string())?;
+    Ok((address, favorites))
+}
+
+// The signer's token account of the gate mint, when gating is on
+fn gate_account(client: &RpcClient, signer: &Pubkey) -> Result<Option<Pubkey>, String> {
+    gate_token_account(client, signer).map_err(|err| err.to_string())
+}
+
+// The accounts the signer pays the fee with
+fn fee(client: &RpcClient, signer: &Pubkey) -> Result<FeeAccounts, String> {
+    fee_accounts(client, signer).map_err(|err| err.to_string())
+}
+
+fn fetch_proposal(client: &RpcClient, proposal: &Pubkey) -> Result<Proposal, String> {
+    let account = client
+        .get_account_with_commitment(proposal, client.commitment())
+        .map_err(|err| err.to_string())?
+        .value
+        .ok_or_else(|| format!("No proposal found at {proposal}"))?;
+    Proposal::try_from_bytes(&account.data).map_err(|err| err.to_string())
+}
+
+// A pubkey, or the owner of an @handle
+fn user_address(client: &RpcClient, input: &str) -> Result<Pubkey, String> {
+    resolve(client, input).map_err(|err| err.to_string())
+}
+
+fn show(client: &RpcClient, user: &Pubkey, json: bool) -> Result<(), String> {
+    let (address, favorites) = fetch_favorites(client, user)?;
+    print_favorites(&FavoritesView::new(user, &address, &favorites), json);
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let client =
+        RpcClient::new_with_commitment(cluster_url(&cli.url), CommitmentConfig::confirmed());
+    let wallet = || keypair::load_keypair(&cli.keypair_env);
+
+    match cli.command {
+        Command::Init {
+            number,
+            color,
+            referrer,
+        } => {
+            let wallet = wallet()?;
+            let referrer = match referrer {
+                Some(referrer) => Some(user_address(&client, &referrer)?),
+                None => None,
+            };
+            let referrers = referrers(&client, referrer).map_err(|err| err.to_string())?;
+            let open_page = open_page(&client).map_err(|err| err.to_string())?;
+            let gate = gate_account(&client, &wallet.pubkey())?;
+            let fee = fee(&client, &wallet.pubkey())?;
+            let ix = instructions::set_favorites(
+                &wallet.pubkey(),
+                open_page,
+                gate,
+                fee,
+                &referrers,
+                number,
+                &color,
+            );
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Favorites created!", &signature, cli.json);
+        }
+        Command::Update {
+            number,
+            color,
+            owner,
+            session,
+        } => {
+            let wallet = wallet()?;
+            let owner = owner.unwrap_or(wallet.pubkey());
+            let gate = gate_account(&client, &wallet.pubkey())?;
+            let fee = fee(&client, &wallet.pubkey())?;
+            let ix = if session {
+                instructions::update_favorites_with_session(
+                    &owner,
+                    &wallet.pubkey(),
+                    gate,
+                    fee,
+                    number,
+                    &color,
+                )
+            } else {
+                instructions::update_favorites(&owner, &wallet.pubkey(), gate, fee, number, &color)
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Favorites updated!", &signature, cli.json);
+        }
+        Command::SignUpdate {
+            number,
+            color,
+            expires_in,
+        } => {
+            let wallet = wallet()?;
+            let user = wallet.pubkey();
+            let (_, favorites) = fetch_favorites(&client, &user)?;
+            let expiry = unix_now()? + expires_in;
+            let message = signed_update_message(&user, number, &color, favorites.nonce, expiry);
+            let update = SignedUpdateView {
+                user: user.to_string(),
+                number,
+                color,
+                nonce: favorites.nonce,
+                expiry,
+                signature: wallet.sign_message(&message).to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&update).unwrap());
+        }
+        Command::Relay { file } => {
+            let relayer = wallet()?;
+            let update = std::fs::read_to_string(&file).map_err(|err| err.to_string())?;
+            let update: SignedUpdateView =
+                serde_json::from_str(&update).map_err(|err| err.to_string())?;
+            let user = Pubkey::from_str(&update.user).map_err(|err| err.to_string())?;
+            let signature =
+                Signature::from_str(&update.signature).map_err(|err| err.to_string())?;
+            let instructions = signed_update_instructions(
+                &user,
+                &relayer.pubkey(),
+                gate_account(&client, &user)?,
+                fee(&client, &relayer.pubkey())?,
+                update.number,
+                &update.color,
+                update.nonce,
+                update.expiry,
+                &signature.into(),
+            );
+            let signature = send_all(&client, &instructions, &[&relayer])?;
+            print_signature("Signed update relayed!", &signature, cli.json);
+        }
+        Command::Show { user } => {
+            let user = match user {
+                Some(user) => user_address(&client, &user)?,
+                None => wallet()?.pubkey(),
+            };
+            show(&client, &user, cli.json)?;
+        }
+        Command::Referrals { user } => {
+            let user = match user {
+                Some(user) => user_address(&client, &user)?,
+                None => wallet()?.pubkey(),
+            };
+            let address = referrals_address(&user).0;
+            // No counter yet means no referrals
+            let count = match client
+                .get_account_with_commitment(&address, client.commitment())
+                .map_err(|err| err.to_string())?
+                .value
+            {
+                Some(account) => {
+                    Referrals::try_from_bytes(&account.data)
+                        .map_err(|err| err.to_string())?
+                        .count
+                }
+                None => 0,
+            };
+            print_referrals(&ReferralsView::new(&user, &address, count), cli.json);
+        }
+        Command::List {
+            delegate,
+            no_delegate,
+            csv,
+        } => {
+            let filter = match (delegate, no_delegate) {
+                (Some(delegate), _) => DelegateFilter::Is(delegate),
+                (None, true) => DelegateFilter::Unset,
+                (None, false) => DelegateFilter::Any,
+            };
+            let rows: Vec<FavoritesRow> = list_favorites(&client, filter)
+                .map_err(|err| err.to_string())?
+                .iter()
+                .map(|(address, favorites)| FavoritesRow::new(address, favorites))
+                .collect();
+            print_rows(&rows, cli.json, csv)?;
+        }
+        Command::Users => {
+            let users = registry_users(&client)
+                .map_err(|err| err.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| err.to_string())?;
+            print_users(&users, cli.json);
+        }
+        Command::Stats => {
+            let stats = fetch_stats(&client)
+                .map_err(|err| err.to_string())?
+                .ok_or("Nobody has set favorites yet")?;
+            print_stats(&StatsView::new(&stats), cli.json);
+        }
+        Command::Delegate { command } => {
+            let wallet = wallet()?;
+            let (delegate, message) = match command {
+                DelegateCommand::Set { delegate } => (Some(delegate), "Delegate set!"),
+                DelegateCommand::Clear => (None, "Delegate removed!"),
+            };
+            let ix = instructions::set_authority(&wallet.pubkey(), delegate);
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Session { command } => {
+            let wallet = wallet()?;
+            let (ix, message) = match command {
+                SessionCommand::Create {
+                    key,
+                    uses,
+                    expires_in,
+                } => (
+                    instructions::create_session(
+                        &wallet.pubkey(),
+                        &key,
+                        uses,
+                        unix_now()? + expires_in,
+                    ),
+                    "Session key created!",
+                ),
+                SessionCommand::Revoke { key } => (
+                    instructions::revoke_session(&wallet.pubkey(), &key),
+                    "Session key revoked!",
+                ),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Tip { user, lamports } => {
+            let wallet = wallet()?;
+            let user = user_address(&client, &user)?;
+            let ix = instructions::tip(&wallet.pubkey(), &user, lamports);
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Tip sent!", &signature, cli.json);
+        }
+        Command::WithdrawTips => {
+            let wallet = wallet()?;
+            let ix = instructions::withdraw_tips(&wallet.pubkey());
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Tips withdrawn!", &signature, cli.json);
+        }
+        Command::Badge => {
+            let wallet = wallet()?;
+            let badge_mint = Keypair::new();
+            let ix = instructions::mint_favorites_badge(&wallet.pubkey(), &badge_mint.pubkey());
+            let signature = send_all(&client, &[ix], &[&wallet, &badge_mint])?;
+            print_signature(
+                &format!("Badge {} minted!", badge_mint.pubkey()),
+                &signature,
+                cli.json,
+            );
+        }
+        Command::Nft {
+            command: NftCommand::Show { mint },
+        } => {
+            let address = nft_favorites_address(&mint).0;
+            let account = client
+                .get_account_with_commitment(&address, client.commitment())
+                .map_err(|err| err.to_string())?
+                .value
+                .ok_or_else(|| format!("No favorites found for NFT {mint}"))?;
+            let favorites =
+                NftFavorites::try_from_bytes(&account.data).map_err(|err| err.to_string())?;
+            print_nft_favorites(&NftFavoritesView::new(&address, &favorites), cli.json);
+        }
+        Command::Nft { command } => {
+            let wallet = wallet()?;
+            let holder = wallet.pubkey();
+            let (ix, message) = match command {
+                NftCommand::Init {
+                    mint,
+                    number,
+                    color,
+                    token_account,
+                } => (
+                    instructions::set_nft_favorites(
+                        &holder,
+                        &mint,
+                        &token_account.unwrap_or(associated_token_address(&holder, &mint)),
+                        number,
+                        &color,
+                    ),
+                    "NFT favorites created!",
+                ),
+                NftCommand::Update {
+                    mint,
+                    number,
+                    color,
+                    token_account,
+                } => (
+                    instructions::update_nft_favorites(
+                        &holder,
+                        &mint,
+                        &token_account.unwrap_or(associated_token_address(&holder, &mint)),
+                        number,
+                        &color,
+                    ),
+                    "NFT favorites updated!",
+                ),
+                NftCommand::Show { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Handle {
+            command: HandleCommand::Show { handle },
+        } => {
+            let handle = fetch_handle(&client, &handle)
+                .map_err(|err| err.to_string())?
+                .ok_or_else(|| format!("Nobody holds @{}", handle.trim_start_matches('@')))?;
+            print_handle(&HandleView::new(&handle), cli.json);
+        }
+        Command::Handle { command } => {
+            let wallet = wallet()?;
+            let owner = wallet.pubkey();
+            let (ix, message) = match command {
+                HandleCommand::Register { handle } => (
+                    instructions::register_handle(&owner, handle.trim_start_matches('@')),
+                    "Handle registered!",
+                ),
+                HandleCommand::Transfer { handle, new_owner } => (
+                    instructions::transfer_handle(
+                        &owner,
+                        handle.trim_start_matches('@'),
+                        &user_address(&client, &new_owner)?,
+                    ),
+                    "Handle transferred!",
+                ),
+                HandleCommand::Release { handle } => (
+                    instructions::release_handle(&owner, handle.trim_start_matches('@')),
+                    "Handle released!",
+                ),
+                HandleCommand::Show { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Proposal {
+            command: ProposalCommand::Show { proposer, id },
+        } => {
+            let address = proposal_address(&proposer, id).0;
+            let proposal = fetch_proposal(&client, &address)?;
+            let tally = client
+                .get_account_data(&tally_address(&address).0)
+                .map_err(|err| err.to_string())?;
+            let tally = Tally::try_from_bytes(&tally).map_err(|err| err.to_string())?;
+            print_proposal(&ProposalView::new(&address, &proposal, &tally), cli.json);
+        }
+        Command::Proposal { command } => {
+            let wallet = wallet()?;
+            let voter = wallet.pubkey();
+            let (ix, message) = match command {
+                ProposalCommand::Create {
+                    id,
+                    colors,
+                    voting_period,
+                } => {
+                    let vote_mint = fetch_config(&client)
+                        .map_err(|err| err.to_string())?
+                        .and_then(|config| config.vote_mint)
+                        .ok_or("No vote mint is configured")?;
+                    (
+                        instructions::create_proposal(
+                            &voter,
+                            &vote_mint,
+                            id,
+                            &colors,
+                            voting_period,
+                        ),
+                        "Proposal created!",
+                    )
+                }
+                ProposalCommand::Vote {
+                    proposer,
+                    id,
+                    choice,
+                    token_account,
+                } => {
+                    let proposal = proposal_address(&proposer, id).0;
+                    let vote_mint = fetch_proposal(&client, &proposal)?.vote_mint;
+                    (
+                        instructions::vote(
+                            &voter,
+                            &proposal,
+                            &token_account.unwrap_or(associated_token_address(&voter, &vote_mint)),
+                            choice,
+                        ),
+                        "Vote cast!",
+                    )
+                }
+                ProposalCommand::Finalize { proposer, id } => (
+                    instructions::finalize_proposal(&voter, &proposal_address(&proposer, id).0),
+                    "Proposal finalized!",
+                ),
+                ProposalCommand::Reclaim {
+                    proposer,
+                    id,
+                    token_account,
+                } => {
+                    let proposal = proposal_address(&proposer, id).0;
+                    let vote_mint = fetch_proposal(&client, &proposal)?.vote_mint;
+                    (
+                        instructions::reclaim_vote(
+                            &voter,
+                            &proposal,
+                            &token_account.unwrap_or(associated_token_address(&voter, &vote_mint)),
+                        ),
+                        "Vote reclaimed!",
+                    )
+                }
+                ProposalCommand::Show { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Config {
+            command: ConfigCommand::Show,
+        } => {
+            let config = fetch_config(&client)
+                .map_err(|err| err.to_string())?
+                .ok_or("The config has not been initialized")?;
+            print_config(&ConfigView::new(&config_address().0, &config), cli.json);
+        }
+        Command::Config {
+            command: ConfigCommand::SetFee { amount, fee_mint },
+        } => {
+            let wallet = wallet()?;
+            let mut ixs = Vec::new();
+            // Token fees need the treasury's token account to exist
+            if let Some(mint) = fee_mint.filter(|_| amount > 0) {
+                ixs.push(create_associated_token_account(
+                    &wallet.pubkey(),
+                    &treasury_address().0,
+                    &mint,
+                ));
+            }
+            ixs.push(instructions::set_fee(&wallet.pubkey(), fee_mint, amount));
+            let signature = send_all(&client, &ixs, &[&wallet])?;
+            print_signature("Fee updated!", &signature, cli.json);
+        }
+        Command::Config {
+            command:
+                ConfigCommand::SetPremium {
+                    price,
+                    premium_mint,
+                },
+        } => {
+            let wallet = wallet()?;
+            let mut ixs = Vec::new();
+            // Premium is paid into the treasury's token account
+            if let Some(mint) = premium_mint {
+                ixs.push(create_associated_token_account(
+                    &wallet.pubkey(),
+                    &treasury_address().0,
+                    &mint,
+                ));
+            }
+            ixs.push(instructions::set_premium(
+                &wallet.pubkey(),
+                premium_mint,
+                price,
+            ));
+            let signature = send_all(&client, &ixs, &[&wallet])?;
+            print_signature("Premium updated!", &signature, cli.json);
+        }
+        Command::Config {
+            command: ConfigCommand::WithdrawFees { mint: Some(mint) },
+        } => {
+            let wallet = wallet()?;
+            let ixs = [
+                create_associated_token_account(&wallet.pubkey(), &wallet.pubkey(), &mint),
+                instructions::withdraw_fee_tokens(
+                    &wallet.pubkey(),
+                    &mint,
+                    &associated_token_address(&wallet.pubkey(), &mint),
+                ),
+            ];
+            let signature = send_all(&client, &ixs, &[&wallet])?;
+            print_signature("Fees withdrawn!", &signature, cli.json);
+        }
+        Command::Config { command } => {
+            let wallet = wallet()?;
+            let (ix, message) = match command {
+                ConfigCommand::Init {
+                    gate_mint,
+                    min_amount,
+                } => (
+                    instructions::initialize_config(&wallet.pubkey(), gate_mint, min_amount),
+                    "Config created!",
+                ),
+                ConfigCommand::SetGate {
+                    gate_mint,
+                    min_amount,
+                } => (
+                    instructions::set_token_gate(&wallet.pubkey(), gate_mint, min_amount),
+                    "Token gate updated!",
+                ),
+                ConfigCommand::SetVoteMint { vote_mint } => (
+                    instructions::set_vote_mint(&wallet.pubkey(), vote_mint),
+                    "Vote mint updated!",
+                ),
+                ConfigCommand::Exempt { user } => (
+                    instructions::add_fee_exemption(
+                        &wallet.pubkey(),
+                        &user_address(&client, &user)?,
+                    ),
+                    "User exempted from fees!",
+                ),
+                ConfigCommand::Unexempt { user } => (
+                    instructions::remove_fee_exemption(
+                        &wallet.pubkey(),
+                        &user_address(&client, &user)?,
+                    ),
+                    "User pays fees again!",
+                ),
+                ConfigCommand::WithdrawFees { mint: None } => (
+                    instructions::withdraw_fees(&wallet.pubkey()),
+                    "Fees withdrawn!",
+                ),
+                ConfigCommand::Show
+                | ConfigCommand::SetFee { .. }
+                | ConfigCommand::SetPremium { .. }
+                | ConfigCommand::WithdrawFees { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Premium {
+            command: PremiumCommand::Renew { months },
+        } => {
+            let wallet = wallet()?;
+            let (payment, treasury) = premium_token_accounts(&client, &wallet.pubkey())
+                .map_err(|err| err.to_string())?
+                .ok_or("Premium is not for sale")?;
+            let ix = instructions::renew_premium(&wallet.pubkey(), &payment, &treasury, months);
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Premium renewed!", &signature, cli.json);
+        }
+        Command::Premium {
+            command: PremiumCommand::Cancel,
+        } => {
+            let wallet = wallet()?;
+            let ix = instructions::cancel_premium(&wallet.pubkey());
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Premium cancelled!", &signature, cli.json);
+        }
+        Command::Profile {
+            command: ProfileCommand::List { user },
+        } => {
+            let user = match user {
+                Some(user) => user_address(&client, &user)?,
+                None => wallet()?.pubkey(),
+            };
+            let profiles = fetch_profiles(&client, &user).map_err(|err| err.to_string())?;
+            let rows: Vec<ProfileRow> = profiles
+                .iter()
+                .map(|(index, profile)| ProfileRow::new(&user, *index, profile))
+                .collect();
+            print_profiles(&rows, cli.json);
+        }
+        Command::Profile { command } => {
+            let wallet = wallet()?;
+            let (ix, message) = match command {
+                ProfileCommand::Set {
+                    index,
+                    number,
+                    color,
+                } => (
+                    instructions::set_profile(&wallet.pubkey(), index, number, &color),
+                    "Profile set!",
+                ),
+                ProfileCommand::Close { index } => (
+                    instructions::close_profile(&wallet.pubkey(), index),
+                    "Profile closed!",
+                ),
+                ProfileCommand::List { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Collection {
+            command: CollectionCommand::Show { user },
+        } => {
+            let user = match user {
+                Some(user) => user_address(&client, &user)?,
+                None => wallet()?.pubkey(),
+            };
+            let address = collection_address(&user).0;
+            let account = client
+                .get_account_with_commitment(&address, client.commitment())
+                .map_err(|err| err.to_string())?
+                .value
+                .ok_or_else(|| format!("{user} has no collection"))?;
+            let collection =
+                Collection::try_from_bytes(&account.data).map_err(|err| err.to_string())?;
+            print_collection(&CollectionView::new(&user, &address, &collection), cli.json);
+        }
+        Command::Collection { command } => {
+            let wallet = wallet()?;
+            let user = wallet.pubkey();
+            let (ixs, message) = match command {
+                CollectionCommand::Create => (
+                    instructions::create_full_collection(&user),
+                    "Collection created!",
+                ),
+                CollectionCommand::Add { number, color } => (
+                    vec![instructions::append_collection_item(&user, number, &color)],
+                    "Item added!",
+                ),
+                CollectionCommand::Update {
+                    index,
+                    number,
+                    color,
+                } => (
+                    vec![instructions::update_collection_item(
+                        &user, index, number, &color,
+                    )],
+                    "Item updated!",
+                ),
+                CollectionCommand::Remove { index } => (
+                    vec![instructions::remove_collection_item(&user, index)],
+                    "Item removed!",
+                ),
+                CollectionCommand::Close => (
+                    vec![instructions::close_collection(&user)],
+                    "Collection closed!",
+                ),
+                CollectionCommand::Show { .. } => unreachable!("handled above"),
+            };
+            let signature = send_all(&client, &ixs, &[&wallet])?;
+            print_signature(message, &signature, cli.json);
+        }
+        Command::Transfer {
+            new_owner_env,
+            session_keys,
+        } => {
+            let wallet = wallet()?;
+            let new_owner = keypair::load_keypair(&new_owner_env)?;
+            let (_, favorites) = fetch_favorites(&client, &wallet.pubkey())?;
+            let referrers =
+                referrers(&client, favorites.referrer).map_err(|err| err.to_string())?;
+            let ix = instructions::transfer_favorites(
+                &wallet.pubkey(),
+                &new_owner.pubkey(),
+                favorites.registry_page,
+                &referrers,
+                &session_keys,
+            );
+            let signature = send_all(&client, &[ix], &[&wallet, &new_owner])?;
+            print_signature(
+                &format!("Favorites moved to {}!", new_owner.pubkey()),
+                &signature,
+                cli.json,
+            );
+        }
+        Command::Recovery {
+            command: RecoveryCommand::Show { user },
+        } => {
+            let user = match user {
+                Some(user) => user_address(&client, &user)?,
+                None => wallet()?.pubkey(),
+            };
+            let address = recovery_address(&user).0;
+            let account = client
+                .get_account_with_commitment(&address, client.commitment())
+                .map_err(|err| err.to_string())?
+                .value
+                .ok_or_else(|| format!("No recovery key is set for {user}"))?;
+            let recovery =
+                Recovery::try_from_bytes(&account.data).map_err(|err| err.to_string())?;
+            print_recovery(
+                &RecoveryView::new(&user, &address, &recovery, unix_now()?),
+                cli.json,
+            );
+        }
+        Command::Recovery { command } => {
+            let wallet = wallet()?;
+            let (ix, message) = match command {
+                RecoveryCommand::Set { key } => (
+                    instructions::set_recovery_key(&wallet.pubkey(), &key),
+                    "Recovery key set!".to_string(),
+                ),
+                RecoveryCommand::Remove => (
+                    instructions::remove_recovery_key(&wallet.pubkey()),
+                    "Recovery key removed!".to_string(),
+                ),
+                RecoveryCommand::Start { user, new_owner } => (
+                    instructions::start_recovery(
+                        &wallet.pubkey(),
+                        &user_address(&client, &user)?,
+                        &new_owner,
+                    ),
+                    "Recovery started, it can complete in a week!".to_string(),
+                ),
+                RecoveryCommand::Cancel => (
+                    instructions::cancel_recovery(&wallet.pubkey()),
+                    "Recovery cancelled!".to_string(),
+                ),
+                RecoveryCommand::Complete { user } => {
+                    let user = user_address(&client, &user)?;
+                    let (_, favorites) = fetch_favorites(&client, &user)?;
+                    let referrers =
+                        referrers(&client, favorites.referrer).map_err(|err| err.to_string())?;
+                    (
+                        instructions::complete_recovery(
+                            &user,
+                            &wallet.pubkey(),
+                            favorites.registry_page,
+                            &referrers,
+                            &[],
+                        ),
+                        format!("Favorites of {user} recovered!"),
+                    )
+                }
+                RecoveryCommand::Show { .. } => unreachable!("handled above"),
+            };
+            let signature = send(&client, ix, &wallet)?;
+            print_signature(&message, &signature, cli.json);
+        }
+        Command::Watch {
+            user,
+            events_only,
+            include_failed,
+            ws_url,
+        } => {
+            let filter = LogFilter {
+                user: match user {
+                    Some(user) => Some(user_address(&client, &user)?),
+                    None => None,
+                },
+                events_only,
+                include_failed,
+            };
+            let ws_url = ws_url.unwrap_or_else(|| websocket_url(&client.url()));
+            let config = RpcTransactionLogsConfig {
+                commitment: Some(client.commitment()),
+            };
+            watch(
+                &ws_url,
+                &favorites_client::ID,
+                config,
+                &filter,
+                |notification| print_notification(&NotificationView::new(&notification), cli.json),
+            )
+            .map_err(|err| format!("{ws_url}: {err}"))?;
+        }
+        Command::Migrate => {
+            let wallet = wallet()?;
+            let open_page = open_page(&client).map_err(|err| err.to_string())?;
+            let ix = instructions::migrate_favorites(&wallet.pubkey(), open_page);
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Favorites migrated!", &signature, cli.json);
+        }
+        Command::Close { session_keys } => {
+            let wallet = wallet()?;
+            let (_, favorites) = fetch_favorites(&client, &wallet.pubkey())?;
+            let ix = instructions::close_favorites(
+                &wallet.pubkey(),
+                favorites.registry_page,
+                favorites.referrer,
+                &session_keys,
+            );
+            let signature = send(&client, ix, &wallet)?;
+            print_signature("Favorites closed!", &signature, cli.json);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run(Cli::parse()) {
+        print_error(&message);
+        std::process::exit(1);
+    }
+}
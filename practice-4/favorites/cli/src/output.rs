@@ -0,0 +1,624 @@
+use favorites_client::{
+    accounts::{
+        collection_layout, Collection, Config, Handle, NftFavorites, Profile, Proposal, Recovery,
+        Stats, Tally,
+    },
+    logs::{LogEntry, Notification},
+    pda::profile_address,
+    stats::render_stats,
+    Favorites,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+#[derive(Serialize)]
+pub struct FavoritesView {
+    pub user: String,
+    pub address: String,
+    pub number: u64,
+    pub color: String,
+    pub delegate: Option<String>,
+    // Unix timestamp, once premium was ever bought
+    pub premium_until: Option<i64>,
+    pub referrer: Option<String>,
+    // False until `migrate` brings the account to the current layout
+    pub migrated: bool,
+}
+
+impl FavoritesView {
+    pub fn new(user: &Pubkey, address: &Pubkey, favorites: &Favorites) -> Self {
+        FavoritesView {
+            user: user.to_string(),
+            address: address.to_string(),
+            number: favorites.number,
+            color: favorites.color.clone(),
+            delegate: favorites.delegate.map(|delegate| delegate.to_string()),
+            premium_until: (favorites.premium_until > 0).then_some(favorites.premium_until),
+            referrer: favorites.referrer.map(|referrer| referrer.to_string()),
+            migrated: favorites.is_migrated(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReferralsView {
+    pub user: String,
+    pub address: String,
+    pub count: u64,
+}
+
+impl ReferralsView {
+    pub fn new(user: &Pubkey, address: &Pubkey, count: u64) -> Self {
+        ReferralsView {
+            user: user.to_string(),
+            address: address.to_string(),
+            count,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RecoveryView {
+    pub user: String,
+    pub address: String,
+    pub recovery_key: String,
+    pub new_owner: Option<String>,
+    // Unix timestamp, while a recovery is in progress
+    pub unlocks_at: Option<i64>,
+    pub unlocked: bool,
+}
+
+impl RecoveryView {
+    pub fn new(user: &Pubkey, address: &Pubkey, recovery: &Recovery, now: i64) -> Self {
+        RecoveryView {
+            user: user.to_string(),
+            address: address.to_string(),
+            recovery_key: recovery.recovery_key.to_string(),
+            new_owner: recovery.new_owner.map(|new_owner| new_owner.to_string()),
+            unlocks_at: recovery.new_owner.map(|_| recovery.unlocks_at),
+            unlocked: recovery.is_unlocked(now),
+        }
+    }
+}
+
+// One transaction of `favorites watch`
+#[derive(Serialize)]
+pub struct NotificationView {
+    pub slot: u64,
+    pub signature: String,
+    pub failed: bool,
+    pub entries: Vec<LogEntryView>,
+}
+
+#[derive(Serialize)]
+pub struct LogEntryView {
+    // "favorites_set", "message" or the event's name
+    pub kind: String,
+    pub user: Option<String>,
+    pub text: String,
+}
+
+impl NotificationView {
+    pub fn new(notification: &Notification) -> Self {
+        let entries = notification
+            .entries
+            .iter()
+            .map(|entry| {
+                let (kind, user) = match entry {
+                    LogEntry::FavoritesSet { user, .. } => ("favorites_set", Some(*user)),
+                    LogEntry::Message(_) => ("message", None),
+                    LogEntry::Event(event) => (event.name(), Some(event.user())),
+                };
+                LogEntryView {
+                    kind: kind.to_string(),
+                    user: user.map(|user| user.to_string()),
+                    text: entry.to_string(),
+                }
+            })
+            .collect();
+        NotificationView {
+            slot: notification.slot,
+            signature: notification.signature.clone(),
+            failed: notification.failed,
+            entries,
+        }
+    }
+}
+
+// One line of `favorites profile list`
+#[derive(Serialize)]
+pub struct ProfileRow {
+    pub index: u8,
+    pub address: String,
+    pub number: u64,
+    pub color: String,
+}
+
+impl ProfileRow {
+    pub fn new(user: &Pubkey, index: u8, profile: &Profile) -> Self {
+        ProfileRow {
+            index,
+            address: profile_address(user, index).0.to_string(),
+            number: profile.number,
+            color: profile.color.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CollectionView {
+    pub user: String,
+    pub address: String,
+    pub capacity: usize,
+    pub items: Vec<CollectionItemRow>,
+}
+
+#[derive(Serialize)]
+pub struct CollectionItemRow {
+    pub index: usize,
+    pub number: u64,
+    pub color: String,
+}
+
+impl CollectionView {
+    pub fn new(user: &Pubkey, address: &Pubkey, collection: &Collection) -> Self {
+        CollectionView {
+            user: user.to_string(),
+            address: address.to_string(),
+            capacity: collection_layout::CAPACITY,
+            items: collection
+                .items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| CollectionItemRow {
+                    index,
+                    number: item.number,
+                    color: item.color.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NftFavoritesView {
+    pub mint: String,
+    pub address: String,
+    pub number: u64,
+    pub color: String,
+}
+
+impl NftFavoritesView {
+    pub fn new(address: &Pubkey, favorites: &NftFavorites) -> Self {
+        NftFavoritesView {
+            mint: favorites.mint.to_string(),
+            address: address.to_string(),
+            number: favorites.number,
+            color: favorites.color.clone(),
+        }
+    }
+}
+
+// One line of `favorites list`. The owner is not stored in the account, only
+// baked into its address.
+#[derive(Serialize)]
+pub struct FavoritesRow {
+    pub address: String,
+    pub number: u64,
+    pub color: String,
+    pub delegate: Option<String>,
+}
+
+impl FavoritesRow {
+    pub fn new(address: &Pubkey, favorites: &Favorites) -> Self {
+        FavoritesRow {
+            address: address.to_string(),
+            number: favorites.number,
+            color: favorites.color.clone(),
+            delegate: favorites.delegate.map(|delegate| delegate.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HandleView {
+    pub handle: String,
+    pub owner: String,
+    pub favorites: String,
+}
+
+impl HandleView {
+    pub fn new(handle: &Handle) -> Self {
+        HandleView {
+            handle: format!("@{}", handle.handle),
+            owner: handle.owner.to_string(),
+            favorites: handle.favorites.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ColorCountView {
+    pub color: String,
+    pub count: u64,
+}
+
+#[derive(Serialize)]
+pub struct StatsView {
+    pub user_count: u64,
+    // A string, as JSON numbers lose precision past 2^53
+    pub number_sum: String,
+    pub average_number: Option<f64>,
+    pub min_number: Option<u64>,
+    pub max_number: Option<u64>,
+    pub top_colors: Vec<ColorCountView>,
+    // The human readable output comes from the client's renderer
+    #[serde(skip)]
+    pub stats: Stats,
+}
+
+impl StatsView {
+    pub fn new(stats: &Stats) -> Self {
+        StatsView {
+            user_count: stats.user_count,
+            number_sum: stats.number_sum.to_string(),
+            average_number: stats.average_number(),
+            min_number: stats.min_number,
+            max_number: stats.max_number,
+            top_colors: stats
+                .ranked_colors()
+                .into_iter()
+                .map(|(color, count)| ColorCountView {
+                    color: color.to_string(),
+                    count,
+                })
+                .collect(),
+            stats: stats.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ConfigView {
+    pub address: String,
+    pub admin: String,
+    pub gate_mint: Option<String>,
+    pub gate_min_amount: u64,
+    pub vote_mint: Option<String>,
+    pub fee_mint: Option<String>,
+    pub fee_amount: u64,
+    pub premium_mint: Option<String>,
+    pub premium_price: u64,
+}
+
+impl ConfigView {
+    pub fn new(address: &Pubkey, config: &Config) -> Self {
+        ConfigView {
+            address: address.to_string(),
+            admin: config.admin.to_string(),
+            gate_mint: config.gate_mint.map(|mint| mint.to_string()),
+            gate_min_amount: config.gate_min_amount,
+            vote_mint: config.vote_mint.map(|mint| mint.to_string()),
+            fee_mint: config.fee_mint.map(|mint| mint.to_string()),
+            fee_amount: config.fee_amount,
+            premium_mint: config.premium_mint.map(|mint| mint.to_string()),
+            premium_price: config.premium_price,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProposalOption {
+    pub color: String,
+    pub votes: u64,
+}
+
+#[derive(Serialize)]
+pub struct ProposalView {
+    pub address: String,
+    pub proposer: String,
+    pub id: u64,
+    pub vote_mint: String,
+    pub voting_starts_at: i64,
+    pub voting_ends_at: i64,
+    pub finalized: bool,
+    pub options: Vec<ProposalOption>,
+}
+
+impl ProposalView {
+    pub fn new(address: &Pubkey, proposal: &Proposal, tally: &Tally) -> Self {
+        ProposalView {
+            address: address.to_string(),
+            proposer: proposal.proposer.to_string(),
+            id: proposal.id,
+            vote_mint: proposal.vote_mint.to_string(),
+            voting_starts_at: proposal.voting_starts_at,
+            voting_ends_at: proposal.voting_ends_at,
+            finalized: proposal.finalized,
+            options: proposal
+                .colors
+                .iter()
+                .zip(&tally.votes)
+                .map(|(color, votes)| ProposalOption {
+                    color: color.clone(),
+                    votes: *votes,
+                })
+                .collect(),
+        }
+    }
+}
+
+// What sign-update prints and relay reads back.
+#[derive(Deserialize, Serialize)]
+pub struct SignedUpdateView {
+    pub user: String,
+    pub number: u64,
+    pub color: String,
+    pub nonce: u64,
+    pub expiry: i64,
+    pub signature: String,
+}
+
+pub fn print_favorites(view: &FavoritesView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("🔑 user:     \x1b[95m{}\x1b[0m", view.user);
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    println!("🔢 number:   \x1b[33m{}\x1b[0m", view.number);
+    println!("🎨 color:    \x1b[33m{}\x1b[0m", view.color);
+    match &view.delegate {
+        Some(delegate) => println!("🤝 delegate: \x1b[95m{}\x1b[0m", delegate),
+        None => println!("🤝 delegate: none"),
+    }
+    match view.premium_until {
+        Some(until) => println!("⭐ premium:  until \x1b[33m{}\x1b[0m", until),
+        None => println!("⭐ premium:  none"),
+    }
+    if let Some(referrer) = &view.referrer {
+        println!("🎁 referrer: \x1b[95m{}\x1b[0m", referrer);
+    }
+    if !view.migrated {
+        println!("⚠️  old account layout, run `favorites migrate` to update it");
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_nft_favorites(view: &NftFavoritesView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("🖼️ nft:      \x1b[95m{}\x1b[0m", view.mint);
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    println!("🔢 number:   \x1b[33m{}\x1b[0m", view.number);
+    println!("🎨 color:    \x1b[33m{}\x1b[0m", view.color);
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_handle(view: &HandleView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("🏷️ handle:   \x1b[33m{}\x1b[0m", view.handle);
+    println!("🔑 owner:    \x1b[95m{}\x1b[0m", view.owner);
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.favorites);
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_referrals(view: &ReferralsView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!(
+        "🎁 \x1b[95m{}\x1b[0m referred \x1b[33m{}\x1b[0m user(s)",
+        view.user, view.count
+    );
+}
+
+pub fn print_recovery(view: &RecoveryView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("🔑 user:     \x1b[95m{}\x1b[0m", view.user);
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    println!("🛟 key:      \x1b[95m{}\x1b[0m", view.recovery_key);
+    match (&view.new_owner, view.unlocks_at) {
+        (Some(new_owner), Some(unlocks_at)) => {
+            println!("➡️ to:       \x1b[95m{}\x1b[0m", new_owner);
+            let state = if view.unlocked { "unlocked" } else { "locked" };
+            println!("⏰ unlocks:  \x1b[33m{}\x1b[0m ({state})", unlocks_at);
+        }
+        _ => println!("➡️ to:       no recovery in progress"),
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+// Prints as notifications arrive, so JSON goes out one line each
+pub fn print_notification(view: &NotificationView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string(view).unwrap());
+        return;
+    }
+    let status = if view.failed {
+        " \x1b[31mfailed\x1b[0m"
+    } else {
+        ""
+    };
+    println!(
+        "📡 slot \x1b[33m{}\x1b[0m \x1b[95m{}\x1b[0m{}",
+        view.slot, view.signature, status
+    );
+    for entry in &view.entries {
+        match entry.kind.as_str() {
+            "message" => println!("   {}", entry.text),
+            _ => println!("   \x1b[32m{}\x1b[0m", entry.text),
+        }
+    }
+}
+
+pub fn print_stats(view: &StatsView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    print!("{}", render_stats(&view.stats));
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_config(view: &ConfigView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    println!("🔑 admin:    \x1b[95m{}\x1b[0m", view.admin);
+    match &view.gate_mint {
+        Some(mint) => println!(
+            "🪙 gate:     \x1b[33m{}\x1b[0m of \x1b[95m{}\x1b[0m",
+            view.gate_min_amount, mint
+        ),
+        None => println!("🪙 gate:     none"),
+    }
+    match &view.vote_mint {
+        Some(mint) => println!("🗳️ votes:    \x1b[95m{}\x1b[0m", mint),
+        None => println!("🗳️ votes:    disabled"),
+    }
+    match (&view.fee_mint, view.fee_amount) {
+        (_, 0) => println!("💸 fee:      none"),
+        (Some(mint), amount) => println!(
+            "💸 fee:      \x1b[33m{}\x1b[0m of \x1b[95m{}\x1b[0m",
+            amount, mint
+        ),
+        (None, amount) => println!("💸 fee:      \x1b[33m{}\x1b[0m lamports", amount),
+    }
+    match &view.premium_mint {
+        Some(mint) => println!(
+            "⭐ premium:  \x1b[33m{}\x1b[0m of \x1b[95m{}\x1b[0m a month",
+            view.premium_price, mint
+        ),
+        None => println!("⭐ premium:  not for sale"),
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_proposal(view: &ProposalView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    println!("🔑 proposer: \x1b[95m{}\x1b[0m #{}", view.proposer, view.id);
+    println!("🗳️ mint:     \x1b[95m{}\x1b[0m", view.vote_mint);
+    println!("⏰ started:  {}", view.voting_starts_at);
+    println!(
+        "⏰ ends at:  {}{}",
+        view.voting_ends_at,
+        if view.finalized { " (finalized)" } else { "" }
+    );
+    for (choice, option) in view.options.iter().enumerate() {
+        println!(
+            "🎨 {choice}: \x1b[33m{}\x1b[0m with \x1b[33m{}\x1b[0m votes",
+            option.color, option.votes
+        );
+    }
+    println!("\x1b[32m----------------------------\x1b[0m");
+}
+
+pub fn print_rows(rows: &[FavoritesRow], json: bool, csv: bool) -> Result<(), String> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(rows).unwrap());
+        return Ok(());
+    }
+    if csv {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        for row in rows {
+            writer.serialize(row).map_err(|err| err.to_string())?;
+        }
+        return writer.flush().map_err(|err| err.to_string());
+    }
+    for row in rows {
+        println!(
+            "📦 \x1b[95m{}\x1b[0m  🔢 \x1b[33m{}\x1b[0m  🎨 \x1b[33m{}\x1b[0m  🤝 {}",
+            row.address,
+            row.number,
+            row.color,
+            row.delegate.as_deref().unwrap_or("none")
+        );
+    }
+    println!("\x1b[32m{} favorites account(s)\x1b[0m", rows.len());
+    Ok(())
+}
+
+pub fn print_profiles(rows: &[ProfileRow], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(rows).unwrap());
+        return;
+    }
+    for row in rows {
+        println!(
+            "#{} 📦 \x1b[95m{}\x1b[0m  🔢 \x1b[33m{}\x1b[0m  🎨 \x1b[33m{}\x1b[0m",
+            row.index, row.address, row.number, row.color
+        );
+    }
+    println!("\x1b[32m{} profile(s)\x1b[0m", rows.len());
+}
+
+pub fn print_collection(view: &CollectionView, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view).unwrap());
+        return;
+    }
+    println!("🔑 user:     \x1b[95m{}\x1b[0m", view.user);
+    println!("📦 account:  \x1b[95m{}\x1b[0m", view.address);
+    for item in &view.items {
+        println!(
+            "#{} 🔢 \x1b[33m{}\x1b[0m  🎨 \x1b[33m{}\x1b[0m",
+            item.index, item.number, item.color
+        );
+    }
+    println!(
+        "\x1b[32m{} of {} item(s)\x1b[0m",
+        view.items.len(),
+        view.capacity
+    );
+}
+
+pub fn print_users(users: &[Pubkey], json: bool) {
+    if json {
+        let users: Vec<String> = users.iter().map(|user| user.to_string()).collect();
+        println!("{}", serde_json::to_string_pretty(&users).unwrap());
+        return;
+    }
+    for user in users {
+        println!("🔑 \x1b[95m{}\x1b[0m", user);
+    }
+    println!("\x1b[32m{} registered user(s)\x1b[0m", users.len());
+}
+
+pub fn print_signature(message: &str, signature: &Signature, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "signature": signature.to_string() })
+        );
+        return;
+    }
+    println!("✅ {}", message);
+    println!("Signature: \x1b[35m{}\x1b[0m", signature);
+}
+
+pub fn print_error(message: &str) {
+    eprintln!("❌ Error: \x1b[91m{}\x1b[0m", message);
+}
@@ -0,0 +1,601 @@
+use std::fmt;
+
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+
+use crate::DISCRIMINATOR_SIZE;
+
+pub const FAVORITES_DISCRIMINATOR: [u8; 8] = [44, 205, 48, 25, 172, 96, 48, 27];
+pub const REGISTRY_DISCRIMINATOR: [u8; 8] = [47, 174, 110, 246, 184, 182, 252, 218];
+pub const REGISTRY_PAGE_DISCRIMINATOR: [u8; 8] = [190, 151, 207, 163, 226, 253, 16, 250];
+pub const SESSION_DISCRIMINATOR: [u8; 8] = [243, 81, 72, 115, 214, 188, 72, 144];
+pub const CONFIG_DISCRIMINATOR: [u8; 8] = [155, 12, 170, 224, 30, 250, 204, 130];
+pub const NFT_FAVORITES_DISCRIMINATOR: [u8; 8] = [54, 151, 64, 22, 98, 22, 71, 235];
+pub const TIP_VAULT_DISCRIMINATOR: [u8; 8] = [142, 77, 243, 158, 142, 87, 253, 9];
+pub const TIP_RECEIVED_DISCRIMINATOR: [u8; 8] = [24, 50, 123, 10, 169, 249, 154, 112];
+pub const HANDLE_DISCRIMINATOR: [u8; 8] = [150, 96, 143, 54, 64, 147, 63, 63];
+pub const STATS_DISCRIMINATOR: [u8; 8] = [190, 125, 51, 63, 169, 197, 36, 238];
+pub const PROPOSAL_DISCRIMINATOR: [u8; 8] = [26, 94, 189, 187, 116, 136, 53, 33];
+pub const TALLY_DISCRIMINATOR: [u8; 8] = [126, 11, 29, 33, 32, 101, 239, 25];
+pub const VOTE_RECEIPT_DISCRIMINATOR: [u8; 8] = [104, 20, 204, 252, 45, 84, 37, 195];
+pub const COMMUNITY_COLOR_DISCRIMINATOR: [u8; 8] = [160, 229, 197, 167, 207, 8, 121, 110];
+pub const PROFILE_DISCRIMINATOR: [u8; 8] = [184, 101, 165, 188, 95, 63, 127, 188];
+pub const REFERRALS_DISCRIMINATOR: [u8; 8] = [249, 160, 110, 143, 15, 40, 167, 21];
+pub const RECOVERY_DISCRIMINATOR: [u8; 8] = [242, 166, 34, 227, 71, 217, 155, 103];
+pub const RECOVERY_KEY_SET_DISCRIMINATOR: [u8; 8] = [32, 219, 127, 69, 158, 109, 92, 34];
+pub const RECOVERY_KEY_REMOVED_DISCRIMINATOR: [u8; 8] = [43, 193, 24, 62, 53, 56, 246, 39];
+pub const RECOVERY_STARTED_DISCRIMINATOR: [u8; 8] = [170, 195, 145, 167, 147, 234, 156, 155];
+pub const RECOVERY_CANCELLED_DISCRIMINATOR: [u8; 8] = [191, 25, 236, 86, 25, 77, 117, 96];
+pub const RECOVERY_COMPLETED_DISCRIMINATOR: [u8; 8] = [166, 134, 134, 187, 135, 194, 105, 144];
+pub const COLLECTION_DISCRIMINATOR: [u8; 8] = [48, 160, 232, 205, 191, 207, 26, 141];
+
+/// Decoded `Favorites` account.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Favorites {
+    pub number: u64,
+    pub color: String,
+    pub delegate: Option<Pubkey>,
+    /// Layout version, zero for accounts that still need `migrate_favorites`.
+    /// Those decode with the fields below at their defaults.
+    pub version: u8,
+    pub registry_page: u32,
+    pub nonce: u64,
+    /// Unix timestamp premium lasts until, zero if it was never bought.
+    pub premium_until: i64,
+    /// The wallet that brought the owner, named at signup.
+    pub referrer: Option<Pubkey>,
+    /// Only sessions opened under this generation apply.
+    pub session_generation: u64,
+}
+
+/// Byte offsets and sizes of raw `Favorites` account data, discriminator
+/// included. Only `number` and `color` sit at fixed offsets: the fields after
+/// the color move with its length.
+pub mod favorites_layout {
+    use std::mem::size_of;
+
+    use solana_program::pubkey::Pubkey;
+
+    use crate::DISCRIMINATOR_SIZE;
+
+    pub const NUMBER_OFFSET: usize = DISCRIMINATOR_SIZE;
+    /// Borsh writes the color's `u32` length here, followed by its bytes.
+    pub const COLOR_OFFSET: usize = NUMBER_OFFSET + size_of::<u64>();
+    pub const MAX_COLOR_LEN: usize = 50;
+    /// Longest color with premium. Setting one grows the account past
+    /// `ACCOUNT_SIZE`, and it never shrinks back.
+    pub const MAX_PREMIUM_COLOR_LEN: usize = 128;
+    /// `version` of accounts in the current layout.
+    pub const VERSION: u8 = 1;
+    /// Space of accounts from before the version byte: a number, a color and
+    /// a delegate.
+    pub const LEGACY_ACCOUNT_SIZE: usize =
+        COLOR_OFFSET + size_of::<u32>() + MAX_COLOR_LEN + 1 + size_of::<Pubkey>();
+    /// Space the program allocates for a new `Favorites` account, with room
+    /// for both a delegate and a referrer.
+    pub const ACCOUNT_SIZE: usize = LEGACY_ACCOUNT_SIZE
+        + 1
+        + size_of::<u32>()
+        + size_of::<u64>()
+        + size_of::<i64>()
+        + 1
+        + size_of::<Pubkey>()
+        + size_of::<u64>();
+}
+
+impl Favorites {
+    /// Decodes raw account data, discriminator included, in the current or
+    /// the legacy layout. Anchor allocates the account for the longest color,
+    /// so trailing bytes are ignored.
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, FAVORITES_DISCRIMINATOR)?;
+        let invalid = |error: std::io::Error| DecodeError::InvalidData(error.to_string());
+        if data.len() > favorites_layout::LEGACY_ACCOUNT_SIZE {
+            return Self::deserialize(&mut &body[..]).map_err(invalid);
+        }
+        let (number, color, delegate) =
+            <(u64, String, Option<Pubkey>)>::deserialize(&mut &body[..]).map_err(invalid)?;
+        Ok(Self {
+            number,
+            color,
+            delegate,
+            version: 0,
+            registry_page: 0,
+            nonce: 0,
+            premium_until: 0,
+            referrer: None,
+            session_generation: 0,
+        })
+    }
+
+    /// Whether the account is in the layout the program reads, rather than
+    /// waiting for `migrate_favorites`.
+    pub fn is_migrated(&self) -> bool {
+        self.version == favorites_layout::VERSION
+    }
+
+    /// Whether premium is active at the unix timestamp `now`.
+    pub fn is_premium(&self, now: i64) -> bool {
+        now < self.premium_until
+    }
+}
+
+/// Decoded `Registry` account: the page new users go to and how many users
+/// are registered in total.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Registry {
+    pub open_page: u32,
+    pub user_count: u64,
+}
+
+impl Registry {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, REGISTRY_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `RegistryPage` account.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegistryPage {
+    pub users: Vec<Pubkey>,
+}
+
+impl RegistryPage {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, REGISTRY_PAGE_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Session` account: a key allowed `uses_left` more updates until
+/// the unix timestamp `expiry`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub session_key: Pubkey,
+    pub uses_left: u32,
+    pub expiry: i64,
+    /// The favorites' `session_generation` when the session was opened. The
+    /// session stops applying once they move or close.
+    pub generation: u64,
+}
+
+impl Session {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, SESSION_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Stats` account: totals over every favorites account.
+/// `min_number` and `max_number` are all-time extremes, and once more colors
+/// are in use than `top_colors` holds its counts may overestimate.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub user_count: u64,
+    pub number_sum: u128,
+    pub min_number: Option<u64>,
+    pub max_number: Option<u64>,
+    pub top_colors: Vec<ColorCount>,
+    /// Favorites accounts ever created, which never counts down.
+    pub favorites_created: u64,
+}
+
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ColorCount {
+    pub color: String,
+    pub count: u64,
+}
+
+impl Stats {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, STATS_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Handle` account: `@handle` names `owner`, whose favorites are at
+/// `favorites`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Handle {
+    pub owner: Pubkey,
+    pub favorites: Pubkey,
+    pub handle: String,
+}
+
+impl Handle {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, HANDLE_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Config` account. While `gate_mint` is set, creating and updating
+/// favorites requires the signer to hold `gate_min_amount` of that mint.
+/// Community color proposals weigh votes by balance of `vote_mint`. Setting
+/// and updating favorites costs `fee_amount` lamports, or base units of
+/// `fee_mint` when set, see [`crate::fees`]. Premium costs `premium_price`
+/// base units of `premium_mint` a month, see [`crate::premium`].
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub gate_mint: Option<Pubkey>,
+    pub gate_min_amount: u64,
+    pub vote_mint: Option<Pubkey>,
+    pub fee_mint: Option<Pubkey>,
+    pub fee_amount: u64,
+    pub premium_mint: Option<Pubkey>,
+    pub premium_price: u64,
+}
+
+impl Config {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, CONFIG_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `NftFavorites` account, controlled by whoever holds the NFT `mint`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NftFavorites {
+    pub mint: Pubkey,
+    pub number: u64,
+    pub color: String,
+}
+
+impl NftFavorites {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, NFT_FAVORITES_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `TipVault` account. Its balance above rent is what the owner can
+/// withdraw; `tips_total` counts every tip ever received.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TipVault {
+    pub tips_total: u64,
+}
+
+impl TipVault {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, TIP_VAULT_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `TipReceived` event, emitted by `tip`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TipReceived {
+    pub user: Pubkey,
+    pub tipper: Pubkey,
+    pub amount: u64,
+    pub tips_total: u64,
+}
+
+impl TipReceived {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, TIP_RECEIVED_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Proposal` account: a vote between `colors`, open until
+/// `voting_ends_at`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Proposal {
+    pub proposer: Pubkey,
+    pub id: u64,
+    pub vote_mint: Pubkey,
+    pub voting_starts_at: i64,
+    pub voting_ends_at: i64,
+    pub finalized: bool,
+    pub colors: Vec<String>,
+}
+
+impl Proposal {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, PROPOSAL_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Tally` account: vote weight per color of `proposal`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Tally {
+    pub proposal: Pubkey,
+    pub votes: Vec<u64>,
+}
+
+impl Tally {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, TALLY_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `VoteReceipt` account: one voter's locked vote on a proposal.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VoteReceipt {
+    pub choice: u8,
+    pub amount: u64,
+}
+
+impl VoteReceipt {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, VOTE_RECEIPT_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `CommunityColor` account, the winner of the newest decided
+/// proposal, created at `voting_started_at`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CommunityColor {
+    pub proposal: Pubkey,
+    pub voting_started_at: i64,
+    pub decided_at: i64,
+    pub color: String,
+}
+
+impl CommunityColor {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, COMMUNITY_COLOR_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Profile` account: one of the extra favorites a user keeps beside
+/// their main ones, see [`crate::premium`].
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub number: u64,
+    pub color: String,
+}
+
+impl Profile {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, PROFILE_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `Referrals` account: how many users with favorites named the wallet
+/// it belongs to as their referrer.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Referrals {
+    pub count: u64,
+}
+
+impl Referrals {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, REFERRALS_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Seconds between `start_recovery` and the earliest `complete_recovery`.
+pub const RECOVERY_TIMELOCK: i64 = 7 * 24 * 60 * 60;
+
+/// Decoded `Recovery` account: the key that may move its owner's favorites
+/// to `new_owner` once `unlocks_at` has passed. `new_owner` is `None` while
+/// no recovery is in progress.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    pub recovery_key: Pubkey,
+    pub new_owner: Option<Pubkey>,
+    pub unlocks_at: i64,
+}
+
+impl Recovery {
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+
+    /// Whether `complete_recovery` succeeds at the unix timestamp `now`.
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        self.new_owner.is_some() && now >= self.unlocks_at
+    }
+}
+
+/// Decoded `RecoveryKeySet` event, emitted by `set_recovery_key`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryKeySet {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+}
+
+impl RecoveryKeySet {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_KEY_SET_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `RecoveryKeyRemoved` event, emitted by `remove_recovery_key`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryKeyRemoved {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+}
+
+impl RecoveryKeyRemoved {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_KEY_REMOVED_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `RecoveryStarted` event, emitted by `start_recovery`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryStarted {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+    pub new_owner: Pubkey,
+    pub unlocks_at: i64,
+}
+
+impl RecoveryStarted {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_STARTED_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `RecoveryCancelled` event, emitted by `cancel_recovery`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryCancelled {
+    pub user: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+impl RecoveryCancelled {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_CANCELLED_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Decoded `RecoveryCompleted` event, emitted by `complete_recovery`.
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryCompleted {
+    pub user: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+impl RecoveryCompleted {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>".
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let body = strip_discriminator(data, RECOVERY_COMPLETED_DISCRIMINATOR)?;
+        Self::deserialize(&mut &body[..])
+            .map_err(|error| DecodeError::InvalidData(error.to_string()))
+    }
+}
+
+/// Byte offsets into raw `Collection` account data, discriminator included.
+/// The account is zero-copy: a `len` followed by `CAPACITY` fixed-size items,
+/// of which the first `len` are set.
+pub mod collection_layout {
+    use std::mem::size_of;
+
+    use crate::DISCRIMINATOR_SIZE;
+
+    pub const CAPACITY: usize = 400;
+    pub const MAX_COLOR_LEN: usize = 50;
+    pub const LEN_OFFSET: usize = DISCRIMINATOR_SIZE;
+    pub const ITEMS_OFFSET: usize = LEN_OFFSET + size_of::<u64>();
+    /// Offsets within an item.
+    pub const ITEM_NUMBER_OFFSET: usize = 0;
+    pub const ITEM_COLOR_OFFSET: usize = ITEM_NUMBER_OFFSET + size_of::<u64>();
+    pub const ITEM_COLOR_LEN_OFFSET: usize = ITEM_COLOR_OFFSET + MAX_COLOR_LEN;
+    /// Padded to a multiple of 8 bytes.
+    pub const ITEM_SIZE: usize = 64;
+    pub const ACCOUNT_SIZE: usize = ITEMS_OFFSET + CAPACITY * ITEM_SIZE;
+}
+
+/// Decoded `Collection` account: the extra favorites its owner keeps, in
+/// order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collection {
+    pub items: Vec<CollectionItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectionItem {
+    pub number: u64,
+    pub color: String,
+}
+
+impl Collection {
+    /// Decodes raw account data, discriminator included. Only the items set
+    /// are read.
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        use collection_layout::*;
+
+        strip_discriminator(data, COLLECTION_DISCRIMINATOR)?;
+        if data.len() < ACCOUNT_SIZE {
+            return Err(DecodeError::InvalidData(format!(
+                "collection is {} bytes, not {ACCOUNT_SIZE}",
+                data.len()
+            )));
+        }
+        let len = u64::from_le_bytes(data[LEN_OFFSET..][..8].try_into().unwrap());
+        if len > CAPACITY as u64 {
+            return Err(DecodeError::InvalidData(format!(
+                "collection of {len} items holds at most {CAPACITY}"
+            )));
+        }
+        let items = data[ITEMS_OFFSET..]
+            .chunks_exact(ITEM_SIZE)
+            .take(len as usize)
+            .map(|item| {
+                let number =
+                    u64::from_le_bytes(item[ITEM_NUMBER_OFFSET..][..8].try_into().unwrap());
+                let color_len = usize::from(item[ITEM_COLOR_LEN_OFFSET]).min(MAX_COLOR_LEN);
+                let color = &item[ITEM_COLOR_OFFSET..][..color_len];
+                let color = String::from_utf8(color.to_vec())
+                    .map_err(|error| DecodeError::InvalidData(error.to_string()))?;
+                Ok(CollectionItem { number, color })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Collection { items })
+    }
+}
+
+fn strip_discriminator(data: &[u8], discriminator: [u8; 8]) -> Result<&[u8], DecodeError> {
+    if data.len() < DISCRIMINATOR_SIZE {
+        return Err(DecodeError::AccountTooShort);
+    }
+    let (head, body) = data.split_at(DISCRIMINATOR_SIZE);
+    if head != discriminator {
+        return Err(DecodeError::DiscriminatorMismatch);
+    }
+    Ok(body)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    AccountTooShort,
+    DiscriminatorMismatch,
+    InvalidData(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::AccountTooShort => {
+                write!(f, "account data is shorter than a discriminator")
+            }
+            DecodeError::DiscriminatorMismatch => write!(f, "account discriminator does not match"),
+            DecodeError::InvalidData(error) => write!(f, "invalid account data: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,717 @@
+//! Decoding the errors a transaction fails with.
+//!
+//! A failed instruction only carries `InstructionError::Custom(code)`; what
+//! the code means depends on the program that returned it. The favorites
+//! program numbers its own errors from 6000 and Anchor's framework errors
+//! from 100 to 5000, below which codes come from the programs it invokes,
+//! in practice the system program creating accounts and moving lamports.
+use std::fmt;
+
+use solana_client::client_error::ClientError;
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_transaction_error::TransactionError;
+
+use crate::errors::{CustomError, ERROR_CODE_OFFSET};
+
+/// Errors the Anchor framework returns on behalf of the program, such as a
+/// violated account constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnchorError {
+    InstructionMissing,
+    InstructionFallbackNotFound,
+    InstructionDidNotDeserialize,
+    InstructionDidNotSerialize,
+    IdlInstructionStub,
+    IdlInstructionInvalidProgram,
+    IdlAccountNotEmpty,
+    EventInstructionStub,
+    ConstraintMut,
+    ConstraintHasOne,
+    ConstraintSigner,
+    ConstraintRaw,
+    ConstraintOwner,
+    ConstraintRentExempt,
+    ConstraintSeeds,
+    ConstraintExecutable,
+    ConstraintState,
+    ConstraintAssociated,
+    ConstraintAssociatedInit,
+    ConstraintClose,
+    ConstraintAddress,
+    ConstraintZero,
+    ConstraintTokenMint,
+    ConstraintTokenOwner,
+    ConstraintMintMintAuthority,
+    ConstraintMintFreezeAuthority,
+    ConstraintMintDecimals,
+    ConstraintSpace,
+    ConstraintAccountIsNone,
+    ConstraintTokenTokenProgram,
+    ConstraintMintTokenProgram,
+    ConstraintAssociatedTokenTokenProgram,
+    ConstraintMintGroupPointerExtension,
+    ConstraintMintGroupPointerExtensionAuthority,
+    ConstraintMintGroupPointerExtensionGroupAddress,
+    ConstraintMintGroupMemberPointerExtension,
+    ConstraintMintGroupMemberPointerExtensionAuthority,
+    ConstraintMintGroupMemberPointerExtensionMemberAddress,
+    ConstraintMintMetadataPointerExtension,
+    ConstraintMintMetadataPointerExtensionAuthority,
+    ConstraintMintMetadataPointerExtensionMetadataAddress,
+    ConstraintMintCloseAuthorityExtension,
+    ConstraintMintCloseAuthorityExtensionAuthority,
+    ConstraintMintPermanentDelegateExtension,
+    ConstraintMintPermanentDelegateExtensionDelegate,
+    ConstraintMintTransferHookExtension,
+    ConstraintMintTransferHookExtensionAuthority,
+    ConstraintMintTransferHookExtensionProgramId,
+    RequireViolated,
+    RequireEqViolated,
+    RequireKeysEqViolated,
+    RequireNeqViolated,
+    RequireKeysNeqViolated,
+    RequireGtViolated,
+    RequireGteViolated,
+    AccountDiscriminatorAlreadySet,
+    AccountDiscriminatorNotFound,
+    AccountDiscriminatorMismatch,
+    AccountDidNotDeserialize,
+    AccountDidNotSerialize,
+    AccountNotEnoughKeys,
+    AccountNotMutable,
+    AccountOwnedByWrongProgram,
+    InvalidProgramId,
+    InvalidProgramExecutable,
+    AccountNotSigner,
+    AccountNotSystemOwned,
+    AccountNotInitialized,
+    AccountNotProgramData,
+    AccountNotAssociatedTokenAccount,
+    AccountSysvarMismatch,
+    AccountReallocExceedsLimit,
+    AccountDuplicateReallocs,
+    DeclaredProgramIdMismatch,
+    TryingToInitPayerAsProgramAccount,
+    InvalidNumericConversion,
+    Deprecated,
+}
+
+impl AnchorError {
+    pub const ALL: &'static [AnchorError] = &[
+        AnchorError::InstructionMissing,
+        AnchorError::InstructionFallbackNotFound,
+        AnchorError::InstructionDidNotDeserialize,
+        AnchorError::InstructionDidNotSerialize,
+        AnchorError::IdlInstructionStub,
+        AnchorError::IdlInstructionInvalidProgram,
+        AnchorError::IdlAccountNotEmpty,
+        AnchorError::EventInstructionStub,
+        AnchorError::ConstraintMut,
+        AnchorError::ConstraintHasOne,
+        AnchorError::ConstraintSigner,
+        AnchorError::ConstraintRaw,
+        AnchorError::ConstraintOwner,
+        AnchorError::ConstraintRentExempt,
+        AnchorError::ConstraintSeeds,
+        AnchorError::ConstraintExecutable,
+        AnchorError::ConstraintState,
+        AnchorError::ConstraintAssociated,
+        AnchorError::ConstraintAssociatedInit,
+        AnchorError::ConstraintClose,
+        AnchorError::ConstraintAddress,
+        AnchorError::ConstraintZero,
+        AnchorError::ConstraintTokenMint,
+        AnchorError::ConstraintTokenOwner,
+        AnchorError::ConstraintMintMintAuthority,
+        AnchorError::ConstraintMintFreezeAuthority,
+        AnchorError::ConstraintMintDecimals,
+        AnchorError::ConstraintSpace,
+        AnchorError::ConstraintAccountIsNone,
+        AnchorError::ConstraintTokenTokenProgram,
+        AnchorError::ConstraintMintTokenProgram,
+        AnchorError::ConstraintAssociatedTokenTokenProgram,
+        AnchorError::ConstraintMintGroupPointerExtension,
+        AnchorError::ConstraintMintGroupPointerExtensionAuthority,
+        AnchorError::ConstraintMintGroupPointerExtensionGroupAddress,
+        AnchorError::ConstraintMintGroupMemberPointerExtension,
+        AnchorError::ConstraintMintGroupMemberPointerExtensionAuthority,
+        AnchorError::ConstraintMintGroupMemberPointerExtensionMemberAddress,
+        AnchorError::ConstraintMintMetadataPointerExtension,
+        AnchorError::ConstraintMintMetadataPointerExtensionAuthority,
+        AnchorError::ConstraintMintMetadataPointerExtensionMetadataAddress,
+        AnchorError::ConstraintMintCloseAuthorityExtension,
+        AnchorError::ConstraintMintCloseAuthorityExtensionAuthority,
+        AnchorError::ConstraintMintPermanentDelegateExtension,
+        AnchorError::ConstraintMintPermanentDelegateExtensionDelegate,
+        AnchorError::ConstraintMintTransferHookExtension,
+        AnchorError::ConstraintMintTransferHookExtensionAuthority,
+        AnchorError::ConstraintMintTransferHookExtensionProgramId,
+        AnchorError::RequireViolated,
+        AnchorError::RequireEqViolated,
+        AnchorError::RequireKeysEqViolated,
+        AnchorError::RequireNeqViolated,
+        AnchorError::RequireKeysNeqViolated,
+        AnchorError::RequireGtViolated,
+        AnchorError::RequireGteViolated,
+        AnchorError::AccountDiscriminatorAlreadySet,
+        AnchorError::AccountDiscriminatorNotFound,
+        AnchorError::AccountDiscriminatorMismatch,
+        AnchorError::AccountDidNotDeserialize,
+        AnchorError::AccountDidNotSerialize,
+        AnchorError::AccountNotEnoughKeys,
+        AnchorError::AccountNotMutable,
+        AnchorError::AccountOwnedByWrongProgram,
+        AnchorError::InvalidProgramId,
+        AnchorError::InvalidProgramExecutable,
+        AnchorError::AccountNotSigner,
+        AnchorError::AccountNotSystemOwned,
+        AnchorError::AccountNotInitialized,
+        AnchorError::AccountNotProgramData,
+        AnchorError::AccountNotAssociatedTokenAccount,
+        AnchorError::AccountSysvarMismatch,
+        AnchorError::AccountReallocExceedsLimit,
+        AnchorError::AccountDuplicateReallocs,
+        AnchorError::DeclaredProgramIdMismatch,
+        AnchorError::TryingToInitPayerAsProgramAccount,
+        AnchorError::InvalidNumericConversion,
+        AnchorError::Deprecated,
+    ];
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|error| error.code() == code)
+    }
+
+    pub fn code(self) -> u32 {
+        match self {
+            AnchorError::InstructionMissing => 100,
+            AnchorError::InstructionFallbackNotFound => 101,
+            AnchorError::InstructionDidNotDeserialize => 102,
+            AnchorError::InstructionDidNotSerialize => 103,
+            AnchorError::IdlInstructionStub => 1000,
+            AnchorError::IdlInstructionInvalidProgram => 1001,
+            AnchorError::IdlAccountNotEmpty => 1002,
+            AnchorError::EventInstructionStub => 1500,
+            AnchorError::ConstraintMut => 2000,
+            AnchorError::ConstraintHasOne => 2001,
+            AnchorError::ConstraintSigner => 2002,
+            AnchorError::ConstraintRaw => 2003,
+            AnchorError::ConstraintOwner => 2004,
+            AnchorError::ConstraintRentExempt => 2005,
+            AnchorError::ConstraintSeeds => 2006,
+            AnchorError::ConstraintExecutable => 2007,
+            AnchorError::ConstraintState => 2008,
+            AnchorError::ConstraintAssociated => 2009,
+            AnchorError::ConstraintAssociatedInit => 2010,
+            AnchorError::ConstraintClose => 2011,
+            AnchorError::ConstraintAddress => 2012,
+            AnchorError::ConstraintZero => 2013,
+            AnchorError::ConstraintTokenMint => 2014,
+            AnchorError::ConstraintTokenOwner => 2015,
+            AnchorError::ConstraintMintMintAuthority => 2016,
+            AnchorError::ConstraintMintFreezeAuthority => 2017,
+            AnchorError::ConstraintMintDecimals => 2018,
+            AnchorError::ConstraintSpace => 2019,
+            AnchorError::ConstraintAccountIsNone => 2020,
+            AnchorError::ConstraintTokenTokenProgram => 2021,
+            AnchorError::ConstraintMintTokenProgram => 2022,
+            AnchorError::ConstraintAssociatedTokenTokenProgram => 2023,
+            AnchorError::ConstraintMintGroupPointerExtension => 2024,
+            AnchorError::ConstraintMintGroupPointerExtensionAuthority => 2025,
+            AnchorError::ConstraintMintGroupPointerExtensionGroupAddress => 2026,
+            AnchorError::ConstraintMintGroupMemberPointerExtension => 2027,
+            AnchorError::ConstraintMintGroupMemberPointerExtensionAuthority => 2028,
+            AnchorError::ConstraintMintGroupMemberPointerExtensionMemberAddress => 2029,
+            AnchorError::ConstraintMintMetadataPointerExtension => 2030,
+            AnchorError::ConstraintMintMetadataPointerExtensionAuthority => 2031,
+            AnchorError::ConstraintMintMetadataPointerExtensionMetadataAddress => 2032,
+            AnchorError::ConstraintMintCloseAuthorityExtension => 2033,
+            AnchorError::ConstraintMintCloseAuthorityExtensionAuthority => 2034,
+            AnchorError::ConstraintMintPermanentDelegateExtension => 2035,
+            AnchorError::ConstraintMintPermanentDelegateExtensionDelegate => 2036,
+            AnchorError::ConstraintMintTransferHookExtension => 2037,
+            AnchorError::ConstraintMintTransferHookExtensionAuthority => 2038,
+            AnchorError::ConstraintMintTransferHookExtensionProgramId => 2039,
+            AnchorError::RequireViolated => 2500,
+            AnchorError::RequireEqViolated => 2501,
+            AnchorError::RequireKeysEqViolated => 2502,
+            AnchorError::RequireNeqViolated => 2503,
+            AnchorError::RequireKeysNeqViolated => 2504,
+            AnchorError::RequireGtViolated => 2505,
+            AnchorError::RequireGteViolated => 2506,
+            AnchorError::AccountDiscriminatorAlreadySet => 3000,
+            AnchorError::AccountDiscriminatorNotFound => 3001,
+            AnchorError::AccountDiscriminatorMismatch => 3002,
+            AnchorError::AccountDidNotDeserialize => 3003,
+            AnchorError::AccountDidNotSerialize => 3004,
+            AnchorError::AccountNotEnoughKeys => 3005,
+            AnchorError::AccountNotMutable => 3006,
+            AnchorError::AccountOwnedByWrongProgram => 3007,
+            AnchorError::InvalidProgramId => 3008,
+            AnchorError::InvalidProgramExecutable => 3009,
+            AnchorError::AccountNotSigner => 3010,
+            AnchorError::AccountNotSystemOwned => 3011,
+            AnchorError::AccountNotInitialized => 3012,
+            AnchorError::AccountNotProgramData => 3013,
+            AnchorError::AccountNotAssociatedTokenAccount => 3014,
+            AnchorError::AccountSysvarMismatch => 3015,
+            AnchorError::AccountReallocExceedsLimit => 3016,
+            AnchorError::AccountDuplicateReallocs => 3017,
+            AnchorError::DeclaredProgramIdMismatch => 4100,
+            AnchorError::TryingToInitPayerAsProgramAccount => 4101,
+            AnchorError::InvalidNumericConversion => 4102,
+            AnchorError::Deprecated => 5000,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AnchorError::InstructionMissing => "InstructionMissing",
+            AnchorError::InstructionFallbackNotFound => "InstructionFallbackNotFound",
+            AnchorError::InstructionDidNotDeserialize => "InstructionDidNotDeserialize",
+            AnchorError::InstructionDidNotSerialize => "InstructionDidNotSerialize",
+            AnchorError::IdlInstructionStub => "IdlInstructionStub",
+            AnchorError::IdlInstructionInvalidProgram => "IdlInstructionInvalidProgram",
+            AnchorError::IdlAccountNotEmpty => "IdlAccountNotEmpty",
+            AnchorError::EventInstructionStub => "EventInstructionStub",
+            AnchorError::ConstraintMut => "ConstraintMut",
+            AnchorError::ConstraintHasOne => "ConstraintHasOne",
+            AnchorError::ConstraintSigner => "ConstraintSigner",
+            AnchorError::ConstraintRaw => "ConstraintRaw",
+            AnchorError::ConstraintOwner => "ConstraintOwner",
+            AnchorError::ConstraintRentExempt => "ConstraintRentExempt",
+            AnchorError::ConstraintSeeds => "ConstraintSeeds",
+            AnchorError::ConstraintExecutable => "ConstraintExecutable",
+            AnchorError::ConstraintState => "ConstraintState",
+            AnchorError::ConstraintAssociated => "ConstraintAssociated",
+            AnchorError::ConstraintAssociatedInit => "ConstraintAssociatedInit",
+            AnchorError::ConstraintClose => "ConstraintClose",
+            AnchorError::ConstraintAddress => "ConstraintAddress",
+            AnchorError::ConstraintZero => "ConstraintZero",
+            AnchorError::ConstraintTokenMint => "ConstraintTokenMint",
+            AnchorError::ConstraintTokenOwner => "ConstraintTokenOwner",
+            AnchorError::ConstraintMintMintAuthority => "ConstraintMintMintAuthority",
+            AnchorError::ConstraintMintFreezeAuthority => "ConstraintMintFreezeAuthority",
+            AnchorError::ConstraintMintDecimals => "ConstraintMintDecimals",
+            AnchorError::ConstraintSpace => "ConstraintSpace",
+            AnchorError::ConstraintAccountIsNone => "ConstraintAccountIsNone",
+            AnchorError::ConstraintTokenTokenProgram => "ConstraintTokenTokenProgram",
+            AnchorError::ConstraintMintTokenProgram => "ConstraintMintTokenProgram",
+            AnchorError::ConstraintAssociatedTokenTokenProgram => {
+                "ConstraintAssociatedTokenTokenProgram"
+            }
+            AnchorError::ConstraintMintGroupPointerExtension => {
+                "ConstraintMintGroupPointerExtension"
+            }
+            AnchorError::ConstraintMintGroupPointerExtensionAuthority => {
+                "ConstraintMintGroupPointerExtensionAuthority"
+            }
+            AnchorError::ConstraintMintGroupPointerExtensionGroupAddress => {
+                "ConstraintMintGroupPointerExtensionGroupAddress"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtension => {
+                "ConstraintMintGroupMemberPointerExtension"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtensionAuthority => {
+                "ConstraintMintGroupMemberPointerExtensionAuthority"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtensionMemberAddress => {
+                "ConstraintMintGroupMemberPointerExtensionMemberAddress"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtension => {
+                "ConstraintMintMetadataPointerExtension"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtensionAuthority => {
+                "ConstraintMintMetadataPointerExtensionAuthority"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtensionMetadataAddress => {
+                "ConstraintMintMetadataPointerExtensionMetadataAddress"
+            }
+            AnchorError::ConstraintMintCloseAuthorityExtension => {
+                "ConstraintMintCloseAuthorityExtension"
+            }
+            AnchorError::ConstraintMintCloseAuthorityExtensionAuthority => {
+                "ConstraintMintCloseAuthorityExtensionAuthority"
+            }
+            AnchorError::ConstraintMintPermanentDelegateExtension => {
+                "ConstraintMintPermanentDelegateExtension"
+            }
+            AnchorError::ConstraintMintPermanentDelegateExtensionDelegate => {
+                "ConstraintMintPermanentDelegateExtensionDelegate"
+            }
+            AnchorError::ConstraintMintTransferHookExtension => {
+                "ConstraintMintTransferHookExtension"
+            }
+            AnchorError::ConstraintMintTransferHookExtensionAuthority => {
+                "ConstraintMintTransferHookExtensionAuthority"
+            }
+            AnchorError::ConstraintMintTransferHookExtensionProgramId => {
+                "ConstraintMintTransferHookExtensionProgramId"
+            }
+            AnchorError::RequireViolated => "RequireViolated",
+            AnchorError::RequireEqViolated => "RequireEqViolated",
+            AnchorError::RequireKeysEqViolated => "RequireKeysEqViolated",
+            AnchorError::RequireNeqViolated => "RequireNeqViolated",
+            AnchorError::RequireKeysNeqViolated => "RequireKeysNeqViolated",
+            AnchorError::RequireGtViolated => "RequireGtViolated",
+            AnchorError::RequireGteViolated => "RequireGteViolated",
+            AnchorError::AccountDiscriminatorAlreadySet => "AccountDiscriminatorAlreadySet",
+            AnchorError::AccountDiscriminatorNotFound => "AccountDiscriminatorNotFound",
+            AnchorError::AccountDiscriminatorMismatch => "AccountDiscriminatorMismatch",
+            AnchorError::AccountDidNotDeserialize => "AccountDidNotDeserialize",
+            AnchorError::AccountDidNotSerialize => "AccountDidNotSerialize",
+            AnchorError::AccountNotEnoughKeys => "AccountNotEnoughKeys",
+            AnchorError::AccountNotMutable => "AccountNotMutable",
+            AnchorError::AccountOwnedByWrongProgram => "AccountOwnedByWrongProgram",
+            AnchorError::InvalidProgramId => "InvalidProgramId",
+            AnchorError::InvalidProgramExecutable => "InvalidProgramExecutable",
+            AnchorError::AccountNotSigner => "AccountNotSigner",
+            AnchorError::AccountNotSystemOwned => "AccountNotSystemOwned",
+            AnchorError::AccountNotInitialized => "AccountNotInitialized",
+            AnchorError::AccountNotProgramData => "AccountNotProgramData",
+            AnchorError::AccountNotAssociatedTokenAccount => "AccountNotAssociatedTokenAccount",
+            AnchorError::AccountSysvarMismatch => "AccountSysvarMismatch",
+            AnchorError::AccountReallocExceedsLimit => "AccountReallocExceedsLimit",
+            AnchorError::AccountDuplicateReallocs => "AccountDuplicateReallocs",
+            AnchorError::DeclaredProgramIdMismatch => "DeclaredProgramIdMismatch",
+            AnchorError::TryingToInitPayerAsProgramAccount => "TryingToInitPayerAsProgramAccount",
+            AnchorError::InvalidNumericConversion => "InvalidNumericConversion",
+            AnchorError::Deprecated => "Deprecated",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            AnchorError::InstructionMissing => "Instruction discriminator not provided",
+            AnchorError::InstructionFallbackNotFound => "Fallback functions are not supported",
+            AnchorError::InstructionDidNotDeserialize => {
+                "The program could not deserialize the given instruction"
+            }
+            AnchorError::InstructionDidNotSerialize => {
+                "The program could not serialize the given instruction"
+            }
+            AnchorError::IdlInstructionStub => "The program was compiled without idl instructions",
+            AnchorError::IdlInstructionInvalidProgram => {
+                "Invalid program given to the IDL instruction"
+            }
+            AnchorError::IdlAccountNotEmpty => {
+                "IDL account must be empty in order to resize, try closing first"
+            }
+            AnchorError::EventInstructionStub => {
+                "The program was compiled without `event-cpi` feature"
+            }
+            AnchorError::ConstraintMut => "A mut constraint was violated",
+            AnchorError::ConstraintHasOne => "A has one constraint was violated",
+            AnchorError::ConstraintSigner => "A signer constraint was violated",
+            AnchorError::ConstraintRaw => "A raw constraint was violated",
+            AnchorError::ConstraintOwner => "An owner constraint was violated",
+            AnchorError::ConstraintRentExempt => "A rent exemption constraint was violated",
+            AnchorError::ConstraintSeeds => "A seeds constraint was violated",
+            AnchorError::ConstraintExecutable => "An executable constraint was violated",
+            AnchorError::ConstraintState => {
+                "Deprecated Error, feel free to replace with something else"
+            }
+            AnchorError::ConstraintAssociated => "An associated constraint was violated",
+            AnchorError::ConstraintAssociatedInit => "An associated init constraint was violated",
+            AnchorError::ConstraintClose => "A close constraint was violated",
+            AnchorError::ConstraintAddress => "An address constraint was violated",
+            AnchorError::ConstraintZero => "Expected zero account discriminant",
+            AnchorError::ConstraintTokenMint => "A token mint constraint was violated",
+            AnchorError::ConstraintTokenOwner => "A token owner constraint was violated",
+            AnchorError::ConstraintMintMintAuthority => {
+                "A mint mint authority constraint was violated"
+            }
+            AnchorError::ConstraintMintFreezeAuthority => {
+                "A mint freeze authority constraint was violated"
+            }
+            AnchorError::ConstraintMintDecimals => "A mint decimals constraint was violated",
+            AnchorError::ConstraintSpace => "A space constraint was violated",
+            AnchorError::ConstraintAccountIsNone => "A required account for the constraint is None",
+            AnchorError::ConstraintTokenTokenProgram => {
+                "A token account token program constraint was violated"
+            }
+            AnchorError::ConstraintMintTokenProgram => {
+                "A mint token program constraint was violated"
+            }
+            AnchorError::ConstraintAssociatedTokenTokenProgram => {
+                "An associated token account token program constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupPointerExtension => {
+                "A group pointer extension constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupPointerExtensionAuthority => {
+                "A group pointer extension authority constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupPointerExtensionGroupAddress => {
+                "A group pointer extension group address constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtension => {
+                "A group member pointer extension constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtensionAuthority => {
+                "A group member pointer extension authority constraint was violated"
+            }
+            AnchorError::ConstraintMintGroupMemberPointerExtensionMemberAddress => {
+                "A group member pointer extension group address constraint was violated"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtension => {
+                "A metadata pointer extension constraint was violated"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtensionAuthority => {
+                "A metadata pointer extension authority constraint was violated"
+            }
+            AnchorError::ConstraintMintMetadataPointerExtensionMetadataAddress => {
+                "A metadata pointer extension metadata address constraint was violated"
+            }
+            AnchorError::ConstraintMintCloseAuthorityExtension => {
+                "A close authority constraint was violated"
+            }
+            AnchorError::ConstraintMintCloseAuthorityExtensionAuthority => {
+                "A close authority extension authority constraint was violated"
+            }
+            AnchorError::ConstraintMintPermanentDelegateExtension => {
+                "A permanent delegate extension constraint was violated"
+            }
+            AnchorError::ConstraintMintPermanentDelegateExtensionDelegate => {
+                "A permanent delegate extension delegate constraint was violated"
+            }
+            AnchorError::ConstraintMintTransferHookExtension => {
+                "A transfer hook extension constraint was violated"
+            }
+            AnchorError::ConstraintMintTransferHookExtensionAuthority => {
+                "A transfer hook extension authority constraint was violated"
+            }
+            AnchorError::ConstraintMintTransferHookExtensionProgramId => {
+                "A transfer hook extension transfer hook program id constraint was violated"
+            }
+            AnchorError::RequireViolated => "A require expression was violated",
+            AnchorError::RequireEqViolated => "A require_eq expression was violated",
+            AnchorError::RequireKeysEqViolated => "A require_keys_eq expression was violated",
+            AnchorError::RequireNeqViolated => "A require_neq expression was violated",
+            AnchorError::RequireKeysNeqViolated => "A require_keys_neq expression was violated",
+            AnchorError::RequireGtViolated => "A require_gt expression was violated",
+            AnchorError::RequireGteViolated => "A require_gte expression was violated",
+            AnchorError::AccountDiscriminatorAlreadySet => {
+                "The account discriminator was already set on this account"
+            }
+            AnchorError::AccountDiscriminatorNotFound => {
+                "No discriminator was found on the account"
+            }
+            AnchorError::AccountDiscriminatorMismatch => {
+                "Account discriminator did not match what was expected"
+            }
+            AnchorError::AccountDidNotDeserialize => "Failed to deserialize the account",
+            AnchorError::AccountDidNotSerialize => "Failed to serialize the account",
+            AnchorError::AccountNotEnoughKeys => "Not enough account keys given to the instruction",
+            AnchorError::AccountNotMutable => "The given account is not mutable",
+            AnchorError::AccountOwnedByWrongProgram => {
+                "The given account is owned by a different program than expected"
+            }
+            AnchorError::InvalidProgramId => "Program ID was not as expected",
+            AnchorError::InvalidProgramExecutable => "Program account is not executable",
+            AnchorError::AccountNotSigner => "The given account did not sign",
+            AnchorError::AccountNotSystemOwned => {
+                "The given account is not owned by the system program"
+            }
+            AnchorError::AccountNotInitialized => {
+                "The program expected this account to be already initialized"
+            }
+            AnchorError::AccountNotProgramData => "The given account is not a program data account",
+            AnchorError::AccountNotAssociatedTokenAccount => {
+                "The given account is not the associated token account"
+            }
+            AnchorError::AccountSysvarMismatch => {
+                "The given public key does not match the required sysvar"
+            }
+            AnchorError::AccountReallocExceedsLimit => {
+                "The account reallocation exceeds the MAX_PERMITTED_DATA_INCREASE limit"
+            }
+            AnchorError::AccountDuplicateReallocs => {
+                "The account was duplicated for more than one reallocation"
+            }
+            AnchorError::DeclaredProgramIdMismatch => {
+                "The declared program id does not match the actual program id"
+            }
+            AnchorError::TryingToInitPayerAsProgramAccount => {
+                "You cannot/should not initialize the payer account as a program account"
+            }
+            AnchorError::InvalidNumericConversion => "Error during numeric conversion",
+            AnchorError::Deprecated => {
+                "The API being used is deprecated and should no longer be used"
+            }
+        }
+    }
+}
+
+impl fmt::Display for AnchorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name(), self.code(), self.message())
+    }
+}
+
+impl std::error::Error for AnchorError {}
+
+/// Errors of the system program, numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemError {
+    AccountAlreadyInUse,
+    ResultWithNegativeLamports,
+    InvalidProgramId,
+    InvalidAccountDataLength,
+    MaxSeedLengthExceeded,
+    AddressWithSeedMismatch,
+    NonceNoRecentBlockhashes,
+    NonceBlockhashNotExpired,
+    NonceUnexpectedBlockhashValue,
+}
+
+impl SystemError {
+    pub const ALL: &'static [SystemError] = &[
+        SystemError::AccountAlreadyInUse,
+        SystemError::ResultWithNegativeLamports,
+        SystemError::InvalidProgramId,
+        SystemError::InvalidAccountDataLength,
+        SystemError::MaxSeedLengthExceeded,
+        SystemError::AddressWithSeedMismatch,
+        SystemError::NonceNoRecentBlockhashes,
+        SystemError::NonceBlockhashNotExpired,
+        SystemError::NonceUnexpectedBlockhashValue,
+    ];
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SystemError::AccountAlreadyInUse => "AccountAlreadyInUse",
+            SystemError::ResultWithNegativeLamports => "ResultWithNegativeLamports",
+            SystemError::InvalidProgramId => "InvalidProgramId",
+            SystemError::InvalidAccountDataLength => "InvalidAccountDataLength",
+            SystemError::MaxSeedLengthExceeded => "MaxSeedLengthExceeded",
+            SystemError::AddressWithSeedMismatch => "AddressWithSeedMismatch",
+            SystemError::NonceNoRecentBlockhashes => "NonceNoRecentBlockhashes",
+            SystemError::NonceBlockhashNotExpired => "NonceBlockhashNotExpired",
+            SystemError::NonceUnexpectedBlockhashValue => "NonceUnexpectedBlockhashValue",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            SystemError::AccountAlreadyInUse => "an account with the same address already exists",
+            SystemError::ResultWithNegativeLamports => {
+                "account does not have enough SOL to perform the operation"
+            }
+            SystemError::InvalidProgramId => "cannot assign account to this program id",
+            SystemError::InvalidAccountDataLength => "cannot allocate account data of this length",
+            SystemError::MaxSeedLengthExceeded => "length of requested seed is too long",
+            SystemError::AddressWithSeedMismatch => {
+                "provided address does not match addressed derived from seed"
+            }
+            SystemError::NonceNoRecentBlockhashes => {
+                "advancing stored nonce requires a populated RecentBlockhashes sysvar"
+            }
+            SystemError::NonceBlockhashNotExpired => "stored nonce is still in recent_blockhashes",
+            SystemError::NonceUnexpectedBlockhashValue => {
+                "specified nonce does not match stored nonce"
+            }
+        }
+    }
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name(), self.code(), self.message())
+    }
+}
+
+impl std::error::Error for SystemError {}
+
+/// A custom error code, decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodedError {
+    Favorites(CustomError),
+    Anchor(AnchorError),
+    System(SystemError),
+    /// A code of another program, or one none of the above know.
+    Unknown {
+        program_id: Pubkey,
+        code: u32,
+    },
+}
+
+impl fmt::Display for DecodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedError::Favorites(error) => write!(f, "{error}"),
+            DecodedError::Anchor(error) => write!(f, "{error}"),
+            DecodedError::System(error) => write!(f, "{error}"),
+            DecodedError::Unknown { program_id, code } => {
+                write!(f, "custom program error {code:#x} from {program_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodedError {}
+
+/// Decodes `code`, returned by an instruction of `program_id`.
+pub fn decode_custom(program_id: &Pubkey, code: u32) -> DecodedError {
+    let decoded = if *program_id == system_program::ID {
+        SystemError::from_code(code).map(DecodedError::System)
+    } else if *program_id == crate::ID {
+        if code >= ERROR_CODE_OFFSET {
+            CustomError::from_code(code).map(DecodedError::Favorites)
+        } else if code >= 100 {
+            AnchorError::from_code(code).map(DecodedError::Anchor)
+        } else {
+            SystemError::from_code(code).map(DecodedError::System)
+        }
+    } else {
+        None
+    };
+    decoded.unwrap_or(DecodedError::Unknown {
+        program_id: *program_id,
+        code,
+    })
+}
+
+/// Decodes the error of a transaction made of `instructions`, if one of them
+/// failed with a custom error.
+pub fn decode_transaction_error(
+    error: &TransactionError,
+    instructions: &[Instruction],
+) -> Option<DecodedError> {
+    match error {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let instruction = instructions.get(*index as usize)?;
+            Some(decode_custom(&instruction.program_id, *code))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes the error of sending a transaction made of `instructions`, whether
+/// it failed in simulation or once processed.
+pub fn decode_client_error(
+    error: &ClientError,
+    instructions: &[Instruction],
+) -> Option<DecodedError> {
+    decode_transaction_error(&error.get_transaction_error()?, instructions)
+}
+
+/// What to tell the user when sending `instructions` failed: the decoded
+/// error if there is one, the client's error otherwise.
+pub fn describe_client_error(error: &ClientError, instructions: &[Instruction]) -> String {
+    match decode_client_error(error, instructions) {
+        Some(decoded) => decoded.to_string(),
+        None => error.to_string(),
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+
+// Anchor numbers a program's own error codes from here
+pub const ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Errors returned by the favorites program as `InstructionError::Custom(code)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CustomError {
+    Unauthorized,
+    ColorTooLong,
+    NotRegistered,
+    InvalidSignature,
+    NonceMismatch,
+    SignedUpdateExpired,
+    SessionExpired,
+    SessionExhausted,
+    TokenGateNotMet,
+    NotAnNft,
+    NotNftHolder,
+    BadgeUriTooLong,
+    ZeroTip,
+    NoTipsToWithdraw,
+    VotingDisabled,
+    InvalidProposal,
+    VotingClosed,
+    VotingNotOver,
+    InvalidChoice,
+    NoVotingPower,
+    AlreadyFinalized,
+    InvalidHandle,
+    InvalidFeeAccounts,
+    NoFeesToWithdraw,
+    PremiumUnavailable,
+    InvalidPremiumAccounts,
+    ZeroPremiumMonths,
+    PremiumRequired,
+    InvalidProfile,
+    SelfReferral,
+    ReferralCycle,
+    InvalidReferralAccounts,
+    InvalidRecoveryKey,
+    RecoveryInProgress,
+    NoRecoveryInProgress,
+    RecoveryLocked,
+    CollectionFull,
+    InvalidCollectionIndex,
+    NotMigrated,
+    AlreadyMigrated,
+    StaleProposal,
+    ReferrerWithoutFavorites,
+    CollectionNotGrown,
+    CollectionGrown,
+    SessionOutdated,
+}
+
+impl CustomError {
+    pub const ALL: &'static [CustomError] = &[
+        CustomError::Unauthorized,
+        CustomError::ColorTooLong,
+        CustomError::NotRegistered,
+        CustomError::InvalidSignature,
+        CustomError::NonceMismatch,
+        CustomError::SignedUpdateExpired,
+        CustomError::SessionExpired,
+        CustomError::SessionExhausted,
+        CustomError::TokenGateNotMet,
+        CustomError::NotAnNft,
+        CustomError::NotNftHolder,
+        CustomError::BadgeUriTooLong,
+        CustomError::ZeroTip,
+        CustomError::NoTipsToWithdraw,
+        CustomError::VotingDisabled,
+        CustomError::InvalidProposal,
+        CustomError::VotingClosed,
+        CustomError::VotingNotOver,
+        CustomError::InvalidChoice,
+        CustomError::NoVotingPower,
+        CustomError::AlreadyFinalized,
+        CustomError::InvalidHandle,
+        CustomError::InvalidFeeAccounts,
+        CustomError::NoFeesToWithdraw,
+        CustomError::PremiumUnavailable,
+        CustomError::InvalidPremiumAccounts,
+        CustomError::ZeroPremiumMonths,
+        CustomError::PremiumRequired,
+        CustomError::InvalidProfile,
+        CustomError::SelfReferral,
+        CustomError::ReferralCycle,
+        CustomError::InvalidReferralAccounts,
+        CustomError::InvalidRecoveryKey,
+        CustomError::RecoveryInProgress,
+        CustomError::NoRecoveryInProgress,
+        CustomError::RecoveryLocked,
+        CustomError::CollectionFull,
+        CustomError::InvalidCollectionIndex,
+        CustomError::NotMigrated,
+        CustomError::AlreadyMigrated,
+        CustomError::StaleProposal,
+        CustomError::ReferrerWithoutFavorites,
+        CustomError::CollectionNotGrown,
+        CustomError::CollectionGrown,
+        CustomError::SessionOutdated,
+    ];
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        let index = code.checked_sub(ERROR_CODE_OFFSET)?;
+        Self::ALL.get(index as usize).copied()
+    }
+
+    pub fn code(self) -> u32 {
+        ERROR_CODE_OFFSET + self as u32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CustomError::Unauthorized => "Unauthorized",
+            CustomError::ColorTooLong => "ColorTooLong",
+            CustomError::NotRegistered => "NotRegistered",
+            CustomError::InvalidSignature => "InvalidSignature",
+            CustomError::NonceMismatch => "NonceMismatch",
+            CustomError::SignedUpdateExpired => "SignedUpdateExpired",
+            CustomError::SessionExpired => "SessionExpired",
+            CustomError::SessionExhausted => "SessionExhausted",
+            CustomError::TokenGateNotMet => "TokenGateNotMet",
+            CustomError::NotAnNft => "NotAnNft",
+            CustomError::NotNftHolder => "NotNftHolder",
+            CustomError::BadgeUriTooLong => "BadgeUriTooLong",
+            CustomError::ZeroTip => "ZeroTip",
+            CustomError::NoTipsToWithdraw => "NoTipsToWithdraw",
+            CustomError::VotingDisabled => "VotingDisabled",
+            CustomError::InvalidProposal => "InvalidProposal",
+            CustomError::VotingClosed => "VotingClosed",
+            CustomError::VotingNotOver => "VotingNotOver",
+            CustomError::InvalidChoice => "InvalidChoice",
+            CustomError::NoVotingPower => "NoVotingPower",
+            CustomError::AlreadyFinalized => "AlreadyFinalized",
+            CustomError::InvalidHandle => "InvalidHandle",
+            CustomError::InvalidFeeAccounts => "InvalidFeeAccounts",
+            CustomError::NoFeesToWithdraw => "NoFeesToWithdraw",
+            CustomError::PremiumUnavailable => "PremiumUnavailable",
+            CustomError::InvalidPremiumAccounts => "InvalidPremiumAccounts",
+            CustomError::ZeroPremiumMonths => "ZeroPremiumMonths",
+            CustomError::PremiumRequired => "PremiumRequired",
+            CustomError::InvalidProfile => "InvalidProfile",
+            CustomError::SelfReferral => "SelfReferral",
+            CustomError::ReferralCycle => "ReferralCycle",
+            CustomError::InvalidReferralAccounts => "InvalidReferralAccounts",
+            CustomError::InvalidRecoveryKey => "InvalidRecoveryKey",
+            CustomError::RecoveryInProgress => "RecoveryInProgress",
+            CustomError::NoRecoveryInProgress => "NoRecoveryInProgress",
+            CustomError::RecoveryLocked => "RecoveryLocked",
+            CustomError::CollectionFull => "CollectionFull",
+            CustomError::InvalidCollectionIndex => "InvalidCollectionIndex",
+            CustomError::NotMigrated => "NotMigrated",
+            CustomError::AlreadyMigrated => "AlreadyMigrated",
+            CustomError::StaleProposal => "StaleProposal",
+            CustomError::ReferrerWithoutFavorites => "ReferrerWithoutFavorites",
+            CustomError::CollectionNotGrown => "CollectionNotGrown",
+            CustomError::CollectionGrown => "CollectionGrown",
+            CustomError::SessionOutdated => "SessionOutdated",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            CustomError::Unauthorized => "Only the authority or delegate can update this account.",
+            CustomError::ColorTooLong => "Favorite color is longer than the space reserved for it.",
+            CustomError::NotRegistered => "The user is missing from their registry page.",
+            CustomError::InvalidSignature => "Missing or invalid ed25519 signature of the owner.",
+            CustomError::NonceMismatch => "The signed update's nonce does not match the account's.",
+            CustomError::SignedUpdateExpired => "The signed update has expired.",
+            CustomError::SessionExpired => "The session key has expired.",
+            CustomError::SessionExhausted => "The session key has no uses left.",
+            CustomError::TokenGateNotMet => "The signer does not hold enough of the gating token.",
+            CustomError::NotAnNft => "The mint is not an NFT: one token, no decimals and no mint authority.",
+            CustomError::NotNftHolder => {
+                "The signer does not hold the NFT these favorites are bound to."
+            }
+            CustomError::BadgeUriTooLong => "The color does not fit in the badge's metadata URI.",
+            CustomError::ZeroTip => "A tip must be at least one lamport.",
+            CustomError::NoTipsToWithdraw => "The tip vault holds nothing above its rent.",
+            CustomError::VotingDisabled => "No vote mint is configured, so proposals are disabled.",
+            CustomError::InvalidProposal => {
+                "A proposal needs 2 to 4 colors that fit and a voting period of at least a day."
+            }
+            CustomError::VotingClosed => "Voting on this proposal has ended.",
+            CustomError::VotingNotOver => "Voting on this proposal has not ended yet.",
+            CustomError::InvalidChoice => "The proposal has no color with this index.",
+            CustomError::NoVotingPower => "The voter holds none of the vote mint.",
+            CustomError::AlreadyFinalized => "The proposal is already finalized.",
+            CustomError::InvalidHandle => {
+                "A handle is 3 to 32 lowercase letters, digits or underscores."
+            }
+            CustomError::InvalidFeeAccounts => {
+                "The fee's token accounts are missing or do not match the config."
+            }
+            CustomError::NoFeesToWithdraw => "The treasury holds no fees to withdraw.",
+            CustomError::PremiumUnavailable => {
+                "No premium mint is configured, so premium is not for sale."
+            }
+            CustomError::InvalidPremiumAccounts => {
+                "The payment's token accounts do not match the premium mint and treasury."
+            }
+            CustomError::ZeroPremiumMonths => "Premium is renewed for at least one month.",
+            CustomError::PremiumRequired => "This needs an active premium subscription.",
+            CustomError::InvalidProfile => "A profile index is below 8.",
+            CustomError::SelfReferral => "Users cannot refer themselves.",
+            CustomError::ReferralCycle => {
+                "The referrer was referred by this wallet, which would close a cycle."
+            }
+            CustomError::InvalidReferralAccounts => {
+                "The referrer, their referrals counter and their referral chain are passed together."
+            }
+            CustomError::InvalidRecoveryKey => "The recovery key cannot be the owner's own key.",
+            CustomError::RecoveryInProgress => {
+                "A recovery is in progress; the owner has to cancel it first."
+            }
+            CustomError::NoRecoveryInProgress => "No recovery of these favorites is in progress.",
+            CustomError::RecoveryLocked => "The recovery's timelock has not passed yet.",
+            CustomError::CollectionFull => "The collection holds as many items as it can.",
+            CustomError::InvalidCollectionIndex => "No item of the collection has this index.",
+            CustomError::NotMigrated => {
+                "These favorites predate the current layout; migrate them first."
+            }
+            CustomError::AlreadyMigrated => "These favorites are already in the current layout.",
+            CustomError::StaleProposal => "A newer proposal already decided the community color.",
+            CustomError::ReferrerWithoutFavorites => "Referrers must have favorites.",
+            CustomError::CollectionNotGrown => "The collection takes items once grow_collection allocated all of it.",
+            CustomError::CollectionGrown => "The collection is already allocated in full.",
+            CustomError::SessionOutdated => "The session was opened for favorites that have since moved or closed.",
+        }
+    }
+}
+
+impl fmt::Display for CustomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name(), self.code(), self.message())
+    }
+}
+
+impl std::error::Error for CustomError {}
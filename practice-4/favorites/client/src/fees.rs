@@ -0,0 +1,57 @@
+//! Fees: while the program's `Config` sets a fee amount, `set_favorites` and
+//! `update_favorites` charge it to their signer and pay it into the treasury
+//! PDA, unless the admin exempted the signer. Lamport fees go to the treasury
+//! itself, token fees to its associated token account of the fee mint.
+use solana_program::pubkey::Pubkey;
+
+use crate::gate::{associated_token_address, fetch_config, GateError};
+use crate::pda::{fee_exemption_address, treasury_address};
+use crate::registry::AccountSource;
+
+/// The fee accounts `set_favorites` and `update_favorites` take. The default
+/// fits a free config and lamport fees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeAccounts {
+    /// The payer's `FeeExemption`, which waives the fee.
+    pub exemption: Option<Pubkey>,
+    /// For token fees: the payer's token account of the fee mint, then the
+    /// treasury's.
+    pub token_accounts: Option<(Pubkey, Pubkey)>,
+}
+
+/// The treasury's associated token account of `mint`, where token fees go.
+pub fn treasury_token_address(mint: &Pubkey) -> Pubkey {
+    associated_token_address(&treasury_address().0, mint)
+}
+
+/// The fee accounts to pass for `payer`: their exemption when they have one,
+/// else the associated token accounts of the fee mint when fees are in tokens.
+pub fn fee_accounts(source: &impl AccountSource, payer: &Pubkey) -> Result<FeeAccounts, GateError> {
+    let Some(config) = fetch_config(source)? else {
+        return Ok(FeeAccounts::default());
+    };
+    if config.fee_amount == 0 {
+        return Ok(FeeAccounts::default());
+    }
+
+    let exemption = fee_exemption_address(payer).0;
+    if source
+        .account(&exemption)
+        .map_err(GateError::Rpc)?
+        .is_some()
+    {
+        return Ok(FeeAccounts {
+            exemption: Some(exemption),
+            token_accounts: None,
+        });
+    }
+    Ok(FeeAccounts {
+        exemption: None,
+        token_accounts: config.fee_mint.map(|mint| {
+            (
+                associated_token_address(payer, &mint),
+                treasury_token_address(&mint),
+            )
+        }),
+    })
+}
@@ -0,0 +1,89 @@
+//! Token gating: while the program's `Config` names a gate mint, creating and
+//! updating favorites needs a token account of that mint held by the signer.
+use std::fmt;
+
+use solana_client::client_error::ClientError;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::accounts::{Config, DecodeError};
+use crate::pda::config_address;
+use crate::registry::AccountSource;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// The associated token account of `owner` for `mint`.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Creates the associated token account of `owner` for `mint` unless it
+/// already exists, `payer` paying its rent.
+pub fn create_associated_token_account(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        // CreateIdempotent
+        data: vec![1],
+    }
+}
+
+/// The `Config` account, or `None` before `initialize_config`.
+pub fn fetch_config(source: &impl AccountSource) -> Result<Option<Config>, GateError> {
+    let address = config_address().0;
+    match source.account(&address).map_err(GateError::Rpc)? {
+        Some(account) => Config::try_from_bytes(&account.data)
+            .map(Some)
+            .map_err(|error| GateError::Decode(address, error)),
+        None => Ok(None),
+    }
+}
+
+/// The token account to pass for `holder`: their associated token account of
+/// the gate mint, or `None` while nothing is gated.
+pub fn gate_token_account(
+    source: &impl AccountSource,
+    holder: &Pubkey,
+) -> Result<Option<Pubkey>, GateError> {
+    Ok(fetch_config(source)?
+        .and_then(|config| config.gate_mint)
+        .map(|mint| associated_token_address(holder, &mint)))
+}
+
+#[derive(Debug)]
+pub enum GateError {
+    Rpc(Box<ClientError>),
+    Decode(Pubkey, DecodeError),
+}
+
+impl fmt::Display for GateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateError::Rpc(error) => write!(f, "getAccountInfo failed: {error}"),
+            GateError::Decode(address, error) => write!(f, "{address}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for GateError {}
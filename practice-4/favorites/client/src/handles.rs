@@ -0,0 +1,81 @@
+//! Handles: `@name` aliases for users, each a PDA seeded by the name that
+//! points at its owner. Resolving one takes a single `getAccountInfo`.
+use std::fmt;
+use std::str::FromStr;
+
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{DecodeError, Handle};
+use crate::pda::handle_address;
+use crate::registry::AccountSource;
+
+pub const MIN_HANDLE_LEN: usize = 3;
+pub const MAX_HANDLE_LEN: usize = 32;
+
+/// Whether the program accepts `handle`, given without its `@`: 3 to 32
+/// lowercase ASCII letters, digits and underscores.
+pub fn is_valid_handle(handle: &str) -> bool {
+    (MIN_HANDLE_LEN..=MAX_HANDLE_LEN).contains(&handle.len())
+        && handle
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+}
+
+/// The `Handle` account of `handle`, given with or without its `@`, or
+/// `None` while nobody holds it.
+pub fn fetch_handle(
+    source: &impl AccountSource,
+    handle: &str,
+) -> Result<Option<Handle>, HandleError> {
+    let handle = handle.strip_prefix('@').unwrap_or(handle);
+    if !is_valid_handle(handle) {
+        return Err(HandleError::Invalid(handle.to_string()));
+    }
+    let address = handle_address(handle).0;
+    match source.account(&address).map_err(HandleError::Rpc)? {
+        Some(account) => Handle::try_from_bytes(&account.data)
+            .map(Some)
+            .map_err(|error| HandleError::Decode(address, error)),
+        None => Ok(None),
+    }
+}
+
+/// Turns user input into an address: `@handle` resolves to the handle's
+/// owner, anything else must be a base58 pubkey.
+pub fn resolve(source: &impl AccountSource, input: &str) -> Result<Pubkey, HandleError> {
+    match input.strip_prefix('@') {
+        Some(handle) => fetch_handle(source, handle)?
+            .map(|handle| handle.owner)
+            .ok_or_else(|| HandleError::Unknown(handle.to_string())),
+        None => Pubkey::from_str(input).map_err(|_| HandleError::InvalidPubkey(input.to_string())),
+    }
+}
+
+#[derive(Debug)]
+pub enum HandleError {
+    Rpc(Box<ClientError>),
+    Decode(Pubkey, DecodeError),
+    Invalid(String),
+    Unknown(String),
+    InvalidPubkey(String),
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandleError::Rpc(error) => write!(f, "getAccountInfo failed: {error}"),
+            HandleError::Decode(address, error) => write!(f, "{address}: {error}"),
+            HandleError::Invalid(handle) => write!(
+                f,
+                "@{handle} is not a handle: use 3 to 32 lowercase letters, digits or underscores"
+            ),
+            HandleError::Unknown(handle) => write!(f, "Nobody holds @{handle}"),
+            HandleError::InvalidPubkey(input) => {
+                write!(f, "{input} is neither a pubkey nor an @handle")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HandleError {}
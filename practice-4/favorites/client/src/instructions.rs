@@ -0,0 +1,1219 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::accounts::collection_layout;
+use crate::fees::{treasury_token_address, FeeAccounts};
+use crate::gate::{associated_token_address, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID};
+
+use crate::pda::{
+    badge_authority_address, collection_address, community_color_address, config_address,
+    favorites_address, fee_exemption_address, handle_address, master_edition_address,
+    metadata_address, nft_favorites_address, profile_address, program_data_address,
+    proposal_address, recovery_address, referrals_address, registry_address, registry_page_address,
+    session_address, stats_address, tally_address, tip_vault_address, treasury_address,
+    vote_escrow_address, vote_receipt_address, TOKEN_METADATA_PROGRAM_ID,
+};
+
+pub const SET_FAVORITES_DISCRIMINATOR: [u8; 8] = [211, 137, 87, 135, 161, 224, 187, 120];
+pub const MIGRATE_FAVORITES_DISCRIMINATOR: [u8; 8] = [178, 116, 153, 0, 142, 17, 2, 79];
+pub const UPDATE_FAVORITES_DISCRIMINATOR: [u8; 8] = [138, 31, 158, 61, 111, 33, 209, 79];
+pub const SET_AUTHORITY_DISCRIMINATOR: [u8; 8] = [133, 250, 37, 21, 110, 163, 26, 121];
+pub const UPDATE_FAVORITES_SIGNED_DISCRIMINATOR: [u8; 8] = [24, 230, 106, 66, 164, 155, 57, 87];
+pub const CREATE_SESSION_DISCRIMINATOR: [u8; 8] = [242, 193, 143, 179, 150, 25, 122, 227];
+pub const REVOKE_SESSION_DISCRIMINATOR: [u8; 8] = [86, 92, 198, 120, 144, 2, 7, 194];
+pub const CLOSE_FAVORITES_DISCRIMINATOR: [u8; 8] = [222, 24, 77, 68, 254, 55, 20, 180];
+pub const INITIALIZE_CONFIG_DISCRIMINATOR: [u8; 8] = [208, 127, 21, 1, 194, 190, 196, 70];
+pub const SET_TOKEN_GATE_DISCRIMINATOR: [u8; 8] = [181, 246, 120, 133, 255, 105, 150, 113];
+pub const SET_NFT_FAVORITES_DISCRIMINATOR: [u8; 8] = [118, 206, 212, 241, 108, 185, 91, 194];
+pub const UPDATE_NFT_FAVORITES_DISCRIMINATOR: [u8; 8] = [99, 80, 67, 64, 111, 127, 6, 148];
+pub const MINT_FAVORITES_BADGE_DISCRIMINATOR: [u8; 8] = [132, 80, 120, 166, 115, 7, 53, 53];
+pub const TIP_DISCRIMINATOR: [u8; 8] = [77, 164, 35, 21, 36, 121, 213, 51];
+pub const WITHDRAW_TIPS_DISCRIMINATOR: [u8; 8] = [107, 192, 228, 68, 165, 120, 164, 23];
+pub const REGISTER_HANDLE_DISCRIMINATOR: [u8; 8] = [15, 173, 21, 158, 125, 204, 221, 29];
+pub const TRANSFER_HANDLE_DISCRIMINATOR: [u8; 8] = [205, 82, 18, 134, 165, 126, 5, 18];
+pub const RELEASE_HANDLE_DISCRIMINATOR: [u8; 8] = [19, 58, 205, 41, 216, 105, 195, 14];
+pub const SET_VOTE_MINT_DISCRIMINATOR: [u8; 8] = [65, 224, 97, 135, 191, 24, 163, 103];
+pub const CREATE_PROPOSAL_DISCRIMINATOR: [u8; 8] = [132, 116, 68, 174, 216, 160, 198, 22];
+pub const VOTE_DISCRIMINATOR: [u8; 8] = [227, 110, 155, 23, 136, 126, 172, 25];
+pub const FINALIZE_PROPOSAL_DISCRIMINATOR: [u8; 8] = [23, 68, 51, 167, 109, 173, 187, 164];
+pub const RECLAIM_VOTE_DISCRIMINATOR: [u8; 8] = [54, 249, 247, 137, 93, 95, 134, 91];
+pub const TRANSFER_FAVORITES_DISCRIMINATOR: [u8; 8] = [7, 165, 204, 41, 38, 203, 44, 96];
+pub const SET_FEE_DISCRIMINATOR: [u8; 8] = [18, 154, 24, 18, 237, 214, 19, 80];
+pub const ADD_FEE_EXEMPTION_DISCRIMINATOR: [u8; 8] = [189, 238, 101, 182, 238, 47, 93, 30];
+pub const REMOVE_FEE_EXEMPTION_DISCRIMINATOR: [u8; 8] = [158, 59, 24, 139, 29, 141, 63, 15];
+pub const WITHDRAW_FEES_DISCRIMINATOR: [u8; 8] = [198, 212, 171, 109, 144, 215, 174, 89];
+pub const WITHDRAW_FEE_TOKENS_DISCRIMINATOR: [u8; 8] = [130, 83, 245, 243, 236, 40, 197, 229];
+pub const SET_PREMIUM_DISCRIMINATOR: [u8; 8] = [186, 59, 94, 138, 221, 232, 140, 172];
+pub const RENEW_PREMIUM_DISCRIMINATOR: [u8; 8] = [9, 211, 219, 80, 193, 110, 192, 249];
+pub const CANCEL_PREMIUM_DISCRIMINATOR: [u8; 8] = [17, 3, 155, 171, 248, 91, 62, 233];
+pub const SET_PROFILE_DISCRIMINATOR: [u8; 8] = [221, 221, 195, 121, 133, 71, 113, 170];
+pub const CLOSE_PROFILE_DISCRIMINATOR: [u8; 8] = [167, 36, 181, 8, 136, 158, 46, 207];
+pub const SET_RECOVERY_KEY_DISCRIMINATOR: [u8; 8] = [230, 42, 236, 185, 16, 53, 66, 186];
+pub const REMOVE_RECOVERY_KEY_DISCRIMINATOR: [u8; 8] = [199, 53, 61, 195, 95, 7, 152, 142];
+pub const START_RECOVERY_DISCRIMINATOR: [u8; 8] = [161, 236, 250, 55, 152, 23, 28, 30];
+pub const CANCEL_RECOVERY_DISCRIMINATOR: [u8; 8] = [176, 23, 203, 37, 121, 251, 227, 83];
+pub const COMPLETE_RECOVERY_DISCRIMINATOR: [u8; 8] = [195, 236, 5, 5, 131, 32, 196, 110];
+pub const CREATE_COLLECTION_DISCRIMINATOR: [u8; 8] = [156, 251, 92, 54, 233, 2, 16, 82];
+pub const GROW_COLLECTION_DISCRIMINATOR: [u8; 8] = [150, 244, 114, 81, 132, 114, 52, 26];
+pub const APPEND_COLLECTION_ITEM_DISCRIMINATOR: [u8; 8] = [193, 143, 88, 190, 202, 77, 207, 31];
+pub const UPDATE_COLLECTION_ITEM_DISCRIMINATOR: [u8; 8] = [213, 169, 109, 115, 237, 240, 178, 214];
+pub const REMOVE_COLLECTION_ITEM_DISCRIMINATOR: [u8; 8] = [220, 59, 244, 69, 248, 98, 61, 174];
+pub const CLOSE_COLLECTION_DISCRIMINATOR: [u8; 8] = [18, 123, 173, 247, 218, 117, 230, 105];
+
+#[derive(BorshSerialize)]
+struct FavoritesArgs<'a> {
+    number: u64,
+    color: &'a str,
+}
+
+#[derive(BorshSerialize)]
+struct SignedFavoritesArgs<'a> {
+    number: u64,
+    color: &'a str,
+    nonce: u64,
+    expiry: i64,
+}
+
+#[derive(BorshSerialize)]
+struct CreateSessionArgs {
+    session_key: Pubkey,
+    max_uses: u32,
+    expiry: i64,
+}
+
+#[derive(BorshSerialize)]
+struct TokenGateArgs {
+    gate_mint: Option<Pubkey>,
+    gate_min_amount: u64,
+}
+
+#[derive(BorshSerialize)]
+struct HandleArgs<'a> {
+    handle: &'a str,
+}
+
+#[derive(BorshSerialize)]
+struct VoteMintArgs {
+    vote_mint: Option<Pubkey>,
+}
+
+#[derive(BorshSerialize)]
+struct CreateProposalArgs<'a> {
+    id: u64,
+    colors: &'a [String],
+    voting_period: i64,
+}
+
+#[derive(BorshSerialize)]
+struct VoteArgs {
+    choice: u8,
+}
+
+#[derive(BorshSerialize)]
+struct FeeArgs {
+    fee_mint: Option<Pubkey>,
+    fee_amount: u64,
+}
+
+#[derive(BorshSerialize)]
+struct PremiumArgs {
+    premium_mint: Option<Pubkey>,
+    premium_price: u64,
+}
+
+#[derive(BorshSerialize)]
+struct RenewPremiumArgs {
+    months: u8,
+}
+
+#[derive(BorshSerialize)]
+struct ProfileArgs<'a> {
+    index: u8,
+    number: u64,
+    color: &'a str,
+}
+
+#[derive(BorshSerialize)]
+struct ProfileIndexArgs {
+    index: u8,
+}
+
+#[derive(BorshSerialize)]
+struct TipArgs {
+    amount: u64,
+}
+
+#[derive(BorshSerialize)]
+struct SetRecoveryKeyArgs {
+    recovery_key: Pubkey,
+}
+
+#[derive(BorshSerialize)]
+struct StartRecoveryArgs {
+    new_owner: Pubkey,
+}
+
+#[derive(BorshSerialize)]
+struct CollectionItemArgs<'a> {
+    number: u64,
+    color: &'a str,
+}
+
+#[derive(BorshSerialize)]
+struct CollectionIndexArgs {
+    index: u32,
+}
+
+#[derive(BorshSerialize)]
+struct UpdateCollectionItemArgs<'a> {
+    index: u32,
+    number: u64,
+    color: &'a str,
+}
+
+#[derive(BorshSerialize)]
+struct SetAuthorityArgs {
+    delegate: Option<Pubkey>,
+}
+
+fn data(discriminator: [u8; 8], args: impl BorshSerialize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data)
+        .expect("serializing into a Vec never fails");
+    data
+}
+
+/// Creates `user`'s favorites account and lists `user` in the registry.
+/// `user` signs and pays for both. `open_page` is `Registry::open_page`, or 0
+/// while the registry does not exist yet. `gate_token_account` is `user`'s
+/// token account of the gate mint, see [`crate::gate`], and `fee` how `user`
+/// pays the fee, see [`crate::fees`]. `referrers` starts with the wallet that
+/// brought `user`, see [`crate::referrals`]; `user` also pays for the
+/// referrer's counter when it is the first.
+pub fn set_favorites(
+    user: &Pubkey,
+    open_page: u32,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+    referrers: &[Pubkey],
+    number: u64,
+    color: &str,
+) -> Instruction {
+    let [config, gate_token_account] = gate_accounts(gate_token_account);
+    let placeholder = AccountMeta::new_readonly(crate::ID, false);
+    let referral = match referrers.first().copied() {
+        Some(referrer) => [
+            AccountMeta::new_readonly(referrer, false),
+            AccountMeta::new(referrals_address(&referrer).0, false),
+        ],
+        None => [placeholder.clone(), placeholder],
+    };
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(registry_address().0, false),
+            AccountMeta::new(registry_page_address(open_page).0, false),
+            AccountMeta::new(stats_address().0, false),
+            config,
+            gate_token_account,
+        ]
+        .into_iter()
+        .chain(fee_metas(fee))
+        .chain(referral)
+        .chain([AccountMeta::new_readonly(system_program::ID, false)])
+        .chain(referral_chain(referrers))
+        .collect(),
+        data: data(SET_FAVORITES_DISCRIMINATOR, FavoritesArgs { number, color }),
+    }
+}
+
+/// Brings `user`'s favorites from before the version byte to the current
+/// layout and lists `user` in the registry. `user` signs and pays for the
+/// account to grow. `open_page` is as for [`set_favorites`].
+pub fn migrate_favorites(user: &Pubkey, open_page: u32) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(registry_address().0, false),
+            AccountMeta::new(registry_page_address(open_page).0, false),
+            AccountMeta::new(stats_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: MIGRATE_FAVORITES_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Overwrites `user`'s number and color. `signer` is the owner or the delegate,
+/// `gate_token_account` their token account of the gate mint and `fee` how
+/// they pay the fee.
+pub fn update_favorites(
+    user: &Pubkey,
+    signer: &Pubkey,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: update_accounts(user, signer, None, gate_token_account, fee),
+        data: data(
+            UPDATE_FAVORITES_DISCRIMINATOR,
+            FavoritesArgs { number, color },
+        ),
+    }
+}
+
+/// Overwrites `user`'s number and color, signed by `session_key` and using up
+/// one of its session's uses. The session key pays the fee.
+pub fn update_favorites_with_session(
+    user: &Pubkey,
+    session_key: &Pubkey,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: update_accounts(
+            user,
+            session_key,
+            Some(session_address(user, session_key).0),
+            gate_token_account,
+            fee,
+        ),
+        data: data(
+            UPDATE_FAVORITES_DISCRIMINATOR,
+            FavoritesArgs { number, color },
+        ),
+    }
+}
+
+/// Applies an update `user` signed off-chain. Only valid right after the
+/// Ed25519 instruction verifying that signature, see [`crate::signed`].
+/// `relayer` submits it, paying the fee as `fee` says. The gate applies to
+/// `user`.
+#[allow(clippy::too_many_arguments)]
+pub fn update_favorites_signed(
+    user: &Pubkey,
+    relayer: &Pubkey,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+    number: u64,
+    color: &str,
+    nonce: u64,
+    expiry: i64,
+) -> Instruction {
+    let [config, gate_token_account] = gate_accounts(gate_token_account);
+    Instruction {
+        program_id: crate::ID,
+        accounts: [
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(*relayer, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(stats_address().0, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            config,
+            gate_token_account,
+        ]
+        .into_iter()
+        .chain(fee_metas(fee))
+        .chain([AccountMeta::new_readonly(system_program::ID, false)])
+        .collect(),
+        data: data(
+            UPDATE_FAVORITES_SIGNED_DISCRIMINATOR,
+            SignedFavoritesArgs {
+                number,
+                color,
+                nonce,
+                expiry,
+            },
+        ),
+    }
+}
+
+/// Sets or, with `None`, removes the delegate of `user`'s favorites. Only
+/// `user` may sign.
+pub fn set_authority(user: &Pubkey, delegate: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+        ],
+        data: data(SET_AUTHORITY_DISCRIMINATOR, SetAuthorityArgs { delegate }),
+    }
+}
+
+/// Sends `amount` lamports from `tipper` to `user`'s tip vault. The first
+/// tipper of a user also pays the vault's rent.
+pub fn tip(tipper: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*tipper, true),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(tip_vault_address(user).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(TIP_DISCRIMINATOR, TipArgs { amount }),
+    }
+}
+
+/// Moves everything above rent from `user`'s tip vault to `user`.
+pub fn withdraw_tips(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(tip_vault_address(user).0, false),
+        ],
+        data: WITHDRAW_TIPS_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Mints `user` a 1-of-1 badge NFT of their current favorites into their
+/// associated token account. `badge_mint` is a fresh keypair that signs too.
+pub fn mint_favorites_badge(user: &Pubkey, badge_mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(*badge_mint, true),
+            AccountMeta::new(associated_token_address(user, badge_mint), false),
+            AccountMeta::new_readonly(badge_authority_address().0, false),
+            AccountMeta::new(metadata_address(badge_mint), false),
+            AccountMeta::new(master_edition_address(badge_mint), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_METADATA_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: MINT_FAVORITES_BADGE_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Creates the favorites bound to the NFT `mint`. `holder` signs, pays and
+/// must own `holder_token_account`, the token account holding the NFT.
+pub fn set_nft_favorites(
+    holder: &Pubkey,
+    mint: &Pubkey,
+    holder_token_account: &Pubkey,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*holder, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*holder_token_account, false),
+            AccountMeta::new(nft_favorites_address(mint).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            SET_NFT_FAVORITES_DISCRIMINATOR,
+            FavoritesArgs { number, color },
+        ),
+    }
+}
+
+/// Overwrites the favorites bound to the NFT `mint`, signed by its current
+/// holder.
+pub fn update_nft_favorites(
+    holder: &Pubkey,
+    mint: &Pubkey,
+    holder_token_account: &Pubkey,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*holder, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*holder_token_account, false),
+            AccountMeta::new(nft_favorites_address(mint).0, false),
+        ],
+        data: data(
+            UPDATE_NFT_FAVORITES_DISCRIMINATOR,
+            FavoritesArgs { number, color },
+        ),
+    }
+}
+
+/// Creates the program config with `admin` as its admin, gating on holding
+/// `gate_min_amount` of `gate_mint` when set. `admin` has to be the program's
+/// upgrade authority, and only the first call succeeds.
+pub fn initialize_config(
+    admin: &Pubkey,
+    gate_mint: Option<Pubkey>,
+    gate_min_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address().0, false),
+            AccountMeta::new_readonly(program_data_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            INITIALIZE_CONFIG_DISCRIMINATOR,
+            TokenGateArgs {
+                gate_mint,
+                gate_min_amount,
+            },
+        ),
+    }
+}
+
+/// Changes the token gate, or turns it off with `None`. Only the config's
+/// admin may sign.
+pub fn set_token_gate(
+    admin: &Pubkey,
+    gate_mint: Option<Pubkey>,
+    gate_min_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_address().0, false),
+        ],
+        data: data(
+            SET_TOKEN_GATE_DISCRIMINATOR,
+            TokenGateArgs {
+                gate_mint,
+                gate_min_amount,
+            },
+        ),
+    }
+}
+
+/// Lets `session_key` update `user`'s favorites `max_uses` times until the
+/// unix timestamp `expiry`, for as long as these favorites last: not after
+/// they move or close. `user` signs and pays for the session account.
+pub fn create_session(
+    user: &Pubkey,
+    session_key: &Pubkey,
+    max_uses: u32,
+    expiry: i64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(session_address(user, session_key).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            CREATE_SESSION_DISCRIMINATOR,
+            CreateSessionArgs {
+                session_key: *session_key,
+                max_uses,
+                expiry,
+            },
+        ),
+    }
+}
+
+/// Revokes `session_key` and refunds the session's rent to `user`.
+pub fn revoke_session(user: &Pubkey, session_key: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(session_address(user, session_key).0, false),
+        ],
+        data: REVOKE_SESSION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Closes `user`'s favorites account, refunds its rent to `user` and removes
+/// `user` from `registry_page`, the page recorded in `Favorites`. `referrer`,
+/// also recorded there, gets one referral less. `user`'s recovery key is
+/// removed and the sessions of `session_keys` revoked, their rent going to
+/// `user` too.
+pub fn close_favorites(
+    user: &Pubkey,
+    registry_page: u32,
+    referrer: Option<Pubkey>,
+    session_keys: &[Pubkey],
+) -> Instruction {
+    let referrals = match referrer {
+        Some(referrer) => AccountMeta::new(referrals_address(&referrer).0, false),
+        None => AccountMeta::new_readonly(crate::ID, false),
+    };
+    Instruction {
+        program_id: crate::ID,
+        accounts: [
+            AccountMeta::new(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(registry_address().0, false),
+            AccountMeta::new(registry_page_address(registry_page).0, false),
+            AccountMeta::new(stats_address().0, false),
+            referrals,
+            AccountMeta::new(recovery_address(user).0, false),
+        ]
+        .into_iter()
+        .chain(sessions(user, session_keys))
+        .collect(),
+        data: CLOSE_FAVORITES_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Moves `user`'s favorites to `new_owner`, who must not have favorites yet.
+/// Both sign; `new_owner` pays the new account's rent and `user` gets the old
+/// one's back. `registry_page` is the page recorded in `Favorites` and
+/// `referrers` the chain of its referrer, see [`crate::referrals`], which
+/// `new_owner` must not be part of. `user`'s recovery key is removed and the
+/// sessions of `session_keys` revoked, their rent going to `user` too.
+pub fn transfer_favorites(
+    user: &Pubkey,
+    new_owner: &Pubkey,
+    registry_page: u32,
+    referrers: &[Pubkey],
+    session_keys: &[Pubkey],
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: [
+            AccountMeta::new(*user, true),
+            AccountMeta::new(*new_owner, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(favorites_address(new_owner).0, false),
+            AccountMeta::new(recovery_address(user).0, false),
+            AccountMeta::new(registry_page_address(registry_page).0, false),
+            AccountMeta::new(stats_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ]
+        .into_iter()
+        .chain(referral_chain(referrers))
+        .chain(sessions(user, session_keys))
+        .collect(),
+        data: TRANSFER_FAVORITES_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Makes `recovery_key` the key that may move `user`'s favorites after
+/// [`RECOVERY_TIMELOCK`](crate::accounts::RECOVERY_TIMELOCK), replacing any previous one. `user` must have favorites and pays for
+/// the `Recovery` account the first time.
+pub fn set_recovery_key(user: &Pubkey, recovery_key: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(recovery_address(user).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            SET_RECOVERY_KEY_DISCRIMINATOR,
+            SetRecoveryKeyArgs {
+                recovery_key: *recovery_key,
+            },
+        ),
+    }
+}
+
+/// Removes `user`'s recovery key and refunds the `Recovery` rent to `user`.
+/// Fails while a recovery is in progress; cancel it first.
+pub fn remove_recovery_key(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(recovery_address(user).0, false),
+        ],
+        data: REMOVE_RECOVERY_KEY_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Starts moving `user`'s favorites to `new_owner`, signed by `user`'s
+/// recovery key. [`complete_recovery`] works once [`RECOVERY_TIMELOCK`](crate::accounts::RECOVERY_TIMELOCK) has passed, unless
+/// `user` cancels first.
+pub fn start_recovery(recovery_key: &Pubkey, user: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*recovery_key, true),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(recovery_address(user).0, false),
+        ],
+        data: data(
+            START_RECOVERY_DISCRIMINATOR,
+            StartRecoveryArgs {
+                new_owner: *new_owner,
+            },
+        ),
+    }
+}
+
+/// Cancels the recovery in progress on `user`'s favorites. The recovery key
+/// stays set.
+pub fn cancel_recovery(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new(recovery_address(user).0, false),
+        ],
+        data: CANCEL_RECOVERY_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Moves `user`'s favorites to the `new_owner` named by [`start_recovery`],
+/// signed by `new_owner`. They pay the new account's rent and get the old
+/// one's and the `Recovery`'s back, along with that of the `user` sessions
+/// of `session_keys`, which are revoked. `registry_page` and `referrers` are
+/// as for [`transfer_favorites`].
+pub fn complete_recovery(
+    user: &Pubkey,
+    new_owner: &Pubkey,
+    registry_page: u32,
+    referrers: &[Pubkey],
+    session_keys: &[Pubkey],
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: [
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(*new_owner, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new(favorites_address(new_owner).0, false),
+            AccountMeta::new(recovery_address(user).0, false),
+            AccountMeta::new(registry_page_address(registry_page).0, false),
+            AccountMeta::new(stats_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ]
+        .into_iter()
+        .chain(referral_chain(referrers))
+        .chain(sessions(user, session_keys))
+        .collect(),
+        data: COMPLETE_RECOVERY_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Creates `user`'s empty `Collection`, paid for by `user`, who must have
+/// favorites. It holds up to
+/// [`collection_layout::CAPACITY`](crate::accounts::collection_layout::CAPACITY)
+/// items, more than one instruction can allocate, so it only takes them after
+/// [`grow_collection`]. See [`create_full_collection`].
+pub fn create_collection(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(collection_address(user).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: CREATE_COLLECTION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Allocates up to 10 KiB more of `user`'s collection, paid for by `user`.
+pub fn grow_collection(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(collection_address(user).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: GROW_COLLECTION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// [`create_collection`] followed by as many [`grow_collection`] as it takes
+/// for the collection to take items, all of which fit in one transaction.
+pub fn create_full_collection(user: &Pubkey) -> Vec<Instruction> {
+    let grows = collection_layout::ACCOUNT_SIZE.div_ceil(MAX_PERMITTED_DATA_INCREASE) - 1;
+    std::iter::once(create_collection(user))
+        .chain(std::iter::repeat_with(|| grow_collection(user)).take(grows))
+        .collect()
+}
+
+fn modify_collection_accounts(user: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*user, true),
+        AccountMeta::new(collection_address(user).0, false),
+    ]
+}
+
+/// Adds an item after the last one of `user`'s collection.
+pub fn append_collection_item(user: &Pubkey, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: modify_collection_accounts(user),
+        data: data(
+            APPEND_COLLECTION_ITEM_DISCRIMINATOR,
+            CollectionItemArgs { number, color },
+        ),
+    }
+}
+
+/// Replaces the item at `index` of `user`'s collection.
+pub fn update_collection_item(user: &Pubkey, index: u32, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: modify_collection_accounts(user),
+        data: data(
+            UPDATE_COLLECTION_ITEM_DISCRIMINATOR,
+            UpdateCollectionItemArgs {
+                index,
+                number,
+                color,
+            },
+        ),
+    }
+}
+
+/// Removes the item at `index` of `user`'s collection. The items after it
+/// move down one index.
+pub fn remove_collection_item(user: &Pubkey, index: u32) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: modify_collection_accounts(user),
+        data: data(
+            REMOVE_COLLECTION_ITEM_DISCRIMINATOR,
+            CollectionIndexArgs { index },
+        ),
+    }
+}
+
+/// Deletes `user`'s collection, items and all, and refunds its rent to `user`.
+pub fn close_collection(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(collection_address(user).0, false),
+        ],
+        data: CLOSE_COLLECTION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+fn update_accounts(
+    user: &Pubkey,
+    signer: &Pubkey,
+    session: Option<Pubkey>,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+) -> Vec<AccountMeta> {
+    let [config, gate_token_account] = gate_accounts(gate_token_account);
+    vec![
+        AccountMeta::new_readonly(*user, false),
+        AccountMeta::new(*signer, true),
+        AccountMeta::new(favorites_address(user).0, false),
+        AccountMeta::new(stats_address().0, false),
+        // Anchor reads the program's own address as "no session"
+        match session {
+            Some(session) => AccountMeta::new(session, false),
+            None => AccountMeta::new_readonly(crate::ID, false),
+        },
+        config,
+        gate_token_account,
+    ]
+    .into_iter()
+    .chain(fee_metas(fee))
+    .chain([AccountMeta::new_readonly(system_program::ID, false)])
+    .collect()
+}
+
+// The config is always passed, so the program sees whether gating is on
+fn gate_accounts(gate_token_account: Option<Pubkey>) -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(config_address().0, false),
+        AccountMeta::new_readonly(gate_token_account.unwrap_or(crate::ID), false),
+    ]
+}
+
+// The fee accounts following the gate token account, the program's address
+// standing in for those left out
+fn fee_metas(fee: FeeAccounts) -> [AccountMeta; 5] {
+    let placeholder = AccountMeta::new_readonly(crate::ID, false);
+    let exemption = fee.exemption.map_or(placeholder.clone(), |exemption| {
+        AccountMeta::new_readonly(exemption, false)
+    });
+    let treasury = AccountMeta::new(treasury_address().0, false);
+    match fee.token_accounts {
+        Some((fee_token_account, treasury_token_account)) => [
+            exemption,
+            treasury,
+            AccountMeta::new(fee_token_account, false),
+            AccountMeta::new(treasury_token_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        None => [
+            exemption,
+            treasury,
+            placeholder.clone(),
+            placeholder.clone(),
+            placeholder,
+        ],
+    }
+}
+
+// The favorites of each of `referrers`, passed after the named accounts for
+// the program to follow the referral chain
+fn referral_chain(referrers: &[Pubkey]) -> impl Iterator<Item = AccountMeta> + '_ {
+    referrers
+        .iter()
+        .map(|referrer| AccountMeta::new_readonly(favorites_address(referrer).0, false))
+}
+
+// The sessions of `user` to close, passed after the referral chain
+fn sessions<'a>(
+    user: &'a Pubkey,
+    session_keys: &'a [Pubkey],
+) -> impl Iterator<Item = AccountMeta> + 'a {
+    session_keys
+        .iter()
+        .map(|session_key| AccountMeta::new(session_address(user, session_key).0, false))
+}
+
+/// Registers `handle`, without its leading `@`, for `owner`, who must have
+/// favorites. See [`crate::handles::is_valid_handle`] for the format.
+pub fn register_handle(owner: &Pubkey, handle: &str) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(favorites_address(owner).0, false),
+            AccountMeta::new(handle_address(handle).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(REGISTER_HANDLE_DISCRIMINATOR, HandleArgs { handle }),
+    }
+}
+
+/// Hands `handle` over from `owner` to `new_owner`, who must have favorites.
+pub fn transfer_handle(owner: &Pubkey, handle: &str, new_owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(handle_address(handle).0, false),
+            AccountMeta::new_readonly(*new_owner, false),
+            AccountMeta::new_readonly(favorites_address(new_owner).0, false),
+        ],
+        data: TRANSFER_HANDLE_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Frees `handle` and returns its rent to `owner`.
+pub fn release_handle(owner: &Pubkey, handle: &str) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(handle_address(handle).0, false),
+        ],
+        data: RELEASE_HANDLE_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Sets the mint weighing community color votes, or disables new proposals
+/// with `None`. Only the config admin may sign.
+pub fn set_vote_mint(admin: &Pubkey, vote_mint: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_address().0, false),
+        ],
+        data: data(SET_VOTE_MINT_DISCRIMINATOR, VoteMintArgs { vote_mint }),
+    }
+}
+
+/// Charges `fee_amount` lamports, or base units of `fee_mint` when set, on
+/// every `set_favorites` and `update_favorites`; zero makes them free. Only the
+/// config admin may sign, and the first call pays the treasury's rent.
+pub fn set_fee(admin: &Pubkey, fee_mint: Option<Pubkey>, fee_amount: u64) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address().0, false),
+            AccountMeta::new(treasury_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            SET_FEE_DISCRIMINATOR,
+            FeeArgs {
+                fee_mint,
+                fee_amount,
+            },
+        ),
+    }
+}
+
+/// Exempts `user` from fees. Only the config admin may sign, paying the
+/// exemption's rent.
+pub fn add_fee_exemption(admin: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(fee_exemption_address(user).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ADD_FEE_EXEMPTION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Charges `user` fees again, refunding the exemption's rent to `admin`.
+pub fn remove_fee_exemption(admin: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(fee_exemption_address(user).0, false),
+        ],
+        data: REMOVE_FEE_EXEMPTION_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Moves the treasury's lamports above rent to `admin`.
+pub fn withdraw_fees(admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new(treasury_address().0, false),
+        ],
+        data: WITHDRAW_FEES_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Moves every token the treasury collected of `mint` to `destination`, any
+/// token account of that mint. Only the config admin may sign.
+pub fn withdraw_fee_tokens(admin: &Pubkey, mint: &Pubkey, destination: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new_readonly(treasury_address().0, false),
+            AccountMeta::new(treasury_token_address(mint), false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: WITHDRAW_FEE_TOKENS_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Sells premium at `premium_price` base units of `premium_mint` a month, or
+/// stops selling it with `None`. Only the config admin may sign, and the first
+/// call pays the treasury's rent.
+pub fn set_premium(
+    admin: &Pubkey,
+    premium_mint: Option<Pubkey>,
+    premium_price: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_address().0, false),
+            AccountMeta::new(treasury_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            SET_PREMIUM_DISCRIMINATOR,
+            PremiumArgs {
+                premium_mint,
+                premium_price,
+            },
+        ),
+    }
+}
+
+/// Buys `user` `months` of premium, paid from `payment_token_account` into
+/// `treasury_token_account`, see [`crate::premium::premium_token_accounts`].
+/// Renewing early adds to the time left.
+pub fn renew_premium(
+    user: &Pubkey,
+    payment_token_account: &Pubkey,
+    treasury_token_account: &Pubkey,
+    months: u8,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new_readonly(treasury_address().0, false),
+            AccountMeta::new(*payment_token_account, false),
+            AccountMeta::new(*treasury_token_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: data(RENEW_PREMIUM_DISCRIMINATOR, RenewPremiumArgs { months }),
+    }
+}
+
+/// Ends `user`'s premium now, without refunding the time left.
+pub fn cancel_premium(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new(favorites_address(user).0, false),
+        ],
+        data: CANCEL_PREMIUM_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Sets `user`'s profile `index`, creating it at `user`'s expense when
+/// needed. `user` must have favorites; indexes from
+/// [`crate::premium::FREE_PROFILES`] on need premium.
+pub fn set_profile(user: &Pubkey, index: u8, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(favorites_address(user).0, false),
+            AccountMeta::new(profile_address(user, index).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            SET_PROFILE_DISCRIMINATOR,
+            ProfileArgs {
+                index,
+                number,
+                color,
+            },
+        ),
+    }
+}
+
+/// Closes `user`'s profile `index` and refunds its rent to `user`.
+pub fn close_profile(user: &Pubkey, index: u8) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(profile_address(user, index).0, false),
+        ],
+        data: data(CLOSE_PROFILE_DISCRIMINATOR, ProfileIndexArgs { index }),
+    }
+}
+
+/// Opens proposal `id` of `proposer` between 2 to 4 `colors`, voting for
+/// `voting_period` seconds, at least a day. `vote_mint` must be the config's
+/// vote mint.
+pub fn create_proposal(
+    proposer: &Pubkey,
+    vote_mint: &Pubkey,
+    id: u64,
+    colors: &[String],
+    voting_period: i64,
+) -> Instruction {
+    let proposal = proposal_address(proposer, id).0;
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(config_address().0, false),
+            AccountMeta::new_readonly(*vote_mint, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new(tally_address(&proposal).0, false),
+            AccountMeta::new(vote_escrow_address(&proposal).0, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(
+            CREATE_PROPOSAL_DISCRIMINATOR,
+            CreateProposalArgs {
+                id,
+                colors,
+                voting_period,
+            },
+        ),
+    }
+}
+
+/// Votes for color `choice` of `proposal` with the whole balance of
+/// `voter_token_account`, which stays locked until voting ends.
+pub fn vote(
+    voter: &Pubkey,
+    proposal: &Pubkey,
+    voter_token_account: &Pubkey,
+    choice: u8,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*voter, true),
+            AccountMeta::new_readonly(*proposal, false),
+            AccountMeta::new(tally_address(proposal).0, false),
+            AccountMeta::new(vote_receipt_address(proposal, voter).0, false),
+            AccountMeta::new(*voter_token_account, false),
+            AccountMeta::new(vote_escrow_address(proposal).0, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: data(VOTE_DISCRIMINATOR, VoteArgs { choice }),
+    }
+}
+
+/// Closes voting on `proposal` once it has ended and records the winning
+/// color. Anyone may sign; `payer` funds the community color account the
+/// first time.
+pub fn finalize_proposal(payer: &Pubkey, proposal: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(tally_address(proposal).0, false),
+            AccountMeta::new(community_color_address().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: FINALIZE_PROPOSAL_DISCRIMINATOR.to_vec(),
+    }
+}
+
+/// Returns `voter`'s locked tokens to `voter_token_account` once voting on
+/// `proposal` has ended.
+pub fn reclaim_vote(
+    voter: &Pubkey,
+    proposal: &Pubkey,
+    voter_token_account: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*voter, true),
+            AccountMeta::new_readonly(*proposal, false),
+            AccountMeta::new(vote_receipt_address(proposal, voter).0, false),
+            AccountMeta::new(*voter_token_account, false),
+            AccountMeta::new(vote_escrow_address(proposal).0, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: RECLAIM_VOTE_DISCRIMINATOR.to_vec(),
+    }
+}
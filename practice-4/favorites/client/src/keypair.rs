@@ -0,0 +1,20 @@
+//! Wallets of the favorites CLI and deploy tool.
+use dotenv::dotenv;
+use solana_keypair::Keypair;
+use std::env;
+
+/// Loads the keypair whose base58 secret key is in env variable `env_name`,
+/// which may come from a `.env` file. Same lookup as the practice-2 bins.
+pub fn load_keypair(env_name: &str) -> Result<Keypair, String> {
+    dotenv().ok();
+    let secret_key =
+        env::var(env_name).map_err(|_| format!("{env_name} value not found in the env"))?;
+
+    let secret_key_bytes: Vec<u8> = match bs58::decode(&secret_key).into_vec() {
+        Ok(bytes) if bytes.len() == 64 => bytes,
+        _ => return Err("Invalid private key format".to_string()),
+    };
+
+    Keypair::try_from(secret_key_bytes.as_slice())
+        .map_err(|_| "Failed to create Keypair".to_string())
+}
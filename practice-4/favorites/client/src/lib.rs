@@ -0,0 +1,33 @@
+//! Typed client for the favorites program.
+//!
+//! Builds instructions, derives PDAs, decodes accounts, errors and logs and
+//! lists `Favorites` accounts over RPC without pulling in Anchor. Every
+//! discriminator and layout here is checked against `idl/favorites.json`, the
+//! IDL produced by the program's `idl-build` feature.
+
+pub mod accounts;
+pub mod decode;
+pub mod errors;
+pub mod fees;
+pub mod gate;
+pub mod handles;
+pub mod instructions;
+pub mod keypair;
+pub mod logs;
+pub mod pda;
+pub mod premium;
+pub mod referrals;
+pub mod registry;
+pub mod scan;
+pub mod signed;
+pub mod stats;
+
+pub use accounts::Favorites;
+pub use errors::CustomError;
+
+use solana_program::{pubkey, pubkey::Pubkey};
+
+pub const ID: Pubkey = pubkey!("GfwhbDPReQUpdUK6ikBbM9AzkrCE1uVb2dKvr6rqVJQW");
+
+// Anchor prefixes every instruction and account with an 8 byte discriminator
+pub const DISCRIMINATOR_SIZE: usize = 8;
@@ -0,0 +1,305 @@
+//! Live feed of the program's logs over `logsSubscribe`. Each notification is
+//! split into what the favorites program itself logged: "Program data:" lines
+//! decode into its events, the "favorite number is" line `set_favorites` logs
+//! is parsed, and other `msg!` lines are kept as text.
+use std::fmt;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use solana_client::{
+    pubsub_client::{PubsubClient, PubsubClientError},
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::{Response, RpcLogsResponse},
+};
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{
+    RecoveryCancelled, RecoveryCompleted, RecoveryKeyRemoved, RecoveryKeySet, RecoveryStarted,
+    TipReceived, RECOVERY_CANCELLED_DISCRIMINATOR, RECOVERY_COMPLETED_DISCRIMINATOR,
+    RECOVERY_KEY_REMOVED_DISCRIMINATOR, RECOVERY_KEY_SET_DISCRIMINATOR,
+    RECOVERY_STARTED_DISCRIMINATOR, TIP_RECEIVED_DISCRIMINATOR,
+};
+use crate::DISCRIMINATOR_SIZE;
+
+/// An event the program emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    TipReceived(TipReceived),
+    RecoveryKeySet(RecoveryKeySet),
+    RecoveryKeyRemoved(RecoveryKeyRemoved),
+    RecoveryStarted(RecoveryStarted),
+    RecoveryCancelled(RecoveryCancelled),
+    RecoveryCompleted(RecoveryCompleted),
+}
+
+impl Event {
+    /// Decodes the bytes `emit!` logs as "Program data: <base64>". `None`
+    /// for data that is not one of the program's events.
+    pub fn try_from_bytes(data: &[u8]) -> Option<Event> {
+        let discriminator: [u8; 8] = data.get(..DISCRIMINATOR_SIZE)?.try_into().ok()?;
+        let event = match discriminator {
+            TIP_RECEIVED_DISCRIMINATOR => {
+                Event::TipReceived(TipReceived::try_from_bytes(data).ok()?)
+            }
+            RECOVERY_KEY_SET_DISCRIMINATOR => {
+                Event::RecoveryKeySet(RecoveryKeySet::try_from_bytes(data).ok()?)
+            }
+            RECOVERY_KEY_REMOVED_DISCRIMINATOR => {
+                Event::RecoveryKeyRemoved(RecoveryKeyRemoved::try_from_bytes(data).ok()?)
+            }
+            RECOVERY_STARTED_DISCRIMINATOR => {
+                Event::RecoveryStarted(RecoveryStarted::try_from_bytes(data).ok()?)
+            }
+            RECOVERY_CANCELLED_DISCRIMINATOR => {
+                Event::RecoveryCancelled(RecoveryCancelled::try_from_bytes(data).ok()?)
+            }
+            RECOVERY_COMPLETED_DISCRIMINATOR => {
+                Event::RecoveryCompleted(RecoveryCompleted::try_from_bytes(data).ok()?)
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// The event's name in the IDL.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::TipReceived(_) => "TipReceived",
+            Event::RecoveryKeySet(_) => "RecoveryKeySet",
+            Event::RecoveryKeyRemoved(_) => "RecoveryKeyRemoved",
+            Event::RecoveryStarted(_) => "RecoveryStarted",
+            Event::RecoveryCancelled(_) => "RecoveryCancelled",
+            Event::RecoveryCompleted(_) => "RecoveryCompleted",
+        }
+    }
+
+    /// The user whose favorites the event is about.
+    pub fn user(&self) -> Pubkey {
+        match self {
+            Event::TipReceived(event) => event.user,
+            Event::RecoveryKeySet(event) => event.user,
+            Event::RecoveryKeyRemoved(event) => event.user,
+            Event::RecoveryStarted(event) => event.user,
+            Event::RecoveryCancelled(event) => event.user,
+            Event::RecoveryCompleted(event) => event.user,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::TipReceived(event) => write!(
+                f,
+                "{} tipped {} {} lamports, {} in total",
+                event.tipper, event.user, event.amount, event.tips_total
+            ),
+            Event::RecoveryKeySet(event) => write!(
+                f,
+                "{} made {} their recovery key",
+                event.user, event.recovery_key
+            ),
+            Event::RecoveryKeyRemoved(event) => write!(
+                f,
+                "{} removed their recovery key {}",
+                event.user, event.recovery_key
+            ),
+            Event::RecoveryStarted(event) => write!(
+                f,
+                "{} started moving {}'s favorites to {}, unlocking at {}",
+                event.recovery_key, event.user, event.new_owner, event.unlocks_at
+            ),
+            Event::RecoveryCancelled(event) => write!(
+                f,
+                "{} cancelled moving their favorites to {}",
+                event.user, event.new_owner
+            ),
+            Event::RecoveryCompleted(event) => {
+                write!(f, "{}'s favorites moved to {}", event.user, event.new_owner)
+            }
+        }
+    }
+}
+
+/// One line the program logged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogEntry {
+    /// "User <user>'s favorite number is <number> and favorite color is:
+    /// <color>", logged by `set_favorites`.
+    FavoritesSet {
+        user: Pubkey,
+        number: u64,
+        color: String,
+    },
+    /// Any other `msg!` line.
+    Message(String),
+    Event(Event),
+}
+
+impl LogEntry {
+    /// Whether the entry is about `user`. Messages are when they name them.
+    pub fn mentions(&self, user: &Pubkey) -> bool {
+        match self {
+            LogEntry::FavoritesSet { user: set_by, .. } => set_by == user,
+            LogEntry::Message(message) => message.contains(&user.to_string()),
+            LogEntry::Event(event) => event.user() == *user,
+        }
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogEntry::FavoritesSet {
+                user,
+                number,
+                color,
+            } => write!(f, "{user} set favorites: {number}, {color}"),
+            LogEntry::Message(message) => f.write_str(message),
+            LogEntry::Event(event) => write!(f, "{}: {event}", event.name()),
+        }
+    }
+}
+
+/// What the program at `program_id` logged in a transaction's `logs`, in
+/// order. Lines logged while another program runs, including ones the program
+/// invoked, are left out, as are "Program data:" lines that are not events.
+pub fn program_entries(logs: &[String], program_id: &Pubkey) -> Vec<LogEntry> {
+    let program = program_id.to_string();
+    let mut entries = Vec::new();
+    // Whether each running program is ours, innermost last
+    let mut stack: Vec<bool> = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some((id, depth)) = rest.split_once(" invoke [") {
+            if depth.ends_with(']') {
+                stack.push(id == program);
+                continue;
+            }
+        }
+        let ours = stack.last().copied().unwrap_or(false);
+        if let Some(message) = rest.strip_prefix("log: ") {
+            if ours {
+                entries.push(parse_message(message));
+            }
+        } else if let Some(data) = rest.strip_prefix("data: ") {
+            let event = BASE64_STANDARD
+                .decode(data)
+                .ok()
+                .and_then(|bytes| Event::try_from_bytes(&bytes));
+            if let (true, Some(event)) = (ours, event) {
+                entries.push(LogEntry::Event(event));
+            }
+        } else if rest.ends_with(" success") || rest.contains(" failed") {
+            stack.pop();
+        }
+    }
+    entries
+}
+
+fn parse_message(message: &str) -> LogEntry {
+    let favorites_set = message
+        .strip_prefix("User ")
+        .and_then(|rest| rest.split_once("'s favorite number is "))
+        .and_then(|(user, rest)| {
+            let (number, color) = rest.split_once(" and favorite color is: ")?;
+            Some(LogEntry::FavoritesSet {
+                user: user.parse().ok()?,
+                number: number.parse().ok()?,
+                color: color.to_string(),
+            })
+        });
+    favorites_set.unwrap_or_else(|| LogEntry::Message(message.to_string()))
+}
+
+/// A transaction that mentioned the program, as the feed shows it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub slot: u64,
+    pub signature: String,
+    /// Whether the transaction failed. Its logs up to the failure still show.
+    pub failed: bool,
+    pub entries: Vec<LogEntry>,
+}
+
+impl Notification {
+    /// Splits a `logsSubscribe` notification into the entries of
+    /// `program_id`.
+    pub fn new(response: &Response<RpcLogsResponse>, program_id: &Pubkey) -> Self {
+        Notification {
+            slot: response.context.slot,
+            signature: response.value.signature.clone(),
+            failed: response.value.err.is_some(),
+            entries: program_entries(&response.value.logs, program_id),
+        }
+    }
+}
+
+/// Which notifications and entries the feed shows.
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    /// Only entries about this user.
+    pub user: Option<Pubkey>,
+    /// Only events, no `msg!` lines.
+    pub events_only: bool,
+    /// Failed transactions too.
+    pub include_failed: bool,
+}
+
+impl LogFilter {
+    /// Drops the entries the filter hides from `notification`. `None` when it
+    /// has none left, or failed and failures are hidden.
+    pub fn apply(&self, mut notification: Notification) -> Option<Notification> {
+        if notification.failed && !self.include_failed {
+            return None;
+        }
+        notification.entries.retain(|entry| {
+            let kind = !self.events_only || matches!(entry, LogEntry::Event(_));
+            kind && self.user.is_none_or(|user| entry.mentions(&user))
+        });
+        (!notification.entries.is_empty()).then_some(notification)
+    }
+}
+
+/// The websocket URL of the RPC node at `rpc_url`. Like the Solana CLI, it
+/// swaps the scheme and, for an explicit port, takes the next one, which is
+/// where `solana-test-validator` listens.
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) => match port.parse::<u32>() {
+            Ok(port) => format!("{name}:{}", port + 1),
+            Err(_) => host.to_string(),
+        },
+        None => host.to_string(),
+    };
+    format!("{scheme}://{host}{path}")
+}
+
+/// Subscribes at `ws_url` to the logs of transactions mentioning
+/// `program_id` and passes each notification `filter` keeps to
+/// `on_notification`, until the server closes the connection.
+pub fn watch(
+    ws_url: &str,
+    program_id: &Pubkey,
+    config: RpcTransactionLogsConfig,
+    filter: &LogFilter,
+    mut on_notification: impl FnMut(Notification),
+) -> Result<(), Box<PubsubClientError>> {
+    let filter_by = RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]);
+    let (_subscription, receiver) =
+        PubsubClient::logs_subscribe(ws_url, filter_by, config).map_err(Box::new)?;
+    // The subscription drops the sender once the connection closes
+    for response in receiver {
+        if let Some(notification) = filter.apply(Notification::new(&response, program_id)) {
+            on_notification(notification);
+        }
+    }
+    Ok(())
+}
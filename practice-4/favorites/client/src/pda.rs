@@ -0,0 +1,176 @@
+use solana_program::{pubkey, pubkey::Pubkey};
+
+pub const FAVORITES_SEED: &[u8] = b"favorites";
+pub const REGISTRY_SEED: &[u8] = b"registry";
+pub const SESSION_SEED: &[u8] = b"session";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const STATS_SEED: &[u8] = b"stats";
+pub const HANDLE_SEED: &[u8] = b"handle";
+pub const NFT_FAVORITES_SEED: &[u8] = b"nft_favorites";
+pub const BADGE_AUTHORITY_SEED: &[u8] = b"badge_authority";
+pub const TIP_VAULT_SEED: &[u8] = b"tip_vault";
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+pub const TALLY_SEED: &[u8] = b"tally";
+pub const VOTE_ESCROW_SEED: &[u8] = b"vote_escrow";
+pub const VOTE_RECEIPT_SEED: &[u8] = b"vote";
+pub const COMMUNITY_COLOR_SEED: &[u8] = b"community_color";
+pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const FEE_EXEMPTION_SEED: &[u8] = b"fee_exempt";
+pub const PROFILE_SEED: &[u8] = b"profile";
+pub const REFERRALS_SEED: &[u8] = b"referrals";
+pub const RECOVERY_SEED: &[u8] = b"recovery";
+pub const COLLECTION_SEED: &[u8] = b"collection";
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey =
+    pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Address and bump of the `Favorites` account owned by `user`.
+pub fn favorites_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FAVORITES_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the `Registry` account.
+pub fn registry_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED], &crate::ID)
+}
+
+/// Address and bump of the `RegistryPage` account number `page`.
+pub fn registry_page_address(page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED, &page.to_le_bytes()], &crate::ID)
+}
+
+/// Address and bump of the `Session` letting `session_key` act for `user`.
+pub fn session_address(user: &Pubkey, session_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SESSION_SEED, user.as_ref(), session_key.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Address and bump of the `Handle` account of `handle`, given without its
+/// leading `@`. Panics for handles over 32 bytes, which are never valid.
+pub fn handle_address(handle: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HANDLE_SEED, handle.as_bytes()], &crate::ID)
+}
+
+/// Address and bump of the `Stats` account.
+pub fn stats_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STATS_SEED], &crate::ID)
+}
+
+/// Address and bump of the program's data account, which holds its upgrade
+/// authority.
+pub fn program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[crate::ID.as_ref()], &BPF_LOADER_UPGRADEABLE_ID)
+}
+
+/// Address and bump of the program's `Config` account.
+pub fn config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], &crate::ID)
+}
+
+/// Address and bump of the `NftFavorites` bound to the NFT `mint`.
+pub fn nft_favorites_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NFT_FAVORITES_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the `TipVault` holding the tips sent to `user`.
+pub fn tip_vault_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TIP_VAULT_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the `Proposal` numbered `id` by `proposer`.
+pub fn proposal_address(proposer: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROPOSAL_SEED, proposer.as_ref(), &id.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Address and bump of the `Tally` of `proposal`.
+pub fn tally_address(proposal: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TALLY_SEED, proposal.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the token account locking the votes on `proposal`.
+pub fn vote_escrow_address(proposal: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOTE_ESCROW_SEED, proposal.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the `VoteReceipt` of `voter` on `proposal`.
+pub fn vote_receipt_address(proposal: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOTE_RECEIPT_SEED, proposal.as_ref(), voter.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Address and bump of the `CommunityColor` account.
+pub fn community_color_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COMMUNITY_COLOR_SEED], &crate::ID)
+}
+
+/// Address and bump of the `Treasury` collecting fees.
+pub fn treasury_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], &crate::ID)
+}
+
+/// Address and bump of the `FeeExemption` of `user`.
+pub fn fee_exemption_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_EXEMPTION_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Address and bump of `user`'s profile number `index`.
+pub fn profile_address(user: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROFILE_SEED, user.as_ref(), &[index]], &crate::ID)
+}
+
+/// Address and bump of the `Referrals` counter of `referrer`.
+pub fn referrals_address(referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFERRALS_SEED, referrer.as_ref()], &crate::ID)
+}
+
+/// Address and bump of `user`'s `Recovery`.
+pub fn recovery_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECOVERY_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the zero-copy `Collection` account owned by `user`.
+pub fn collection_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COLLECTION_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Address and bump of the PDA that mints badges and stays their update
+/// authority.
+pub fn badge_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BADGE_AUTHORITY_SEED], &crate::ID)
+}
+
+/// Address of the Token Metadata account of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Address of the Token Metadata master edition of `mint`.
+pub fn master_edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"edition",
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
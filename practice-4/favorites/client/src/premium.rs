@@ -0,0 +1,62 @@
+//! Premium: while the program's `Config` names a premium mint, users buy
+//! months of premium with it, paid into the treasury's associated token
+//! account of that mint. Until `Favorites::premium_until` their colors may be
+//! up to 128 bytes and they can keep up to 8 profiles instead of 1.
+//! Cancelling ends premium early, without a refund.
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{Favorites, Profile};
+use crate::fees::treasury_token_address;
+use crate::gate::{associated_token_address, fetch_config, GateError};
+use crate::pda::profile_address;
+use crate::registry::AccountSource;
+
+/// Seconds of premium a month buys.
+pub const PREMIUM_PERIOD: i64 = 30 * 24 * 60 * 60;
+/// Profiles every user can have.
+pub const FREE_PROFILES: u8 = 1;
+/// Profiles premium users can have.
+pub const MAX_PROFILES: u8 = 8;
+
+/// The token accounts `renew_premium` takes for `user`: theirs of the premium
+/// mint, then the treasury's. `None` while premium is not for sale.
+pub fn premium_token_accounts(
+    source: &impl AccountSource,
+    user: &Pubkey,
+) -> Result<Option<(Pubkey, Pubkey)>, GateError> {
+    let premium_mint = fetch_config(source)?.and_then(|config| config.premium_mint);
+    Ok(premium_mint.map(|mint| {
+        (
+            associated_token_address(user, &mint),
+            treasury_token_address(&mint),
+        )
+    }))
+}
+
+/// How many profiles the owner of `favorites` can set at the unix timestamp
+/// `now`.
+pub fn profile_limit(favorites: &Favorites, now: i64) -> u8 {
+    if favorites.is_premium(now) {
+        MAX_PROFILES
+    } else {
+        FREE_PROFILES
+    }
+}
+
+/// Every profile `user` has, by index. Profiles set with premium stay after
+/// it ends, so all indexes are read.
+pub fn fetch_profiles(
+    source: &impl AccountSource,
+    user: &Pubkey,
+) -> Result<Vec<(u8, Profile)>, GateError> {
+    let mut profiles = Vec::new();
+    for index in 0..MAX_PROFILES {
+        let address = profile_address(user, index).0;
+        if let Some(account) = source.account(&address).map_err(GateError::Rpc)? {
+            let profile = Profile::try_from_bytes(&account.data)
+                .map_err(|error| GateError::Decode(address, error))?;
+            profiles.push((index, profile));
+        }
+    }
+    Ok(profiles)
+}
@@ -0,0 +1,38 @@
+//! Referrals: a user may name the wallet that brought them when signing up,
+//! which counts them in that wallet's `Referrals`. The program refuses a
+//! referrer whose own chain of referrers leads back to the user, so the
+//! instructions that name or move a referrer pass the favorites of every
+//! wallet up that chain, as far as `MAX_REFERRAL_DEPTH` wallets.
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::Favorites;
+use crate::pda::favorites_address;
+use crate::registry::{AccountSource, RegistryError};
+
+/// How many referrers the program follows up a chain.
+pub const MAX_REFERRAL_DEPTH: usize = 8;
+
+/// `referrer` followed by their own referrer, theirs and so on, up to a
+/// wallet without favorites, whose favorites name no referrer, or the
+/// `MAX_REFERRAL_DEPTH`th wallet: the `referrers` the instruction builders
+/// take. Empty without a referrer.
+pub fn referrers(
+    source: &impl AccountSource,
+    referrer: Option<Pubkey>,
+) -> Result<Vec<Pubkey>, RegistryError> {
+    let mut referrers = Vec::new();
+    let mut next = referrer;
+    while let Some(wallet) = next.filter(|_| referrers.len() < MAX_REFERRAL_DEPTH) {
+        referrers.push(wallet);
+        let address = favorites_address(&wallet).0;
+        next = match source.account(&address).map_err(RegistryError::Rpc)? {
+            Some(account) => {
+                Favorites::try_from_bytes(&account.data)
+                    .map_err(|error| RegistryError::Decode(address, error))?
+                    .referrer
+            }
+            None => None,
+        };
+    }
+    Ok(referrers)
+}
@@ -0,0 +1,111 @@
+//! Enumerating users through the on-chain registry, for RPC providers that
+//! disable `getProgramAccounts`. Only `getAccountInfo` is needed.
+use std::fmt;
+
+use solana_account::Account;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{DecodeError, Registry, RegistryPage};
+use crate::pda::{registry_address, registry_page_address};
+
+/// Anything that answers `getAccountInfo`: an `RpcClient`, or a stand-in in tests.
+pub trait AccountSource {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>>;
+}
+
+impl AccountSource for RpcClient {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>> {
+        self.get_account_with_commitment(address, self.commitment())
+            .map(|response| response.value)
+            .map_err(Box::new)
+    }
+}
+
+/// The `Registry` account, or `None` before the first user registered.
+pub fn fetch_registry(source: &impl AccountSource) -> Result<Option<Registry>, RegistryError> {
+    let address = registry_address().0;
+    match source.account(&address).map_err(RegistryError::Rpc)? {
+        Some(account) => Registry::try_from_bytes(&account.data)
+            .map(Some)
+            .map_err(|error| RegistryError::Decode(address, error)),
+        None => Ok(None),
+    }
+}
+
+/// The page `set_favorites` appends the next user to.
+pub fn open_page(source: &impl AccountSource) -> Result<u32, RegistryError> {
+    Ok(fetch_registry(source)?.map_or(0, |registry| registry.open_page))
+}
+
+/// Every registered user, page by page, fetching one page at a time.
+pub fn registry_users<S: AccountSource>(source: &S) -> Result<RegistryUsers<'_, S>, RegistryError> {
+    // Without a registry there are no pages: start past the last one
+    let (next_page, open_page) = match fetch_registry(source)? {
+        Some(registry) => (0, registry.open_page),
+        None => (1, 0),
+    };
+    Ok(RegistryUsers {
+        source,
+        next_page,
+        open_page,
+        users: Vec::new().into_iter(),
+    })
+}
+
+pub struct RegistryUsers<'a, S> {
+    source: &'a S,
+    next_page: u32,
+    open_page: u32,
+    users: std::vec::IntoIter<Pubkey>,
+}
+
+impl<S: AccountSource> Iterator for RegistryUsers<'_, S> {
+    type Item = Result<Pubkey, RegistryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(user) = self.users.next() {
+                return Some(Ok(user));
+            }
+            if self.next_page > self.open_page {
+                return None;
+            }
+            let address = registry_page_address(self.next_page).0;
+            self.next_page += 1;
+            // The open page is only created by the next registration
+            let account = match self.source.account(&address) {
+                Ok(Some(account)) => account,
+                Ok(None) => continue,
+                Err(error) => {
+                    self.next_page = self.open_page + 1;
+                    return Some(Err(RegistryError::Rpc(error)));
+                }
+            };
+            match RegistryPage::try_from_bytes(&account.data) {
+                Ok(page) => self.users = page.users.into_iter(),
+                Err(error) => {
+                    self.next_page = self.open_page + 1;
+                    return Some(Err(RegistryError::Decode(address, error)));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Rpc(Box<ClientError>),
+    Decode(Pubkey, DecodeError),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Rpc(error) => write!(f, "getAccountInfo failed: {error}"),
+            RegistryError::Decode(address, error) => write!(f, "{address}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
@@ -0,0 +1,137 @@
+//! Enumerating `Favorites` accounts through `getProgramAccounts`.
+use std::{fmt, mem::size_of};
+
+use solana_account::Account;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{favorites_layout, DecodeError, FAVORITES_DISCRIMINATOR};
+use crate::Favorites;
+
+/// Anything that answers `getProgramAccounts`: an `RpcClient`, or a stand-in in tests.
+pub trait ProgramAccountsSource {
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Vec<(Pubkey, Account)>, Box<ClientError>>;
+}
+
+impl ProgramAccountsSource for RpcClient {
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Vec<(Pubkey, Account)>, Box<ClientError>> {
+        self.get_program_accounts_with_config(program_id, config)
+            .map_err(Box::new)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DelegateFilter {
+    Any,
+    /// Accounts without a delegate
+    Unset,
+    /// Accounts whose delegate is this key
+    Is(Pubkey),
+}
+
+impl DelegateFilter {
+    /// The Borsh `Option<Pubkey>` bytes a matching account holds, if any
+    /// particular ones.
+    fn bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            DelegateFilter::Any => None,
+            DelegateFilter::Unset => Some(vec![0]),
+            DelegateFilter::Is(key) => Some([&[1], key.as_ref()].concat()),
+        }
+    }
+}
+
+/// Filters selecting `Favorites` accounts.
+pub fn favorites_filters() -> Vec<RpcFilterType> {
+    vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        &FAVORITES_DISCRIMINATOR,
+    ))]
+}
+
+/// Filters selecting `Favorites` accounts whose color is `color_len` bytes
+/// long and whose delegate matches `delegate`. The delegate follows the
+/// variable-length color, so only once the length is fixed does it sit at a
+/// known offset.
+pub fn delegate_filters(color_len: usize, delegate: &DelegateFilter) -> Vec<RpcFilterType> {
+    let mut filters = favorites_filters();
+    filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        favorites_layout::COLOR_OFFSET,
+        &(color_len as u32).to_le_bytes(),
+    )));
+    if let Some(bytes) = delegate.bytes() {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            favorites_layout::COLOR_OFFSET + size_of::<u32>() + color_len,
+            &bytes,
+        )));
+    }
+    filters
+}
+
+/// Every `Favorites` account matching `delegate`, sorted by address.
+///
+/// Filtering on a delegate takes one request per possible color length, so
+/// that the RPC node only returns the matching accounts.
+pub fn list_favorites(
+    source: &impl ProgramAccountsSource,
+    delegate: DelegateFilter,
+) -> Result<Vec<(Pubkey, Favorites)>, ScanError> {
+    let requests = match delegate {
+        DelegateFilter::Any => vec![favorites_filters()],
+        _ => (0..=favorites_layout::MAX_PREMIUM_COLOR_LEN)
+            .map(|color_len| delegate_filters(color_len, &delegate))
+            .collect(),
+    };
+    let mut favorites = Vec::new();
+    for filters in requests {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        for (address, account) in source
+            .program_accounts(&crate::ID, config)
+            .map_err(ScanError::Rpc)?
+        {
+            let decoded = Favorites::try_from_bytes(&account.data)
+                .map_err(|error| ScanError::Decode(address, error))?;
+            favorites.push((address, decoded));
+        }
+    }
+    favorites.sort_by_key(|(address, _)| *address);
+    Ok(favorites)
+}
+
+#[derive(Debug)]
+pub enum ScanError {
+    Rpc(Box<ClientError>),
+    Decode(Pubkey, DecodeError),
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Rpc(error) => write!(f, "getProgramAccounts failed: {error}"),
+            ScanError::Decode(address, error) => write!(f, "{address}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
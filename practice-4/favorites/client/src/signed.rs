@@ -0,0 +1,75 @@
+//! Gasless updates: the owner signs an update off-chain and a relayer submits
+//! it, paying the fees.
+use borsh::BorshSerialize;
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+use crate::{fees::FeeAccounts, instructions::update_favorites_signed};
+
+/// Starts every message an owner signs for `update_favorites_signed`.
+pub const SIGNED_UPDATE_PREFIX: &[u8] = b"favorites:update_favorites_signed";
+
+#[derive(BorshSerialize)]
+struct SignedUpdate<'a> {
+    program_id: Pubkey,
+    user: Pubkey,
+    number: u64,
+    color: &'a str,
+    nonce: u64,
+    expiry: i64,
+}
+
+/// The bytes `user` signs. `nonce` is the account's current `Favorites::nonce`
+/// and `expiry` the last unix timestamp the update may land at.
+pub fn signed_update_message(
+    user: &Pubkey,
+    number: u64,
+    color: &str,
+    nonce: u64,
+    expiry: i64,
+) -> Vec<u8> {
+    let mut message = SIGNED_UPDATE_PREFIX.to_vec();
+    SignedUpdate {
+        program_id: crate::ID,
+        user: *user,
+        number,
+        color,
+        nonce,
+        expiry,
+    }
+    .serialize(&mut message)
+    .expect("serializing into a Vec never fails");
+    message
+}
+
+/// The Ed25519 verification and the update, in the order the program expects.
+/// `signature` is `user`'s signature of [`signed_update_message`],
+/// `gate_token_account` `user`'s token account of the gate mint and `fee` how
+/// `relayer` pays the fee.
+#[allow(clippy::too_many_arguments)]
+pub fn signed_update_instructions(
+    user: &Pubkey,
+    relayer: &Pubkey,
+    gate_token_account: Option<Pubkey>,
+    fee: FeeAccounts,
+    number: u64,
+    color: &str,
+    nonce: u64,
+    expiry: i64,
+    signature: &[u8; 64],
+) -> [Instruction; 2] {
+    let message = signed_update_message(user, number, color, nonce, expiry);
+    [
+        new_ed25519_instruction_with_signature(&message, signature, &user.to_bytes()),
+        update_favorites_signed(
+            user,
+            relayer,
+            gate_token_account,
+            fee,
+            number,
+            color,
+            nonce,
+            expiry,
+        ),
+    ]
+}
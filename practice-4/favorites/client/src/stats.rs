@@ -0,0 +1,78 @@
+//! The program-wide `Stats` account, read with a single `getAccountInfo`.
+use std::fmt::{self, Write};
+
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts::{DecodeError, Stats};
+use crate::pda::stats_address;
+use crate::registry::AccountSource;
+
+/// The `Stats` account, or `None` before the first user set favorites.
+pub fn fetch_stats(source: &impl AccountSource) -> Result<Option<Stats>, StatsError> {
+    let address = stats_address().0;
+    match source.account(&address).map_err(StatsError::Rpc)? {
+        Some(account) => Stats::try_from_bytes(&account.data)
+            .map(Some)
+            .map_err(|error| StatsError::Decode(address, error)),
+        None => Ok(None),
+    }
+}
+
+impl Stats {
+    /// Mean favorite number of the current users.
+    pub fn average_number(&self) -> Option<f64> {
+        (self.user_count > 0).then(|| self.number_sum as f64 / self.user_count as f64)
+    }
+
+    /// `top_colors` from most to least used, ties by color.
+    pub fn ranked_colors(&self) -> Vec<(&str, u64)> {
+        let mut colors: Vec<(&str, u64)> = self
+            .top_colors
+            .iter()
+            .map(|entry| (entry.color.as_str(), entry.count))
+            .collect();
+        colors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        colors
+    }
+}
+
+/// Plain text summary of `stats`, one line per figure and per top color.
+pub fn render_stats(stats: &Stats) -> String {
+    let optional = |value: Option<u64>| value.map_or("-".to_string(), |value| value.to_string());
+    let mut text = String::new();
+    // Writing into a String never fails
+    let _ = writeln!(text, "users:    {}", stats.user_count);
+    let _ = writeln!(text, "sum:      {}", stats.number_sum);
+    let _ = writeln!(
+        text,
+        "average:  {}",
+        stats
+            .average_number()
+            .map_or("-".to_string(), |average| format!("{average:.2}"))
+    );
+    let _ = writeln!(text, "min:      {}", optional(stats.min_number));
+    let _ = writeln!(text, "max:      {}", optional(stats.max_number));
+    let _ = writeln!(text, "colors:");
+    for (rank, (color, count)) in stats.ranked_colors().into_iter().enumerate() {
+        let _ = writeln!(text, "  {}. {color:?} x{count}", rank + 1);
+    }
+    text
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    Rpc(Box<ClientError>),
+    Decode(Pubkey, DecodeError),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::Rpc(error) => write!(f, "getAccountInfo failed: {error}"),
+            StatsError::Decode(address, error) => write!(f, "{address}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
@@ -0,0 +1,169 @@
+// Decoding custom error codes by the program that returned them.
+use anchor_lang::error::ErrorCode;
+use favorites_client::{
+    decode::{decode_custom, decode_transaction_error, AnchorError, DecodedError, SystemError},
+    instructions, CustomError,
+};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    system_instruction::{self, SystemError as ProgramSystemError},
+    system_program,
+};
+use solana_transaction_error::TransactionError;
+
+#[test]
+fn anchor_errors_match_the_framework() {
+    let framework = [
+        (
+            AnchorError::InstructionMissing,
+            ErrorCode::InstructionMissing,
+        ),
+        (
+            AnchorError::IdlInstructionStub,
+            ErrorCode::IdlInstructionStub,
+        ),
+        (
+            AnchorError::EventInstructionStub,
+            ErrorCode::EventInstructionStub,
+        ),
+        (AnchorError::ConstraintMut, ErrorCode::ConstraintMut),
+        (AnchorError::ConstraintSeeds, ErrorCode::ConstraintSeeds),
+        (
+            AnchorError::ConstraintMintTransferHookExtensionProgramId,
+            ErrorCode::ConstraintMintTransferHookExtensionProgramId,
+        ),
+        (
+            AnchorError::RequireGteViolated,
+            ErrorCode::RequireGteViolated,
+        ),
+        (
+            AnchorError::AccountNotInitialized,
+            ErrorCode::AccountNotInitialized,
+        ),
+        (
+            AnchorError::AccountDuplicateReallocs,
+            ErrorCode::AccountDuplicateReallocs,
+        ),
+        (
+            AnchorError::InvalidNumericConversion,
+            ErrorCode::InvalidNumericConversion,
+        ),
+        (AnchorError::Deprecated, ErrorCode::Deprecated),
+    ];
+    for (error, expected) in framework {
+        assert_eq!(error.name(), expected.name());
+        assert_eq!(error.message(), expected.to_string());
+        assert_eq!(error.code(), u32::from(expected));
+    }
+
+    for error in AnchorError::ALL {
+        assert_eq!(AnchorError::from_code(error.code()), Some(*error));
+    }
+    assert!(AnchorError::ALL
+        .windows(2)
+        .all(|pair| pair[0].code() < pair[1].code()));
+    assert_eq!(AnchorError::from_code(104), None);
+}
+
+#[test]
+fn system_errors_match_the_system_program() {
+    let program = [
+        (
+            SystemError::AccountAlreadyInUse,
+            ProgramSystemError::AccountAlreadyInUse,
+        ),
+        (
+            SystemError::ResultWithNegativeLamports,
+            ProgramSystemError::ResultWithNegativeLamports,
+        ),
+        (
+            SystemError::NonceUnexpectedBlockhashValue,
+            ProgramSystemError::NonceUnexpectedBlockhashValue,
+        ),
+    ];
+    for (error, expected) in program {
+        assert_eq!(error.message(), expected.to_string());
+        assert_eq!(error.code(), expected as u32);
+    }
+    for error in SystemError::ALL {
+        assert_eq!(SystemError::from_code(error.code()), Some(*error));
+    }
+    assert_eq!(SystemError::from_code(9), None);
+}
+
+#[test]
+fn codes_decode_by_program_and_range() {
+    let other = Pubkey::new_unique();
+    assert_eq!(
+        decode_custom(&favorites_client::ID, 6000),
+        DecodedError::Favorites(CustomError::Unauthorized)
+    );
+    assert_eq!(
+        decode_custom(&favorites_client::ID, 2006),
+        DecodedError::Anchor(AnchorError::ConstraintSeeds)
+    );
+    // Returned by the system program the favorites program invoked
+    assert_eq!(
+        decode_custom(&favorites_client::ID, 0),
+        DecodedError::System(SystemError::AccountAlreadyInUse)
+    );
+    assert_eq!(
+        decode_custom(&system_program::ID, 1),
+        DecodedError::System(SystemError::ResultWithNegativeLamports)
+    );
+    // Past the last favorites error, and codes of other programs
+    let unknown = [
+        (favorites_client::ID, 6999),
+        (system_program::ID, 6000),
+        (other, 0),
+    ];
+    for (program_id, code) in unknown {
+        assert_eq!(
+            decode_custom(&program_id, code),
+            DecodedError::Unknown { program_id, code }
+        );
+    }
+
+    assert_eq!(
+        decode_custom(&favorites_client::ID, 6001).to_string(),
+        "ColorTooLong (6001): Favorite color is longer than the space reserved for it."
+    );
+    assert_eq!(
+        decode_custom(&other, 42).to_string(),
+        format!("custom program error 0x2a from {other}")
+    );
+}
+
+#[test]
+fn transaction_errors_decode_the_failing_instruction() {
+    let user = Pubkey::new_unique();
+    let tx_instructions: [Instruction; 2] = [
+        system_instruction::transfer(&user, &Pubkey::new_unique(), 1),
+        instructions::register_handle(&user, "alice"),
+    ];
+
+    let failed = |index, error| {
+        decode_transaction_error(
+            &TransactionError::InstructionError(index, error),
+            &tx_instructions,
+        )
+    };
+    assert_eq!(
+        failed(0, InstructionError::Custom(1)),
+        Some(DecodedError::System(
+            SystemError::ResultWithNegativeLamports
+        ))
+    );
+    assert_eq!(
+        failed(1, InstructionError::Custom(6021)),
+        Some(DecodedError::Favorites(CustomError::InvalidHandle))
+    );
+    // Not a custom error, or not an instruction of this transaction
+    assert_eq!(failed(1, InstructionError::MissingRequiredSignature), None);
+    assert_eq!(failed(2, InstructionError::Custom(6000)), None);
+    assert_eq!(
+        decode_transaction_error(&TransactionError::AccountNotFound, &tx_instructions),
+        None
+    );
+}
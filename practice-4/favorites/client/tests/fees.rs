@@ -0,0 +1,123 @@
+// Picking the fee accounts against an in-memory stand-in for `getAccountInfo`.
+use std::collections::HashMap;
+
+use anchor_lang::AccountSerialize;
+use favorites_client::{
+    fees::{fee_accounts, treasury_token_address, FeeAccounts},
+    gate::associated_token_address,
+    pda::{config_address, fee_exemption_address, treasury_address},
+    registry::AccountSource,
+};
+use solana_account::Account;
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Default)]
+struct InMemoryCluster {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl InMemoryCluster {
+    fn add(&mut self, address: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(
+            address,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: favorites::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    fn add_config(&mut self, fee_mint: Option<Pubkey>, fee_amount: u64) {
+        let mut data = Vec::new();
+        favorites::Config {
+            admin: Pubkey::new_unique(),
+            gate_mint: None,
+            gate_min_amount: 0,
+            vote_mint: None,
+            fee_mint,
+            fee_amount,
+            premium_mint: None,
+            premium_price: 0,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        self.add(config_address().0, data);
+    }
+
+    fn add_exemption(&mut self, user: &Pubkey) {
+        let mut data = Vec::new();
+        favorites::FeeExemption {}.try_serialize(&mut data).unwrap();
+        self.add(fee_exemption_address(user).0, data);
+    }
+}
+
+impl AccountSource for InMemoryCluster {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>> {
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+#[test]
+fn nothing_to_pass_while_free_or_in_lamports() {
+    let user = Pubkey::new_unique();
+    let mut cluster = InMemoryCluster::default();
+    assert_eq!(
+        fee_accounts(&cluster, &user).unwrap(),
+        FeeAccounts::default()
+    );
+
+    cluster.add_config(Some(Pubkey::new_unique()), 0);
+    assert_eq!(
+        fee_accounts(&cluster, &user).unwrap(),
+        FeeAccounts::default()
+    );
+
+    cluster.add_config(None, 1_000);
+    assert_eq!(
+        fee_accounts(&cluster, &user).unwrap(),
+        FeeAccounts::default()
+    );
+}
+
+#[test]
+fn token_fees_use_the_associated_token_accounts() {
+    let user = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let mut cluster = InMemoryCluster::default();
+    cluster.add_config(Some(mint), 10);
+
+    assert_eq!(
+        fee_accounts(&cluster, &user).unwrap(),
+        FeeAccounts {
+            exemption: None,
+            token_accounts: Some((
+                associated_token_address(&user, &mint),
+                associated_token_address(&treasury_address().0, &mint),
+            )),
+        }
+    );
+    assert_eq!(
+        treasury_token_address(&mint),
+        associated_token_address(&treasury_address().0, &mint)
+    );
+}
+
+#[test]
+fn exempt_users_pass_only_their_exemption() {
+    let user = Pubkey::new_unique();
+    let mut cluster = InMemoryCluster::default();
+    cluster.add_config(Some(Pubkey::new_unique()), 10);
+    cluster.add_exemption(&user);
+
+    assert_eq!(
+        fee_accounts(&cluster, &user).unwrap(),
+        FeeAccounts {
+            exemption: Some(fee_exemption_address(&user).0),
+            token_accounts: None,
+        }
+    );
+}
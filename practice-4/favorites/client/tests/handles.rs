@@ -0,0 +1,91 @@
+// Resolving `@handle` against an in-memory stand-in for `getAccountInfo`.
+use std::collections::HashMap;
+
+use anchor_lang::AccountSerialize;
+use favorites_client::{
+    handles::{fetch_handle, is_valid_handle, resolve, HandleError},
+    pda::{favorites_address, handle_address},
+    registry::AccountSource,
+};
+use solana_account::Account;
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Default)]
+struct InMemoryCluster {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl InMemoryCluster {
+    fn add_handle(&mut self, handle: &str, owner: Pubkey) {
+        let mut data = Vec::new();
+        favorites::Handle {
+            owner,
+            favorites: favorites_address(&owner).0,
+            handle: handle.into(),
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        self.accounts.insert(
+            handle_address(handle).0,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: favorites::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+}
+
+impl AccountSource for InMemoryCluster {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>> {
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+#[test]
+fn format_matches_the_program() {
+    let long = "a".repeat(32);
+    let too_long = "a".repeat(33);
+    for handle in [
+        "", "ab", "abc", "slava", "user_42", "Slava", "@slava", "sla va", "ѕlava", &long, &too_long,
+    ] {
+        assert_eq!(
+            is_valid_handle(handle),
+            favorites::is_valid_handle(handle),
+            "{handle:?}"
+        );
+    }
+}
+
+#[test]
+fn resolves_handles_and_plain_pubkeys() {
+    let owner = Pubkey::new_unique();
+    let mut cluster = InMemoryCluster::default();
+    cluster.add_handle("slava", owner);
+
+    assert_eq!(resolve(&cluster, "@slava").unwrap(), owner);
+    assert_eq!(resolve(&cluster, &owner.to_string()).unwrap(), owner);
+    let handle = fetch_handle(&cluster, "slava").unwrap().unwrap();
+    assert_eq!(handle.favorites, favorites_address(&owner).0);
+}
+
+#[test]
+fn reports_unknown_and_malformed_input() {
+    let cluster = InMemoryCluster::default();
+    assert!(matches!(
+        resolve(&cluster, "@nobody"),
+        Err(HandleError::Unknown(handle)) if handle == "nobody"
+    ));
+    // Never derives an address for a handle too long to be a seed
+    assert!(matches!(
+        resolve(&cluster, &format!("@{}", "a".repeat(33))),
+        Err(HandleError::Invalid(_))
+    ));
+    assert!(matches!(
+        resolve(&cluster, "slava"),
+        Err(HandleError::InvalidPubkey(input)) if input == "slava"
+    ));
+}
@@ -0,0 +1,1323 @@
+// Checks the hand-written client against `idl/favorites.json` and against the
+// program crate itself.
+use std::path::Path;
+
+use anchor_lang::{
+    AccountSerialize, AnchorSerialize, Discriminator, InstructionData, Space, ToAccountMetas,
+};
+use anchor_lang_idl::types::{
+    Idl, IdlArrayLen, IdlDefinedFields, IdlInstructionAccountItem, IdlType, IdlTypeDefTy,
+};
+use favorites_client::{
+    accounts::{
+        collection_layout, favorites_layout, Collection, CollectionItem, CommunityColor, Config,
+        Handle, NftFavorites, Profile, Proposal, Recovery, RecoveryStarted, Referrals, Registry,
+        RegistryPage, Session, Stats, Tally, TipReceived, TipVault, VoteReceipt,
+        COLLECTION_DISCRIMINATOR, COMMUNITY_COLOR_DISCRIMINATOR, CONFIG_DISCRIMINATOR,
+        FAVORITES_DISCRIMINATOR, HANDLE_DISCRIMINATOR, NFT_FAVORITES_DISCRIMINATOR,
+        PROFILE_DISCRIMINATOR, PROPOSAL_DISCRIMINATOR, RECOVERY_CANCELLED_DISCRIMINATOR,
+        RECOVERY_COMPLETED_DISCRIMINATOR, RECOVERY_DISCRIMINATOR,
+        RECOVERY_KEY_REMOVED_DISCRIMINATOR, RECOVERY_KEY_SET_DISCRIMINATOR,
+        RECOVERY_STARTED_DISCRIMINATOR, RECOVERY_TIMELOCK, REFERRALS_DISCRIMINATOR,
+        REGISTRY_DISCRIMINATOR, REGISTRY_PAGE_DISCRIMINATOR, SESSION_DISCRIMINATOR,
+        STATS_DISCRIMINATOR, TALLY_DISCRIMINATOR, TIP_RECEIVED_DISCRIMINATOR,
+        TIP_VAULT_DISCRIMINATOR, VOTE_RECEIPT_DISCRIMINATOR,
+    },
+    fees::FeeAccounts,
+    instructions, premium, referrals, signed, CustomError, Favorites,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+const IDL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/favorites.json");
+
+fn checked_in_idl() -> Idl {
+    let idl = std::fs::read_to_string(IDL_PATH).expect("idl/favorites.json is missing");
+    serde_json::from_str(&idl).unwrap()
+}
+
+// One sample instruction per IDL instruction, paired with the bytes the
+// program's own Anchor types produce for the same arguments.
+fn samples() -> Vec<(&'static str, Instruction, Vec<u8>)> {
+    let user = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+    let delegate = Pubkey::new_unique();
+    let token_account = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let fee = FeeAccounts {
+        exemption: Some(Pubkey::new_unique()),
+        token_accounts: Some((Pubkey::new_unique(), Pubkey::new_unique())),
+    };
+    vec![
+        (
+            "set_favorites",
+            instructions::set_favorites(
+                &user,
+                2,
+                Some(token_account),
+                fee,
+                &[Pubkey::new_unique()],
+                7,
+                "blue",
+            ),
+            favorites::instruction::SetFavorites {
+                number: 7,
+                color: "blue".into(),
+            }
+            .data(),
+        ),
+        (
+            "migrate_favorites",
+            instructions::migrate_favorites(&user, 2),
+            favorites::instruction::MigrateFavorites {}.data(),
+        ),
+        (
+            "update_favorites",
+            instructions::update_favorites_with_session(
+                &user,
+                &signer,
+                Some(token_account),
+                fee,
+                9,
+                "green",
+            ),
+            favorites::instruction::UpdateFavorites {
+                number: 9,
+                color: "green".into(),
+            }
+            .data(),
+        ),
+        (
+            "update_favorites_signed",
+            instructions::update_favorites_signed(
+                &user,
+                &signer,
+                Some(token_account),
+                fee,
+                9,
+                "green",
+                4,
+                1_700_000_000,
+            ),
+            favorites::instruction::UpdateFavoritesSigned {
+                number: 9,
+                color: "green".into(),
+                nonce: 4,
+                expiry: 1_700_000_000,
+            }
+            .data(),
+        ),
+        (
+            "set_authority",
+            instructions::set_authority(&user, Some(delegate)),
+            favorites::instruction::SetAuthority {
+                delegate: Some(delegate),
+            }
+            .data(),
+        ),
+        (
+            "tip",
+            instructions::tip(&signer, &user, 5_000),
+            favorites::instruction::Tip { amount: 5_000 }.data(),
+        ),
+        (
+            "withdraw_tips",
+            instructions::withdraw_tips(&user),
+            favorites::instruction::WithdrawTips {}.data(),
+        ),
+        (
+            "mint_favorites_badge",
+            instructions::mint_favorites_badge(&user, &mint),
+            favorites::instruction::MintFavoritesBadge {}.data(),
+        ),
+        (
+            "set_nft_favorites",
+            instructions::set_nft_favorites(&user, &mint, &token_account, 7, "blue"),
+            favorites::instruction::SetNftFavorites {
+                number: 7,
+                color: "blue".into(),
+            }
+            .data(),
+        ),
+        (
+            "update_nft_favorites",
+            instructions::update_nft_favorites(&user, &mint, &token_account, 9, "green"),
+            favorites::instruction::UpdateNftFavorites {
+                number: 9,
+                color: "green".into(),
+            }
+            .data(),
+        ),
+        (
+            "initialize_config",
+            instructions::initialize_config(&user, Some(mint), 10),
+            favorites::instruction::InitializeConfig {
+                gate_mint: Some(mint),
+                gate_min_amount: 10,
+            }
+            .data(),
+        ),
+        (
+            "set_token_gate",
+            instructions::set_token_gate(&user, None, 0),
+            favorites::instruction::SetTokenGate {
+                gate_mint: None,
+                gate_min_amount: 0,
+            }
+            .data(),
+        ),
+        (
+            "register_handle",
+            instructions::register_handle(&user, "slava"),
+            favorites::instruction::RegisterHandle {
+                handle: "slava".into(),
+            }
+            .data(),
+        ),
+        (
+            "transfer_handle",
+            instructions::transfer_handle(&user, "slava", &signer),
+            favorites::instruction::TransferHandle {}.data(),
+        ),
+        (
+            "release_handle",
+            instructions::release_handle(&user, "slava"),
+            favorites::instruction::ReleaseHandle {}.data(),
+        ),
+        (
+            "set_vote_mint",
+            instructions::set_vote_mint(&user, Some(mint)),
+            favorites::instruction::SetVoteMint {
+                vote_mint: Some(mint),
+            }
+            .data(),
+        ),
+        (
+            "create_proposal",
+            instructions::create_proposal(&user, &mint, 3, &["red".into(), "blue".into()], 600),
+            favorites::instruction::CreateProposal {
+                id: 3,
+                colors: vec!["red".into(), "blue".into()],
+                voting_period: 600,
+            }
+            .data(),
+        ),
+        (
+            "vote",
+            instructions::vote(&signer, &user, &token_account, 1),
+            favorites::instruction::Vote { choice: 1 }.data(),
+        ),
+        (
+            "finalize_proposal",
+            instructions::finalize_proposal(&signer, &user),
+            favorites::instruction::FinalizeProposal {}.data(),
+        ),
+        (
+            "reclaim_vote",
+            instructions::reclaim_vote(&signer, &user, &token_account),
+            favorites::instruction::ReclaimVote {}.data(),
+        ),
+        (
+            "set_fee",
+            instructions::set_fee(&user, Some(mint), 10),
+            favorites::instruction::SetFee {
+                fee_mint: Some(mint),
+                fee_amount: 10,
+            }
+            .data(),
+        ),
+        (
+            "add_fee_exemption",
+            instructions::add_fee_exemption(&user, &signer),
+            favorites::instruction::AddFeeExemption {}.data(),
+        ),
+        (
+            "remove_fee_exemption",
+            instructions::remove_fee_exemption(&user, &signer),
+            favorites::instruction::RemoveFeeExemption {}.data(),
+        ),
+        (
+            "withdraw_fees",
+            instructions::withdraw_fees(&user),
+            favorites::instruction::WithdrawFees {}.data(),
+        ),
+        (
+            "withdraw_fee_tokens",
+            instructions::withdraw_fee_tokens(&user, &mint, &token_account),
+            favorites::instruction::WithdrawFeeTokens {}.data(),
+        ),
+        (
+            "set_premium",
+            instructions::set_premium(&user, Some(mint), 10),
+            favorites::instruction::SetPremium {
+                premium_mint: Some(mint),
+                premium_price: 10,
+            }
+            .data(),
+        ),
+        (
+            "renew_premium",
+            instructions::renew_premium(&user, &token_account, &signer, 3),
+            favorites::instruction::RenewPremium { months: 3 }.data(),
+        ),
+        (
+            "cancel_premium",
+            instructions::cancel_premium(&user),
+            favorites::instruction::CancelPremium {}.data(),
+        ),
+        (
+            "set_profile",
+            instructions::set_profile(&user, 2, 7, "blue"),
+            favorites::instruction::SetProfile {
+                index: 2,
+                number: 7,
+                color: "blue".into(),
+            }
+            .data(),
+        ),
+        (
+            "close_profile",
+            instructions::close_profile(&user, 2),
+            favorites::instruction::CloseProfile { index: 2 }.data(),
+        ),
+        (
+            "create_session",
+            instructions::create_session(&user, &signer, 3, 1_700_000_000),
+            favorites::instruction::CreateSession {
+                session_key: signer,
+                max_uses: 3,
+                expiry: 1_700_000_000,
+            }
+            .data(),
+        ),
+        (
+            "revoke_session",
+            instructions::revoke_session(&user, &signer),
+            favorites::instruction::RevokeSession {}.data(),
+        ),
+        (
+            "transfer_favorites",
+            instructions::transfer_favorites(&user, &signer, 2, &[delegate], &[]),
+            favorites::instruction::TransferFavorites {}.data(),
+        ),
+        (
+            "set_recovery_key",
+            instructions::set_recovery_key(&user, &signer),
+            favorites::instruction::SetRecoveryKey {
+                recovery_key: signer,
+            }
+            .data(),
+        ),
+        (
+            "remove_recovery_key",
+            instructions::remove_recovery_key(&user),
+            favorites::instruction::RemoveRecoveryKey {}.data(),
+        ),
+        (
+            "start_recovery",
+            instructions::start_recovery(&signer, &user, &delegate),
+            favorites::instruction::StartRecovery {
+                new_owner: delegate,
+            }
+            .data(),
+        ),
+        (
+            "cancel_recovery",
+            instructions::cancel_recovery(&user),
+            favorites::instruction::CancelRecovery {}.data(),
+        ),
+        (
+            "complete_recovery",
+            instructions::complete_recovery(&user, &delegate, 2, &[signer], &[]),
+            favorites::instruction::CompleteRecovery {}.data(),
+        ),
+        (
+            "create_collection",
+            instructions::create_collection(&user),
+            favorites::instruction::CreateCollection {}.data(),
+        ),
+        (
+            "grow_collection",
+            instructions::grow_collection(&user),
+            favorites::instruction::GrowCollection {}.data(),
+        ),
+        (
+            "append_collection_item",
+            instructions::append_collection_item(&user, 42, "red"),
+            favorites::instruction::AppendCollectionItem {
+                number: 42,
+                color: "red".into(),
+            }
+            .data(),
+        ),
+        (
+            "update_collection_item",
+            instructions::update_collection_item(&user, 3, 42, "red"),
+            favorites::instruction::UpdateCollectionItem {
+                index: 3,
+                number: 42,
+                color: "red".into(),
+            }
+            .data(),
+        ),
+        (
+            "remove_collection_item",
+            instructions::remove_collection_item(&user, 3),
+            favorites::instruction::RemoveCollectionItem { index: 3 }.data(),
+        ),
+        (
+            "close_collection",
+            instructions::close_collection(&user),
+            favorites::instruction::CloseCollection {}.data(),
+        ),
+        (
+            "close_favorites",
+            instructions::close_favorites(&user, 2, Some(delegate), &[]),
+            favorites::instruction::CloseFavorites {}.data(),
+        ),
+    ]
+}
+
+fn fields<'a>(idl: &'a Idl, name: &str) -> Vec<(&'a str, IdlType)> {
+    let ty = idl.types.iter().find(|ty| ty.name == name).unwrap();
+    let IdlTypeDefTy::Struct {
+        fields: Some(IdlDefinedFields::Named(fields)),
+    } = &ty.ty
+    else {
+        panic!("{name} is not a struct with named fields");
+    };
+    fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.ty.clone()))
+        .collect()
+}
+
+#[test]
+fn program_address_matches() {
+    assert_eq!(checked_in_idl().address, favorites_client::ID.to_string());
+    assert_eq!(favorites_client::ID, favorites::ID);
+}
+
+#[test]
+fn every_instruction_has_a_matching_builder() {
+    let idl = checked_in_idl();
+    let mut samples = samples();
+    samples.sort_by_key(|(name, ..)| *name);
+    assert_eq!(
+        idl.instructions
+            .iter()
+            .map(|ix| ix.name.as_str())
+            .collect::<Vec<_>>(),
+        samples.iter().map(|(name, ..)| *name).collect::<Vec<_>>(),
+        "the IDL and the client disagree on the instruction set"
+    );
+
+    for (idl_ix, (name, instruction, expected_data)) in idl.instructions.iter().zip(samples) {
+        assert_eq!(
+            instruction.data[..8],
+            idl_ix.discriminator[..],
+            "{name} discriminator"
+        );
+        assert_eq!(instruction.data, expected_data, "{name} data");
+
+        let idl_accounts: Vec<_> = idl_ix
+            .accounts
+            .iter()
+            .map(|item| match item {
+                IdlInstructionAccountItem::Single(account) => account,
+                IdlInstructionAccountItem::Composite(_) => panic!("{name} has nested accounts"),
+            })
+            .collect();
+        // The samples pass a one wallet referral chain after the named accounts
+        let chain = match name {
+            "set_favorites" | "transfer_favorites" | "complete_recovery" => 1,
+            _ => 0,
+        };
+        assert_eq!(
+            idl_accounts.len() + chain,
+            instruction.accounts.len(),
+            "{name} account count"
+        );
+        for (idl_account, meta) in idl_accounts.iter().zip(&instruction.accounts) {
+            assert_eq!(
+                meta.is_writable, idl_account.writable,
+                "{name}.{}",
+                idl_account.name
+            );
+            assert_eq!(
+                meta.is_signer, idl_account.signer,
+                "{name}.{}",
+                idl_account.name
+            );
+            if let Some(address) = &idl_account.address {
+                assert_eq!(
+                    &meta.pubkey.to_string(),
+                    address,
+                    "{name}.{}",
+                    idl_account.name
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn favorites_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Favorites")
+        .unwrap();
+    assert_eq!(account.discriminator[..], FAVORITES_DISCRIMINATOR[..]);
+
+    // The first release's fields come first, in their order
+    assert_eq!(
+        fields(&idl, "Favorites"),
+        [
+            ("number", IdlType::U64),
+            ("color", IdlType::String),
+            ("delegate", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("version", IdlType::U8),
+            ("registry_page", IdlType::U32),
+            ("nonce", IdlType::U64),
+            ("premium_until", IdlType::I64),
+            ("referrer", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("session_generation", IdlType::U64),
+        ]
+    );
+
+    let (delegate, referrer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = Vec::new();
+    favorites::Favorites {
+        number: 42,
+        color: "red".into(),
+        delegate: Some(delegate),
+        version: favorites::FAVORITES_VERSION,
+        registry_page: 3,
+        nonce: 5,
+        premium_until: 1_700_000_000,
+        referrer: Some(referrer),
+        session_generation: 6,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        data[favorites_layout::NUMBER_OFFSET..][..8],
+        42u64.to_le_bytes()
+    );
+    assert_eq!(
+        data[favorites_layout::COLOR_OFFSET..][..4],
+        3u32.to_le_bytes()
+    );
+    // The program allocates room for the longest color
+    assert_eq!(
+        favorites_layout::ACCOUNT_SIZE,
+        favorites::ANCHOR_DISCRIMINATOR_SIZE + favorites::Favorites::INIT_SPACE
+    );
+    assert_eq!(favorites_layout::VERSION, favorites::FAVORITES_VERSION);
+    assert_eq!(favorites_layout::MAX_COLOR_LEN, favorites::MAX_COLOR_LEN);
+    assert_eq!(
+        favorites_layout::MAX_PREMIUM_COLOR_LEN,
+        favorites::MAX_PREMIUM_COLOR_LEN
+    );
+    data.resize(favorites_layout::ACCOUNT_SIZE, 0);
+    let decoded = Favorites::try_from_bytes(&data).unwrap();
+    assert!(decoded.is_migrated());
+    assert_eq!(
+        decoded,
+        Favorites {
+            number: 42,
+            color: "red".into(),
+            delegate: Some(delegate),
+            version: favorites_layout::VERSION,
+            registry_page: 3,
+            nonce: 5,
+            premium_until: 1_700_000_000,
+            referrer: Some(referrer),
+            session_generation: 6,
+        }
+    );
+}
+
+#[test]
+fn legacy_favorites_decode() {
+    // The first release's account: a number, a color and a delegate, in
+    // space allocated for exactly those
+    let delegate = Pubkey::new_unique();
+    let mut data = FAVORITES_DISCRIMINATOR.to_vec();
+    (42u64, "red".to_string(), Some(delegate))
+        .serialize(&mut data)
+        .unwrap();
+    data.resize(favorites_layout::LEGACY_ACCOUNT_SIZE, 0);
+
+    let decoded = Favorites::try_from_bytes(&data).unwrap();
+    assert!(!decoded.is_migrated());
+    assert_eq!(
+        decoded,
+        Favorites {
+            number: 42,
+            color: "red".into(),
+            delegate: Some(delegate),
+            version: 0,
+            registry_page: 0,
+            nonce: 0,
+            premium_until: 0,
+            referrer: None,
+            session_generation: 0,
+        }
+    );
+}
+
+#[test]
+fn registry_layouts_match() {
+    let idl = checked_in_idl();
+    for (name, discriminator) in [
+        ("Registry", REGISTRY_DISCRIMINATOR),
+        ("RegistryPage", REGISTRY_PAGE_DISCRIMINATOR),
+    ] {
+        let account = idl
+            .accounts
+            .iter()
+            .find(|account| account.name == name)
+            .unwrap();
+        assert_eq!(account.discriminator[..], discriminator[..], "{name}");
+    }
+    assert_eq!(
+        fields(&idl, "Registry"),
+        [("open_page", IdlType::U32), ("user_count", IdlType::U64)]
+    );
+    assert_eq!(
+        fields(&idl, "RegistryPage"),
+        [("users", IdlType::Vec(Box::new(IdlType::Pubkey)))]
+    );
+
+    let mut data = Vec::new();
+    favorites::Registry {
+        open_page: 2,
+        user_count: 70,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Registry::try_from_bytes(&data).unwrap(),
+        Registry {
+            open_page: 2,
+            user_count: 70,
+        }
+    );
+
+    let users = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut data = Vec::new();
+    favorites::RegistryPage {
+        users: users.clone(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(
+        favorites::ANCHOR_DISCRIMINATOR_SIZE + favorites::RegistryPage::INIT_SPACE,
+        0,
+    );
+    assert_eq!(
+        RegistryPage::try_from_bytes(&data).unwrap(),
+        RegistryPage { users }
+    );
+}
+
+#[test]
+fn session_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Session")
+        .unwrap();
+    assert_eq!(account.discriminator[..], SESSION_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Session"),
+        [
+            ("session_key", IdlType::Pubkey),
+            ("uses_left", IdlType::U32),
+            ("expiry", IdlType::I64),
+            ("generation", IdlType::U64),
+        ]
+    );
+
+    let session_key = Pubkey::new_unique();
+    let mut data = Vec::new();
+    favorites::Session {
+        session_key,
+        uses_left: 3,
+        expiry: 1_700_000_000,
+        generation: 4,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Session::try_from_bytes(&data).unwrap(),
+        Session {
+            session_key,
+            uses_left: 3,
+            expiry: 1_700_000_000,
+            generation: 4,
+        }
+    );
+}
+
+#[test]
+fn stats_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Stats")
+        .unwrap();
+    assert_eq!(account.discriminator[..], STATS_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Stats"),
+        [
+            ("user_count", IdlType::U64),
+            ("number_sum", IdlType::U128),
+            ("min_number", IdlType::Option(Box::new(IdlType::U64))),
+            ("max_number", IdlType::Option(Box::new(IdlType::U64))),
+            (
+                "top_colors",
+                IdlType::Vec(Box::new(IdlType::Defined {
+                    name: "ColorCount".into(),
+                    generics: vec![],
+                }))
+            ),
+            ("favorites_created", IdlType::U64),
+        ]
+    );
+    assert_eq!(
+        fields(&idl, "ColorCount"),
+        [("color", IdlType::String), ("count", IdlType::U64)]
+    );
+
+    let mut data = Vec::new();
+    favorites::Stats {
+        user_count: 2,
+        number_sum: u64::MAX as u128 + 1,
+        min_number: Some(1),
+        max_number: None,
+        top_colors: vec![favorites::ColorCount {
+            color: "red".into(),
+            count: 2,
+        }],
+        favorites_created: 5,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Stats::try_from_bytes(&data).unwrap(),
+        Stats {
+            user_count: 2,
+            number_sum: u64::MAX as u128 + 1,
+            min_number: Some(1),
+            max_number: None,
+            top_colors: vec![favorites_client::accounts::ColorCount {
+                color: "red".into(),
+                count: 2,
+            }],
+            favorites_created: 5,
+        }
+    );
+}
+
+#[test]
+fn handle_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Handle")
+        .unwrap();
+    assert_eq!(account.discriminator[..], HANDLE_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Handle"),
+        [
+            ("owner", IdlType::Pubkey),
+            ("favorites", IdlType::Pubkey),
+            ("handle", IdlType::String),
+        ]
+    );
+
+    let (owner, favorites) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = Vec::new();
+    favorites::Handle {
+        owner,
+        favorites,
+        handle: "slava".into(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Handle::try_from_bytes(&data).unwrap(),
+        Handle {
+            owner,
+            favorites,
+            handle: "slava".into(),
+        }
+    );
+}
+
+#[test]
+fn config_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Config")
+        .unwrap();
+    assert_eq!(account.discriminator[..], CONFIG_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Config"),
+        [
+            ("admin", IdlType::Pubkey),
+            ("gate_mint", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("gate_min_amount", IdlType::U64),
+            ("vote_mint", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("fee_mint", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("fee_amount", IdlType::U64),
+            ("premium_mint", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("premium_price", IdlType::U64),
+        ]
+    );
+
+    let admin = Pubkey::new_unique();
+    let gate_mint = Some(Pubkey::new_unique());
+    let vote_mint = Some(Pubkey::new_unique());
+    let fee_mint = Some(Pubkey::new_unique());
+    let premium_mint = Some(Pubkey::new_unique());
+    let mut data = Vec::new();
+    favorites::Config {
+        admin,
+        gate_mint,
+        gate_min_amount: 10,
+        vote_mint,
+        fee_mint,
+        fee_amount: 1_000,
+        premium_mint,
+        premium_price: 20,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Config::try_from_bytes(&data).unwrap(),
+        Config {
+            admin,
+            gate_mint,
+            gate_min_amount: 10,
+            vote_mint,
+            fee_mint,
+            fee_amount: 1_000,
+            premium_mint,
+            premium_price: 20,
+        }
+    );
+}
+
+#[test]
+fn nft_favorites_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "NftFavorites")
+        .unwrap();
+    assert_eq!(account.discriminator[..], NFT_FAVORITES_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "NftFavorites"),
+        [
+            ("mint", IdlType::Pubkey),
+            ("number", IdlType::U64),
+            ("color", IdlType::String),
+        ]
+    );
+
+    let mint = Pubkey::new_unique();
+    let mut data = Vec::new();
+    favorites::NftFavorites {
+        mint,
+        number: 42,
+        color: "red".into(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        NftFavorites::try_from_bytes(&data).unwrap(),
+        NftFavorites {
+            mint,
+            number: 42,
+            color: "red".into(),
+        }
+    );
+}
+
+#[test]
+fn profile_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Profile")
+        .unwrap();
+    assert_eq!(account.discriminator[..], PROFILE_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Profile"),
+        [("number", IdlType::U64), ("color", IdlType::String)]
+    );
+    assert_eq!(premium::FREE_PROFILES, favorites::FREE_PROFILES);
+    assert_eq!(premium::MAX_PROFILES, favorites::MAX_PROFILES);
+    assert_eq!(premium::PREMIUM_PERIOD, favorites::PREMIUM_PERIOD);
+
+    let mut data = Vec::new();
+    favorites::Profile {
+        number: 42,
+        color: "red".into(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(
+        favorites::ANCHOR_DISCRIMINATOR_SIZE + favorites::Profile::INIT_SPACE,
+        0,
+    );
+    assert_eq!(
+        Profile::try_from_bytes(&data).unwrap(),
+        Profile {
+            number: 42,
+            color: "red".into(),
+        }
+    );
+}
+
+#[test]
+fn referrals_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Referrals")
+        .unwrap();
+    assert_eq!(account.discriminator[..], REFERRALS_DISCRIMINATOR[..]);
+    assert_eq!(fields(&idl, "Referrals"), [("count", IdlType::U64)]);
+
+    let mut data = Vec::new();
+    favorites::Referrals { count: 3 }
+        .try_serialize(&mut data)
+        .unwrap();
+    assert_eq!(
+        Referrals::try_from_bytes(&data).unwrap(),
+        Referrals { count: 3 }
+    );
+    assert_eq!(referrals::MAX_REFERRAL_DEPTH, favorites::MAX_REFERRAL_DEPTH);
+}
+
+#[test]
+fn recovery_layouts_match() {
+    assert_eq!(RECOVERY_TIMELOCK, favorites::RECOVERY_TIMELOCK);
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Recovery")
+        .unwrap();
+    assert_eq!(account.discriminator[..], RECOVERY_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "Recovery"),
+        [
+            ("recovery_key", IdlType::Pubkey),
+            ("new_owner", IdlType::Option(Box::new(IdlType::Pubkey))),
+            ("unlocks_at", IdlType::I64),
+        ]
+    );
+    let (user, recovery_key, new_owner) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut data = Vec::new();
+    favorites::Recovery {
+        recovery_key,
+        new_owner: Some(new_owner),
+        unlocks_at: 1_700_000_000,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let recovery = Recovery::try_from_bytes(&data).unwrap();
+    assert_eq!(
+        recovery,
+        Recovery {
+            recovery_key,
+            new_owner: Some(new_owner),
+            unlocks_at: 1_700_000_000,
+        }
+    );
+    assert!(!recovery.is_unlocked(1_699_999_999));
+    assert!(recovery.is_unlocked(1_700_000_000));
+
+    for (name, discriminator) in [
+        ("RecoveryKeySet", RECOVERY_KEY_SET_DISCRIMINATOR),
+        ("RecoveryKeyRemoved", RECOVERY_KEY_REMOVED_DISCRIMINATOR),
+        ("RecoveryStarted", RECOVERY_STARTED_DISCRIMINATOR),
+        ("RecoveryCancelled", RECOVERY_CANCELLED_DISCRIMINATOR),
+        ("RecoveryCompleted", RECOVERY_COMPLETED_DISCRIMINATOR),
+    ] {
+        let event = idl.events.iter().find(|event| event.name == name).unwrap();
+        assert_eq!(event.discriminator[..], discriminator[..], "{name}");
+    }
+    let mut data = favorites::RecoveryStarted::DISCRIMINATOR.to_vec();
+    favorites::RecoveryStarted {
+        user,
+        recovery_key,
+        new_owner,
+        unlocks_at: 1_700_000_000,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        RecoveryStarted::try_from_bytes(&data).unwrap(),
+        RecoveryStarted {
+            user,
+            recovery_key,
+            new_owner,
+            unlocks_at: 1_700_000_000,
+        }
+    );
+}
+
+#[test]
+fn tip_layouts_match() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "TipVault")
+        .unwrap();
+    assert_eq!(account.discriminator[..], TIP_VAULT_DISCRIMINATOR[..]);
+    assert_eq!(fields(&idl, "TipVault"), [("tips_total", IdlType::U64)]);
+    let mut data = Vec::new();
+    favorites::TipVault { tips_total: 9 }
+        .try_serialize(&mut data)
+        .unwrap();
+    assert_eq!(
+        TipVault::try_from_bytes(&data).unwrap(),
+        TipVault { tips_total: 9 }
+    );
+
+    let event = idl
+        .events
+        .iter()
+        .find(|event| event.name == "TipReceived")
+        .unwrap();
+    assert_eq!(event.discriminator[..], TIP_RECEIVED_DISCRIMINATOR[..]);
+    assert_eq!(
+        fields(&idl, "TipReceived"),
+        [
+            ("user", IdlType::Pubkey),
+            ("tipper", IdlType::Pubkey),
+            ("amount", IdlType::U64),
+            ("tips_total", IdlType::U64),
+        ]
+    );
+    let (user, tipper) = (Pubkey::new_unique(), Pubkey::new_unique());
+    // What `emit!` logs: the discriminator, then the event
+    let mut data = favorites::TipReceived::DISCRIMINATOR.to_vec();
+    favorites::TipReceived {
+        user,
+        tipper,
+        amount: 5,
+        tips_total: 9,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        TipReceived::try_from_bytes(&data).unwrap(),
+        TipReceived {
+            user,
+            tipper,
+            amount: 5,
+            tips_total: 9,
+        }
+    );
+}
+
+#[test]
+fn governance_layouts_match() {
+    let idl = checked_in_idl();
+    let discriminator = |name: &str| {
+        idl.accounts
+            .iter()
+            .find(|account| account.name == name)
+            .unwrap()
+            .discriminator
+            .clone()
+    };
+    assert_eq!(discriminator("Proposal")[..], PROPOSAL_DISCRIMINATOR[..]);
+    assert_eq!(discriminator("Tally")[..], TALLY_DISCRIMINATOR[..]);
+    assert_eq!(
+        discriminator("VoteReceipt")[..],
+        VOTE_RECEIPT_DISCRIMINATOR[..]
+    );
+    assert_eq!(
+        discriminator("CommunityColor")[..],
+        COMMUNITY_COLOR_DISCRIMINATOR[..]
+    );
+    assert_eq!(
+        fields(&idl, "Proposal"),
+        [
+            ("proposer", IdlType::Pubkey),
+            ("id", IdlType::U64),
+            ("vote_mint", IdlType::Pubkey),
+            ("voting_starts_at", IdlType::I64),
+            ("voting_ends_at", IdlType::I64),
+            ("finalized", IdlType::Bool),
+            ("colors", IdlType::Vec(Box::new(IdlType::String))),
+        ]
+    );
+    assert_eq!(
+        fields(&idl, "Tally"),
+        [
+            ("proposal", IdlType::Pubkey),
+            ("votes", IdlType::Vec(Box::new(IdlType::U64))),
+        ]
+    );
+    assert_eq!(
+        fields(&idl, "VoteReceipt"),
+        [("choice", IdlType::U8), ("amount", IdlType::U64)]
+    );
+    assert_eq!(
+        fields(&idl, "CommunityColor"),
+        [
+            ("proposal", IdlType::Pubkey),
+            ("voting_started_at", IdlType::I64),
+            ("decided_at", IdlType::I64),
+            ("color", IdlType::String),
+        ]
+    );
+
+    let (proposer, vote_mint, proposal) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut data = Vec::new();
+    favorites::Proposal {
+        proposer,
+        id: 3,
+        vote_mint,
+        voting_starts_at: 1_699_900_000,
+        voting_ends_at: 1_700_000_000,
+        finalized: true,
+        colors: vec!["red".into(), "blue".into()],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Proposal::try_from_bytes(&data).unwrap(),
+        Proposal {
+            proposer,
+            id: 3,
+            vote_mint,
+            voting_starts_at: 1_699_900_000,
+            voting_ends_at: 1_700_000_000,
+            finalized: true,
+            colors: vec!["red".into(), "blue".into()],
+        }
+    );
+
+    let mut data = Vec::new();
+    favorites::Tally {
+        proposal,
+        votes: vec![5, 7],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        Tally::try_from_bytes(&data).unwrap(),
+        Tally {
+            proposal,
+            votes: vec![5, 7],
+        }
+    );
+
+    let mut data = Vec::new();
+    favorites::VoteReceipt {
+        choice: 1,
+        amount: 7,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        VoteReceipt::try_from_bytes(&data).unwrap(),
+        VoteReceipt {
+            choice: 1,
+            amount: 7,
+        }
+    );
+
+    let mut data = Vec::new();
+    favorites::CommunityColor {
+        proposal,
+        voting_started_at: 1_699_900_000,
+        decided_at: 1_700_000_000,
+        color: "blue".into(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert_eq!(
+        CommunityColor::try_from_bytes(&data).unwrap(),
+        CommunityColor {
+            proposal,
+            voting_started_at: 1_699_900_000,
+            decided_at: 1_700_000_000,
+            color: "blue".into(),
+        }
+    );
+}
+
+#[test]
+fn update_without_session_passes_the_program_as_placeholder() {
+    let user = Pubkey::new_unique();
+    let ix = instructions::update_favorites(&user, &user, None, FeeAccounts::default(), 1, "red");
+    let expected: Vec<_> = favorites::accounts::UpdateFavorites {
+        user,
+        signer: user,
+        favorites: favorites_client::pda::favorites_address(&user).0,
+        stats: favorites_client::pda::stats_address().0,
+        session: None,
+        config: favorites_client::pda::config_address().0,
+        gate_token_account: None,
+        fee_exemption: None,
+        treasury: favorites_client::pda::treasury_address().0,
+        fee_token_account: None,
+        treasury_token_account: None,
+        token_program: None,
+        system_program: solana_program::system_program::ID,
+    }
+    .to_account_metas(None)
+    .into_iter()
+    .map(|meta| meta.pubkey)
+    .collect();
+    let actual: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn signed_update_message_matches() {
+    let user = Pubkey::new_unique();
+    assert_eq!(
+        signed::signed_update_message(&user, 9, "green", 4, 1_700_000_000),
+        favorites::signed_update_message(&user, 9, "green", 4, 1_700_000_000)
+    );
+    assert_eq!(
+        signed::SIGNED_UPDATE_PREFIX,
+        favorites::SIGNED_UPDATE_PREFIX
+    );
+}
+
+#[test]
+fn custom_errors_match() {
+    let idl = checked_in_idl();
+    let idl_errors: Vec<_> = idl
+        .errors
+        .iter()
+        .map(|error| (error.code, error.name.as_str(), error.msg.as_deref()))
+        .collect();
+    let client_errors: Vec<_> = CustomError::ALL
+        .iter()
+        .map(|error| (error.code(), error.name(), Some(error.message())))
+        .collect();
+    assert_eq!(idl_errors, client_errors);
+
+    for error in CustomError::ALL {
+        assert_eq!(CustomError::from_code(error.code()), Some(*error));
+    }
+    assert_eq!(CustomError::from_code(0), None);
+}
+
+// Rebuilds the IDL through the program's `idl-build` feature, the same way
+// `anchor idl build` does. Needs a nightly toolchain and takes minutes, so
+// it is ignored here and the CI `test` job runs it on its own:
+//
+//     cargo test -p favorites-client --test idl -- --ignored
+//
+// Regenerate the checked-in copy with
+// `anchor idl build -p favorites -o client/idl/favorites.json`.
+#[test]
+#[ignore]
+fn checked_in_idl_matches_program_build() {
+    // Otherwise the builder inherits the toolchain `cargo test` runs under
+    std::env::remove_var("RUSTUP_TOOLCHAIN");
+    let program_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../programs/favorites");
+    let built = anchor_lang_idl::build::IdlBuilder::new()
+        .program_path(program_path.canonicalize().unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(built).unwrap(),
+        serde_json::to_value(checked_in_idl()).unwrap(),
+        "idl/favorites.json is out of date"
+    );
+}
+
+#[test]
+fn collection_layout_matches() {
+    let idl = checked_in_idl();
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == "Collection")
+        .unwrap();
+    assert_eq!(account.discriminator[..], COLLECTION_DISCRIMINATOR[..]);
+    assert_eq!(fields(&idl, "Collection")[0], ("len", IdlType::U64));
+    let bytes = |len| IdlType::Array(Box::new(IdlType::U8), IdlArrayLen::Value(len));
+    assert_eq!(
+        fields(&idl, "CollectionItem"),
+        [
+            ("number", IdlType::U64),
+            ("color", bytes(collection_layout::MAX_COLOR_LEN)),
+            ("color_len", IdlType::U8),
+            ("padding", bytes(5)),
+        ]
+    );
+    assert_eq!(collection_layout::CAPACITY, favorites::COLLECTION_CAPACITY);
+    assert_eq!(collection_layout::MAX_COLOR_LEN, favorites::MAX_COLOR_LEN);
+    assert_eq!(
+        collection_layout::ITEM_SIZE,
+        std::mem::size_of::<favorites::CollectionItem>()
+    );
+    assert_eq!(
+        collection_layout::ACCOUNT_SIZE,
+        favorites::ANCHOR_DISCRIMINATOR_SIZE + std::mem::size_of::<favorites::Collection>()
+    );
+
+    let mut collection: favorites::Collection = bytemuck::Zeroable::zeroed();
+    for (index, (number, color)) in [(42, "red"), (7, "sea green")].into_iter().enumerate() {
+        let item = &mut collection.items[index];
+        item.number = number;
+        item.color[..color.len()].copy_from_slice(color.as_bytes());
+        item.color_len = color.len() as u8;
+    }
+    // Items past `len` are ignored
+    collection.items[2].number = 9;
+    collection.len = 2;
+    let mut data = favorites::Collection::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&collection));
+    assert_eq!(
+        Collection::try_from_bytes(&data).unwrap(),
+        Collection {
+            items: vec![
+                CollectionItem {
+                    number: 42,
+                    color: "red".into(),
+                },
+                CollectionItem {
+                    number: 7,
+                    color: "sea green".into(),
+                },
+            ],
+        }
+    );
+    assert!(Collection::try_from_bytes(&data[..data.len() - 1]).is_err());
+}
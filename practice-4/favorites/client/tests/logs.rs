@@ -0,0 +1,277 @@
+// Log decoding, and `watch` against a mock `logsSubscribe` websocket server.
+use std::{net::TcpListener, thread};
+
+use anchor_lang::{AnchorSerialize, Discriminator};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use favorites_client::{
+    accounts::{RecoveryStarted, TipReceived},
+    logs::{program_entries, watch, websocket_url, Event, LogEntry, LogFilter, Notification},
+};
+use serde_json::{json, Value};
+use solana_client::rpc_config::RpcTransactionLogsConfig;
+use solana_program::pubkey::Pubkey;
+use tungstenite::Message;
+
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+
+// "Program data:" line of a `TipReceived` event.
+fn tip_data(user: Pubkey, tipper: Pubkey) -> String {
+    let mut data = favorites::TipReceived::DISCRIMINATOR.to_vec();
+    favorites::TipReceived {
+        user,
+        tipper,
+        amount: 5,
+        tips_total: 9,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    format!("Program data: {}", BASE64_STANDARD.encode(data))
+}
+
+// The logs of a `set_favorites` by `user`, which pays the system program.
+fn set_favorites_logs(user: Pubkey) -> Vec<String> {
+    let program = favorites::ID;
+    vec![
+        format!("Program {program} invoke [1]"),
+        "Program log: Instruction: SetFavorites".into(),
+        format!("Program {SYSTEM_PROGRAM} invoke [2]"),
+        "Program log: not ours".into(),
+        format!("Program {SYSTEM_PROGRAM} success"),
+        format!("Program log: Greetings from {program}"),
+        format!("Program log: User {user}'s favorite number is 7 and favorite color is: red"),
+        format!("Program {program} consumed 5000 of 200000 compute units"),
+        format!("Program {program} success"),
+    ]
+}
+
+fn tip_logs(user: Pubkey, tipper: Pubkey) -> Vec<String> {
+    let program = favorites::ID;
+    vec![
+        format!("Program {program} invoke [1]"),
+        "Program log: Instruction: Tip".into(),
+        tip_data(user, tipper),
+        // Not an event of the program
+        "Program data: AAAAAAAAAAA=".into(),
+        format!("Program {program} success"),
+    ]
+}
+
+#[test]
+fn program_entries_keep_the_programs_own_lines() {
+    let (user, tipper) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut logs = set_favorites_logs(user);
+    // A tip emitted while another program runs is not the program's
+    logs.push(format!("Program {SYSTEM_PROGRAM} invoke [1]"));
+    logs.push(tip_data(user, tipper));
+    logs.push(format!("Program {SYSTEM_PROGRAM} success"));
+    logs.extend(tip_logs(user, tipper));
+
+    assert_eq!(
+        program_entries(&logs, &favorites::ID),
+        [
+            LogEntry::Message("Instruction: SetFavorites".into()),
+            LogEntry::Message(format!("Greetings from {}", favorites::ID)),
+            LogEntry::FavoritesSet {
+                user,
+                number: 7,
+                color: "red".into(),
+            },
+            LogEntry::Message("Instruction: Tip".into()),
+            LogEntry::Event(Event::TipReceived(TipReceived {
+                user,
+                tipper,
+                amount: 5,
+                tips_total: 9,
+            })),
+        ]
+    );
+}
+
+#[test]
+fn events_decode_by_discriminator() {
+    let (user, recovery_key, new_owner) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut data = favorites::RecoveryStarted::DISCRIMINATOR.to_vec();
+    favorites::RecoveryStarted {
+        user,
+        recovery_key,
+        new_owner,
+        unlocks_at: 1_700_000_000,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    let event = Event::try_from_bytes(&data).unwrap();
+    assert_eq!(
+        event,
+        Event::RecoveryStarted(RecoveryStarted {
+            user,
+            recovery_key,
+            new_owner,
+            unlocks_at: 1_700_000_000,
+        })
+    );
+    assert_eq!((event.name(), event.user()), ("RecoveryStarted", user));
+
+    assert_eq!(Event::try_from_bytes(&data[..8]), None);
+    assert_eq!(Event::try_from_bytes(&[0; 4]), None);
+}
+
+#[test]
+fn filter_drops_hidden_entries() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let notification = |failed, logs: Vec<String>| Notification {
+        slot: 1,
+        signature: "sig".into(),
+        failed,
+        entries: program_entries(&logs, &favorites::ID),
+    };
+
+    let for_alice = LogFilter {
+        user: Some(alice),
+        ..LogFilter::default()
+    };
+    let kept = for_alice
+        .apply(notification(false, set_favorites_logs(alice)))
+        .unwrap();
+    assert_eq!(
+        kept.entries,
+        [LogEntry::FavoritesSet {
+            user: alice,
+            number: 7,
+            color: "red".into(),
+        }]
+    );
+    assert_eq!(
+        for_alice.apply(notification(false, set_favorites_logs(bob))),
+        None
+    );
+    assert_eq!(
+        for_alice.apply(notification(true, set_favorites_logs(alice))),
+        None
+    );
+
+    let events_only = LogFilter {
+        events_only: true,
+        include_failed: true,
+        ..LogFilter::default()
+    };
+    assert_eq!(
+        events_only.apply(notification(true, set_favorites_logs(alice))),
+        None
+    );
+    let kept = events_only
+        .apply(notification(true, tip_logs(alice, bob)))
+        .unwrap();
+    assert!(kept.failed);
+    assert!(matches!(
+        kept.entries[..],
+        [LogEntry::Event(Event::TipReceived(_))]
+    ));
+}
+
+#[test]
+fn websocket_urls_follow_the_rpc_url() {
+    assert_eq!(
+        websocket_url("https://api.devnet.solana.com"),
+        "wss://api.devnet.solana.com"
+    );
+    assert_eq!(
+        websocket_url("http://localhost:8899"),
+        "ws://localhost:8900"
+    );
+    assert_eq!(
+        websocket_url("http://127.0.0.1:8899/rpc"),
+        "ws://127.0.0.1:8900/rpc"
+    );
+}
+
+// Serves one `logsSubscribe`: confirms it, pushes `notifications` and closes
+// the connection. Returns the URL and the subscribe request it got.
+fn mock_server(notifications: Vec<Value>) -> (String, thread::JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let request: Value =
+            serde_json::from_str(socket.read().unwrap().to_text().unwrap()).unwrap();
+        let confirmation = json!({ "jsonrpc": "2.0", "result": 7, "id": request["id"] });
+        socket
+            .send(Message::Text(confirmation.to_string()))
+            .unwrap();
+        for notification in notifications {
+            socket
+                .send(Message::Text(notification.to_string()))
+                .unwrap();
+        }
+        socket.close(None).unwrap();
+        // Until the client acknowledges the close
+        while socket.read().is_ok() {}
+        request
+    });
+    (url, server)
+}
+
+fn logs_notification(slot: u64, signature: &str, failed: bool, logs: Vec<String>) -> Value {
+    let err = failed.then(|| json!({ "InstructionError": [0, { "Custom": 6000 }] }));
+    json!({
+        "jsonrpc": "2.0",
+        "method": "logsNotification",
+        "params": {
+            "result": {
+                "context": { "slot": slot },
+                "value": { "signature": signature, "err": err, "logs": logs },
+            },
+            "subscription": 7,
+        },
+    })
+}
+
+#[test]
+fn watch_feeds_the_notifications_the_filter_keeps() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (url, server) = mock_server(vec![
+        logs_notification(10, "first", false, set_favorites_logs(alice)),
+        logs_notification(11, "failed", true, set_favorites_logs(alice)),
+        logs_notification(12, "bob", false, set_favorites_logs(bob)),
+        logs_notification(13, "tip", false, tip_logs(alice, bob)),
+    ]);
+
+    let filter = LogFilter {
+        user: Some(alice),
+        ..LogFilter::default()
+    };
+    let mut feed = Vec::new();
+    watch(
+        &url,
+        &favorites::ID,
+        RpcTransactionLogsConfig { commitment: None },
+        &filter,
+        |notification| feed.push(notification),
+    )
+    .unwrap();
+
+    let request = server.join().unwrap();
+    assert_eq!(request["method"], "logsSubscribe");
+    assert_eq!(
+        request["params"][0],
+        json!({ "mentions": [favorites::ID.to_string()] })
+    );
+    let seen: Vec<_> = feed
+        .iter()
+        .map(|notification| (notification.slot, notification.signature.as_str()))
+        .collect();
+    assert_eq!(seen, [(10, "first"), (13, "tip")]);
+    assert_eq!(
+        feed[1].entries,
+        [LogEntry::Event(Event::TipReceived(TipReceived {
+            user: alice,
+            tipper: bob,
+            amount: 5,
+            tips_total: 9,
+        }))]
+    );
+}
@@ -0,0 +1,112 @@
+// `registry_users` against an in-memory stand-in for `getAccountInfo`.
+use std::collections::HashMap;
+
+use anchor_lang::AccountSerialize;
+use favorites_client::{
+    pda::{registry_address, registry_page_address},
+    registry::{open_page, registry_users, AccountSource, RegistryError},
+};
+use solana_account::Account;
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Default)]
+struct InMemoryCluster {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl InMemoryCluster {
+    fn add(&mut self, address: Pubkey, account: impl AccountSerialize) {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        self.accounts.insert(
+            address,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: favorites::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    fn add_registry(&mut self, open_page: u32, user_count: u64) {
+        self.add(
+            registry_address().0,
+            favorites::Registry {
+                open_page,
+                user_count,
+            },
+        );
+    }
+
+    fn add_page(&mut self, page: u32, users: &[Pubkey]) {
+        self.add(
+            registry_page_address(page).0,
+            favorites::RegistryPage {
+                users: users.to_vec(),
+            },
+        );
+    }
+}
+
+impl AccountSource for InMemoryCluster {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>> {
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+fn users(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[test]
+fn walks_every_page_in_order() {
+    let first = users(3);
+    let second = users(2);
+    let mut cluster = InMemoryCluster::default();
+    cluster.add_registry(2, 5);
+    cluster.add_page(0, &first);
+    cluster.add_page(1, &second);
+    // Page 2 is open but nobody registered since page 1 filled up
+
+    let listed: Vec<Pubkey> = registry_users(&cluster)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(listed, [first, second].concat());
+    assert_eq!(open_page(&cluster).unwrap(), 2);
+}
+
+#[test]
+fn empty_without_a_registry() {
+    let cluster = InMemoryCluster::default();
+    assert_eq!(registry_users(&cluster).unwrap().count(), 0);
+    assert_eq!(open_page(&cluster).unwrap(), 0);
+}
+
+#[test]
+fn stops_at_a_page_that_does_not_decode() {
+    let mut cluster = InMemoryCluster::default();
+    cluster.add_registry(1, 2);
+    cluster.add_page(0, &users(1));
+    // A `Registry` where page 1 should be
+    cluster.add(
+        registry_page_address(1).0,
+        favorites::Registry {
+            open_page: 0,
+            user_count: 0,
+        },
+    );
+
+    let listed: Vec<_> = registry_users(&cluster).unwrap().collect();
+
+    assert_eq!(listed.len(), 2);
+    assert!(listed[0].is_ok());
+    assert!(matches!(
+        listed[1],
+        Err(RegistryError::Decode(address, _)) if address == registry_page_address(1).0
+    ));
+}
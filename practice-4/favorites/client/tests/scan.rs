@@ -0,0 +1,220 @@
+// `list_favorites` against an in-memory stand-in for `getProgramAccounts`
+// that applies the request's filters the way the RPC node does.
+use std::cell::Cell;
+
+use anchor_lang::{AccountSerialize, AnchorSerialize, Space};
+use favorites_client::{
+    accounts::{favorites_layout, FAVORITES_DISCRIMINATOR},
+    scan::{
+        delegate_filters, favorites_filters, list_favorites, DelegateFilter, ProgramAccountsSource,
+    },
+    Favorites,
+};
+use solana_account::Account;
+use solana_client::{
+    client_error::ClientError, rpc_config::RpcProgramAccountsConfig, rpc_filter::RpcFilterType,
+};
+use solana_program::pubkey::Pubkey;
+
+#[derive(Default)]
+struct InMemoryCluster {
+    accounts: Vec<(Pubkey, Account)>,
+    /// How many accounts the requests so far returned
+    returned: Cell<usize>,
+}
+
+impl InMemoryCluster {
+    fn add(&mut self, owner: Pubkey, data: Vec<u8>) -> Pubkey {
+        let address = Pubkey::new_unique();
+        let account = Account {
+            lamports: 1_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.accounts.push((address, account));
+        address
+    }
+
+    fn add_favorites(&mut self, number: u64, color: &str, delegate: Option<Pubkey>) -> Pubkey {
+        self.add(favorites::ID, favorites_data(number, color, delegate))
+    }
+}
+
+impl ProgramAccountsSource for InMemoryCluster {
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> Result<Vec<(Pubkey, Account)>, Box<ClientError>> {
+        let filters = config.filters.unwrap_or_default();
+        for filter in &filters {
+            filter
+                .verify()
+                .expect("the RPC node would reject this filter");
+        }
+        let accounts: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.owner == *program_id
+                    && filters.iter().all(|filter| matches(filter, &account.data))
+            })
+            .cloned()
+            .collect();
+        self.returned.set(self.returned.get() + accounts.len());
+        Ok(accounts)
+    }
+}
+
+fn matches(filter: &RpcFilterType, data: &[u8]) -> bool {
+    match filter {
+        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        RpcFilterType::TokenAccountState => false,
+    }
+}
+
+fn favorites_data(number: u64, color: &str, delegate: Option<Pubkey>) -> Vec<u8> {
+    let mut data = Vec::new();
+    favorites::Favorites {
+        number,
+        color: color.into(),
+        delegate,
+        version: favorites::FAVORITES_VERSION,
+        registry_page: 0,
+        nonce: 0,
+        premium_until: 0,
+        referrer: None,
+        session_generation: 1,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    // Premium colors grow the account past the initial space
+    let space = favorites::ANCHOR_DISCRIMINATOR_SIZE + favorites::Favorites::INIT_SPACE;
+    data.resize(data.len().max(space), 0);
+    data
+}
+
+fn addresses(favorites: &[(Pubkey, Favorites)]) -> Vec<Pubkey> {
+    favorites.iter().map(|(address, _)| *address).collect()
+}
+
+fn sorted(mut addresses: Vec<Pubkey>) -> Vec<Pubkey> {
+    addresses.sort();
+    addresses
+}
+
+#[test]
+fn lists_only_favorites_accounts() {
+    let mut cluster = InMemoryCluster::default();
+    let delegate = Pubkey::new_unique();
+    let red = cluster.add_favorites(1, "red", None);
+    let blue = cluster.add_favorites(2, "blue", Some(delegate));
+    // Same bytes under another owner, and another account type of the program
+    cluster.add(Pubkey::new_unique(), favorites_data(3, "green", None));
+    cluster.add(favorites::ID, vec![0; 100]);
+
+    let favorites = list_favorites(&cluster, DelegateFilter::Any).unwrap();
+
+    assert_eq!(addresses(&favorites), sorted(vec![red, blue]));
+    let (_, decoded) = favorites
+        .iter()
+        .find(|(address, _)| *address == blue)
+        .unwrap();
+    assert_eq!(
+        decoded,
+        &Favorites {
+            number: 2,
+            color: "blue".into(),
+            delegate: Some(delegate),
+            version: favorites::FAVORITES_VERSION,
+            registry_page: 0,
+            nonce: 0,
+            premium_until: 0,
+            referrer: None,
+            session_generation: 1,
+        }
+    );
+}
+
+#[test]
+fn filters_by_delegate() {
+    let mut cluster = InMemoryCluster::default();
+    let delegate = Pubkey::new_unique();
+    let without = cluster.add_favorites(1, "red", None);
+    let with = cluster.add_favorites(2, "blue", Some(delegate));
+    let with_too = cluster.add_favorites(3, "green", Some(delegate));
+    cluster.add_favorites(4, "black", Some(Pubkey::new_unique()));
+
+    let delegated = list_favorites(&cluster, DelegateFilter::Is(delegate)).unwrap();
+    assert_eq!(addresses(&delegated), sorted(vec![with, with_too]));
+
+    let undelegated = list_favorites(&cluster, DelegateFilter::Unset).unwrap();
+    assert_eq!(addresses(&undelegated), vec![without]);
+}
+
+#[test]
+fn fetches_only_the_matching_accounts() {
+    let mut cluster = InMemoryCluster::default();
+    let delegate = Pubkey::new_unique();
+    let empty = cluster.add_favorites(1, "", Some(delegate));
+    let premium = cluster.add_favorites(
+        2,
+        &"b".repeat(favorites_layout::MAX_PREMIUM_COLOR_LEN),
+        Some(delegate),
+    );
+    cluster.add_favorites(3, "green", Some(Pubkey::new_unique()));
+    cluster.add_favorites(4, "black", None);
+    // The delegate's key where another color length would put it
+    let mut lookalike = favorites_data(5, "red", None);
+    let offset = favorites_layout::COLOR_OFFSET + 4 + "blue".len();
+    lookalike[offset] = 1;
+    lookalike[offset + 1..offset + 33].copy_from_slice(delegate.as_ref());
+    cluster.add(favorites::ID, lookalike);
+
+    let delegated = list_favorites(&cluster, DelegateFilter::Is(delegate)).unwrap();
+
+    assert_eq!(addresses(&delegated), sorted(vec![empty, premium]));
+    assert_eq!(cluster.returned.get(), 2);
+}
+
+#[test]
+fn lists_and_filters_legacy_favorites() {
+    let mut cluster = InMemoryCluster::default();
+    let delegate = Pubkey::new_unique();
+    let mut data = FAVORITES_DISCRIMINATOR.to_vec();
+    (5u64, "green".to_string(), Some(delegate))
+        .serialize(&mut data)
+        .unwrap();
+    data.resize(favorites_layout::LEGACY_ACCOUNT_SIZE, 0);
+    let legacy = cluster.add(favorites::ID, data);
+    let current = cluster.add_favorites(1, "red", Some(delegate));
+
+    let delegated = list_favorites(&cluster, DelegateFilter::Is(delegate)).unwrap();
+    assert_eq!(addresses(&delegated), sorted(vec![legacy, current]));
+    let (_, decoded) = delegated
+        .iter()
+        .find(|(address, _)| *address == legacy)
+        .unwrap();
+    assert_eq!((decoded.number, decoded.color.as_str()), (5, "green"));
+    assert!(!decoded.is_migrated());
+}
+
+#[test]
+fn filters_are_valid_for_the_rpc_node() {
+    for rpc_filter in favorites_filters() {
+        rpc_filter.verify().unwrap();
+    }
+    for color_len in 0..=favorites_layout::MAX_PREMIUM_COLOR_LEN {
+        for delegate in [
+            DelegateFilter::Unset,
+            DelegateFilter::Is(Pubkey::new_unique()),
+        ] {
+            for rpc_filter in delegate_filters(color_len, &delegate) {
+                rpc_filter.verify().unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// `fetch_stats` and `render_stats` against an in-memory stand-in for
+// `getAccountInfo`.
+use std::collections::HashMap;
+
+use anchor_lang::AccountSerialize;
+use favorites_client::{
+    pda::stats_address,
+    registry::AccountSource,
+    stats::{fetch_stats, render_stats, StatsError},
+};
+use solana_account::Account;
+use solana_client::client_error::ClientError;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Default)]
+struct InMemoryCluster {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl InMemoryCluster {
+    fn add(&mut self, address: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(
+            address,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: favorites::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+}
+
+impl AccountSource for InMemoryCluster {
+    fn account(&self, address: &Pubkey) -> Result<Option<Account>, Box<ClientError>> {
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+fn color(color: &str, count: u64) -> favorites::ColorCount {
+    favorites::ColorCount {
+        color: color.into(),
+        count,
+    }
+}
+
+#[test]
+fn none_before_the_first_user() {
+    assert_eq!(fetch_stats(&InMemoryCluster::default()).unwrap(), None);
+}
+
+#[test]
+fn renders_totals_and_colors_by_count() {
+    let mut cluster = InMemoryCluster::default();
+    let mut data = Vec::new();
+    favorites::Stats {
+        user_count: 3,
+        number_sum: 10,
+        min_number: Some(1),
+        max_number: Some(7),
+        top_colors: vec![color("red", 1), color("blue", 2), color("green", 1)],
+        favorites_created: 4,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    cluster.add(stats_address().0, data);
+
+    let stats = fetch_stats(&cluster).unwrap().unwrap();
+    assert_eq!(
+        stats.ranked_colors(),
+        [("blue", 2), ("green", 1), ("red", 1)]
+    );
+    assert_eq!(
+        render_stats(&stats),
+        "users:    3\n\
+         sum:      10\n\
+         average:  3.33\n\
+         min:      1\n\
+         max:      7\n\
+         colors:\n  \
+           1. \"blue\" x2\n  \
+           2. \"green\" x1\n  \
+           3. \"red\" x1\n"
+    );
+}
+
+#[test]
+fn renders_empty_stats_without_an_average() {
+    let mut cluster = InMemoryCluster::default();
+    let mut data = Vec::new();
+    favorites::Stats {
+        user_count: 0,
+        number_sum: 0,
+        min_number: Some(4),
+        max_number: Some(4),
+        top_colors: vec![],
+        favorites_created: 1,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    cluster.add(stats_address().0, data);
+
+    let rendered = render_stats(&fetch_stats(&cluster).unwrap().unwrap());
+    assert!(rendered.contains("average:  -\n"));
+    assert!(rendered.ends_with("colors:\n"));
+}
+
+#[test]
+fn foreign_account_is_a_decode_error() {
+    let mut cluster = InMemoryCluster::default();
+    cluster.add(stats_address().0, vec![0; 64]);
+    assert!(matches!(
+        fetch_stats(&cluster),
+        Err(StatsError::Decode(address, _)) if address == stats_address().0
+    ));
+}
@@ -0,0 +1,105 @@
+//! Buffer accounts, which hold the program while it is uploaded.
+//!
+//! An upload interrupted halfway leaves a buffer holding part of the program
+//! and its rent. Passing it back to [`pending_chunks`] resumes the upload;
+//! closing it returns the rent.
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_loader_v3_interface::{
+    instruction as loader_instruction, state::UpgradeableLoaderState,
+};
+use solana_sdk::{
+    account::Account, hash::Hash, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    transaction::Transaction,
+};
+use solana_sdk_ids::bpf_loader_upgradeable;
+
+use favorites_client::scan::ProgramAccountsSource;
+
+/// Bytes before the program in a buffer account: the state and the authority.
+pub const BUFFER_METADATA_SIZE: usize = UpgradeableLoaderState::size_of_buffer_metadata();
+
+/// A decoded buffer account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Buffer<'a> {
+    /// Who may write to, deploy from and close the buffer. `None` once used.
+    pub authority: Option<Pubkey>,
+    /// Everything written so far, zeros where nothing was.
+    pub bytes: &'a [u8],
+}
+
+/// Decodes a buffer account, or `None` for any other loader account.
+pub fn parse_buffer(data: &[u8]) -> Option<Buffer<'_>> {
+    match bincode::deserialize(data).ok()? {
+        UpgradeableLoaderState::Buffer { authority_address } => Some(Buffer {
+            authority: authority_address,
+            bytes: data.get(BUFFER_METADATA_SIZE..)?,
+        }),
+        _ => None,
+    }
+}
+
+/// The most program bytes a single `write` fits in a transaction that
+/// `authority` signs and pays for alone.
+pub fn max_chunk_size(buffer: &Pubkey, authority: &Pubkey) -> usize {
+    let ix = loader_instruction::write(buffer, authority, 0, Vec::new());
+    let message = Message::new_with_blockhash(&[ix], Some(authority), &Hash::default());
+    let size = bincode::serialized_size(&Transaction::new_unsigned(message))
+        .expect("a transaction always serializes") as usize;
+    // The instruction data length prefix grows by a byte past 127 bytes
+    PACKET_DATA_SIZE - size - 1
+}
+
+/// One `write` of `bytes` at `offset` in the buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    pub offset: u32,
+    pub bytes: &'a [u8],
+}
+
+/// The chunks of `program` a buffer holding `written` still needs: the ones
+/// that differ. A fresh buffer holds zeros, so chunks of zeros are skipped.
+pub fn pending_chunks<'a>(program: &'a [u8], written: &[u8], chunk_size: usize) -> Vec<Chunk<'a>> {
+    program
+        .chunks(chunk_size)
+        .enumerate()
+        .map(|(index, bytes)| (index * chunk_size, bytes))
+        .filter(|(offset, bytes)| written.get(*offset..*offset + bytes.len()) != Some(*bytes))
+        .map(|(offset, bytes)| Chunk {
+            offset: offset as u32,
+            bytes,
+        })
+        .collect()
+}
+
+/// Filters selecting the buffers `authority` controls.
+pub fn buffer_filters(authority: &Pubkey) -> Vec<RpcFilterType> {
+    // The `Buffer` variant as a little endian u32, then `Some(authority)`
+    let mut bytes = vec![1, 0, 0, 0, 1];
+    bytes.extend_from_slice(authority.as_ref());
+    vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &bytes))]
+}
+
+/// Every buffer `authority` controls, sorted by address. Any of them not
+/// being deployed right now is an orphan of an interrupted upload.
+pub fn list_buffers(
+    source: &impl ProgramAccountsSource,
+    authority: &Pubkey,
+) -> Result<Vec<(Pubkey, Account)>, Box<ClientError>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(buffer_filters(authority)),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let mut buffers = source.program_accounts(&bpf_loader_upgradeable::ID, config)?;
+    buffers.retain(|(_, account)| parse_buffer(&account.data).is_some());
+    buffers.sort_by_key(|(address, _)| *address);
+    Ok(buffers)
+}
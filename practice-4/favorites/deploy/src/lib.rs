@@ -0,0 +1,10 @@
+//! Deploying and upgrading the favorites program through the upgradeable BPF
+//! loader.
+//!
+//! The program is first written into a buffer account, one transaction per
+//! chunk, then deployed or upgraded from it in one instruction. Everything
+//! here builds instructions or reads account data; `favorites-deploy` sends
+//! them.
+
+pub mod buffer;
+pub mod program;
@@ -0,0 +1,428 @@
+use std::{fs, io::Write, path::Path, path::PathBuf, thread, time::Duration};
+
+use clap::{Parser, Subcommand};
+use favorites_client::keypair;
+use favorites_deploy::{
+    buffer::{list_buffers, max_chunk_size, parse_buffer, pending_chunks},
+    program::{get_program_data_address, parse_program_data, program_hash, Verification},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_loader_v3_interface::{
+    instruction as loader_instruction, state::UpgradeableLoaderState,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+const DEFAULT_PROGRAM_PATH: &str = "target/deploy/favorites.so";
+
+#[derive(Parser)]
+#[command(
+    name = "favorites-deploy",
+    version,
+    about = "Deploy and upgrade the favorites program"
+)]
+struct Cli {
+    /// Cluster URL, or one of mainnet-beta, devnet, testnet, localhost
+    #[arg(short, long, global = true, default_value = "devnet")]
+    url: String,
+
+    /// Env variable holding the wallet's base58 secret key. The wallet pays,
+    /// owns the buffers and is the upgrade authority.
+    #[arg(long, global = true, default_value = "SECRET_KEY_S1")]
+    keypair_env: String,
+
+    /// The program to deploy or upgrade
+    #[arg(long, global = true, default_value_t = favorites_client::ID)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Upload the build into a buffer, deploy it or upgrade the program from
+    /// it, then verify the result
+    Deploy {
+        /// The program built by `anchor build`
+        #[arg(long, default_value = DEFAULT_PROGRAM_PATH)]
+        program: PathBuf,
+        /// Keypair file of the program id, only needed for the first deploy
+        #[arg(long, default_value = "target/deploy/favorites-keypair.json")]
+        program_keypair: PathBuf,
+        /// Finish an interrupted upload into this buffer instead of starting over
+        #[arg(long)]
+        resume_buffer: Option<Pubkey>,
+        /// Largest program the first deploy makes room for, in bytes
+        /// [default: twice the build]
+        #[arg(long)]
+        max_len: Option<usize>,
+    },
+    /// Compare the deployed program's hash with the local build's
+    Verify {
+        #[arg(long, default_value = DEFAULT_PROGRAM_PATH)]
+        program: PathBuf,
+    },
+    /// Print the deployed program's last deploy slot, upgrade authority and hash
+    Show,
+    /// Manage the buffers interrupted uploads leave behind
+    Buffers {
+        #[command(subcommand)]
+        command: BufferCommand,
+    },
+    /// Hand over the upgrade authority, or give it up
+    Authority {
+        #[command(subcommand)]
+        command: AuthorityCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BufferCommand {
+    /// List the wallet's buffers
+    List,
+    /// Close buffers, returning their rent to the wallet
+    Close {
+        #[arg(required_unless_present = "all")]
+        buffer: Option<Pubkey>,
+        /// Close every buffer of the wallet
+        #[arg(long, conflicts_with = "buffer")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthorityCommand {
+    /// Let only the new authority upgrade the program. It signs too, so the
+    /// program cannot end up with an authority nobody holds.
+    Transfer {
+        /// Env variable holding the new authority's base58 secret key
+        #[arg(long)]
+        new_authority_env: String,
+    },
+    /// Make the program immutable, for good
+    Finalize {
+        /// Confirms the program can never be upgraded again
+        #[arg(long, required = true)]
+        yes: bool,
+    },
+}
+
+fn cluster_url(url: &str) -> String {
+    match url {
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "localhost" | "l" => "http://localhost:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+// The first signer pays the fees
+fn send(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<Signature, String> {
+    let recent_blockhash = client
+        .get_latest_blockhash()
+        .map_err(|err| err.to_string())?;
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        recent_blockhash,
+    );
+    client
+        .send_and_confirm_transaction(&tx)
+        .map_err(|err| err.to_string())
+}
+
+fn read_program(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn fetch_data(client: &RpcClient, address: &Pubkey) -> Result<Option<Vec<u8>>, String> {
+    Ok(client
+        .get_account_with_commitment(address, client.commitment())
+        .map_err(|err| err.to_string())?
+        .value
+        .map(|account| account.data))
+}
+
+// The program data account, or `None` before the first deploy
+fn fetch_program_data(client: &RpcClient, program_id: &Pubkey) -> Result<Option<Vec<u8>>, String> {
+    fetch_data(client, &get_program_data_address(program_id))
+}
+
+// Creates a buffer for `program`, or checks `resume_buffer` can take it, and
+// writes whatever the buffer is missing
+fn upload(
+    client: &RpcClient,
+    wallet: &Keypair,
+    program: &[u8],
+    resume_buffer: Option<Pubkey>,
+) -> Result<Pubkey, String> {
+    let (buffer, written) = match resume_buffer {
+        Some(buffer) => {
+            let data = fetch_data(client, &buffer)?.ok_or(format!("No buffer at {buffer}"))?;
+            let parsed = parse_buffer(&data).ok_or(format!("{buffer} is not a buffer"))?;
+            if parsed.authority != Some(wallet.pubkey()) {
+                return Err(format!("The wallet is not the authority of {buffer}"));
+            }
+            if parsed.bytes.len() != program.len() {
+                return Err(format!(
+                    "{buffer} holds {} bytes but the build is {} bytes. Close it and start over.",
+                    parsed.bytes.len(),
+                    program.len()
+                ));
+            }
+            (buffer, parsed.bytes.to_vec())
+        }
+        None => {
+            let buffer = Keypair::new();
+            let lamports = client
+                .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(
+                    program.len(),
+                ))
+                .map_err(|err| err.to_string())?;
+            let ixs = loader_instruction::create_buffer(
+                &wallet.pubkey(),
+                &buffer.pubkey(),
+                &wallet.pubkey(),
+                lamports,
+                program.len(),
+            )
+            .map_err(|err| err.to_string())?;
+            send(client, &ixs, &[wallet, &buffer])?;
+            println!(
+                "📦 Buffer \x1b[95m{}\x1b[0m created. If the upload stops, pass it to --resume-buffer.",
+                buffer.pubkey()
+            );
+            (buffer.pubkey(), vec![0; program.len()])
+        }
+    };
+
+    let chunks = pending_chunks(program, &written, max_chunk_size(&buffer, &wallet.pubkey()));
+    for (index, chunk) in chunks.iter().enumerate() {
+        let ix = loader_instruction::write(
+            &buffer,
+            &wallet.pubkey(),
+            chunk.offset,
+            chunk.bytes.to_vec(),
+        );
+        send(client, &[ix], &[wallet])?;
+        print!("\r✍️  Wrote {}/{} chunks", index + 1, chunks.len());
+        std::io::stdout().flush().ok();
+    }
+    if !chunks.is_empty() {
+        println!();
+    }
+    Ok(buffer)
+}
+
+// The loader refuses to upgrade a program extended in the same slot
+fn wait_for_slot_after(client: &RpcClient, slot: u64) -> Result<(), String> {
+    while client.get_slot().map_err(|err| err.to_string())? <= slot {
+        thread::sleep(Duration::from_millis(400));
+    }
+    Ok(())
+}
+
+fn deploy(
+    client: &RpcClient,
+    wallet: &Keypair,
+    program_id: &Pubkey,
+    program: &[u8],
+    program_keypair: &Path,
+    max_len: Option<usize>,
+    buffer: &Pubkey,
+) -> Result<Signature, String> {
+    let Some(program_data) = fetch_program_data(client, program_id)? else {
+        let program_keypair = read_keypair_file(program_keypair)
+            .map_err(|err| format!("{}: {err}", program_keypair.display()))?;
+        if program_keypair.pubkey() != *program_id {
+            return Err(format!(
+                "The program keypair is for {}, not {program_id}",
+                program_keypair.pubkey()
+            ));
+        }
+        let lamports = client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program())
+            .map_err(|err| err.to_string())?;
+        #[allow(deprecated)]
+        let ixs = loader_instruction::deploy_with_max_program_len(
+            &wallet.pubkey(),
+            program_id,
+            buffer,
+            &wallet.pubkey(),
+            lamports,
+            max_len.unwrap_or(2 * program.len()),
+        )
+        .map_err(|err| err.to_string())?;
+        return send(client, &ixs, &[wallet, &program_keypair]);
+    };
+
+    let program_data = parse_program_data(&program_data)
+        .ok_or(format!("{program_id} is not an upgradeable program"))?;
+    if program_data.upgrade_authority != Some(wallet.pubkey()) {
+        return Err(match program_data.upgrade_authority {
+            Some(authority) => format!("The upgrade authority is {authority}, not the wallet"),
+            None => format!("{program_id} is final and cannot be upgraded"),
+        });
+    }
+    if program.len() > program_data.bytes.len() {
+        let additional_bytes = (program.len() - program_data.bytes.len()) as u32;
+        let ix = loader_instruction::extend_program_checked(
+            program_id,
+            &wallet.pubkey(),
+            Some(&wallet.pubkey()),
+            additional_bytes,
+        );
+        send(client, &[ix], &[wallet])?;
+        println!("📏 Program data extended by {additional_bytes} bytes");
+        let slot = client.get_slot().map_err(|err| err.to_string())?;
+        wait_for_slot_after(client, slot)?;
+    }
+    let ix = loader_instruction::upgrade(program_id, buffer, &wallet.pubkey(), &wallet.pubkey());
+    send(client, &[ix], &[wallet])
+}
+
+fn verify(client: &RpcClient, program_id: &Pubkey, program: &[u8]) -> Result<(), String> {
+    let data =
+        fetch_program_data(client, program_id)?.ok_or(format!("{program_id} is not deployed"))?;
+    let program_data =
+        parse_program_data(&data).ok_or(format!("{program_id} is not an upgradeable program"))?;
+    let verification = Verification::new(program_data.bytes, program);
+    println!("🔗 deployed: \x1b[95m{}\x1b[0m", verification.deployed_hash);
+    println!("💻 local:    \x1b[95m{}\x1b[0m", verification.local_hash);
+    if !verification.matches() {
+        return Err("The deployed program differs from the local build".to_string());
+    }
+    println!("✅ The deployed program matches the local build");
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let client =
+        RpcClient::new_with_commitment(cluster_url(&cli.url), CommitmentConfig::confirmed());
+    let wallet = || keypair::load_keypair(&cli.keypair_env);
+    let program_id = cli.program_id;
+
+    match cli.command {
+        Command::Deploy {
+            program,
+            program_keypair,
+            resume_buffer,
+            max_len,
+        } => {
+            let wallet = wallet()?;
+            let program = read_program(&program)?;
+            let buffer = upload(&client, &wallet, &program, resume_buffer)?;
+            let signature = deploy(
+                &client,
+                &wallet,
+                &program_id,
+                &program,
+                &program_keypair,
+                max_len,
+                &buffer,
+            )?;
+            println!("✅ {program_id} deployed");
+            println!("Signature: \x1b[35m{}\x1b[0m", signature);
+            verify(&client, &program_id, &program)?;
+        }
+        Command::Verify { program } => {
+            verify(&client, &program_id, &read_program(&program)?)?;
+        }
+        Command::Show => {
+            let data = fetch_program_data(&client, &program_id)?
+                .ok_or(format!("{program_id} is not deployed"))?;
+            let program_data = parse_program_data(&data)
+                .ok_or(format!("{program_id} is not an upgradeable program"))?;
+            println!("📦 program:   \x1b[95m{}\x1b[0m", program_id);
+            println!("🕒 slot:      {}", program_data.slot);
+            match program_data.upgrade_authority {
+                Some(authority) => println!("🔑 authority: \x1b[95m{}\x1b[0m", authority),
+                None => println!("🔑 authority: none, the program is final"),
+            }
+            println!("📏 capacity:  {} bytes", program_data.bytes.len());
+            println!("#️⃣  hash:      {}", program_hash(program_data.bytes));
+        }
+        Command::Buffers {
+            command: BufferCommand::List,
+        } => {
+            let wallet = wallet()?;
+            let buffers = list_buffers(&client, &wallet.pubkey()).map_err(|err| err.to_string())?;
+            if buffers.is_empty() {
+                println!("No buffers");
+            }
+            for (address, account) in buffers {
+                println!(
+                    "\x1b[95m{}\x1b[0m {} bytes, {} SOL",
+                    address,
+                    account.data.len(),
+                    lamports_to_sol(account.lamports)
+                );
+            }
+        }
+        Command::Buffers {
+            command: BufferCommand::Close { buffer, all },
+        } => {
+            let wallet = wallet()?;
+            let buffers = match buffer {
+                Some(buffer) if !all => vec![buffer],
+                _ => list_buffers(&client, &wallet.pubkey())
+                    .map_err(|err| err.to_string())?
+                    .into_iter()
+                    .map(|(address, _)| address)
+                    .collect(),
+            };
+            for buffer in buffers {
+                let ix = loader_instruction::close(&buffer, &wallet.pubkey(), &wallet.pubkey());
+                send(&client, &[ix], &[&wallet])?;
+                println!("🗑️  Closed \x1b[95m{}\x1b[0m", buffer);
+            }
+        }
+        Command::Authority { command } => {
+            let wallet = wallet()?;
+            let (signature, message) = match command {
+                AuthorityCommand::Transfer { new_authority_env } => {
+                    let new_authority = keypair::load_keypair(&new_authority_env)?;
+                    let ix = loader_instruction::set_upgrade_authority_checked(
+                        &program_id,
+                        &wallet.pubkey(),
+                        &new_authority.pubkey(),
+                    );
+                    let signature = send(&client, &[ix], &[&wallet, &new_authority])?;
+                    (signature, "Upgrade authority transferred")
+                }
+                AuthorityCommand::Finalize { .. } => {
+                    let ix = loader_instruction::set_upgrade_authority(
+                        &program_id,
+                        &wallet.pubkey(),
+                        None,
+                    );
+                    (send(&client, &[ix], &[&wallet])?, "Program finalized")
+                }
+            };
+            println!("✅ {message}");
+            println!("Signature: \x1b[35m{}\x1b[0m", signature);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run(Cli::parse()) {
+        eprintln!("❌ Error: \x1b[91m{}\x1b[0m", message);
+        std::process::exit(1);
+    }
+}
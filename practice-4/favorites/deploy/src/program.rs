@@ -0,0 +1,72 @@
+//! The deployed program: its program data account, and whether it matches a
+//! local build.
+use sha2::{Digest, Sha256};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::pubkey::Pubkey;
+
+pub use solana_loader_v3_interface::get_program_data_address;
+
+/// Bytes before the program in a program data account: the state, the slot of
+/// the last deploy and the upgrade authority.
+pub const PROGRAM_DATA_METADATA_SIZE: usize =
+    UpgradeableLoaderState::size_of_programdata_metadata();
+
+/// A decoded program data account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramData<'a> {
+    /// Slot of the last deploy or upgrade.
+    pub slot: u64,
+    /// Who may upgrade the program. `None` once finalized.
+    pub upgrade_authority: Option<Pubkey>,
+    /// The program, padded with zeros up to the largest upgrade it fits.
+    pub bytes: &'a [u8],
+}
+
+/// Decodes a program data account, or `None` for any other loader account.
+pub fn parse_program_data(data: &[u8]) -> Option<ProgramData<'_>> {
+    match bincode::deserialize(data).ok()? {
+        UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address,
+        } => Some(ProgramData {
+            slot,
+            upgrade_authority: upgrade_authority_address,
+            bytes: data.get(PROGRAM_DATA_METADATA_SIZE..)?,
+        }),
+        _ => None,
+    }
+}
+
+/// SHA-256 of `program` without its trailing zeros, in hex. Program data is
+/// zero padded, so this is what the local build and the deployed program
+/// agree on; `solana-verify get-program-hash` prints the same.
+pub fn program_hash(program: &[u8]) -> String {
+    let end = program
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last + 1);
+    Sha256::digest(&program[..end])
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The deployed program's hash next to the local build's.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verification {
+    pub deployed_hash: String,
+    pub local_hash: String,
+}
+
+impl Verification {
+    pub fn new(deployed: &[u8], local: &[u8]) -> Self {
+        Verification {
+            deployed_hash: program_hash(deployed),
+            local_hash: program_hash(local),
+        }
+    }
+
+    pub fn matches(&self) -> bool {
+        self.deployed_hash == self.local_hash
+    }
+}
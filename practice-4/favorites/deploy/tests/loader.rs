@@ -0,0 +1,255 @@
+// Uploading, deploying and upgrading through the upgradeable loader of a
+// local bank. The SPL programs bundled with solana-program-test stand in for
+// two builds of the favorites program.
+use favorites_deploy::{
+    buffer::{max_chunk_size, parse_buffer, pending_chunks, Chunk},
+    program::{get_program_data_address, parse_program_data, program_hash, Verification},
+};
+use solana_loader_v3_interface::{
+    instruction as loader_instruction, state::UpgradeableLoaderState,
+};
+use solana_program_test::{programs::spl_programs, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::Instruction,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+// The ELF of a bundled SPL program
+fn elf(program_id: &Pubkey) -> Vec<u8> {
+    spl_programs(&Rent::default())
+        .into_iter()
+        .find(|(address, _)| address == program_id)
+        .map(|(_, account)| account.data().to_vec())
+        .unwrap()
+}
+
+async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+}
+
+async fn data(context: &mut ProgramTestContext, address: &Pubkey) -> Option<Vec<u8>> {
+    let account = context.banks_client.get_account(*address).await.unwrap()?;
+    Some(account.data)
+}
+
+// Moves past the slot of the last deploy, which the loader refuses to touch
+// again. Warping further trips the program cache, which then cannot tell the
+// deployed program is already loaded.
+async fn next_slot(context: &mut ProgramTestContext) {
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 1).unwrap();
+}
+
+// Uploads `program` into a fresh buffer the context payer controls, `limit`
+// chunks at most, as if the upload stopped there
+async fn upload(context: &mut ProgramTestContext, program: &[u8], limit: usize) -> Pubkey {
+    let buffer = Keypair::new();
+    let authority = context.payer.pubkey();
+    let lamports =
+        Rent::default().minimum_balance(UpgradeableLoaderState::size_of_buffer(program.len()));
+    let ixs = loader_instruction::create_buffer(
+        &authority,
+        &buffer.pubkey(),
+        &authority,
+        lamports,
+        program.len(),
+    )
+    .unwrap();
+    send(context, &ixs, &[&buffer]).await;
+    write(context, &buffer.pubkey(), program, limit).await;
+    buffer.pubkey()
+}
+
+// Writes up to `limit` of the chunks the buffer is missing
+async fn write(context: &mut ProgramTestContext, buffer: &Pubkey, program: &[u8], limit: usize) {
+    let authority = context.payer.pubkey();
+    let written = data(context, buffer).await.unwrap();
+    let written = parse_buffer(&written).unwrap().bytes.to_vec();
+    let chunk_size = max_chunk_size(buffer, &authority);
+    for Chunk { offset, bytes } in pending_chunks(program, &written, chunk_size)
+        .into_iter()
+        .take(limit)
+    {
+        let ix = loader_instruction::write(buffer, &authority, offset, bytes.to_vec());
+        send(context, &[ix], &[]).await;
+    }
+}
+
+async fn verification(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    local: &[u8],
+) -> Verification {
+    let program_data = data(context, &get_program_data_address(program_id))
+        .await
+        .unwrap();
+    Verification::new(parse_program_data(&program_data).unwrap().bytes, local)
+}
+
+#[test]
+fn resumed_uploads_only_write_what_differs() {
+    let program: Vec<u8> = (0..=255).cycle().take(1_000).collect();
+    let mut written = vec![0; program.len()];
+    // All of it into a fresh buffer, in 300 byte chunks
+    let chunks = pending_chunks(&program, &written, 300);
+    assert_eq!(
+        chunks.iter().map(|chunk| chunk.offset).collect::<Vec<_>>(),
+        [0, 300, 600, 900]
+    );
+    assert_eq!(chunks[3].bytes.len(), 100);
+
+    written[..600].copy_from_slice(&program[..600]);
+    let chunks = pending_chunks(&program, &written, 300);
+    assert_eq!(
+        chunks.iter().map(|chunk| chunk.offset).collect::<Vec<_>>(),
+        [600, 900]
+    );
+
+    // Chunks of zeros are already there in a fresh buffer
+    let sparse = [vec![0; 300], vec![1; 300]].concat();
+    let chunks = pending_chunks(&sparse, &vec![0; 600], 300);
+    assert_eq!(
+        chunks,
+        [Chunk {
+            offset: 300,
+            bytes: &sparse[300..]
+        }]
+    );
+}
+
+#[test]
+fn hash_ignores_the_zero_padding_only() {
+    let program = [7, 0, 3, 0];
+    let padded = [7, 0, 3, 0, 0, 0, 0, 0];
+    assert_eq!(program_hash(&program), program_hash(&padded));
+    // `printf '\x07\x00\x03' | sha256sum`
+    assert_eq!(
+        program_hash(&program),
+        "82b07d32edc34c45a26e4a524887f38d40169ffd7e75d77e8a99f3effba09641"
+    );
+    assert!(!Verification::new(&[7, 0, 4, 0, 0], &program).matches());
+    assert!(Verification::new(&padded, &program).matches());
+}
+
+#[tokio::test]
+async fn deploys_then_upgrades_into_a_larger_program() {
+    let mut context = ProgramTest::default().start_with_context().await;
+    let authority = context.payer.pubkey();
+    let program = Keypair::new();
+    let first = elf(&MEMO_PROGRAM_ID);
+    let second = elf(&TOKEN_PROGRAM_ID);
+    assert!(second.len() > first.len());
+
+    let buffer = upload(&mut context, &first, usize::MAX).await;
+    let lamports = Rent::default().minimum_balance(UpgradeableLoaderState::size_of_program());
+    #[allow(deprecated)]
+    let ixs = loader_instruction::deploy_with_max_program_len(
+        &authority,
+        &program.pubkey(),
+        &buffer,
+        &authority,
+        lamports,
+        first.len(),
+    )
+    .unwrap();
+    send(&mut context, &ixs, &[&program]).await;
+    assert!(verification(&mut context, &program.pubkey(), &first)
+        .await
+        .matches());
+    // The buffer is spent
+    assert!(data(&mut context, &buffer).await.is_none());
+
+    let buffer = upload(&mut context, &second, usize::MAX).await;
+    next_slot(&mut context).await;
+    let additional_bytes = (second.len() - first.len()) as u32;
+    let ix = loader_instruction::extend_program_checked(
+        &program.pubkey(),
+        &authority,
+        Some(&authority),
+        additional_bytes,
+    );
+    send(&mut context, &[ix], &[]).await;
+    next_slot(&mut context).await;
+    let ix = loader_instruction::upgrade(&program.pubkey(), &buffer, &authority, &authority);
+    send(&mut context, &[ix], &[]).await;
+
+    let verification = verification(&mut context, &program.pubkey(), &second).await;
+    assert!(verification.matches());
+    assert_ne!(verification.deployed_hash, program_hash(&first));
+
+    // The new authority co-signs the transfer
+    let new_authority = Keypair::new();
+    let ix = loader_instruction::set_upgrade_authority_checked(
+        &program.pubkey(),
+        &authority,
+        &new_authority.pubkey(),
+    );
+    send(&mut context, &[ix], &[&new_authority]).await;
+    let program_data = data(&mut context, &get_program_data_address(&program.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(
+        parse_program_data(&program_data).unwrap().upgrade_authority,
+        Some(new_authority.pubkey())
+    );
+
+    let ix =
+        loader_instruction::set_upgrade_authority(&program.pubkey(), &new_authority.pubkey(), None);
+    send(&mut context, &[ix], &[&new_authority]).await;
+    let program_data = data(&mut context, &get_program_data_address(&program.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(
+        parse_program_data(&program_data).unwrap().upgrade_authority,
+        None
+    );
+}
+
+#[tokio::test]
+async fn interrupted_uploads_resume_or_close() {
+    let mut context = ProgramTest::default().start_with_context().await;
+    let authority = context.payer.pubkey();
+    let program = elf(&MEMO_PROGRAM_ID);
+
+    let buffer = upload(&mut context, &program, 3).await;
+    let written = data(&mut context, &buffer).await.unwrap();
+    let parsed = parse_buffer(&written).unwrap();
+    assert_eq!(parsed.authority, Some(authority));
+    assert_ne!(parsed.bytes, &program[..]);
+
+    write(&mut context, &buffer, &program, usize::MAX).await;
+    let written = data(&mut context, &buffer).await.unwrap();
+    assert_eq!(parse_buffer(&written).unwrap().bytes, &program[..]);
+
+    let before = context.banks_client.get_balance(authority).await.unwrap();
+    let ix = loader_instruction::close(&buffer, &authority, &authority);
+    send(&mut context, &[ix], &[]).await;
+    assert!(data(&mut context, &buffer).await.is_none());
+    let rent = Rent::default().minimum_balance(written.len());
+    let fee = 5_000;
+    assert_eq!(
+        context.banks_client.get_balance(authority).await.unwrap(),
+        before + rent - fee
+    );
+}
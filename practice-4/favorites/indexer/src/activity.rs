@@ -0,0 +1,364 @@
+//! What a transaction did to users' favorites.
+//!
+//! Instructions say what was set, updated, delegated, moved or closed, and
+//! `TipReceived` events, logged as "Program data: <base64>", what was tipped.
+//! The logs also give the order the program ran in, so events land between
+//! the instructions that emitted them.
+use std::{fmt, str::FromStr};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use borsh::BorshDeserialize;
+use favorites_client::{
+    accounts::{TipReceived, TIP_RECEIVED_DISCRIMINATOR},
+    instructions::{
+        CLOSE_FAVORITES_DISCRIMINATOR, COMPLETE_RECOVERY_DISCRIMINATOR,
+        SET_AUTHORITY_DISCRIMINATOR, SET_FAVORITES_DISCRIMINATOR, TRANSFER_FAVORITES_DISCRIMINATOR,
+        UPDATE_FAVORITES_DISCRIMINATOR, UPDATE_FAVORITES_SIGNED_DISCRIMINATOR,
+    },
+    DISCRIMINATOR_SIZE,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+};
+
+/// What happened to a user's favorites.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// `set_favorites` created them.
+    Set { number: u64, color: String },
+    /// `update_favorites` or `update_favorites_signed` overwrote them.
+    /// `signer` is the owner, their delegate or session key, or for signed
+    /// updates the relayer paying the fees.
+    Update {
+        number: u64,
+        color: String,
+        signer: Pubkey,
+    },
+    /// `set_authority` set or removed the delegate.
+    SetDelegate { delegate: Option<Pubkey> },
+    /// `tip` sent `amount` lamports, bringing the tip vault to `tips_total`.
+    Tip {
+        tipper: Pubkey,
+        amount: u64,
+        tips_total: u64,
+    },
+    /// `transfer_favorites` or `complete_recovery` moved them to `new_owner`.
+    MovedOut { new_owner: Pubkey },
+    /// `transfer_favorites` or `complete_recovery` moved `previous_owner`'s
+    /// favorites here.
+    MovedIn { previous_owner: Pubkey },
+    /// `close_favorites` closed them.
+    Close,
+}
+
+impl Change {
+    /// The name the store records the change under.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::Set { .. } => "set",
+            Change::Update { .. } => "update",
+            Change::SetDelegate { .. } => "set_delegate",
+            Change::Tip { .. } => "tip",
+            Change::MovedOut { .. } => "moved_out",
+            Change::MovedIn { .. } => "moved_in",
+            Change::Close => "close",
+        }
+    }
+}
+
+/// A change to `user`'s favorites.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Activity {
+    pub user: Pubkey,
+    pub change: Change,
+}
+
+/// A transaction mentioning the program, decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Failed transactions change nothing; they are kept so that the cursor
+    /// moves past them.
+    pub failed: bool,
+    /// The changes in the order the program made them.
+    pub activities: Vec<Activity>,
+    /// What the program logged with `msg!`.
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ActivityError {
+    /// The transaction is not in a binary encoding, or does not decode.
+    UndecodableTransaction,
+    /// The transaction has no status meta to tell whether it failed.
+    MissingMeta,
+    /// A favorites instruction whose data or accounts do not decode.
+    InvalidInstruction(String),
+}
+
+impl fmt::Display for ActivityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityError::UndecodableTransaction => {
+                write!(f, "the transaction is not base64 or base58 encoded")
+            }
+            ActivityError::MissingMeta => write!(f, "the transaction has no status meta"),
+            ActivityError::InvalidInstruction(reason) => {
+                write!(f, "invalid favorites instruction: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActivityError {}
+
+#[derive(BorshDeserialize)]
+struct FavoritesArgs {
+    number: u64,
+    color: String,
+}
+
+#[derive(BorshDeserialize)]
+struct SignedFavoritesArgs {
+    number: u64,
+    color: String,
+    _nonce: u64,
+    _expiry: i64,
+}
+
+#[derive(BorshDeserialize)]
+struct SetAuthorityArgs {
+    delegate: Option<Pubkey>,
+}
+
+fn args<T: BorshDeserialize>(data: &[u8]) -> Result<T, ActivityError> {
+    T::deserialize(&mut &data[DISCRIMINATOR_SIZE..])
+        .map_err(|err| ActivityError::InvalidInstruction(err.to_string()))
+}
+
+/// What a favorites instruction did. `accounts` are its accounts and
+/// `fee_payer` the transaction's. Instructions that leave wallet favorites
+/// alone, such as handles, proposals or NFT favorites, did nothing here.
+pub fn decode_instruction(
+    data: &[u8],
+    accounts: &[Pubkey],
+    fee_payer: &Pubkey,
+) -> Result<Vec<Activity>, ActivityError> {
+    let Some(discriminator) = data.get(..DISCRIMINATOR_SIZE) else {
+        return Ok(Vec::new());
+    };
+    let account = |index: usize| {
+        accounts
+            .get(index)
+            .copied()
+            .ok_or_else(|| ActivityError::InvalidInstruction(format!("missing account {index}")))
+    };
+    let activity = |user, change| Activity { user, change };
+    let activities = match <[u8; 8]>::try_from(discriminator).unwrap() {
+        SET_FAVORITES_DISCRIMINATOR => {
+            let FavoritesArgs { number, color } = args(data)?;
+            vec![activity(account(0)?, Change::Set { number, color })]
+        }
+        UPDATE_FAVORITES_DISCRIMINATOR => {
+            let FavoritesArgs { number, color } = args(data)?;
+            let signer = account(1)?;
+            vec![activity(
+                account(0)?,
+                Change::Update {
+                    number,
+                    color,
+                    signer,
+                },
+            )]
+        }
+        UPDATE_FAVORITES_SIGNED_DISCRIMINATOR => {
+            let SignedFavoritesArgs { number, color, .. } = args(data)?;
+            vec![activity(
+                account(0)?,
+                Change::Update {
+                    number,
+                    color,
+                    signer: *fee_payer,
+                },
+            )]
+        }
+        SET_AUTHORITY_DISCRIMINATOR => {
+            let SetAuthorityArgs { delegate } = args(data)?;
+            vec![activity(account(0)?, Change::SetDelegate { delegate })]
+        }
+        // Both take the old owner first and the new one second
+        TRANSFER_FAVORITES_DISCRIMINATOR | COMPLETE_RECOVERY_DISCRIMINATOR => {
+            let (user, new_owner) = (account(0)?, account(1)?);
+            vec![
+                activity(user, Change::MovedOut { new_owner }),
+                activity(
+                    new_owner,
+                    Change::MovedIn {
+                        previous_owner: user,
+                    },
+                ),
+            ]
+        }
+        CLOSE_FAVORITES_DISCRIMINATOR => vec![activity(account(0)?, Change::Close)],
+        _ => Vec::new(),
+    };
+    Ok(activities)
+}
+
+/// The program's logs, split by invocation of `program_id` in the order
+/// they ran.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProgramLogs {
+    /// What each invocation logged with `msg!`.
+    pub messages: Vec<Vec<String>>,
+    /// The `TipReceived` events each invocation emitted.
+    pub events: Vec<Vec<TipReceived>>,
+}
+
+/// Splits a transaction's log messages by invocation of `program_id`.
+/// Lines logged while another program runs, including ones `program_id`
+/// invoked, are not its own.
+pub fn parse_logs(logs: &[String], program_id: &Pubkey) -> ProgramLogs {
+    let program = program_id.to_string();
+    let mut parsed = ProgramLogs::default();
+    // The programs running, innermost last, and for ours which invocation
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    for line in logs {
+        if let Some(rest) = line.strip_prefix("Program ") {
+            if let Some((id, depth)) = rest.split_once(" invoke [") {
+                if depth.ends_with(']') {
+                    let invocation = (id == program).then(|| {
+                        parsed.messages.push(Vec::new());
+                        parsed.events.push(Vec::new());
+                        parsed.messages.len() - 1
+                    });
+                    stack.push(invocation);
+                    continue;
+                }
+            }
+            let ours = stack.last().copied().flatten();
+            if let Some(message) = rest.strip_prefix("log: ") {
+                if let Some(invocation) = ours {
+                    parsed.messages[invocation].push(message.to_string());
+                }
+            } else if let Some(data) = rest.strip_prefix("data: ") {
+                let event = BASE64_STANDARD
+                    .decode(data)
+                    .ok()
+                    .filter(|bytes| bytes.starts_with(&TIP_RECEIVED_DISCRIMINATOR))
+                    .and_then(|bytes| TipReceived::try_from_bytes(&bytes).ok());
+                if let (Some(invocation), Some(event)) = (ours, event) {
+                    parsed.events[invocation].push(event);
+                }
+            } else if rest.ends_with(" success") || rest.contains(" failed") {
+                stack.pop();
+            }
+        }
+    }
+    parsed
+}
+
+/// Decodes a transaction as `getTransaction` returns it, in a binary
+/// encoding, into what it did to the favorites of `program_id`.
+pub fn decode_transaction(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    program_id: &Pubkey,
+) -> Result<IndexedTransaction, ActivityError> {
+    let versioned = transaction
+        .transaction
+        .transaction
+        .decode()
+        .ok_or(ActivityError::UndecodableTransaction)?;
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or(ActivityError::MissingMeta)?;
+    let signature = versioned
+        .signatures
+        .first()
+        .ok_or(ActivityError::UndecodableTransaction)?
+        .to_string();
+    let mut indexed = IndexedTransaction {
+        signature,
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        failed: meta.err.is_some(),
+        activities: Vec::new(),
+        logs: Vec::new(),
+    };
+    if indexed.failed {
+        return Ok(indexed);
+    }
+
+    // Accounts loaded from lookup tables follow the message's own
+    let mut keys = versioned.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.push(Pubkey::from_str(key).map_err(|_| ActivityError::UndecodableTransaction)?);
+        }
+    }
+    let key = |index: u8| {
+        keys.get(index as usize)
+            .copied()
+            .ok_or(ActivityError::UndecodableTransaction)
+    };
+    let fee_payer = key(0)?;
+
+    // Our instructions in the order they ran: each top level instruction,
+    // then whatever it invoked
+    let inner = match &meta.inner_instructions {
+        OptionSerializer::Some(inner) => inner.as_slice(),
+        _ => &[],
+    };
+    let mut instructions = Vec::new();
+    for (index, instruction) in versioned.message.instructions().iter().enumerate() {
+        instructions.push((
+            instruction.program_id_index,
+            instruction.accounts.clone(),
+            instruction.data.clone(),
+        ));
+        let invoked = inner.iter().filter(|inner| inner.index as usize == index);
+        for instruction in invoked.flat_map(|inner| &inner.instructions) {
+            if let UiInstruction::Compiled(compiled) = instruction {
+                let data = bs58::decode(&compiled.data)
+                    .into_vec()
+                    .map_err(|_| ActivityError::UndecodableTransaction)?;
+                instructions.push((compiled.program_id_index, compiled.accounts.clone(), data));
+            }
+        }
+    }
+
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => parse_logs(logs, program_id),
+        _ => ProgramLogs::default(),
+    };
+    let mut invocation = 0;
+    for (program_index, account_indexes, data) in instructions {
+        if key(program_index)? != *program_id {
+            continue;
+        }
+        let accounts = account_indexes
+            .iter()
+            .map(|index| key(*index))
+            .collect::<Result<Vec<_>, _>>()?;
+        indexed
+            .activities
+            .extend(decode_instruction(&data, &accounts, &fee_payer)?);
+        let events = logs.events.get(invocation).into_iter().flatten();
+        indexed.activities.extend(events.map(|event| Activity {
+            user: event.user,
+            change: Change::Tip {
+                tipper: event.tipper,
+                amount: event.amount,
+                tips_total: event.tips_total,
+            },
+        }));
+        invocation += 1;
+    }
+    indexed.logs = logs.messages.into_iter().flatten().collect();
+    Ok(indexed)
+}
@@ -0,0 +1,13 @@
+//! Indexing the favorites program into a local SQLite database.
+//!
+//! Transactions come from a cluster at `confirmed` commitment or from a
+//! recorded stream of them, one JSON object per line. Each is decoded into
+//! what its favorites instructions and events did to whose favorites, see
+//! [`activity`], and applied to the [`store`]: the current favorites of every
+//! user and the full history behind them. [`source`] keeps the store in step
+//! with its source, resuming after the last transaction applied and rolling
+//! back transactions a re-org dropped.
+
+pub mod activity;
+pub mod source;
+pub mod store;
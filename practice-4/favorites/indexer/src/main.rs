@@ -0,0 +1,187 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use clap::{Parser, Subcommand};
+use favorites_indexer::{
+    activity::Change,
+    source::{replay, sync, Synced},
+    store::Store,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+#[derive(Parser)]
+#[command(
+    name = "favorites-indexer",
+    version,
+    about = "Index favorites program transactions into a local SQLite database"
+)]
+struct Cli {
+    /// The SQLite database, created if missing
+    #[arg(long, global = true, default_value = "favorites.db")]
+    db: PathBuf,
+
+    /// The program to index
+    #[arg(long, global = true, default_value_t = favorites_client::ID)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Follow a cluster, resuming after the last transaction indexed
+    Run {
+        /// Cluster URL, or one of mainnet-beta, devnet, testnet, localhost
+        #[arg(short, long, default_value = "localhost")]
+        url: String,
+        /// Seconds between polls
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+        /// Also append every transaction and rollback to this stream, to
+        /// replay later
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Sync once and exit instead of following the cluster
+        #[arg(long)]
+        once: bool,
+    },
+    /// Index a stream recorded by `run --record`
+    Replay { stream: PathBuf },
+    /// Print a user's favorites and their history
+    Show { user: Pubkey },
+}
+
+fn cluster_url(url: &str) -> String {
+    match url {
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "localhost" | "l" => "http://localhost:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+fn report(synced: Synced) {
+    if synced.rolled_back > 0 {
+        println!(
+            "↩️  Rolled back {} transactions dropped by a re-org",
+            synced.rolled_back
+        );
+    }
+    if synced.applied > 0 {
+        println!("📥 Indexed {} transactions", synced.applied);
+    }
+}
+
+fn show(store: &Store, user: &Pubkey) -> Result<(), String> {
+    let describe = |change: &Change| match change {
+        Change::Set { number, color } => format!("set {number} {color}"),
+        Change::Update {
+            number,
+            color,
+            signer,
+        } => format!("updated to {number} {color} by {signer}"),
+        Change::SetDelegate {
+            delegate: Some(delegate),
+        } => format!("delegated to {delegate}"),
+        Change::SetDelegate { delegate: None } => "delegate removed".to_string(),
+        Change::Tip { tipper, amount, .. } => format!("tipped {amount} lamports by {tipper}"),
+        Change::MovedOut { new_owner } => format!("moved to {new_owner}"),
+        Change::MovedIn { previous_owner } => format!("moved from {previous_owner}"),
+        Change::Close => "closed".to_string(),
+    };
+
+    match store.favorites(user).map_err(|err| err.to_string())? {
+        Some(favorites) => {
+            println!("🔢 number:   {}", favorites.number);
+            println!("🎨 color:    {}", favorites.color);
+            if let Some(delegate) = favorites.delegate {
+                println!("🤝 delegate: \x1b[95m{}\x1b[0m", delegate);
+            }
+            println!("💰 tips:     {} lamports", favorites.tips_total);
+        }
+        None => println!("No favorites for \x1b[95m{}\x1b[0m", user),
+    }
+    let history = store.history(user).map_err(|err| err.to_string())?;
+    if !history.is_empty() {
+        println!();
+    }
+    for entry in history {
+        println!(
+            "🕒 slot {}: {} \x1b[35m{}\x1b[0m",
+            entry.slot,
+            describe(&entry.change),
+            entry.signature
+        );
+    }
+    Ok(())
+}
+
+fn open_record(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let mut store = Store::open(&cli.db).map_err(|err| format!("{}: {err}", cli.db.display()))?;
+
+    match cli.command {
+        Command::Run {
+            url,
+            interval,
+            record,
+            once,
+        } => {
+            let client =
+                RpcClient::new_with_commitment(cluster_url(&url), CommitmentConfig::confirmed());
+            let mut record = record.as_deref().map(open_record).transpose()?;
+            if let Some(cursor) = store.cursor().map_err(|err| err.to_string())? {
+                println!(
+                    "⏩ Resuming after slot {}, \x1b[35m{}\x1b[0m",
+                    cursor.slot, cursor.signature
+                );
+            }
+            loop {
+                let synced = sync(
+                    &client,
+                    &mut store,
+                    &cli.program_id,
+                    record.as_mut().map(|file| file as &mut dyn Write),
+                )
+                .map_err(|err| err.to_string())?;
+                report(synced);
+                if once {
+                    return Ok(());
+                }
+                thread::sleep(Duration::from_secs(interval));
+            }
+        }
+        Command::Replay { stream } => {
+            let file = File::open(&stream).map_err(|err| format!("{}: {err}", stream.display()))?;
+            let synced = replay(BufReader::new(file), &mut store, &cli.program_id)
+                .map_err(|err| err.to_string())?;
+            report(synced);
+            println!("✅ Replayed {}", stream.display());
+            Ok(())
+        }
+        Command::Show { user } => show(&store, &user),
+    }
+}
+
+fn main() {
+    if let Err(message) = run(Cli::parse()) {
+        eprintln!("❌ Error: \x1b[91m{}\x1b[0m", message);
+        std::process::exit(1);
+    }
+}
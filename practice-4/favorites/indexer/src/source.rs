@@ -0,0 +1,319 @@
+//! Where transactions come from: a cluster, or a stream recorded from one.
+//!
+//! Both resume after the store's cursor. A cluster is read at `confirmed`
+//! commitment, so every sync first checks that the transactions applied
+//! since the last finalized slot are still where they were, and rolls the
+//! store back to the first one a re-org dropped or moved.
+use std::{
+    fmt,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::{
+    EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding,
+};
+
+use crate::{
+    activity::{decode_transaction, ActivityError, IndexedTransaction},
+    store::Store,
+};
+
+/// `getSignaturesForAddress` returns at most this many signatures a call.
+const SIGNATURES_LIMIT: usize = 1_000;
+/// `getSignatureStatuses` takes at most this many signatures a call.
+const STATUSES_LIMIT: usize = 256;
+
+#[derive(Debug)]
+pub enum SourceError {
+    Cluster(Box<ClientError>),
+    Store(rusqlite::Error),
+    Transaction {
+        signature: String,
+        error: ActivityError,
+    },
+    /// A line of a recorded stream that is not JSON or not a stream entry.
+    Stream {
+        line: usize,
+        error: serde_json::Error,
+    },
+    /// A transaction of a recorded stream that does not decode.
+    StreamTransaction {
+        line: usize,
+        error: ActivityError,
+    },
+    Io(std::io::Error),
+    /// A signature, from a recorded stream or the store's cursor, that does
+    /// not parse.
+    Signature(String),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Cluster(err) => write!(f, "{err}"),
+            SourceError::Store(err) => write!(f, "database: {err}"),
+            SourceError::Transaction { signature, error } => write!(f, "{signature}: {error}"),
+            SourceError::Stream { line, error } => write!(f, "stream line {line}: {error}"),
+            SourceError::StreamTransaction { line, error } => {
+                write!(f, "stream line {line}: {error}")
+            }
+            SourceError::Io(err) => write!(f, "{err}"),
+            SourceError::Signature(signature) => write!(f, "{signature} is not a signature"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<ClientError> for SourceError {
+    fn from(err: ClientError) -> Self {
+        SourceError::Cluster(Box::new(err))
+    }
+}
+
+impl From<rusqlite::Error> for SourceError {
+    fn from(err: rusqlite::Error) -> Self {
+        SourceError::Store(err)
+    }
+}
+
+impl From<std::io::Error> for SourceError {
+    fn from(err: std::io::Error) -> Self {
+        SourceError::Io(err)
+    }
+}
+
+/// What the indexer needs from a cluster, at `confirmed` commitment.
+pub trait Cluster {
+    /// The signatures of the transactions mentioning `address` after `until`,
+    /// or all of them, newest first, with their slot.
+    fn signatures(
+        &self,
+        address: &Pubkey,
+        until: Option<&str>,
+    ) -> Result<Vec<(String, u64)>, SourceError>;
+
+    fn transaction(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, SourceError>;
+
+    /// The slot each of `signatures` landed in, `None` for those the cluster
+    /// does not know (anymore).
+    fn statuses(&self, signatures: &[String]) -> Result<Vec<Option<u64>>, SourceError>;
+
+    /// The latest slot no re-org can drop.
+    fn finalized_slot(&self) -> Result<u64, SourceError>;
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, SourceError> {
+    Signature::from_str(signature).map_err(|_| SourceError::Signature(signature.to_string()))
+}
+
+impl Cluster for RpcClient {
+    fn signatures(
+        &self,
+        address: &Pubkey,
+        until: Option<&str>,
+    ) -> Result<Vec<(String, u64)>, SourceError> {
+        let until = until.map(parse_signature).transpose()?;
+        let mut signatures: Vec<(String, u64)> = Vec::new();
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before: signatures
+                    .last()
+                    .map(|(last, _)| parse_signature(last))
+                    .transpose()?,
+                until,
+                limit: Some(SIGNATURES_LIMIT),
+                commitment: Some(CommitmentConfig::confirmed()),
+            };
+            let page = self.get_signatures_for_address_with_config(address, config)?;
+            let done = page.len() < SIGNATURES_LIMIT;
+            signatures.extend(
+                page.into_iter()
+                    .map(|status| (status.signature, status.slot)),
+            );
+            if done {
+                return Ok(signatures);
+            }
+        }
+    }
+
+    fn transaction(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, SourceError> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        Ok(self.get_transaction_with_config(&parse_signature(signature)?, config)?)
+    }
+
+    fn statuses(&self, signatures: &[String]) -> Result<Vec<Option<u64>>, SourceError> {
+        let mut slots = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(STATUSES_LIMIT) {
+            let chunk = chunk
+                .iter()
+                .map(|signature| parse_signature(signature))
+                .collect::<Result<Vec<_>, _>>()?;
+            let statuses = self.get_signature_statuses_with_history(&chunk)?.value;
+            slots.extend(statuses.into_iter().map(|status| {
+                status
+                    .filter(|status| status.satisfies_commitment(CommitmentConfig::confirmed()))
+                    .map(|status| status.slot)
+            }));
+        }
+        Ok(slots)
+    }
+
+    fn finalized_slot(&self) -> Result<u64, SourceError> {
+        Ok(self.get_slot_with_commitment(CommitmentConfig::finalized())?)
+    }
+}
+
+/// A line of a recorded stream.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamEntry {
+    Transaction(Box<EncodedConfirmedTransactionWithStatusMeta>),
+    /// Transactions from `slot` on were dropped by a re-org.
+    Rollback {
+        slot: u64,
+    },
+}
+
+fn record_entry(
+    record: &mut Option<&mut dyn Write>,
+    entry: &StreamEntry,
+) -> Result<(), SourceError> {
+    if let Some(record) = record {
+        serde_json::to_writer(&mut *record, entry).map_err(std::io::Error::from)?;
+        writeln!(record)?;
+    }
+    Ok(())
+}
+
+/// What a sync or replay did to the store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Synced {
+    pub applied: usize,
+    pub rolled_back: usize,
+}
+
+/// Brings `store` up to date with `cluster`, first taking back what a re-org
+/// dropped. Every transaction and rollback goes to `record` as well, if any.
+pub fn sync(
+    cluster: &impl Cluster,
+    store: &mut Store,
+    program_id: &Pubkey,
+    mut record: Option<&mut dyn Write>,
+) -> Result<Synced, SourceError> {
+    let mut synced = Synced::default();
+
+    let unfinalized = store.transactions_after(cluster.finalized_slot()?)?;
+    let signatures: Vec<String> = unfinalized.iter().map(|(s, _)| s.clone()).collect();
+    let statuses = cluster.statuses(&signatures)?;
+    let dropped = unfinalized
+        .iter()
+        .zip(&statuses)
+        .filter(|((_, slot), status)| **status != Some(*slot))
+        .map(|((_, slot), _)| *slot)
+        .min();
+    if let Some(slot) = dropped {
+        synced.rolled_back = store.rollback(slot)?;
+        record_entry(&mut record, &StreamEntry::Rollback { slot })?;
+    }
+
+    let cursor = store.cursor()?;
+    let mut signatures = cluster.signatures(
+        program_id,
+        cursor.as_ref().map(|cursor| cursor.signature.as_str()),
+    )?;
+    signatures.reverse();
+    for (signature, _) in signatures {
+        let transaction = cluster.transaction(&signature)?;
+        let indexed = decode_transaction(&transaction, program_id)
+            .map_err(|error| SourceError::Transaction { signature, error })?;
+        if store.apply(&indexed)? {
+            synced.applied += 1;
+        }
+        record_entry(
+            &mut record,
+            &StreamEntry::Transaction(Box::new(transaction)),
+        )?;
+    }
+    Ok(synced)
+}
+
+// A decoded line of a recorded stream
+enum Replay {
+    Indexed(IndexedTransaction),
+    RolledBack(u64),
+}
+
+/// Applies a recorded stream to `store`. A stream holding the store's cursor
+/// resumes after it, others are applied whole.
+pub fn replay(
+    stream: impl BufRead,
+    store: &mut Store,
+    program_id: &Pubkey,
+) -> Result<Synced, SourceError> {
+    let mut entries = Vec::new();
+    for (index, line) in stream.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let stream_error = |error| SourceError::Stream {
+            line: index + 1,
+            error,
+        };
+        let entry = match serde_json::from_str(&line).map_err(stream_error)? {
+            StreamEntry::Transaction(transaction) => {
+                let indexed = decode_transaction(&transaction, program_id).map_err(|error| {
+                    SourceError::StreamTransaction {
+                        line: index + 1,
+                        error,
+                    }
+                })?;
+                Replay::Indexed(indexed)
+            }
+            StreamEntry::Rollback { slot } => Replay::RolledBack(slot),
+        };
+        entries.push(entry);
+    }
+
+    let start = match store.cursor()? {
+        Some(cursor) => entries
+            .iter()
+            .rposition(|entry| match entry {
+                Replay::Indexed(indexed) => indexed.signature == cursor.signature,
+                Replay::RolledBack(_) => false,
+            })
+            .map_or(0, |position| position + 1),
+        None => 0,
+    };
+    let mut synced = Synced::default();
+    for entry in &entries[start..] {
+        match entry {
+            Replay::Indexed(indexed) => {
+                if store.apply(indexed)? {
+                    synced.applied += 1;
+                }
+            }
+            Replay::RolledBack(slot) => synced.rolled_back += store.rollback(*slot)?,
+        }
+    }
+    Ok(synced)
+}
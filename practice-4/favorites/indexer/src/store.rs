@@ -0,0 +1,432 @@
+//! The SQLite database the indexer fills.
+//!
+//! `history` holds one row per change with the user's favorites right after
+//! it, `favorites` the latest of those for every user whose favorites exist,
+//! and `transactions` what was applied, so that applying a transaction twice
+//! is harmless and a re-org can take it back out.
+use std::{path::Path, str::FromStr};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::activity::{Activity, Change, IndexedTransaction};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    failed INTEGER NOT NULL,
+    logs TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_by_slot ON transactions (slot);
+
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    user TEXT NOT NULL,
+    change TEXT NOT NULL,
+    counterparty TEXT,
+    amount INTEGER,
+    number INTEGER,
+    color TEXT,
+    delegate TEXT,
+    tips_total INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS history_by_user ON history (user, id);
+CREATE INDEX IF NOT EXISTS history_by_signature ON history (signature);
+
+CREATE TABLE IF NOT EXISTS favorites (
+    user TEXT PRIMARY KEY,
+    number INTEGER NOT NULL,
+    color TEXT NOT NULL,
+    delegate TEXT,
+    tips_total INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    signature TEXT NOT NULL
+);
+";
+
+/// A user's favorites as of some transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserFavorites {
+    pub number: u64,
+    pub color: String,
+    pub delegate: Option<Pubkey>,
+    /// Every lamport ever tipped to the user.
+    pub tips_total: u64,
+    /// The slot and signature of the transaction that last changed them.
+    pub slot: u64,
+    pub signature: String,
+}
+
+/// A row of a user's history: a change and what it left behind.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub change: Change,
+    /// The favorites right after the change, `None` once closed or moved.
+    pub number: Option<u64>,
+    pub color: Option<String>,
+    pub delegate: Option<Pubkey>,
+    pub tips_total: u64,
+}
+
+/// The last transaction applied, where indexing resumes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub slot: u64,
+    pub signature: String,
+}
+
+// What a user's favorites look like between changes; `number` and `color`
+// are `None` while they do not exist
+#[derive(Clone, Debug, Default)]
+struct State {
+    number: Option<u64>,
+    color: Option<String>,
+    delegate: Option<Pubkey>,
+    tips_total: u64,
+}
+
+fn pubkey(value: Option<String>) -> rusqlite::Result<Option<Pubkey>> {
+    value
+        .map(|value| {
+            Pubkey::from_str(&value).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    err.into(),
+                )
+            })
+        })
+        .transpose()
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and its tables as needed.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    /// The last transaction applied: the latest slot, and in it the last
+    /// transaction applied there.
+    pub fn cursor(&self) -> rusqlite::Result<Option<Cursor>> {
+        self.connection
+            .query_row(
+                "SELECT slot, signature FROM transactions ORDER BY slot DESC, rowid DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(Cursor {
+                        slot: row.get(0)?,
+                        signature: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn contains(&self, signature: &str) -> rusqlite::Result<bool> {
+        self.connection
+            .query_row(
+                "SELECT 1 FROM transactions WHERE signature = ?1",
+                [signature],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|found| found.is_some())
+    }
+
+    /// Applies `transaction` unless it already was. Returns whether it was
+    /// applied now.
+    pub fn apply(&mut self, transaction: &IndexedTransaction) -> rusqlite::Result<bool> {
+        if self.contains(&transaction.signature)? {
+            return Ok(false);
+        }
+        let tx = self.connection.transaction()?;
+        tx.execute(
+            "INSERT INTO transactions (signature, slot, block_time, failed, logs)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                transaction.signature,
+                transaction.slot,
+                transaction.block_time,
+                transaction.failed,
+                transaction.logs.join("\n"),
+            ],
+        )?;
+        // Favorites moved out, for the new owner moving them in right after
+        let mut moved = None;
+        for Activity { user, change } in &transaction.activities {
+            let previous = load_state(&tx, user)?;
+            let state = match change {
+                Change::MovedIn { .. } => State {
+                    tips_total: previous.tips_total,
+                    ..moved.take().unwrap_or_default()
+                },
+                Change::MovedOut { .. } => {
+                    moved = Some(previous.clone());
+                    next_state(previous, change)
+                }
+                _ => next_state(previous, change),
+            };
+            let (counterparty, amount) = match change {
+                Change::Update { signer, .. } => (Some(signer.to_string()), None),
+                Change::Tip { tipper, amount, .. } => (Some(tipper.to_string()), Some(*amount)),
+                Change::MovedOut { new_owner } => (Some(new_owner.to_string()), None),
+                Change::MovedIn { previous_owner } => (Some(previous_owner.to_string()), None),
+                _ => (None, None),
+            };
+            tx.execute(
+                "INSERT INTO history (signature, slot, user, change, counterparty, amount,
+                                      number, color, delegate, tips_total)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    transaction.signature,
+                    transaction.slot,
+                    user.to_string(),
+                    change.kind(),
+                    counterparty,
+                    amount,
+                    state.number,
+                    state.color,
+                    state.delegate.map(|delegate| delegate.to_string()),
+                    state.tips_total,
+                ],
+            )?;
+            save_favorites(&tx, user, &state, transaction.slot, &transaction.signature)?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Takes back every transaction from `slot` on, as a re-org dropped
+    /// them, and returns how many there were.
+    pub fn rollback(&mut self, slot: u64) -> rusqlite::Result<usize> {
+        let tx = self.connection.transaction()?;
+        let users = {
+            let mut statement = tx.prepare("SELECT DISTINCT user FROM history WHERE slot >= ?1")?;
+            let users = statement
+                .query_map([slot], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            users
+        };
+        tx.execute("DELETE FROM history WHERE slot >= ?1", [slot])?;
+        let dropped = tx.execute("DELETE FROM transactions WHERE slot >= ?1", [slot])?;
+        // Each user is back to their last change left
+        for user in users {
+            let user = Pubkey::from_str(&user).expect("the store only holds pubkeys");
+            let last = tx
+                .query_row(
+                    "SELECT slot, signature FROM history WHERE user = ?1
+                     ORDER BY id DESC LIMIT 1",
+                    [user.to_string()],
+                    |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+            match last {
+                Some((slot, signature)) => {
+                    let state = load_state(&tx, &user)?;
+                    save_favorites(&tx, &user, &state, slot, &signature)?;
+                }
+                None => {
+                    tx.execute("DELETE FROM favorites WHERE user = ?1", [user.to_string()])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(dropped)
+    }
+
+    /// The transactions applied after `slot`, oldest first, with their slot.
+    pub fn transactions_after(&self, slot: u64) -> rusqlite::Result<Vec<(String, u64)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT signature, slot FROM transactions WHERE slot > ?1 ORDER BY slot, rowid",
+        )?;
+        let rows = statement.query_map([slot], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// `user`'s favorites, if they exist.
+    pub fn favorites(&self, user: &Pubkey) -> rusqlite::Result<Option<UserFavorites>> {
+        self.connection
+            .query_row(
+                "SELECT number, color, delegate, tips_total, slot, signature
+                 FROM favorites WHERE user = ?1",
+                [user.to_string()],
+                |row| {
+                    Ok(UserFavorites {
+                        number: row.get(0)?,
+                        color: row.get(1)?,
+                        delegate: pubkey(row.get(2)?)?,
+                        tips_total: row.get(3)?,
+                        slot: row.get(4)?,
+                        signature: row.get(5)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    /// Every change to `user`'s favorites, oldest first.
+    pub fn history(&self, user: &Pubkey) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT signature, slot, change, counterparty, amount, number, color, delegate,
+                    tips_total
+             FROM history WHERE user = ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map([user.to_string()], history_entry)?;
+        rows.collect()
+    }
+
+    /// What the program logged in `signature`, one message per line.
+    pub fn logs(&self, signature: &str) -> rusqlite::Result<Option<String>> {
+        self.connection
+            .query_row(
+                "SELECT logs FROM transactions WHERE signature = ?1",
+                [signature],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+}
+
+fn history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let kind: String = row.get(2)?;
+    let counterparty = pubkey(row.get(3)?)?;
+    let amount: Option<u64> = row.get(4)?;
+    let number: Option<u64> = row.get(5)?;
+    let color: Option<String> = row.get(6)?;
+    let delegate = pubkey(row.get(7)?)?;
+    let tips_total: u64 = row.get(8)?;
+    let missing =
+        || rusqlite::Error::InvalidColumnType(3, kind.clone(), rusqlite::types::Type::Null);
+    let change = match kind.as_str() {
+        "set" => Change::Set {
+            number: number.ok_or_else(missing)?,
+            color: color.clone().ok_or_else(missing)?,
+        },
+        "update" => Change::Update {
+            number: number.ok_or_else(missing)?,
+            color: color.clone().ok_or_else(missing)?,
+            signer: counterparty.ok_or_else(missing)?,
+        },
+        "set_delegate" => Change::SetDelegate { delegate },
+        "tip" => Change::Tip {
+            tipper: counterparty.ok_or_else(missing)?,
+            amount: amount.ok_or_else(missing)?,
+            tips_total,
+        },
+        "moved_out" => Change::MovedOut {
+            new_owner: counterparty.ok_or_else(missing)?,
+        },
+        "moved_in" => Change::MovedIn {
+            previous_owner: counterparty.ok_or_else(missing)?,
+        },
+        _ => Change::Close,
+    };
+    Ok(HistoryEntry {
+        signature: row.get(0)?,
+        slot: row.get(1)?,
+        change,
+        number,
+        color,
+        delegate,
+        tips_total,
+    })
+}
+
+// The user's favorites after their last change, or nothing yet
+fn load_state(connection: &Connection, user: &Pubkey) -> rusqlite::Result<State> {
+    connection
+        .query_row(
+            "SELECT number, color, delegate, tips_total FROM history WHERE user = ?1
+             ORDER BY id DESC LIMIT 1",
+            [user.to_string()],
+            |row| {
+                Ok(State {
+                    number: row.get(0)?,
+                    color: row.get(1)?,
+                    delegate: pubkey(row.get(2)?)?,
+                    tips_total: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map(Option::unwrap_or_default)
+}
+
+fn next_state(state: State, change: &Change) -> State {
+    match change {
+        Change::Set { number, color } => State {
+            number: Some(*number),
+            color: Some(color.clone()),
+            delegate: None,
+            ..state
+        },
+        Change::Update { number, color, .. } => State {
+            number: Some(*number),
+            color: Some(color.clone()),
+            ..state
+        },
+        Change::SetDelegate { delegate } => State {
+            delegate: *delegate,
+            ..state
+        },
+        Change::Tip { tips_total, .. } => State {
+            tips_total: *tips_total,
+            ..state
+        },
+        // Moving in is up to `Store::apply`, which knows what moved out
+        Change::MovedIn { .. } => state,
+        Change::MovedOut { .. } | Change::Close => State {
+            number: None,
+            color: None,
+            delegate: None,
+            ..state
+        },
+    }
+}
+
+fn save_favorites(
+    connection: &Connection,
+    user: &Pubkey,
+    state: &State,
+    slot: u64,
+    signature: &str,
+) -> rusqlite::Result<()> {
+    match (state.number, &state.color) {
+        (Some(number), Some(color)) => connection.execute(
+            "INSERT INTO favorites (user, number, color, delegate, tips_total, slot, signature)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (user) DO UPDATE SET number = ?2, color = ?3, delegate = ?4,
+                 tips_total = ?5, slot = ?6, signature = ?7",
+            params![
+                user.to_string(),
+                number,
+                color,
+                state.delegate.map(|delegate| delegate.to_string()),
+                state.tips_total,
+                slot,
+                signature,
+            ],
+        )?,
+        _ => connection.execute("DELETE FROM favorites WHERE user = ?1", [user.to_string()])?,
+    };
+    Ok(())
+}
@@ -0,0 +1,451 @@
+// Indexing transactions shaped like `getTransaction` returns them, built from
+// the client's instructions with the logs the program would have written.
+use std::{cell::RefCell, collections::HashMap, io::Write};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use favorites_client::{accounts::TIP_RECEIVED_DISCRIMINATOR, fees::FeeAccounts, instructions};
+use favorites_indexer::{
+    activity::{decode_transaction, Change},
+    source::{replay, sync, Cluster, SourceError, StreamEntry, Synced},
+    store::Store,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionBinaryEncoding,
+    UiTransactionStatusMeta,
+};
+
+// A transaction in `slot` whose first signature is `id`, paid by the first
+// signer of its first instruction. `events` are the tips each favorites
+// instruction emitted: (user, tipper, amount, tips_total).
+fn transaction(
+    id: u8,
+    slot: u64,
+    instructions: &[Instruction],
+    events: &[(Pubkey, Pubkey, u64, u64)],
+    err: Option<TransactionError>,
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    let payer = instructions[0].accounts[0].pubkey;
+    let message = Message::new_with_blockhash(instructions, Some(&payer), &Hash::new_unique());
+    let mut tx = Transaction::new_unsigned(message);
+    tx.signatures[0] = Signature::from([id; 64]);
+
+    let program = favorites_client::ID;
+    let mut logs = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        logs.push(format!("Program {} invoke [1]", instruction.program_id));
+        if instruction.program_id == program {
+            logs.push(format!("Program log: ✅ instruction {index}"));
+            for &(user, tipper, amount, tips_total) in events {
+                let mut data = TIP_RECEIVED_DISCRIMINATOR.to_vec();
+                data.extend_from_slice(user.as_ref());
+                data.extend_from_slice(tipper.as_ref());
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&tips_total.to_le_bytes());
+                logs.push(format!("Program data: {}", BASE64_STANDARD.encode(data)));
+            }
+        }
+        logs.push(format!("Program {} success", instruction.program_id));
+    }
+
+    let meta = UiTransactionStatusMeta {
+        err: err.clone(),
+        status: err.map_or(Ok(()), Err),
+        fee: 5_000,
+        pre_balances: Vec::new(),
+        post_balances: Vec::new(),
+        inner_instructions: OptionSerializer::Some(Vec::new()),
+        log_messages: OptionSerializer::Some(logs),
+        pre_token_balances: OptionSerializer::None,
+        post_token_balances: OptionSerializer::None,
+        rewards: OptionSerializer::None,
+        loaded_addresses: OptionSerializer::Skip,
+        return_data: OptionSerializer::Skip,
+        compute_units_consumed: OptionSerializer::Skip,
+    };
+    EncodedConfirmedTransactionWithStatusMeta {
+        slot,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Binary(
+                BASE64_STANDARD.encode(bincode::serialize(&tx).unwrap()),
+                TransactionBinaryEncoding::Base64,
+            ),
+            meta: Some(meta),
+            version: None,
+        },
+        block_time: Some(1_700_000_000 + slot as i64),
+    }
+}
+
+// The transaction types are not `Clone`, but round trip through JSON as
+// the RPC sends them
+fn copy(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    serde_json::from_value(serde_json::to_value(transaction).unwrap()).unwrap()
+}
+
+fn apply(store: &mut Store, transaction: &EncodedConfirmedTransactionWithStatusMeta) -> bool {
+    let indexed = decode_transaction(transaction, &favorites_client::ID).unwrap();
+    store.apply(&indexed).unwrap()
+}
+
+fn set(user: &Pubkey, number: u64, color: &str) -> Instruction {
+    instructions::set_favorites(user, 0, None, FeeAccounts::default(), &[], number, color)
+}
+
+fn update(user: &Pubkey, signer: &Pubkey, number: u64, color: &str) -> Instruction {
+    instructions::update_favorites(user, signer, None, FeeAccounts::default(), number, color)
+}
+
+fn changes(store: &Store, user: &Pubkey) -> Vec<Change> {
+    let history = store.history(user).unwrap();
+    history.into_iter().map(|entry| entry.change).collect()
+}
+
+// A user's favorites through a set, an update by their delegate and a tip,
+// then moved to another wallet which closes them
+fn lifecycle(
+    alice: Pubkey,
+    bob: Pubkey,
+    carol: Pubkey,
+) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+    vec![
+        transaction(1, 10, &[set(&alice, 7, "blue")], &[], None),
+        transaction(
+            2,
+            11,
+            &[instructions::set_authority(&alice, Some(bob))],
+            &[],
+            None,
+        ),
+        transaction(3, 12, &[update(&alice, &bob, 8, "green")], &[], None),
+        transaction(
+            4,
+            12,
+            &[instructions::tip(&carol, &alice, 500)],
+            &[(alice, carol, 500, 500)],
+            None,
+        ),
+        transaction(
+            5,
+            13,
+            &[instructions::transfer_favorites(
+                &alice,
+                &carol,
+                0,
+                &[],
+                &[],
+            )],
+            &[],
+            None,
+        ),
+        transaction(
+            6,
+            14,
+            &[instructions::close_favorites(&carol, 0, None, &[])],
+            &[],
+            None,
+        ),
+    ]
+}
+
+#[test]
+fn favorites_and_history_follow_each_change() {
+    let (alice, bob, carol) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut store = Store::open_in_memory().unwrap();
+    let transactions = lifecycle(alice, bob, carol);
+
+    for transaction in &transactions[..4] {
+        assert!(apply(&mut store, transaction));
+    }
+    let favorites = store.favorites(&alice).unwrap().unwrap();
+    assert_eq!((favorites.number, favorites.color.as_str()), (8, "green"));
+    assert_eq!(favorites.delegate, Some(bob));
+    assert_eq!(favorites.tips_total, 500);
+    assert_eq!(favorites.slot, 12);
+    assert_eq!(
+        store.logs(&favorites.signature).unwrap().unwrap(),
+        "✅ instruction 0"
+    );
+
+    for transaction in &transactions[4..5] {
+        assert!(apply(&mut store, transaction));
+    }
+    assert_eq!(store.favorites(&alice).unwrap(), None);
+    let moved = store.favorites(&carol).unwrap().unwrap();
+    assert_eq!((moved.number, moved.delegate), (8, Some(bob)));
+    // Tips stay with whoever received them
+    assert_eq!(moved.tips_total, 0);
+
+    assert!(apply(&mut store, &transactions[5]));
+    assert_eq!(store.favorites(&carol).unwrap(), None);
+    assert_eq!(
+        changes(&store, &alice),
+        [
+            Change::Set {
+                number: 7,
+                color: "blue".to_string()
+            },
+            Change::SetDelegate {
+                delegate: Some(bob)
+            },
+            Change::Update {
+                number: 8,
+                color: "green".to_string(),
+                signer: bob
+            },
+            Change::Tip {
+                tipper: carol,
+                amount: 500,
+                tips_total: 500
+            },
+            Change::MovedOut { new_owner: carol },
+        ]
+    );
+    assert_eq!(
+        changes(&store, &carol),
+        [
+            Change::MovedIn {
+                previous_owner: alice
+            },
+            Change::Close
+        ]
+    );
+    let history = store.history(&alice).unwrap();
+    assert_eq!(history[3].tips_total, 500);
+    assert_eq!(history[4].number, None);
+}
+
+#[test]
+fn failed_and_repeated_transactions_change_nothing() {
+    let alice = Pubkey::new_unique();
+    let mut store = Store::open_in_memory().unwrap();
+    let failed = transaction(
+        1,
+        10,
+        &[set(&alice, 7, "blue")],
+        &[],
+        Some(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(6001),
+        )),
+    );
+    assert!(apply(&mut store, &failed));
+    assert_eq!(store.favorites(&alice).unwrap(), None);
+    assert!(store.history(&alice).unwrap().is_empty());
+    // Still where the next sync resumes
+    assert_eq!(
+        store.cursor().unwrap().unwrap().signature,
+        Signature::from([1; 64]).to_string()
+    );
+
+    let set = transaction(2, 11, &[set(&alice, 7, "blue")], &[], None);
+    assert!(apply(&mut store, &set));
+    assert!(!apply(&mut store, &set));
+    assert_eq!(store.history(&alice).unwrap().len(), 1);
+}
+
+#[test]
+fn replays_resume_after_the_cursor() {
+    let (alice, bob, carol) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let transactions = lifecycle(alice, bob, carol);
+    let line = |transaction: &EncodedConfirmedTransactionWithStatusMeta| {
+        let entry = StreamEntry::Transaction(Box::new(copy(transaction)));
+        serde_json::to_string(&entry).unwrap() + "\n"
+    };
+    let mut stream: String = transactions[..3].iter().map(line).collect();
+
+    let mut store = Store::open_in_memory().unwrap();
+    let replayed = replay(stream.as_bytes(), &mut store, &favorites_client::ID).unwrap();
+    assert_eq!(
+        replayed,
+        Synced {
+            applied: 3,
+            rolled_back: 0
+        }
+    );
+
+    // The recording went on, through a re-org dropping slot 12
+    stream.extend(transactions[3..4].iter().map(line));
+    stream.push_str("{\"rollback\":{\"slot\":12}}\n");
+    stream.extend(transactions[3..4].iter().map(line));
+    let replayed = replay(stream.as_bytes(), &mut store, &favorites_client::ID).unwrap();
+    assert_eq!(
+        replayed,
+        Synced {
+            applied: 2,
+            rolled_back: 2
+        }
+    );
+    let favorites = store.favorites(&alice).unwrap().unwrap();
+    assert_eq!((favorites.number, favorites.color.as_str()), (7, "blue"));
+    assert_eq!(favorites.tips_total, 500);
+    assert_eq!(changes(&store, &alice).len(), 3);
+
+    let replayed = replay(stream.as_bytes(), &mut store, &favorites_client::ID).unwrap();
+    assert_eq!(replayed, Synced::default());
+    assert!(matches!(
+        replay("not json\n".as_bytes(), &mut store, &favorites_client::ID),
+        Err(SourceError::Stream { line: 1, .. })
+    ));
+}
+
+// Signatures also come from recorded streams, through the store's cursor, so
+// one that does not parse fails the call instead of panicking
+#[test]
+fn malformed_signatures_are_errors() {
+    let client = RpcClient::new_mock("succeeds".to_string());
+    let malformed = |result| matches!(result, Err(SourceError::Signature(signature)) if signature == "not a signature");
+    assert!(malformed(
+        client
+            .signatures(&favorites_client::ID, Some("not a signature"))
+            .map(drop)
+    ));
+    assert!(malformed(client.transaction("not a signature").map(drop)));
+    assert!(malformed(
+        client.statuses(&["not a signature".to_string()]).map(drop)
+    ));
+}
+
+// A cluster in memory: the transactions it holds, and which are finalized
+#[derive(Default)]
+struct FakeCluster {
+    transactions: RefCell<Vec<EncodedConfirmedTransactionWithStatusMeta>>,
+    finalized_slot: u64,
+}
+
+fn signature(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> String {
+    let decoded = transaction.transaction.transaction.decode().unwrap();
+    decoded.signatures[0].to_string()
+}
+
+impl Cluster for FakeCluster {
+    fn signatures(
+        &self,
+        _address: &Pubkey,
+        until: Option<&str>,
+    ) -> Result<Vec<(String, u64)>, SourceError> {
+        let transactions = self.transactions.borrow();
+        let newest_first = transactions
+            .iter()
+            .rev()
+            .map(|transaction| (signature(transaction), transaction.slot));
+        Ok(newest_first
+            .take_while(|(signature, _)| Some(signature.as_str()) != until)
+            .collect())
+    }
+
+    fn transaction(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, SourceError> {
+        let transactions = self.transactions.borrow();
+        let found = transactions
+            .iter()
+            .find(|transaction| self::signature(transaction) == signature);
+        Ok(copy(found.unwrap()))
+    }
+
+    fn statuses(&self, signatures: &[String]) -> Result<Vec<Option<u64>>, SourceError> {
+        let slots: HashMap<String, u64> = self
+            .transactions
+            .borrow()
+            .iter()
+            .map(|transaction| (signature(transaction), transaction.slot))
+            .collect();
+        Ok(signatures
+            .iter()
+            .map(|signature| slots.get(signature).copied())
+            .collect())
+    }
+
+    fn finalized_slot(&self) -> Result<u64, SourceError> {
+        Ok(self.finalized_slot)
+    }
+}
+
+#[test]
+fn syncs_roll_back_what_a_reorg_dropped() {
+    let (alice, bob, carol) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let transactions = lifecycle(alice, bob, carol);
+    let cluster = FakeCluster {
+        transactions: RefCell::new(transactions[..4].iter().map(copy).collect()),
+        finalized_slot: 10,
+    };
+    let mut store = Store::open_in_memory().unwrap();
+    let mut record = Vec::new();
+    let synced = sync(
+        &cluster,
+        &mut store,
+        &favorites_client::ID,
+        Some(&mut record as &mut dyn Write),
+    )
+    .unwrap();
+    assert_eq!(synced.applied, 4);
+    assert_eq!(store.favorites(&alice).unwrap().unwrap().tips_total, 500);
+
+    // The fork that landed dropped the update and tip of slot 12, and the
+    // delegate moved to slot 13 with the transfer after it
+    let mut moved = copy(&transactions[1]);
+    moved.slot = 13;
+    let mut transfer = copy(&transactions[4]);
+    transfer.slot = 14;
+    *cluster.transactions.borrow_mut() = vec![copy(&transactions[0]), moved, transfer];
+    let synced = sync(
+        &cluster,
+        &mut store,
+        &favorites_client::ID,
+        Some(&mut record as &mut dyn Write),
+    )
+    .unwrap();
+    assert_eq!(
+        synced,
+        Synced {
+            applied: 2,
+            rolled_back: 3
+        }
+    );
+    assert_eq!(store.favorites(&alice).unwrap(), None);
+    let carols = store.favorites(&carol).unwrap().unwrap();
+    assert_eq!((carols.number, carols.color.as_str()), (7, "blue"));
+    assert_eq!(carols.delegate, Some(bob));
+    assert_eq!(store.history(&alice).unwrap().last().unwrap().tips_total, 0);
+
+    // Nothing new
+    let synced = sync(&cluster, &mut store, &favorites_client::ID, None).unwrap();
+    assert_eq!(synced, Synced::default());
+
+    // The recording replays into the same favorites
+    let mut replayed = Store::open_in_memory().unwrap();
+    replay(&record[..], &mut replayed, &favorites_client::ID).unwrap();
+    assert_eq!(
+        replayed.favorites(&carol).unwrap(),
+        store.favorites(&carol).unwrap()
+    );
+    assert_eq!(
+        replayed.history(&alice).unwrap(),
+        store.history(&alice).unwrap()
+    );
+}
@@ -1,103 +1,2502 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable, ed25519_program, entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    sysvar::instructions as instructions_sysvar,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{
+        create_master_edition_v3, create_metadata_accounts_v3, CreateMasterEditionV3,
+        CreateMetadataAccountsV3, Metadata,
+    },
+    token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+use bytemuck::Zeroable;
+use mpl_token_metadata::{types::DataV2, MAX_URI_LENGTH};
 declare_id!("GfwhbDPReQUpdUK6ikBbM9AzkrCE1uVb2dKvr6rqVJQW");
 
 // Anchor programs always use
 pub const ANCHOR_DISCRIMINATOR_SIZE: usize = 8;
 
+// Longest color (in bytes) that fits in the space reserved for `Favorites`
+pub const MAX_COLOR_LEN: usize = 50;
+
+// Longest color with premium. Favorites accounts grow to fit it when set.
+pub const MAX_PREMIUM_COLOR_LEN: usize = 128;
+
+// Layout of `Favorites` this program reads. Accounts from before the version
+// byte are 0 and need `migrate_favorites`.
+pub const FAVORITES_VERSION: u8 = 1;
+
+// Seconds of premium a month of `Config::premium_price` buys
+pub const PREMIUM_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+// Profiles every user can have, and how many premium allows
+pub const FREE_PROFILES: u8 = 1;
+pub const MAX_PROFILES: u8 = 8;
+
+// Items one `Collection` holds. At 64 bytes each they take more than the
+// 10 KiB one instruction can allocate: `create_collection` allocates the first
+// 10 KiB of the `COLLECTION_SPACE` bytes, and `grow_collection` 10 KiB more
+// per call.
+pub const COLLECTION_CAPACITY: usize = 400;
+pub const COLLECTION_SPACE: usize = ANCHOR_DISCRIMINATOR_SIZE + std::mem::size_of::<Collection>();
+
+// The same for the bench's `BorshCollection`
+#[cfg(feature = "bench")]
+pub const BORSH_COLLECTION_SPACE: usize = ANCHOR_DISCRIMINATOR_SIZE + BorshCollection::INIT_SPACE;
+
+// Starts every message an owner signs for `update_favorites_signed`
+pub const SIGNED_UPDATE_PREFIX: &[u8] = b"favorites:update_favorites_signed";
+
+// Users listed by one `RegistryPage` account
+pub const REGISTRY_PAGE_CAPACITY: usize = 32;
+
+// Token Metadata symbol of every favorites badge
+pub const BADGE_SYMBOL: &str = "FAV";
+
+// Most colors a community color proposal can offer
+pub const MAX_PROPOSAL_COLORS: usize = 4;
+
+// Shortest voting period of a proposal in seconds, so that holders get a
+// chance to vote before it can be finalized
+pub const MIN_VOTING_PERIOD: i64 = 24 * 60 * 60;
+
+// Colors counted by `Stats::top_colors`
+pub const MAX_TOP_COLORS: usize = 8;
+
+// Seconds between the recovery key starting a recovery and completing it,
+// during which the owner can cancel it
+pub const RECOVERY_TIMELOCK: i64 = 7 * 24 * 60 * 60;
+
+// Referrers above a user that the program follows when checking for cycles.
+// Each one is an account the transaction passes, so cycles through more
+// wallets than this are not caught.
+pub const MAX_REFERRAL_DEPTH: usize = 8;
+
+// Bounds of a handle's length. Handles are PDA seeds, which hold 32 bytes.
+pub const MIN_HANDLE_LEN: usize = 3;
+pub const MAX_HANDLE_LEN: usize = 32;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Favorites {
     pub number: u64,
 
-    #[max_len(50)]
-    pub color: String,
+    #[max_len(MAX_COLOR_LEN)]
+    pub color: String,
+
+    pub delegate: Option<Pubkey>,
+
+    // New fields go below, after the ones existing accounts already hold
+    pub version: u8,
+
+    // Registry page listing the owner
+    pub registry_page: u32,
+
+    // Next nonce `update_favorites_signed` accepts
+    pub nonce: u64,
+
+    // Unix timestamp premium lasts until, zero if it was never bought
+    pub premium_until: i64,
+
+    // The wallet that brought the owner, set once by `set_favorites`
+    pub referrer: Option<Pubkey>,
+
+    // Sessions opened under another generation are rejected. Every new
+    // favorites account takes one no account had before, so the sessions of
+    // closed or moved favorites never apply again.
+    pub session_generation: u64,
+}
+
+impl Favorites {
+    // Space for favorites with a color of `color_len` bytes. A regular color
+    // always fits, longer ones need the account to grow.
+    pub fn space(color_len: usize) -> usize {
+        ANCHOR_DISCRIMINATOR_SIZE + Favorites::INIT_SPACE + color_len.saturating_sub(MAX_COLOR_LEN)
+    }
+
+    pub fn is_premium(&self, now: i64) -> bool {
+        now < self.premium_until
+    }
+}
+
+// `Favorites` as the first release wrote them, before the version byte. Those
+// accounts were allocated for exactly these fields and never grew.
+#[derive(AnchorDeserialize)]
+struct LegacyFavorites {
+    number: u64,
+    color: String,
+    delegate: Option<Pubkey>,
+}
+
+impl LegacyFavorites {
+    const SPACE: usize = ANCHOR_DISCRIMINATOR_SIZE + 8 + 4 + MAX_COLOR_LEN + 1 + 32;
+}
+
+// One of the extra favorites a user keeps beside their main ones, at
+// [b"profile", user, index]. Sized for premium colors, as premium may lapse
+// and be renewed while the profile lives.
+#[account]
+#[derive(InitSpace)]
+pub struct Profile {
+    pub number: u64,
+
+    #[max_len(MAX_PREMIUM_COLOR_LEN)]
+    pub color: String,
+}
+
+// Up to `COLLECTION_CAPACITY` more favorites a user keeps in one account, at
+// [b"collection", user]. Zero-copy, so instructions touch the items they need
+// in place instead of deserializing all of them. The first `len` items are
+// set; removing one moves the ones after it down. Only usable once
+// `grow_collection` brought it to `COLLECTION_SPACE`.
+#[account(zero_copy)]
+pub struct Collection {
+    pub len: u64,
+    pub items: [CollectionItem; COLLECTION_CAPACITY],
+}
+
+// One favorite of a `Collection`. The color takes the first `color_len`
+// bytes of a buffer sized for the longest color without premium.
+#[zero_copy]
+pub struct CollectionItem {
+    pub number: u64,
+    pub color: [u8; MAX_COLOR_LEN],
+    pub color_len: u8,
+    pub padding: [u8; 5],
+}
+
+impl CollectionItem {
+    fn new(number: u64, color: &str) -> Self {
+        let mut item = CollectionItem::zeroed();
+        item.number = number;
+        item.color[..color.len()].copy_from_slice(color.as_bytes());
+        item.color_len = color.len() as u8;
+        item
+    }
+}
+
+// The same items in a Borsh account, which deserializes all of them on every
+// call. Only built with the `bench` feature, for tests/collection_bench.rs to
+// compare compute units against.
+#[cfg(feature = "bench")]
+#[account]
+#[derive(InitSpace)]
+pub struct BorshCollection {
+    #[max_len(COLLECTION_CAPACITY)]
+    pub items: Vec<BorshCollectionItem>,
+}
+
+#[cfg(feature = "bench")]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct BorshCollectionItem {
+    pub number: u64,
+
+    #[max_len(MAX_COLOR_LEN)]
+    pub color: String,
+}
+
+// How many users with favorites a wallet referred, at [b"referrals",
+// referrer]. Created by the first signup naming the referrer and counted down
+// as their favorites close; the counter itself is never closed.
+#[account]
+#[derive(InitSpace)]
+pub struct Referrals {
+    pub count: u64,
+}
+
+// Lets clients enumerate users with plain account reads, for RPC providers
+// that disable getProgramAccounts. Users are appended to `open_page`; once it
+// is full the next page is opened.
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    pub open_page: u32,
+
+    pub user_count: u64,
+}
+
+// Totals over every favorites account, kept up to date by the instructions
+// creating, updating and closing them so dashboards need not scan accounts.
+#[account]
+#[derive(InitSpace)]
+pub struct Stats {
+    pub user_count: u64,
+
+    pub number_sum: u128,
+
+    // Smallest and largest number ever set. Changing or closing favorites
+    // does not move them back, as finding the new extreme would mean
+    // scanning every account.
+    pub min_number: Option<u64>,
+
+    pub max_number: Option<u64>,
+
+    // Most used colors. Once more colors are in use than fit, a new color
+    // takes the place of the least used one and inherits its count, so
+    // counts may overestimate (the Space-Saving algorithm).
+    #[max_len(MAX_TOP_COLORS)]
+    pub top_colors: Vec<ColorCount>,
+
+    // Favorites accounts ever created, by signups, migrations, transfers and
+    // recoveries. It never counts down, so it numbers session generations.
+    pub favorites_created: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, PartialEq, Eq, Debug)]
+pub struct ColorCount {
+    #[max_len(MAX_COLOR_LEN)]
+    pub color: String,
+
+    pub count: u64,
+}
+
+impl Stats {
+    // Counts a new favorites account, returning its session generation
+    fn next_session_generation(&mut self) -> u64 {
+        self.favorites_created += 1;
+        self.favorites_created
+    }
+
+    fn add(&mut self, number: u64, color: &str) {
+        self.number_sum += number as u128;
+        self.min_number = Some(self.min_number.map_or(number, |min| min.min(number)));
+        self.max_number = Some(self.max_number.map_or(number, |max| max.max(number)));
+
+        // Premium colors do not fit a `ColorCount`, so only regular ones rank
+        if color.len() > MAX_COLOR_LEN {
+            return;
+        }
+        let colors = &mut self.top_colors;
+        if let Some(entry) = colors.iter_mut().find(|entry| entry.color == color) {
+            entry.count += 1;
+        } else if colors.len() < MAX_TOP_COLORS {
+            colors.push(ColorCount {
+                color: color.to_string(),
+                count: 1,
+            });
+        } else if let Some(least) = colors.iter_mut().min_by_key(|entry| entry.count) {
+            least.color = color.to_string();
+            least.count += 1;
+        }
+    }
+
+    // Saturates, as favorites set before the stats existed were never added
+    fn remove(&mut self, number: u64, color: &str) {
+        self.number_sum = self.number_sum.saturating_sub(number as u128);
+
+        let colors = &mut self.top_colors;
+        if let Some(index) = colors.iter().position(|entry| entry.color == color) {
+            colors[index].count -= 1;
+            if colors[index].count == 0 {
+                colors.swap_remove(index);
+            }
+        }
+    }
+}
+
+// A unique human-readable name such as `slava`, shown as `@slava`, at the
+// PDA seeded by the handle itself so clients can resolve it with one read
+#[account]
+#[derive(InitSpace)]
+pub struct Handle {
+    pub owner: Pubkey,
+
+    // The owner's favorites account
+    pub favorites: Pubkey,
+
+    #[max_len(MAX_HANDLE_LEN)]
+    pub handle: String,
+}
+
+// Program-wide settings, created once by `initialize_config`. While it does
+// not exist nothing is gated.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+
+    // When set, `set_favorites` and the updates require the signer to hold
+    // at least `gate_min_amount` of this mint
+    pub gate_mint: Option<Pubkey>,
+
+    pub gate_min_amount: u64,
+
+    // Mint whose balance weighs community color votes. No proposals while unset.
+    pub vote_mint: Option<Pubkey>,
+
+    // Charged to the signer of `set_favorites` and `update_favorites` unless
+    // they are fee exempt: `fee_amount` lamports while `fee_mint` is unset,
+    // or `fee_amount` base units of `fee_mint`. Signed relays stay free, as
+    // the owner does not sign their transaction.
+    pub fee_mint: Option<Pubkey>,
+
+    pub fee_amount: u64,
+
+    // Premium costs `premium_price` base units of this mint a month, paid
+    // into the treasury. Nobody can buy or renew it while unset.
+    pub premium_mint: Option<Pubkey>,
+
+    pub premium_price: u64,
+}
+
+// Collects the fees, holding lamport fees itself and owning the token
+// accounts token fees are paid into. Created by the first `set_fee`.
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {}
+
+// Marks `user` as exempt from fees, at [b"fee_exempt", user]
+#[account]
+#[derive(InitSpace)]
+pub struct FeeExemption {}
+
+// An ephemeral key the owner allows to call `update_favorites` a limited
+// number of times until `expiry`. Revoking closes the account.
+#[account]
+#[derive(InitSpace)]
+pub struct Session {
+    pub session_key: Pubkey,
+
+    pub uses_left: u32,
+
+    // Unix timestamp after which the key is rejected
+    pub expiry: i64,
+
+    // `Favorites::session_generation` of the favorites it was opened for
+    pub generation: u64,
+}
+
+// Favorites bound to an NFT instead of a wallet: whoever holds the single
+// token of `mint` controls them, so they change hands with the NFT.
+#[account]
+#[derive(InitSpace)]
+pub struct NftFavorites {
+    pub mint: Pubkey,
+
+    pub number: u64,
+
+    #[max_len(MAX_COLOR_LEN)]
+    pub color: String,
+}
+
+// Holds the tips sent to a user on top of its own rent. `tips_total` only
+// grows: withdrawing leaves it untouched.
+#[account]
+#[derive(InitSpace)]
+pub struct TipVault {
+    pub tips_total: u64,
+}
+
+#[event]
+pub struct TipReceived {
+    pub user: Pubkey,
+    pub tipper: Pubkey,
+    pub amount: u64,
+    pub tips_total: u64,
+}
+
+// The key `user` trusts to move their favorites to a new wallet should they
+// lose theirs, at [b"recovery", user]. A recovery it starts can complete
+// `RECOVERY_TIMELOCK` later, leaving the owner time to cancel it.
+#[account]
+#[derive(InitSpace)]
+pub struct Recovery {
+    pub recovery_key: Pubkey,
+
+    // Where the recovery in progress moves the favorites, if any
+    pub new_owner: Option<Pubkey>,
+
+    // Unix timestamp from which the recovery in progress can complete
+    pub unlocks_at: i64,
+}
+
+// Every recovery step emits an event, for auditing who moved what and when
+#[event]
+pub struct RecoveryKeySet {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+}
+
+#[event]
+pub struct RecoveryKeyRemoved {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+}
+
+#[event]
+pub struct RecoveryStarted {
+    pub user: Pubkey,
+    pub recovery_key: Pubkey,
+    pub new_owner: Pubkey,
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct RecoveryCancelled {
+    pub user: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct RecoveryCompleted {
+    pub user: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+// A vote on the community color between `colors`, open until
+// `voting_ends_at`. Votes are weighed by balance of `vote_mint`, which stays
+// locked in the proposal's escrow until voting ends.
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub proposer: Pubkey,
+
+    // Chosen by the proposer, to tell their proposals apart
+    pub id: u64,
+
+    pub vote_mint: Pubkey,
+
+    // Unix timestamp of its creation
+    pub voting_starts_at: i64,
+
+    // Unix timestamp from which votes are rejected and the proposal can be finalized
+    pub voting_ends_at: i64,
+
+    pub finalized: bool,
+
+    #[max_len(MAX_PROPOSAL_COLORS, MAX_COLOR_LEN)]
+    pub colors: Vec<String>,
+}
+
+// Vote weight per color of `proposal`, in the same order as its colors
+#[account]
+#[derive(InitSpace)]
+pub struct Tally {
+    pub proposal: Pubkey,
+
+    #[max_len(MAX_PROPOSAL_COLORS)]
+    pub votes: Vec<u64>,
+}
+
+// One voter's vote. Its existence stops them voting twice; reclaiming the
+// locked tokens closes it.
+#[account]
+#[derive(InitSpace)]
+pub struct VoteReceipt {
+    pub choice: u8,
+
+    pub amount: u64,
+}
+
+// The color the newest finalized proposal with any votes picked
+#[account]
+#[derive(InitSpace)]
+pub struct CommunityColor {
+    pub proposal: Pubkey,
+
+    // When `proposal` was created; proposals created before it can no longer
+    // be finalized, so an old one ending late cannot override a newer decision
+    pub voting_started_at: i64,
+
+    pub decided_at: i64,
+
+    #[max_len(MAX_COLOR_LEN)]
+    pub color: String,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    #[max_len(REGISTRY_PAGE_CAPACITY)]
+    pub users: Vec<Pubkey>,
+}
+
+#[derive(Accounts)]
+pub struct SetFavorites<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Favorites::INIT_SPACE,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Registry::INIT_SPACE,
+        seeds = [b"registry"],
+        bump,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + RegistryPage::INIT_SPACE,
+        seeds = [b"registry", registry.open_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    /// CHECK: The config PDA, which may not exist yet. Read in handler.
+    #[account(seeds = [b"config"], bump)]
+    pub config: AccountInfo<'info>,
+
+    // The signer's token account for the gate mint, when gating is on
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+
+    // Only exists for fee exempt payers
+    #[account(seeds = [b"fee_exempt", user.key().as_ref()], bump)]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    /// CHECK: The treasury PDA, which only needs to exist while lamport fees are charged.
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: AccountInfo<'info>,
+
+    // Only needed for token fees: the payer's tokens of the fee mint, and the
+    // treasury's token account they go to
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// CHECK: The wallet that referred the signer, if any. Any wallet but theirs
+    /// with favorites, followed up its referral chain by the handler.
+    #[account(constraint = referrer.key() != user.key() @ CustomError::SelfReferral)]
+    pub referrer: Option<AccountInfo<'info>>,
+
+    // The referrer's counter, passed along with them. Passed alone, it is
+    // rejected by the handler.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Referrals::INIT_SPACE,
+        seeds = [
+            b"referrals",
+            referrer.as_ref().map_or::<&[u8], _>(&[], |referrer| referrer.key.as_ref()),
+        ],
+        bump,
+    )]
+    pub referrals: Option<Account<'info, Referrals>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFavorites<'info> {
+    // Pays for the account to grow
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Favorites in the first release's layout, which `Account` cannot decode. Checked in handler.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+    )]
+    pub favorites: UncheckedAccount<'info>,
+
+    // The first release had no registry, so migrated users join it like new ones
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Registry::INIT_SPACE,
+        seeds = [b"registry"],
+        bump,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + RegistryPage::INIT_SPACE,
+        seeds = [b"registry", registry.open_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+
+
+#[derive(Accounts)]
+#[instruction(number: u64, color: String)]
+pub struct UpdateFavorites<'info> {
+    /// CHECK: The owner of the favorites, who need not sign. Only used for seeds.
+    pub user: AccountInfo<'info>,
+
+    /// CHECK: The owner, the delegate or a session key, who pays any fee. Manual validation is done in handler.
+    #[account(mut, signer)]
+    pub signer: AccountInfo<'info>,
+
+    // Grows to fit a premium color at the signer's expense, and never shrinks
+    #[account(
+        mut,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        realloc = Favorites::space(color.len()).max(favorites.to_account_info().data_len()),
+        realloc::payer = signer,
+        realloc::zero = false,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: Account<'info, Stats>,
+
+    // Only needed when `signer` is a session key
+    #[account(
+        mut,
+        seeds = [b"session", user.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub session: Option<Account<'info, Session>>,
+
+    /// CHECK: The config PDA, which may not exist yet. Read in handler.
+    #[account(seeds = [b"config"], bump)]
+    pub config: AccountInfo<'info>,
+
+    // The signer's token account for the gate mint, when gating is on
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+
+    // Only exists for fee exempt payers
+    #[account(seeds = [b"fee_exempt", signer.key().as_ref()], bump)]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    /// CHECK: The treasury PDA, which only needs to exist while lamport fees are charged.
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: AccountInfo<'info>,
+
+    // Only needed for token fees: the payer's tokens of the fee mint, and the
+    // treasury's token account they go to
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    // Only the program's upgrade authority, so nobody can claim the config
+    // between the deploy and its initialization
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // The program's data account, which names its upgrade authority
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ CustomError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Treasury::INIT_SPACE,
+        seeds = [b"treasury"],
+        bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddFeeExemption<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The user to exempt. Only used for seeds.
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ANCHOR_DISCRIMINATOR_SIZE + FeeExemption::INIT_SPACE,
+        seeds = [b"fee_exempt", user.key().as_ref()],
+        bump,
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeExemption<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The user to charge again. Only used for seeds.
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"fee_exempt", user.key().as_ref()],
+        bump,
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFeeTokens<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut, token::authority = treasury)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    // Any token account of the same mint
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        init,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Session::INIT_SPACE,
+        seeds = [b"session", user.key().as_ref(), session_key.as_ref()],
+        bump,
+    )]
+    pub session: Account<'info, Session>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"session", user.key().as_ref(), session.session_key.as_ref()],
+        bump,
+    )]
+    pub session: Account<'info, Session>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFavoritesSigned<'info> {
+    /// CHECK: The owner does not sign the transaction. Their ed25519 signature over the update is checked in handler.
+    pub user: AccountInfo<'info>,
+
+    // Submits the update and pays any fee, which may be the owner themselves
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: Account<'info, Stats>,
+
+    /// CHECK: The instructions sysvar, checked by address.
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    /// CHECK: The config PDA, which may not exist yet. Read in handler.
+    #[account(seeds = [b"config"], bump)]
+    pub config: AccountInfo<'info>,
+
+    // The owner's token account for the gate mint, when gating is on
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+
+    // Only exists for fee exempt relayers
+    #[account(seeds = [b"fee_exempt", relayer.key().as_ref()], bump)]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    /// CHECK: The treasury PDA, which only needs to exist while lamport fees are charged.
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: AccountInfo<'info>,
+
+    // Only needed for token fees: the relayer's tokens of the fee mint, and
+    // the treasury's token account they go to
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftFavorites<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    // The holder's token account for `mint`. Validated in handler.
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = ANCHOR_DISCRIMINATOR_SIZE + NftFavorites::INIT_SPACE,
+        seeds = [b"nft_favorites", mint.key().as_ref()],
+        bump,
+    )]
+    pub nft_favorites: Account<'info, NftFavorites>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateNftFavorites<'info> {
+    pub holder: Signer<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    // The holder's token account for `mint`. Validated in handler.
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"nft_favorites", mint.key().as_ref()],
+        bump,
+    )]
+    pub nft_favorites: Account<'info, NftFavorites>,
+}
+
+#[derive(Accounts)]
+pub struct MintFavoritesBadge<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    // A fresh keypair for every badge
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = badge_authority,
+        mint::freeze_authority = badge_authority,
+    )]
+    pub badge_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = badge_mint,
+        associated_token::authority = user,
+    )]
+    pub badge_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Holds no data. Mint and update authority of every badge.
+    #[account(seeds = [b"badge_authority"], bump)]
+    pub badge_authority: AccountInfo<'info>,
+
+    /// CHECK: Created by Token Metadata.
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), badge_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump,
+    )]
+    pub metadata: AccountInfo<'info>,
+
+    /// CHECK: Created by Token Metadata.
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            badge_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Tip<'info> {
+    #[account(mut)]
+    pub tipper: Signer<'info>,
+
+    /// CHECK: The user being tipped. Only used for seeds.
+    pub user: AccountInfo<'info>,
+
+    // Only users with favorites can be tipped
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    // The first tipper pays the vault's rent
+    #[account(
+        init_if_needed,
+        payer = tipper,
+        space = ANCHOR_DISCRIMINATOR_SIZE + TipVault::INIT_SPACE,
+        seeds = [b"tip_vault", user.key().as_ref()],
+        bump,
+    )]
+    pub tip_vault: Account<'info, TipVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTips<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"tip_vault", user.key().as_ref()],
+        bump,
+    )]
+    pub tip_vault: Account<'info, TipVault>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.vote_mint.ok_or(CustomError::VotingDisabled)?)]
+    pub vote_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Proposal::INIT_SPACE,
+        seeds = [b"proposal", proposer.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Tally::INIT_SPACE,
+        seeds = [b"tally", proposal.key().as_ref()],
+        bump,
+    )]
+    pub tally: Account<'info, Tally>,
+
+    // Holds the votes' tokens, with the proposal as its authority
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"vote_escrow", proposal.key().as_ref()],
+        bump,
+        token::mint = vote_mint,
+        token::authority = proposal,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"tally", proposal.key().as_ref()],
+        bump,
+    )]
+    pub tally: Account<'info, Tally>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = ANCHOR_DISCRIMINATOR_SIZE + VoteReceipt::INIT_SPACE,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub vote_receipt: Account<'info, VoteReceipt>,
+
+    // The voter's tokens of the vote mint, all of which are locked
+    #[account(
+        mut,
+        token::mint = proposal.vote_mint,
+        token::authority = voter,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vote_escrow", proposal.key().as_ref()],
+        bump,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    // Anyone may finalize, paying for the community color the first time
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"tally", proposal.key().as_ref()],
+        bump,
+    )]
+    pub tally: Account<'info, Tally>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ANCHOR_DISCRIMINATOR_SIZE + CommunityColor::INIT_SPACE,
+        seeds = [b"community_color"],
+        bump,
+    )]
+    pub community_color: Account<'info, CommunityColor>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"proposal", proposal.proposer.as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub vote_receipt: Account<'info, VoteReceipt>,
+
+    #[account(
+        mut,
+        token::mint = proposal.vote_mint,
+        token::authority = voter,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vote_escrow", proposal.key().as_ref()],
+        bump,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(handle: String)]
+pub struct RegisterHandle<'info> {
+    #[account(mut, constraint = is_valid_handle(&handle) @ CustomError::InvalidHandle)]
+    pub owner: Signer<'info>,
+
+    // Only users with favorites can take a handle
+    #[account(
+        seeds = [b"favorites", owner.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    // `init` fails if the handle is taken. Seeds hold 32 bytes, so longer
+    // handles are cut to get as far as the check on `owner`.
+    #[account(
+        init,
+        payer = owner,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Handle::INIT_SPACE,
+        seeds = [b"handle", &handle.as_bytes()[..handle.len().min(MAX_HANDLE_LEN)]],
+        bump,
+    )]
+    pub handle_account: Account<'info, Handle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHandle<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ CustomError::Unauthorized,
+        seeds = [b"handle", handle_account.handle.as_bytes()],
+        bump,
+    )]
+    pub handle_account: Account<'info, Handle>,
+
+    /// CHECK: The new owner, who need not sign. Only used for seeds.
+    pub new_owner: AccountInfo<'info>,
+
+    // The new owner needs favorites for the handle to point at
+    #[account(
+        seeds = [b"favorites", new_owner.key().as_ref()],
+        bump,
+        constraint = new_owner_favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub new_owner_favorites: Account<'info, Favorites>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHandle<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ CustomError::Unauthorized,
+        seeds = [b"handle", handle_account.handle.as_bytes()],
+        bump,
+    )]
+    pub handle_account: Account<'info, Handle>,
+}
+
+#[derive(Accounts)]
+pub struct TransferFavorites<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // Pays for the new account
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    // As large as the old account, which may have grown for a premium color
+    #[account(
+        init,
+        payer = new_owner,
+        space = favorites.to_account_info().data_len(),
+        seeds = [b"favorites", new_owner.key().as_ref()],
+        bump,
+    )]
+    pub new_favorites: Account<'info, Favorites>,
+
+    /// CHECK: The old owner's recovery PDA, closed by the handler when it exists.
+    #[account(mut, seeds = [b"recovery", user.key().as_ref()], bump)]
+    pub recovery: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"registry", favorites.registry_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewPremium<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The treasury PDA, owner of the token account premium is paid into.
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: AccountInfo<'info>,
+
+    // The user's tokens of the premium mint, and the treasury's token account
+    // they go to
+    #[account(mut)]
+    pub payment_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPremium<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct SetProfile<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // Says whether the user has premium
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Profile::INIT_SPACE,
+        seeds = [b"profile", user.key().as_ref(), &[index]],
+        bump,
+    )]
+    pub profile: Account<'info, Profile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct CloseProfile<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"profile", user.key().as_ref(), &[index]],
+        bump,
+    )]
+    pub profile: Account<'info, Profile>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCollection<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // Only users with favorites keep a collection beside them
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    // As much of it as one instruction can allocate, see `grow_collection`
+    #[account(
+        init,
+        payer = user,
+        space = COLLECTION_SPACE.min(MAX_PERMITTED_DATA_INCREASE),
+        seeds = [b"collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: AccountLoader<'info, Collection>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrowCollection<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: AccountLoader<'info, Collection>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Appending, updating and removing items all take the same accounts
+#[derive(Accounts)]
+pub struct ModifyCollection<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"collection", user.key().as_ref()],
+        bump,
+        constraint = collection.as_ref().data_len() == COLLECTION_SPACE
+            @ CustomError::CollectionNotGrown,
+    )]
+    pub collection: AccountLoader<'info, Collection>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCollection<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: AccountLoader<'info, Collection>,
+}
+
+#[cfg(feature = "bench")]
+#[derive(Accounts)]
+pub struct CreateBorshCollection<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = BORSH_COLLECTION_SPACE.min(MAX_PERMITTED_DATA_INCREASE),
+        seeds = [b"borsh_collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: Account<'info, BorshCollection>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "bench")]
+#[derive(Accounts)]
+pub struct GrowBorshCollection<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"borsh_collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: Account<'info, BorshCollection>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "bench")]
+#[derive(Accounts)]
+pub struct ModifyBorshCollection<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"borsh_collection", user.key().as_ref()],
+        bump,
+    )]
+    pub collection: Account<'info, BorshCollection>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecoveryKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // Only users with favorites have anything to recover
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ANCHOR_DISCRIMINATOR_SIZE + Recovery::INIT_SPACE,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveRecoveryKey<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+}
+
+#[derive(Accounts)]
+pub struct StartRecovery<'info> {
+    pub recovery_key: Signer<'info>,
+
+    /// CHECK: The owner who lost their key. Only seeds the PDAs below.
+    pub user: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        mut,
+        has_one = recovery_key @ CustomError::Unauthorized,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+}
+
+// Moves the favorites like `TransferFavorites`, without the owner's signature.
+// The new owner gets the rent of both closed accounts, as the old wallet is
+// presumed lost.
+#[derive(Accounts)]
+pub struct CompleteRecovery<'info> {
+    /// CHECK: The owner who lost their key. Only seeds the PDAs below.
+    pub user: AccountInfo<'info>,
+
+    // Pays for the new account
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = new_owner,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        init,
+        payer = new_owner,
+        space = favorites.to_account_info().data_len(),
+        seeds = [b"favorites", new_owner.key().as_ref()],
+        bump,
+    )]
+    pub new_favorites: Account<'info, Favorites>,
+
+    #[account(
+        mut,
+        close = new_owner,
+        seeds = [b"recovery", user.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    #[account(
+        mut,
+        seeds = [b"registry", favorites.registry_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFavorites<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"favorites", user.key().as_ref()],
+        bump,
+        constraint = favorites.version == FAVORITES_VERSION @ CustomError::NotMigrated,
+    )]
+    pub favorites: Account<'info, Favorites>,
+
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    #[account(
+        mut,
+        seeds = [b"registry", favorites.registry_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub registry_page: Account<'info, RegistryPage>,
+
+    #[account(mut, seeds = [b"stats"], bump)]
+    pub stats: Account<'info, Stats>,
+
+    // The referrer's counter, required when the favorites name one
+    #[account(
+        mut,
+        seeds = [
+            b"referrals",
+            favorites.referrer.as_ref().map_or::<&[u8], _>(&[], |referrer| referrer.as_ref()),
+        ],
+        bump,
+    )]
+    pub referrals: Option<Account<'info, Referrals>>,
+
+    /// CHECK: The owner's recovery PDA, closed by the handler when it exists.
+    #[account(mut, seeds = [b"recovery", user.key().as_ref()], bump)]
+    pub recovery: AccountInfo<'info>,
+}
+
+// Our Solana program!
+#[program]
+pub mod favorites {
+    use super::*;
+
+    // Our instruction handler! It sets the user's favorite number and color.
+    // A referred signup passes the favorites of the referrer and of every
+    // wallet up their referral chain after the named accounts.
+    pub fn set_favorites(context: Context<SetFavorites>, number: u64, color: String) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        check_token_gate(
+            &context.accounts.config,
+            context.accounts.gate_token_account.as_ref(),
+            context.accounts.user.key,
+        )?;
+        let accounts = &context.accounts;
+        charge_fee(FeeAccounts {
+            config: &accounts.config,
+            payer: &accounts.user,
+            fee_exemption: accounts.fee_exemption.as_ref(),
+            treasury: &accounts.treasury,
+            fee_token_account: accounts.fee_token_account.as_ref(),
+            treasury_token_account: accounts.treasury_token_account.as_ref(),
+            token_program: accounts.token_program.as_ref(),
+            system_program: &accounts.system_program,
+        })?;
+
+        let user_public_key = context.accounts.user.key();
+        msg!("Greetings from {}", context.program_id);
+        msg!(
+            "User {}'s favorite number is {} and favorite color is: {}",
+            user_public_key,
+            number,
+            color
+        );
+
+        let registry_page = register(
+            user_public_key,
+            number,
+            &color,
+            &mut context.accounts.registry,
+            &mut context.accounts.registry_page,
+            &mut context.accounts.stats,
+        );
+
+        let referrer = context.accounts.referrer.as_ref().map(|referrer| referrer.key());
+        match (referrer, context.accounts.referrals.as_mut()) {
+            (Some(referrer), Some(referrals)) => {
+                let chain = context.remaining_accounts;
+                check_referral_chain(user_public_key, Some(referrer), chain)?;
+                // The chain starts with the referrer's own favorites
+                require!(!chain[0].data_is_empty(), CustomError::ReferrerWithoutFavorites);
+                referrals.count += 1;
+                msg!("Referred by {}", referrer);
+            }
+            (None, None) => {}
+            _ => return err!(CustomError::InvalidReferralAccounts),
+        }
+
+        context.accounts.favorites.set_inner(Favorites {
+            number,
+            color,
+            delegate: None,
+            version: FAVORITES_VERSION,
+            registry_page,
+            nonce: 0,
+            premium_until: 0,
+            referrer,
+            session_generation: context.accounts.stats.next_session_generation(),
+        });
+        Ok(())
+    }
+
+    // Brings favorites from before the version byte to the current layout,
+    // growing the account at the owner's expense and registering them
+    pub fn migrate_favorites(context: Context<MigrateFavorites>) -> Result<()> {
+        let user = context.accounts.user.key();
+        let favorites = context.accounts.favorites.to_account_info();
+        // Current accounts are allocated for every field, so they never come
+        // in as small as a legacy one
+        require!(
+            favorites.data_len() <= LegacyFavorites::SPACE,
+            CustomError::AlreadyMigrated
+        );
+        let legacy = {
+            let data = favorites.try_borrow_data()?;
+            require!(
+                data.starts_with(Favorites::DISCRIMINATOR),
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            LegacyFavorites::deserialize(&mut &data[ANCHOR_DISCRIMINATOR_SIZE..])
+                .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+        };
+
+        let space = Favorites::space(legacy.color.len());
+        let missing = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(favorites.lamports());
+        if missing > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    context.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: context.accounts.user.to_account_info(),
+                        to: favorites.clone(),
+                    },
+                ),
+                missing,
+            )?;
+        }
+        favorites.realloc(space, true)?;
+
+        let registry_page = register(
+            user,
+            legacy.number,
+            &legacy.color,
+            &mut context.accounts.registry,
+            &mut context.accounts.registry_page,
+            &mut context.accounts.stats,
+        );
+        let migrated = Favorites {
+            number: legacy.number,
+            color: legacy.color,
+            delegate: legacy.delegate,
+            version: FAVORITES_VERSION,
+            registry_page,
+            nonce: 0,
+            premium_until: 0,
+            referrer: None,
+            session_generation: context.accounts.stats.next_session_generation(),
+        };
+        migrated.try_serialize(&mut &mut favorites.try_borrow_mut_data()?[..])?;
+        msg!("✅ Favorites of {} migrated.", user);
+        Ok(())
+    }
+
+    pub fn update_favorites(
+        context: Context<UpdateFavorites>,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let premium = context.accounts.favorites.is_premium(now);
+        require!(color.len() <= max_color_len(premium), CustomError::ColorTooLong);
+
+        let current_delegate: Option<Pubkey> = context.accounts.favorites.delegate;
+        let signer = context.accounts.signer.key;
+        check_token_gate(
+            &context.accounts.config,
+            context.accounts.gate_token_account.as_ref(),
+            signer,
+        )?;
+
+        let is_owner = signer == context.accounts.user.key;
+        let is_delegate = current_delegate == Some(*signer);
+
+        if !is_owner && !is_delegate {
+            // The seeds tie the session to this owner and signer
+            let session = context
+                .accounts
+                .session
+                .as_mut()
+                .ok_or(CustomError::Unauthorized)?;
+            require!(
+                session.generation == context.accounts.favorites.session_generation,
+                CustomError::SessionOutdated
+            );
+            require!(now <= session.expiry, CustomError::SessionExpired);
+            require!(session.uses_left > 0, CustomError::SessionExhausted);
+            session.uses_left -= 1;
+        }
+
+        let accounts = &context.accounts;
+        charge_fee(FeeAccounts {
+            config: &accounts.config,
+            payer: &accounts.signer,
+            fee_exemption: accounts.fee_exemption.as_ref(),
+            treasury: &accounts.treasury,
+            fee_token_account: accounts.fee_token_account.as_ref(),
+            treasury_token_account: accounts.treasury_token_account.as_ref(),
+            token_program: accounts.token_program.as_ref(),
+            system_program: &accounts.system_program,
+        })?;
+
+        let favorites = &mut context.accounts.favorites;
+        let stats = &mut context.accounts.stats;
+        stats.remove(favorites.number, &favorites.color);
+        stats.add(number, &color);
+        favorites.number = number;
+        favorites.color = color;
+        Ok(())
+    }
+
+    // Applies an update the owner signed off-chain, so that a relayer can
+    // submit it and pay the fees, the program's fee included. The instruction
+    // right before this one must verify the owner's signature of
+    // `signed_update_message` through the Ed25519 program. The relayer does not
+    // pay for space, so premium colors need `update_favorites`.
+    pub fn update_favorites_signed(
+        context: Context<UpdateFavoritesSigned>,
+        number: u64,
+        color: String,
+        nonce: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        require!(
+            Clock::get()?.unix_timestamp <= expiry,
+            CustomError::SignedUpdateExpired
+        );
+        require!(
+            nonce == context.accounts.favorites.nonce,
+            CustomError::NonceMismatch
+        );
+
+        let user = context.accounts.user.key();
+        let message = signed_update_message(&user, number, &color, nonce, expiry);
+        verify_ed25519_signature(&context.accounts.instructions, &user, &message)?;
+        check_token_gate(
+            &context.accounts.config,
+            context.accounts.gate_token_account.as_ref(),
+            &user,
+        )?;
+        let accounts = &context.accounts;
+        charge_fee(FeeAccounts {
+            config: &accounts.config,
+            payer: &accounts.relayer,
+            fee_exemption: accounts.fee_exemption.as_ref(),
+            treasury: &accounts.treasury,
+            fee_token_account: accounts.fee_token_account.as_ref(),
+            treasury_token_account: accounts.treasury_token_account.as_ref(),
+            token_program: accounts.token_program.as_ref(),
+            system_program: &accounts.system_program,
+        })?;
+
+        let favorites = &mut context.accounts.favorites;
+        let stats = &mut context.accounts.stats;
+        stats.remove(favorites.number, &favorites.color);
+        stats.add(number, &color);
+        favorites.number = number;
+        favorites.color = color;
+        favorites.nonce += 1;
+        Ok(())
+    }
+
+    // Mints the user a 1-of-1 NFT showing their current number and color.
+    // The program's badge authority PDA stays its update authority.
+    pub fn mint_favorites_badge(context: Context<MintFavoritesBadge>) -> Result<()> {
+        let favorites = &context.accounts.favorites;
+        let uri = badge_uri(favorites.number, &favorites.color);
+        require!(uri.len() <= MAX_URI_LENGTH, CustomError::BadgeUriTooLong);
+
+        let accounts = &context.accounts;
+        let bump = [context.bumps.badge_authority];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"badge_authority", &bump]];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: accounts.badge_mint.to_account_info(),
+                    to: accounts.badge_token_account.to_account_info(),
+                    authority: accounts.badge_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: accounts.metadata.to_account_info(),
+                    mint: accounts.badge_mint.to_account_info(),
+                    mint_authority: accounts.badge_authority.to_account_info(),
+                    payer: accounts.user.to_account_info(),
+                    update_authority: accounts.badge_authority.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    rent: accounts.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            DataV2 {
+                name: badge_name(favorites.number),
+                symbol: BADGE_SYMBOL.to_string(),
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        // A max supply of zero makes it a 1-of-1: no prints, and the edition
+        // takes over the mint authority
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: accounts.master_edition.to_account_info(),
+                    mint: accounts.badge_mint.to_account_info(),
+                    update_authority: accounts.badge_authority.to_account_info(),
+                    mint_authority: accounts.badge_authority.to_account_info(),
+                    payer: accounts.user.to_account_info(),
+                    metadata: accounts.metadata.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    rent: accounts.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            Some(0),
+        )?;
+
+        msg!("✅ Badge {} minted.", accounts.badge_mint.key());
+        Ok(())
+    }
+
+    // Creates the favorites bound to an NFT. Only its current holder may.
+    pub fn set_nft_favorites(
+        context: Context<SetNftFavorites>,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        check_nft_holder(
+            &context.accounts.mint,
+            &context.accounts.holder_token_account,
+            context.accounts.holder.key,
+        )?;
+
+        let mint = context.accounts.mint.key();
+        context.accounts.nft_favorites.set_inner(NftFavorites {
+            mint,
+            number,
+            color,
+        });
+        msg!("✅ Favorites bound to NFT {} created.", mint);
+        Ok(())
+    }
+
+    // Overwrites the favorites bound to an NFT. Whoever holds it now may,
+    // whoever created them.
+    pub fn update_nft_favorites(
+        context: Context<UpdateNftFavorites>,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        check_nft_holder(
+            &context.accounts.mint,
+            &context.accounts.holder_token_account,
+            context.accounts.holder.key,
+        )?;
+
+        let nft_favorites = &mut context.accounts.nft_favorites;
+        nft_favorites.number = number;
+        nft_favorites.color = color;
+        Ok(())
+    }
+
+    // Sends `amount` lamports from the tipper to the user's tip vault
+    pub fn tip(context: Context<Tip>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::ZeroTip);
+
+        system_program::transfer(
+            CpiContext::new(
+                context.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: context.accounts.tipper.to_account_info(),
+                    to: context.accounts.tip_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let tip_vault = &mut context.accounts.tip_vault;
+        tip_vault.tips_total = tip_vault
+            .tips_total
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        emit!(TipReceived {
+            user: context.accounts.user.key(),
+            tipper: context.accounts.tipper.key(),
+            amount,
+            tips_total: tip_vault.tips_total,
+        });
+        Ok(())
+    }
+
+    // Moves every tipped lamport to the owner, leaving the vault rent exempt
+    pub fn withdraw_tips(context: Context<WithdrawTips>) -> Result<()> {
+        let tip_vault = context.accounts.tip_vault.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(tip_vault.data_len());
+        let amount = tip_vault.lamports().saturating_sub(rent_exempt);
+        require!(amount > 0, CustomError::NoTipsToWithdraw);
+
+        // The program owns the vault, so it can debit it directly
+        tip_vault.sub_lamports(amount)?;
+        context.accounts.user.add_lamports(amount)?;
+        msg!("✅ Withdrew {} lamports of tips.", amount);
+        Ok(())
+    }
+
+    // Creates the program config. Only the program's upgrade authority can,
+    // and becomes its admin.
+    pub fn initialize_config(
+        context: Context<InitializeConfig>,
+        gate_mint: Option<Pubkey>,
+        gate_min_amount: u64,
+    ) -> Result<()> {
+        context.accounts.config.set_inner(Config {
+            admin: context.accounts.admin.key(),
+            gate_mint,
+            gate_min_amount,
+            vote_mint: None,
+            fee_mint: None,
+            fee_amount: 0,
+            premium_mint: None,
+            premium_price: 0,
+        });
+        msg!("✅ Config initialized by {}", context.accounts.admin.key());
+        Ok(())
+    }
+
+    // Turns token gating on with `gate_mint` set, or off with `None`
+    pub fn set_token_gate(
+        context: Context<UpdateConfig>,
+        gate_mint: Option<Pubkey>,
+        gate_min_amount: u64,
+    ) -> Result<()> {
+        let config = &mut context.accounts.config;
+        config.gate_mint = gate_mint;
+        config.gate_min_amount = gate_min_amount;
+        Ok(())
+    }
+
+    // Sets the fee on creating and updating favorites: `fee_amount` lamports
+    // without `fee_mint`, or base units of it. Zero makes them free.
+    pub fn set_fee(
+        context: Context<SetFee>,
+        fee_mint: Option<Pubkey>,
+        fee_amount: u64,
+    ) -> Result<()> {
+        let config = &mut context.accounts.config;
+        config.fee_mint = fee_mint;
+        config.fee_amount = fee_amount;
+        Ok(())
+    }
+
+    // Prices premium at `premium_price` base units of `premium_mint` a month,
+    // or stops sales with `None`. Premium already bought keeps running.
+    pub fn set_premium(
+        context: Context<SetFee>,
+        premium_mint: Option<Pubkey>,
+        premium_price: u64,
+    ) -> Result<()> {
+        let config = &mut context.accounts.config;
+        config.premium_mint = premium_mint;
+        config.premium_price = premium_price;
+        Ok(())
+    }
 
-    pub delegate: Option<Pubkey>,
-}
+    // Buys `months` of premium, paid into the treasury's token account of the
+    // premium mint. Renewing early extends premium; after it lapsed, the new
+    // months start now.
+    pub fn renew_premium(context: Context<RenewPremium>, months: u8) -> Result<()> {
+        require!(months > 0, CustomError::ZeroPremiumMonths);
+        let config = &context.accounts.config;
+        let premium_mint = config
+            .premium_mint
+            .ok_or(CustomError::PremiumUnavailable)?;
+        let from = &context.accounts.payment_token_account;
+        let to = &context.accounts.treasury_token_account;
+        require_keys_eq!(from.mint, premium_mint, CustomError::InvalidPremiumAccounts);
+        require_keys_eq!(to.mint, premium_mint, CustomError::InvalidPremiumAccounts);
+        require_keys_eq!(
+            to.owner,
+            context.accounts.treasury.key(),
+            CustomError::InvalidPremiumAccounts
+        );
+        let price = config
+            .premium_price
+            .checked_mul(months as u64)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        transfer(
+            CpiContext::new(
+                context.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: context.accounts.user.to_account_info(),
+                },
+            ),
+            price,
+        )?;
 
-#[derive(Accounts)]
-pub struct SetFavorites<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        let now = Clock::get()?.unix_timestamp;
+        let favorites = &mut context.accounts.favorites;
+        favorites.premium_until = favorites.premium_until.max(now) + months as i64 * PREMIUM_PERIOD;
+        msg!(
+            "✅ Premium of {} runs until {}.",
+            context.accounts.user.key(),
+            favorites.premium_until
+        );
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = user,
-        space = ANCHOR_DISCRIMINATOR_SIZE + Favorites::INIT_SPACE,
-        seeds = [b"favorites", user.key().as_ref()],
-        bump,
-    )]
-    pub favorites: Account<'info, Favorites>,
+    // Ends premium now. The months left are not refunded. Colors and profiles
+    // set with premium stay, but only change within the regular limits.
+    pub fn cancel_premium(context: Context<CancelPremium>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let favorites = &mut context.accounts.favorites;
+        require!(favorites.is_premium(now), CustomError::PremiumRequired);
+        favorites.premium_until = now;
+        msg!("✅ Premium of {} cancelled.", context.accounts.user.key());
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    pub fn add_fee_exemption(context: Context<AddFeeExemption>) -> Result<()> {
+        msg!("✅ {} no longer pays fees.", context.accounts.user.key());
+        Ok(())
+    }
 
+    pub fn remove_fee_exemption(context: Context<RemoveFeeExemption>) -> Result<()> {
+        msg!("✅ {} pays fees again.", context.accounts.user.key());
+        Ok(())
+    }
 
+    // Moves the lamport fees above the treasury's rent to the admin
+    pub fn withdraw_fees(context: Context<WithdrawFees>) -> Result<()> {
+        let treasury = context.accounts.treasury.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(treasury.data_len());
+        let amount = treasury.lamports().saturating_sub(rent_exempt);
+        require!(amount > 0, CustomError::NoFeesToWithdraw);
 
-#[derive(Accounts)]
-pub struct UpdateFavorites<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        treasury.sub_lamports(amount)?;
+        context.accounts.admin.add_lamports(amount)?;
+        msg!("✅ Withdrew {} lamports of fees.", amount);
+        Ok(())
+    }
 
-    /// CHECK: This signer is a delegate. Manual validation is done in handler.
-    #[account(signer)]
-    pub signer: AccountInfo<'info>,
+    // Moves every token in one of the treasury's token accounts to `destination`
+    pub fn withdraw_fee_tokens(context: Context<WithdrawFeeTokens>) -> Result<()> {
+        let amount = context.accounts.treasury_token_account.amount;
+        require!(amount > 0, CustomError::NoFeesToWithdraw);
 
-    #[account(
-        mut,
-        seeds = [b"favorites", user.key().as_ref()],
-        bump,
-    )]
-    pub favorites: Account<'info, Favorites>,
+        let signer_seeds: &[&[&[u8]]] = &[&[b"treasury", &[context.bumps.treasury]]];
+        transfer(
+            CpiContext::new_with_signer(
+                context.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: context.accounts.treasury_token_account.to_account_info(),
+                    to: context.accounts.destination.to_account_info(),
+                    authority: context.accounts.treasury.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    // Sets the mint weighing community color votes, or stops new proposals
+    // with `None`. Open proposals keep the mint they started with.
+    pub fn set_vote_mint(context: Context<UpdateConfig>, vote_mint: Option<Pubkey>) -> Result<()> {
+        context.accounts.config.vote_mint = vote_mint;
+        Ok(())
+    }
 
-// Our Solana program!
-#[program]
-pub mod favorites {
-    use super::*;
+    // Opens a vote between `colors` for the next `voting_period` seconds, at
+    // least `MIN_VOTING_PERIOD`
+    pub fn create_proposal(
+        context: Context<CreateProposal>,
+        id: u64,
+        colors: Vec<String>,
+        voting_period: i64,
+    ) -> Result<()> {
+        require!(
+            (2..=MAX_PROPOSAL_COLORS).contains(&colors.len())
+                && colors.iter().all(|color| color.len() <= MAX_COLOR_LEN)
+                && voting_period >= MIN_VOTING_PERIOD,
+            CustomError::InvalidProposal
+        );
 
-    // Our instruction handler! It sets the user's favorite number and color
-    pub fn set_favorites(context: Context<SetFavorites>, number: u64, color: String) -> Result<()> {
-        let user_public_key = context.accounts.user.key();
-        msg!("Greetings from {}", context.program_id);
-        msg!(
-            "User {}'s favorite number is {} and favorite color is: {}",
-            user_public_key,
-            number,
-            color
+        let voting_starts_at = Clock::get()?.unix_timestamp;
+        let voting_ends_at = voting_starts_at
+            .checked_add(voting_period)
+            .ok_or(CustomError::InvalidProposal)?;
+        let proposal_key = context.accounts.proposal.key();
+        context.accounts.tally.set_inner(Tally {
+            proposal: proposal_key,
+            votes: vec![0; colors.len()],
+        });
+        context.accounts.proposal.set_inner(Proposal {
+            proposer: context.accounts.proposer.key(),
+            id,
+            vote_mint: context.accounts.vote_mint.key(),
+            voting_starts_at,
+            voting_ends_at,
+            finalized: false,
+            colors,
+        });
+        msg!("✅ Proposal {} open until {}.", proposal_key, voting_ends_at);
+        Ok(())
+    }
+
+    // Votes for color `choice` with the voter's whole balance, locking it in
+    // the proposal's escrow until voting ends
+    pub fn vote(context: Context<Vote>, choice: u8) -> Result<()> {
+        let proposal = &context.accounts.proposal;
+        require!(
+            Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            CustomError::VotingClosed
         );
+        require!(
+            (choice as usize) < proposal.colors.len(),
+            CustomError::InvalidChoice
+        );
+        let amount = context.accounts.voter_token_account.amount;
+        require!(amount > 0, CustomError::NoVotingPower);
+
+        transfer(
+            CpiContext::new(
+                context.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: context.accounts.voter_token_account.to_account_info(),
+                    to: context.accounts.vote_escrow.to_account_info(),
+                    authority: context.accounts.voter.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
+        context.accounts.tally.votes[choice as usize] += amount;
         context
             .accounts
-            .favorites
-            .set_inner(Favorites { number, color, delegate: None });
+            .vote_receipt
+            .set_inner(VoteReceipt { choice, amount });
         Ok(())
     }
 
-    pub fn update_favorites(
-        context: Context<UpdateFavorites>,
-        number: u64,
-        color: String,
+    // Closes voting and, if anyone voted, makes the color with the most
+    // weight the community color. Ties go to the color listed first. A
+    // proposal created before the one that set the community color is stale
+    // and cannot be finalized.
+    pub fn finalize_proposal(context: Context<FinalizeProposal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut context.accounts.proposal;
+        require!(now >= proposal.voting_ends_at, CustomError::VotingNotOver);
+        require!(!proposal.finalized, CustomError::AlreadyFinalized);
+        require!(
+            proposal.voting_starts_at >= context.accounts.community_color.voting_started_at,
+            CustomError::StaleProposal
+        );
+        proposal.finalized = true;
+
+        let votes = &context.accounts.tally.votes;
+        let Some((winner, _)) = votes
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .min_by_key(|(index, weight)| (std::cmp::Reverse(**weight), *index))
+        else {
+            msg!("Proposal {} ended without votes.", proposal.key());
+            return Ok(());
+        };
+
+        let color = proposal.colors[winner].clone();
+        msg!("✅ The community color is now {}.", color);
+        context.accounts.community_color.set_inner(CommunityColor {
+            proposal: proposal.key(),
+            voting_started_at: proposal.voting_starts_at,
+            decided_at: now,
+            color,
+        });
+        Ok(())
+    }
+
+    // Returns the voter's locked tokens once voting has ended
+    pub fn reclaim_vote(context: Context<ReclaimVote>) -> Result<()> {
+        let proposal = &context.accounts.proposal;
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+            CustomError::VotingNotOver
+        );
+
+        let id = proposal.id.to_le_bytes();
+        let bump = [context.bumps.proposal];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"proposal", proposal.proposer.as_ref(), &id, &bump]];
+        transfer(
+            CpiContext::new_with_signer(
+                context.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: context.accounts.vote_escrow.to_account_info(),
+                    to: context.accounts.voter_token_account.to_account_info(),
+                    authority: proposal.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            context.accounts.vote_receipt.amount,
+        )
+    }
+
+    // Lets `session_key` update the owner's favorites `max_uses` times until `expiry`,
+    // as long as these favorites are not moved or closed
+    pub fn create_session(
+        context: Context<CreateSession>,
+        session_key: Pubkey,
+        max_uses: u32,
+        expiry: i64,
     ) -> Result<()> {
-        let current_delegate: Option<Pubkey> = context.accounts.favorites.delegate;
-        let signer = context.accounts.user.key;
-        
-        let is_owner = true;
-        let is_delegate = current_delegate == Some(*signer);
+        require!(
+            Clock::get()?.unix_timestamp <= expiry,
+            CustomError::SessionExpired
+        );
 
-        require!(is_owner || is_delegate, CustomError::Unauthorized);
+        context.accounts.session.set_inner(Session {
+            session_key,
+            uses_left: max_uses,
+            expiry,
+            generation: context.accounts.favorites.session_generation,
+        });
+        msg!("✅ Session key {} created.", session_key);
+        Ok(())
+    }
 
-        context
-            .accounts
-            .favorites
-            .set_inner(Favorites { number, color, delegate: current_delegate });
+    // Revokes a session key and returns the session's rent to the owner
+    pub fn revoke_session(context: Context<RevokeSession>) -> Result<()> {
+        msg!("✅ Session key {} revoked.", context.accounts.session.session_key);
         Ok(())
     }
 
     pub fn set_authority(
-        context: Context<UpdateFavorites>,
+        context: Context<SetAuthority>,
         delegate: Option<Pubkey>,
     ) -> Result<()> {
         let favorites = &mut context.accounts.favorites;
@@ -115,12 +2514,815 @@ pub mod favorites {
             }
         }
     }
+
+    // Claims `handle` for the signer, pointing at their favorites
+    pub fn register_handle(context: Context<RegisterHandle>, handle: String) -> Result<()> {
+        msg!("✅ @{} now points at {}.", handle, context.accounts.owner.key());
+        context.accounts.handle_account.set_inner(Handle {
+            owner: context.accounts.owner.key(),
+            favorites: context.accounts.favorites.key(),
+            handle,
+        });
+        Ok(())
+    }
+
+    // Hands the handle over to `new_owner`, who must have favorites
+    pub fn transfer_handle(context: Context<TransferHandle>) -> Result<()> {
+        let handle_account = &mut context.accounts.handle_account;
+        handle_account.owner = context.accounts.new_owner.key();
+        handle_account.favorites = context.accounts.new_owner_favorites.key();
+        msg!(
+            "✅ @{} now points at {}.",
+            handle_account.handle,
+            handle_account.owner
+        );
+        Ok(())
+    }
+
+    // Frees the handle for anyone to register and returns its rent
+    pub fn release_handle(context: Context<ReleaseHandle>) -> Result<()> {
+        msg!("✅ @{} released.", context.accounts.handle_account.handle);
+        Ok(())
+    }
+
+    // Moves the favorites to `new_owner`'s PDA, for users rotating wallets,
+    // premium and referrer included. Referred favorites pass the referrer's
+    // chain as for `set_favorites`, and cannot move to a wallet up that chain.
+    // The registry lists the new owner in the old one's place; the totals in
+    // stats do not change. The old owner's recovery key goes, so that it does
+    // not apply should they create favorites again, and their sessions stop
+    // applying; those passed after the chain are closed. Handles, profiles
+    // and tips stay with the old owner.
+    pub fn transfer_favorites(context: Context<TransferFavorites>) -> Result<()> {
+        let accounts = context.accounts;
+        let user = accounts.user.key();
+        move_favorites(
+            user,
+            accounts.new_owner.key(),
+            &accounts.favorites,
+            &mut accounts.new_favorites,
+            &mut accounts.registry_page,
+            &mut accounts.stats,
+            context.remaining_accounts,
+            &accounts.user.to_account_info(),
+        )?;
+        close_recovery(user, &accounts.recovery, &accounts.user)
+    }
+
+    // Names the key that can move the favorites to a new wallet if the owner
+    // loses theirs. Replacing it waits until no recovery is in progress.
+    pub fn set_recovery_key(context: Context<SetRecoveryKey>, recovery_key: Pubkey) -> Result<()> {
+        let user = context.accounts.user.key();
+        require!(recovery_key != user, CustomError::InvalidRecoveryKey);
+        let recovery = &mut context.accounts.recovery;
+        require!(recovery.new_owner.is_none(), CustomError::RecoveryInProgress);
+        recovery.recovery_key = recovery_key;
+        recovery.unlocks_at = 0;
+        emit!(RecoveryKeySet { user, recovery_key });
+        Ok(())
+    }
+
+    pub fn remove_recovery_key(context: Context<RemoveRecoveryKey>) -> Result<()> {
+        let recovery = &context.accounts.recovery;
+        require!(recovery.new_owner.is_none(), CustomError::RecoveryInProgress);
+        emit!(RecoveryKeyRemoved {
+            user: context.accounts.user.key(),
+            recovery_key: recovery.recovery_key,
+        });
+        Ok(())
+    }
+
+    // Signed by the recovery key: moves the favorites to `new_owner` once
+    // `RECOVERY_TIMELOCK` has passed, unless the owner cancels first
+    pub fn start_recovery(context: Context<StartRecovery>, new_owner: Pubkey) -> Result<()> {
+        let recovery = &mut context.accounts.recovery;
+        require!(recovery.new_owner.is_none(), CustomError::RecoveryInProgress);
+        recovery.new_owner = Some(new_owner);
+        recovery.unlocks_at = Clock::get()?.unix_timestamp + RECOVERY_TIMELOCK;
+        emit!(RecoveryStarted {
+            user: context.accounts.user.key(),
+            recovery_key: recovery.recovery_key,
+            new_owner,
+            unlocks_at: recovery.unlocks_at,
+        });
+        Ok(())
+    }
+
+    // Signed by the owner, proving they still hold their key
+    pub fn cancel_recovery(context: Context<CancelRecovery>) -> Result<()> {
+        let recovery = &mut context.accounts.recovery;
+        let new_owner = recovery
+            .new_owner
+            .take()
+            .ok_or(CustomError::NoRecoveryInProgress)?;
+        recovery.unlocks_at = 0;
+        emit!(RecoveryCancelled {
+            user: context.accounts.user.key(),
+            new_owner,
+        });
+        Ok(())
+    }
+
+    // Signed by the new owner the recovery names, once its timelock passed.
+    // Closes the recovery, and the lost wallet's sessions passed after the
+    // referral chain: the new owner sets their own key if they want one.
+    pub fn complete_recovery(context: Context<CompleteRecovery>) -> Result<()> {
+        let accounts = context.accounts;
+        let (user, new_owner) = (accounts.user.key(), accounts.new_owner.key());
+        match accounts.recovery.new_owner {
+            Some(named) => require_keys_eq!(named, new_owner, CustomError::Unauthorized),
+            None => return err!(CustomError::NoRecoveryInProgress),
+        }
+        require!(
+            Clock::get()?.unix_timestamp >= accounts.recovery.unlocks_at,
+            CustomError::RecoveryLocked
+        );
+        move_favorites(
+            user,
+            new_owner,
+            &accounts.favorites,
+            &mut accounts.new_favorites,
+            &mut accounts.registry_page,
+            &mut accounts.stats,
+            context.remaining_accounts,
+            &accounts.new_owner.to_account_info(),
+        )?;
+        emit!(RecoveryCompleted { user, new_owner });
+        Ok(())
+    }
+
+    // Sets the number and color of profile `index`, creating it when needed.
+    // Profiles from `FREE_PROFILES` on and premium colors need premium.
+    pub fn set_profile(
+        context: Context<SetProfile>,
+        index: u8,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(index < MAX_PROFILES, CustomError::InvalidProfile);
+        let premium = context
+            .accounts
+            .favorites
+            .is_premium(Clock::get()?.unix_timestamp);
+        require!(
+            premium || index < FREE_PROFILES,
+            CustomError::PremiumRequired
+        );
+        require!(color.len() <= max_color_len(premium), CustomError::ColorTooLong);
+
+        context
+            .accounts
+            .profile
+            .set_inner(Profile { number, color });
+        Ok(())
+    }
+
+    // Deletes profile `index` and returns its rent, with or without premium
+    pub fn close_profile(context: Context<CloseProfile>, index: u8) -> Result<()> {
+        msg!(
+            "✅ Profile {} of {} closed.",
+            index,
+            context.accounts.user.key()
+        );
+        Ok(())
+    }
+
+    // Starts the user's collection, empty. It takes items once
+    // `grow_collection` allocated the rest of it.
+    pub fn create_collection(_context: Context<CreateCollection>) -> Result<()> {
+        Ok(())
+    }
+
+    // Allocates up to 10 KiB more of the user's collection, at their expense
+    pub fn grow_collection(context: Context<GrowCollection>) -> Result<()> {
+        let accounts = &context.accounts;
+        grow_account(
+            &accounts.collection.to_account_info(),
+            &accounts.user.to_account_info(),
+            &accounts.system_program.to_account_info(),
+            COLLECTION_SPACE,
+        )
+    }
+
+    // Adds an item after the last one
+    pub fn append_collection_item(
+        context: Context<ModifyCollection>,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        let mut collection = context.accounts.collection.load_mut()?;
+        let len = collection.len as usize;
+        require!(len < COLLECTION_CAPACITY, CustomError::CollectionFull);
+        collection.items[len] = CollectionItem::new(number, &color);
+        collection.len += 1;
+        Ok(())
+    }
+
+    // Overwrites item `index`, reading no other item
+    pub fn update_collection_item(
+        context: Context<ModifyCollection>,
+        index: u32,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        let mut collection = context.accounts.collection.load_mut()?;
+        require!(
+            (index as u64) < collection.len,
+            CustomError::InvalidCollectionIndex
+        );
+        collection.items[index as usize] = CollectionItem::new(number, &color);
+        Ok(())
+    }
+
+    // Removes item `index`, moving the ones after it down to keep their order
+    pub fn remove_collection_item(context: Context<ModifyCollection>, index: u32) -> Result<()> {
+        let mut collection = context.accounts.collection.load_mut()?;
+        let (index, len) = (index as usize, collection.len as usize);
+        require!(index < len, CustomError::InvalidCollectionIndex);
+        collection.items.copy_within(index + 1..len, index);
+        collection.items[len - 1] = CollectionItem::zeroed();
+        collection.len -= 1;
+        Ok(())
+    }
+
+    // Deletes the collection and returns its rent, whatever it still holds
+    pub fn close_collection(context: Context<CloseCollection>) -> Result<()> {
+        msg!("✅ Collection of {} closed.", context.accounts.user.key());
+        Ok(())
+    }
+
+    // The Borsh counterparts of the collection instructions, for the bench
+    #[cfg(feature = "bench")]
+    pub fn create_borsh_collection(_context: Context<CreateBorshCollection>) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "bench")]
+    pub fn grow_borsh_collection(context: Context<GrowBorshCollection>) -> Result<()> {
+        let accounts = &context.accounts;
+        grow_account(
+            &accounts.collection.to_account_info(),
+            &accounts.user.to_account_info(),
+            &accounts.system_program.to_account_info(),
+            BORSH_COLLECTION_SPACE,
+        )
+    }
+
+    #[cfg(feature = "bench")]
+    pub fn append_borsh_collection_item(
+        context: Context<ModifyBorshCollection>,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        let items = &mut context.accounts.collection.items;
+        require!(items.len() < COLLECTION_CAPACITY, CustomError::CollectionFull);
+        items.push(BorshCollectionItem { number, color });
+        Ok(())
+    }
+
+    #[cfg(feature = "bench")]
+    pub fn update_borsh_collection_item(
+        context: Context<ModifyBorshCollection>,
+        index: u32,
+        number: u64,
+        color: String,
+    ) -> Result<()> {
+        require!(color.len() <= MAX_COLOR_LEN, CustomError::ColorTooLong);
+        let item = context
+            .accounts
+            .collection
+            .items
+            .get_mut(index as usize)
+            .ok_or(CustomError::InvalidCollectionIndex)?;
+        *item = BorshCollectionItem { number, color };
+        Ok(())
+    }
+
+    #[cfg(feature = "bench")]
+    pub fn remove_borsh_collection_item(
+        context: Context<ModifyBorshCollection>,
+        index: u32,
+    ) -> Result<()> {
+        let items = &mut context.accounts.collection.items;
+        require!(
+            (index as usize) < items.len(),
+            CustomError::InvalidCollectionIndex
+        );
+        items.remove(index as usize);
+        Ok(())
+    }
+
+    // Deletes the favorites account and returns its rent to the owner, along
+    // with that of the recovery PDA and of the sessions passed after the
+    // named accounts. Sessions not passed no longer apply either way.
+    pub fn close_favorites(context: Context<CloseFavorites>) -> Result<()> {
+        let user = context.accounts.user.key();
+        let users = &mut context.accounts.registry_page.users;
+        let index = users
+            .iter()
+            .position(|registered| *registered == user)
+            .ok_or(CustomError::NotRegistered)?;
+        users.swap_remove(index);
+        context.accounts.registry.user_count -= 1;
+
+        let favorites = &context.accounts.favorites;
+        let stats = &mut context.accounts.stats;
+        stats.user_count = stats.user_count.saturating_sub(1);
+        stats.remove(favorites.number, &favorites.color);
+
+        match (favorites.referrer, context.accounts.referrals.as_mut()) {
+            (Some(_), Some(referrals)) => referrals.count = referrals.count.saturating_sub(1),
+            (None, None) => {}
+            _ => return err!(CustomError::InvalidReferralAccounts),
+        }
+
+        let refund = context.accounts.user.to_account_info();
+        close_recovery(user, &context.accounts.recovery, &refund)?;
+        for session in context.remaining_accounts {
+            close_session(user, session, &refund)?;
+        }
+        msg!("✅ Favorites of {} closed.", user);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize)]
+struct SignedUpdate<'a> {
+    program_id: Pubkey,
+    user: Pubkey,
+    number: u64,
+    color: &'a str,
+    nonce: u64,
+    expiry: i64,
+}
+
+// Whether `handle` can be registered: 3 to 32 of lowercase ASCII letters,
+// digits and underscores, without the leading `@`. One spelling per name
+// keeps handles unique.
+pub fn is_valid_handle(handle: &str) -> bool {
+    (MIN_HANDLE_LEN..=MAX_HANDLE_LEN).contains(&handle.len())
+        && handle
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+}
+
+// The bytes an owner signs to authorize `update_favorites_signed`
+pub fn signed_update_message(
+    user: &Pubkey,
+    number: u64,
+    color: &str,
+    nonce: u64,
+    expiry: i64,
+) -> Vec<u8> {
+    let mut message = SIGNED_UPDATE_PREFIX.to_vec();
+    SignedUpdate {
+        program_id: ID,
+        user: *user,
+        number,
+        color,
+        nonce,
+        expiry,
+    }
+    .serialize(&mut message)
+    .unwrap();
+    message
+}
+
+// Name of the badge NFT for `number`, well within Token Metadata's 32 bytes
+pub fn badge_name(number: u64) -> String {
+    format!("Favorites {number}")
+}
+
+// Token Metadata only stores a URI, so the attributes travel in a JSON data
+// URI. Characters JSON or the URI would misread are escaped.
+pub fn badge_uri(number: u64, color: &str) -> String {
+    let mut escaped = String::with_capacity(color.len());
+    for c in color.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '%' => escaped.push_str("%25"),
+            '#' => escaped.push_str("%23"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    format!(
+        "data:application/json,{{\"attributes\":[{{\"trait_type\":\"number\",\"value\":{number}}},{{\"trait_type\":\"color\",\"value\":\"{escaped}\"}}]}}"
+    )
+}
+
+// Copies `favorites` of `user` into `new_favorites` of `new_owner`, under a
+// new session generation, and lists the new owner in the registry in the old
+// one's place. `remaining_accounts` holds the referral chain, then sessions
+// of `user` to close, refunded to `refund`. Closing the old account is left
+// to the caller's constraints.
+#[allow(clippy::too_many_arguments)]
+fn move_favorites<'info>(
+    user: Pubkey,
+    new_owner: Pubkey,
+    favorites: &Favorites,
+    new_favorites: &mut Account<'info, Favorites>,
+    registry_page: &mut RegistryPage,
+    stats: &mut Stats,
+    remaining_accounts: &[AccountInfo],
+    refund: &AccountInfo,
+) -> Result<()> {
+    let users = &mut registry_page.users;
+    let index = users
+        .iter()
+        .position(|registered| *registered == user)
+        .ok_or(CustomError::NotRegistered)?;
+    users[index] = new_owner;
+
+    let sessions = check_referral_chain(new_owner, favorites.referrer, remaining_accounts)?;
+    for session in sessions {
+        close_session(user, session, refund)?;
+    }
+    new_favorites.set_inner(Favorites {
+        number: favorites.number,
+        color: favorites.color.clone(),
+        delegate: favorites.delegate,
+        version: FAVORITES_VERSION,
+        registry_page: favorites.registry_page,
+        // Signed updates name the owner, so the old ones cannot replay
+        nonce: 0,
+        premium_until: favorites.premium_until,
+        referrer: favorites.referrer,
+        session_generation: stats.next_session_generation(),
+    });
+    msg!("✅ Favorites of {} moved to {}.", user, new_owner);
+    Ok(())
+}
+
+// Fails if following referrers up from `referrer` reaches `user`, which
+// naming `referrer` for `user` would turn into a cycle. `chain` starts with
+// the favorites of `referrer`, of their own referrer and so on, up to
+// favorites without a referrer, a wallet without favorites or
+// `MAX_REFERRAL_DEPTH` wallets. Returns the accounts that follow.
+fn check_referral_chain<'a, 'info>(
+    user: Pubkey,
+    mut referrer: Option<Pubkey>,
+    chain: &'a [AccountInfo<'info>],
+) -> Result<&'a [AccountInfo<'info>]> {
+    let mut links = chain.iter();
+    for _ in 0..MAX_REFERRAL_DEPTH {
+        let Some(wallet) = referrer else { break };
+        require_keys_neq!(wallet, user, CustomError::ReferralCycle);
+        let link = links.next().ok_or(CustomError::InvalidReferralAccounts)?;
+        let (address, _) =
+            Pubkey::find_program_address(&[b"favorites", wallet.as_ref()], &crate::ID);
+        require_keys_eq!(link.key(), address, CustomError::InvalidReferralAccounts);
+        // Closed favorites end the chain, and so do legacy ones, which
+        // predate referrers
+        referrer = if link.data_len() > LegacyFavorites::SPACE {
+            Favorites::try_deserialize(&mut &link.try_borrow_data()?[..])?.referrer
+        } else {
+            None
+        };
+    }
+    Ok(links.as_slice())
+}
+
+// Closes a session `user` opened, refunding its rent to `refund`
+fn close_session(user: Pubkey, session: &AccountInfo, refund: &AccountInfo) -> Result<()> {
+    require_keys_eq!(*session.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    let session_key = Session::try_deserialize(&mut &session.try_borrow_data()?[..])?.session_key;
+    let (address, _) = Pubkey::find_program_address(
+        &[b"session", user.as_ref(), session_key.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(session.key(), address, ErrorCode::ConstraintSeeds);
+    close_account(session, refund)?;
+    msg!("Session key {} revoked.", session_key);
+    Ok(())
+}
+
+// Closes the recovery PDA of `user` if it exists, refunding its rent to
+// `refund`, and reports the recovery key and any recovery in progress gone
+fn close_recovery(user: Pubkey, recovery: &AccountInfo, refund: &AccountInfo) -> Result<()> {
+    if recovery.data_is_empty() {
+        return Ok(());
+    }
+    let state = Recovery::try_deserialize(&mut &recovery.try_borrow_data()?[..])?;
+    if let Some(new_owner) = state.new_owner {
+        emit!(RecoveryCancelled { user, new_owner });
+    }
+    emit!(RecoveryKeyRemoved {
+        user,
+        recovery_key: state.recovery_key,
+    });
+    close_account(recovery, refund)
+}
+
+// Closes `account` as the `close` constraint does, for accounts that may not
+// exist: its rent goes to `refund` and the system program gets it back
+fn close_account(account: &AccountInfo, refund: &AccountInfo) -> Result<()> {
+    refund.add_lamports(account.lamports())?;
+    account.sub_lamports(account.lamports())?;
+    account.assign(&system_program::ID);
+    account.realloc(0, false)?;
+    Ok(())
+}
+
+// Brings `account` as close to `space` bytes as one instruction can realloc,
+// with `payer` topping up its rent
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    require!(account.data_len() < space, CustomError::CollectionGrown);
+    let new_space = space.min(account.data_len() + MAX_PERMITTED_DATA_INCREASE);
+    let missing = Rent::get()?
+        .minimum_balance(new_space)
+        .saturating_sub(account.lamports());
+    if missing > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            missing,
+        )?;
+    }
+    account.realloc(new_space, false)?;
+    Ok(())
+}
+
+// Lists `user` on the registry's open page and counts their favorites in the
+// stats. Returns the page they went to.
+fn register(
+    user: Pubkey,
+    number: u64,
+    color: &str,
+    registry: &mut Registry,
+    registry_page: &mut RegistryPage,
+    stats: &mut Stats,
+) -> u32 {
+    let page = registry.open_page;
+    let users = &mut registry_page.users;
+    users.push(user);
+    if users.len() == REGISTRY_PAGE_CAPACITY {
+        registry.open_page += 1;
+    }
+    registry.user_count += 1;
+
+    stats.user_count += 1;
+    stats.add(number, color);
+    page
+}
+
+// Longest color favorites can be set to, with or without premium
+fn max_color_len(premium: bool) -> usize {
+    if premium {
+        MAX_PREMIUM_COLOR_LEN
+    } else {
+        MAX_COLOR_LEN
+    }
+}
+
+// Accounts `charge_fee` reads, borrowed from the instruction's context
+struct FeeAccounts<'a, 'info> {
+    config: &'a AccountInfo<'info>,
+    payer: &'a AccountInfo<'info>,
+    fee_exemption: Option<&'a Account<'info, FeeExemption>>,
+    treasury: &'a AccountInfo<'info>,
+    fee_token_account: Option<&'a Account<'info, TokenAccount>>,
+    treasury_token_account: Option<&'a Account<'info, TokenAccount>>,
+    token_program: Option<&'a Program<'info, Token>>,
+    system_program: &'a Program<'info, System>,
+}
+
+// Moves the configured fee from the payer to the treasury, unless nothing is
+// charged or the payer is exempt
+fn charge_fee(accounts: FeeAccounts) -> Result<()> {
+    if accounts.config.data_is_empty() || accounts.fee_exemption.is_some() {
+        return Ok(());
+    }
+    let config = Config::try_deserialize(&mut &accounts.config.try_borrow_data()?[..])?;
+    if config.fee_amount == 0 {
+        return Ok(());
+    }
+
+    let Some(fee_mint) = config.fee_mint else {
+        return system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.payer.clone(),
+                    to: accounts.treasury.clone(),
+                },
+            ),
+            config.fee_amount,
+        );
+    };
+
+    let (Some(from), Some(to), Some(token_program)) = (
+        accounts.fee_token_account,
+        accounts.treasury_token_account,
+        accounts.token_program,
+    ) else {
+        return err!(CustomError::InvalidFeeAccounts);
+    };
+    require_keys_eq!(from.mint, fee_mint, CustomError::InvalidFeeAccounts);
+    require_keys_eq!(to.mint, fee_mint, CustomError::InvalidFeeAccounts);
+    require_keys_eq!(to.owner, accounts.treasury.key(), CustomError::InvalidFeeAccounts);
+    transfer(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Transfer {
+                from: from.to_account_info(),
+                to: to.to_account_info(),
+                authority: accounts.payer.clone(),
+            },
+        ),
+        config.fee_amount,
+    )
+}
+
+// Enforces the token gate of `config`, when there is a config and it names a
+// mint: `holder` must own `token_account`, holding enough of that mint.
+fn check_token_gate(
+    config: &AccountInfo,
+    token_account: Option<&Account<TokenAccount>>,
+    holder: &Pubkey,
+) -> Result<()> {
+    if config.data_is_empty() {
+        return Ok(());
+    }
+    let config = Config::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+    let Some(gate_mint) = config.gate_mint else {
+        return Ok(());
+    };
+
+    let token_account = token_account.ok_or(CustomError::TokenGateNotMet)?;
+    require_keys_eq!(token_account.mint, gate_mint, CustomError::TokenGateNotMet);
+    require_keys_eq!(token_account.owner, *holder, CustomError::TokenGateNotMet);
+    require!(
+        token_account.amount >= config.gate_min_amount,
+        CustomError::TokenGateNotMet
+    );
+    Ok(())
+}
+
+// Requires `mint` to be an NFT, a mint of a single indivisible token that can
+// never mint another, and `holder` to own the token account holding it.
+fn check_nft_holder(
+    mint: &Account<Mint>,
+    token_account: &Account<TokenAccount>,
+    holder: &Pubkey,
+) -> Result<()> {
+    require!(
+        mint.supply == 1 && mint.decimals == 0 && mint.mint_authority.is_none(),
+        CustomError::NotAnNft
+    );
+    require_keys_eq!(token_account.mint, mint.key(), CustomError::NotNftHolder);
+    require_keys_eq!(token_account.owner, *holder, CustomError::NotNftHolder);
+    require!(token_account.amount == 1, CustomError::NotNftHolder);
+    Ok(())
+}
+
+// Checks that the instruction before the current one makes the Ed25519
+// program verify a single signature by `signer` over `message`. The runtime
+// rejects the transaction if that signature is invalid, so only the public
+// key and message need checking here.
+fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = instructions_sysvar::load_current_index_checked(instructions)?;
+    require!(current > 0, CustomError::InvalidSignature);
+    let ed25519_ix =
+        instructions_sysvar::load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(
+        ed25519_ix.program_id,
+        ed25519_program::ID,
+        CustomError::InvalidSignature
+    );
+
+    // One signature count byte, one padding byte, then seven u16 offsets
+    let data = &ed25519_ix.data;
+    require!(data.len() >= 16 && data[0] == 1, CustomError::InvalidSignature);
+    let read = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+
+    // Every piece has to come from the Ed25519 instruction's own data
+    let own_data = u16::MAX as usize;
+    require!(
+        read(4) == own_data && read(8) == own_data && read(14) == own_data,
+        CustomError::InvalidSignature
+    );
+
+    let (public_key_offset, message_offset, message_size) = (read(6), read(10), read(12));
+    let signed_by = data.get(public_key_offset..public_key_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    require!(
+        signed_by == Some(signer.as_ref()) && signed_message == Some(message),
+        CustomError::InvalidSignature
+    );
+    Ok(())
 }
 
 #[error_code]
 pub enum CustomError {
     #[msg("Only the authority or delegate can update this account.")]
     Unauthorized,
+    #[msg("Favorite color is longer than the space reserved for it.")]
+    ColorTooLong,
+    #[msg("The user is missing from their registry page.")]
+    NotRegistered,
+    #[msg("Missing or invalid ed25519 signature of the owner.")]
+    InvalidSignature,
+    #[msg("The signed update's nonce does not match the account's.")]
+    NonceMismatch,
+    #[msg("The signed update has expired.")]
+    SignedUpdateExpired,
+    #[msg("The session key has expired.")]
+    SessionExpired,
+    #[msg("The session key has no uses left.")]
+    SessionExhausted,
+    #[msg("The signer does not hold enough of the gating token.")]
+    TokenGateNotMet,
+    #[msg("The mint is not an NFT: one token, no decimals and no mint authority.")]
+    NotAnNft,
+    #[msg("The signer does not hold the NFT these favorites are bound to.")]
+    NotNftHolder,
+    #[msg("The color does not fit in the badge's metadata URI.")]
+    BadgeUriTooLong,
+    #[msg("A tip must be at least one lamport.")]
+    ZeroTip,
+    #[msg("The tip vault holds nothing above its rent.")]
+    NoTipsToWithdraw,
+    #[msg("No vote mint is configured, so proposals are disabled.")]
+    VotingDisabled,
+    #[msg("A proposal needs 2 to 4 colors that fit and a voting period of at least a day.")]
+    InvalidProposal,
+    #[msg("Voting on this proposal has ended.")]
+    VotingClosed,
+    #[msg("Voting on this proposal has not ended yet.")]
+    VotingNotOver,
+    #[msg("The proposal has no color with this index.")]
+    InvalidChoice,
+    #[msg("The voter holds none of the vote mint.")]
+    NoVotingPower,
+    #[msg("The proposal is already finalized.")]
+    AlreadyFinalized,
+    #[msg("A handle is 3 to 32 lowercase letters, digits or underscores.")]
+    InvalidHandle,
+    #[msg("The fee's token accounts are missing or do not match the config.")]
+    InvalidFeeAccounts,
+    #[msg("The treasury holds no fees to withdraw.")]
+    NoFeesToWithdraw,
+    #[msg("No premium mint is configured, so premium is not for sale.")]
+    PremiumUnavailable,
+    #[msg("The payment's token accounts do not match the premium mint and treasury.")]
+    InvalidPremiumAccounts,
+    #[msg("Premium is renewed for at least one month.")]
+    ZeroPremiumMonths,
+    #[msg("This needs an active premium subscription.")]
+    PremiumRequired,
+    #[msg("A profile index is below 8.")]
+    InvalidProfile,
+    #[msg("Users cannot refer themselves.")]
+    SelfReferral,
+    #[msg("The referrer was referred by this wallet, which would close a cycle.")]
+    ReferralCycle,
+    #[msg("The referrer, their referrals counter and their referral chain are passed together.")]
+    InvalidReferralAccounts,
+    #[msg("The recovery key cannot be the owner's own key.")]
+    InvalidRecoveryKey,
+    #[msg("A recovery is in progress; the owner has to cancel it first.")]
+    RecoveryInProgress,
+    #[msg("No recovery of these favorites is in progress.")]
+    NoRecoveryInProgress,
+    #[msg("The recovery's timelock has not passed yet.")]
+    RecoveryLocked,
+    #[msg("The collection holds as many items as it can.")]
+    CollectionFull,
+    #[msg("No item of the collection has this index.")]
+    InvalidCollectionIndex,
+    #[msg("These favorites predate the current layout; migrate them first.")]
+    NotMigrated,
+    #[msg("These favorites are already in the current layout.")]
+    AlreadyMigrated,
+    #[msg("A newer proposal already decided the community color.")]
+    StaleProposal,
+    #[msg("Referrers must have favorites.")]
+    ReferrerWithoutFavorites,
+    #[msg("The collection takes items once grow_collection allocated all of it.")]
+    CollectionNotGrown,
+    #[msg("The collection is already allocated in full.")]
+    CollectionGrown,
+    #[msg("The session was opened for favorites that have since moved or closed.")]
+    SessionOutdated,
 }
 
 
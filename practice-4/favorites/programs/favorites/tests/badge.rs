@@ -0,0 +1,102 @@
+// Badge NFTs minted through Token Metadata.
+//
+// The end-to-end tests run the real Token Metadata program, which is not
+// bundled with solana-program-test. Dump it once with
+//
+//     scripts/dump-fixtures.sh
+//
+// from the workspace root, then run them with
+//
+//     cargo test -p favorites --test badge -- --ignored
+//
+// as CI does.
+mod common;
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{program_option::COption, program_pack::Pack},
+};
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::spl_token::state::{Account as TokenState, Mint},
+};
+use favorites::{badge_name, badge_uri, BADGE_SYMBOL};
+use mpl_token_metadata::accounts::{MasterEdition, Metadata};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+// Funds `users`, of which only the first sets favorites.
+async fn setup(users: &[Keypair]) -> ProgramTestContext {
+    let mut program_test = common::program_test();
+    common::add_token_metadata(&mut program_test);
+    common::start_with_favorites(program_test, users, 1).await
+}
+
+async fn account_data(context: &mut ProgramTestContext, address: &Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+#[test]
+fn badge_uri_carries_the_number_and_an_escaped_color() {
+    assert_eq!(
+        badge_uri(7, "red"),
+        r#"data:application/json,{"attributes":[{"trait_type":"number","value":7},{"trait_type":"color","value":"red"}]}"#
+    );
+    assert!(badge_uri(7, "\"50% #red\"\n").ends_with(r#""value":"\"50%25 %23red\"\u000a"}]}"#));
+    assert_eq!(badge_name(u64::MAX).len(), 30);
+}
+
+#[tokio::test]
+#[ignore = "needs the Token Metadata fixture, see the top of this file"]
+async fn badge_is_a_one_of_one_showing_the_current_favorites() {
+    let user = Keypair::new();
+    let badge_mint = Keypair::new();
+    let mut context = setup(std::slice::from_ref(&user)).await;
+
+    let ix = common::mint_favorites_badge_ix(&user.pubkey(), &badge_mint.pubkey());
+    common::send(&mut context, &[ix], &[&user, &badge_mint])
+        .await
+        .unwrap();
+
+    let token_account = get_associated_token_address(&user.pubkey(), &badge_mint.pubkey());
+    let token_account =
+        TokenState::unpack(&account_data(&mut context, &token_account).await).unwrap();
+    assert_eq!(token_account.amount, 1);
+
+    // The master edition took over minting, so no second token can exist
+    let (master_edition, _) = MasterEdition::find_pda(&badge_mint.pubkey());
+    let mint = Mint::unpack(&account_data(&mut context, &badge_mint.pubkey()).await).unwrap();
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.mint_authority, COption::Some(master_edition));
+    let edition =
+        MasterEdition::from_bytes(&account_data(&mut context, &master_edition).await).unwrap();
+    assert_eq!(edition.max_supply, Some(0));
+
+    let (metadata, _) = Metadata::find_pda(&badge_mint.pubkey());
+    let metadata = Metadata::from_bytes(&account_data(&mut context, &metadata).await).unwrap();
+    assert_eq!(metadata.update_authority, common::badge_authority_pda());
+    // Token Metadata pads the strings to their maximum length
+    assert_eq!(metadata.name.trim_end_matches('\0'), badge_name(7));
+    assert_eq!(metadata.symbol.trim_end_matches('\0'), BADGE_SYMBOL);
+    assert_eq!(metadata.uri.trim_end_matches('\0'), badge_uri(7, "red"));
+}
+
+#[tokio::test]
+#[ignore = "needs the Token Metadata fixture, see the top of this file"]
+async fn badge_needs_favorites() {
+    let users = [Keypair::new(), Keypair::new()];
+    let stranger = &users[1];
+    let mut context = setup(&users).await;
+
+    let badge_mint = Keypair::new();
+    let ix = common::mint_favorites_badge_ix(&stranger.pubkey(), &badge_mint.pubkey());
+    let result = common::send(&mut context, &[ix], &[stranger, &badge_mint]).await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,418 @@
+// Compute units of every instruction and the size of every account, against
+// the baseline in tests/budget.txt.
+//
+// Compute units are only metered for the program built for SBF, so this
+// runs the build `anchor build` leaves in target/deploy:
+//
+//     anchor build
+//     cargo test --test budget -- --nocapture
+//
+// It prints the table and fails when an instruction uses more than its
+// baseline, or has none. `mint_favorites_badge` also needs the Token Metadata
+// fixture, see tests/badge.rs. After a deliberate change, run again with
+// BLESS_BUDGET=1 to write the new numbers into the baseline. When the check
+// fails in CI, the job uploads the blessed tests/budget.txt it measured.
+mod common;
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anchor_lang::Space;
+use common::meter::Meter;
+use favorites::{
+    signed_update_message, Collection, CommunityColor, Config, FeeExemption, Handle, NftFavorites,
+    Profile, Proposal, Recovery, Referrals, Registry, RegistryPage, Session, Stats, Tally,
+    TipVault, Treasury, VoteReceipt, ANCHOR_DISCRIMINATOR_SIZE, COLLECTION_CAPACITY, MAX_COLOR_LEN,
+    MAX_HANDLE_LEN, MAX_PREMIUM_COLOR_LEN, MAX_PROFILES, MAX_PROPOSAL_COLORS, MAX_TOP_COLORS,
+    RECOVERY_TIMELOCK,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    rent::Rent,
+    signature::{Keypair, Signer},
+};
+
+const FEE: u64 = 1_000;
+const VOTING_PERIOD: i64 = favorites::MIN_VOTING_PERIOD;
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/budget.txt")
+}
+
+fn blessing() -> bool {
+    std::env::var_os("BLESS_BUDGET").is_some()
+}
+
+// The baseline's "<kind> <name> <value>" lines of `kind`
+fn read_baseline(kind: &str) -> BTreeMap<String, u64> {
+    let baseline = fs::read_to_string(baseline_path()).unwrap_or_default();
+    baseline
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            (fields.next() == Some(kind)).then_some(())?;
+            let (name, value) = (fields.next()?, fields.next()?);
+            Some((name.to_string(), value.parse().unwrap()))
+        })
+        .collect()
+}
+
+// Replaces the baseline's lines of `kind` with `values`, keeping the rest
+fn write_baseline(kind: &str, values: &BTreeMap<String, u64>) {
+    let baseline = fs::read_to_string(baseline_path()).unwrap_or_default();
+    let mut lines: Vec<String> = baseline
+        .lines()
+        .filter(|line| line.split_whitespace().next() != Some(kind))
+        .map(str::to_string)
+        .collect();
+    lines.extend(
+        values
+            .iter()
+            .map(|(name, value)| format!("{kind} {name} {value}")),
+    );
+    fs::write(baseline_path(), lines.join("\n") + "\n").unwrap();
+}
+
+// Every account the program creates, with the space it allocates
+fn account_sizes() -> BTreeMap<String, u64> {
+    let sizes = [
+        // Zero-copy, sized by its layout
+        ("Collection", std::mem::size_of::<Collection>()),
+        ("CommunityColor", CommunityColor::INIT_SPACE),
+        ("Config", Config::INIT_SPACE),
+        ("Favorites", favorites::Favorites::INIT_SPACE),
+        ("FeeExemption", FeeExemption::INIT_SPACE),
+        ("Handle", Handle::INIT_SPACE),
+        ("NftFavorites", NftFavorites::INIT_SPACE),
+        ("Profile", Profile::INIT_SPACE),
+        ("Proposal", Proposal::INIT_SPACE),
+        ("Recovery", Recovery::INIT_SPACE),
+        ("Referrals", Referrals::INIT_SPACE),
+        ("Registry", Registry::INIT_SPACE),
+        ("RegistryPage", RegistryPage::INIT_SPACE),
+        ("Session", Session::INIT_SPACE),
+        ("Stats", Stats::INIT_SPACE),
+        ("Tally", Tally::INIT_SPACE),
+        ("TipVault", TipVault::INIT_SPACE),
+        ("Treasury", Treasury::INIT_SPACE),
+        ("VoteReceipt", VoteReceipt::INIT_SPACE),
+    ];
+    sizes
+        .into_iter()
+        .map(|(name, space)| (name.to_string(), (ANCHOR_DISCRIMINATOR_SIZE + space) as u64))
+        .collect()
+}
+
+#[test]
+fn account_sizes_match_the_baseline() {
+    let sizes = account_sizes();
+    println!("{:<16} {:>6} {:>14}", "account", "bytes", "rent lamports");
+    for (name, size) in &sizes {
+        let rent = Rent::default().minimum_balance(*size as usize);
+        println!("{name:<16} {size:>6} {rent:>14}");
+    }
+    if blessing() {
+        write_baseline("account", &sizes);
+        return;
+    }
+    // Accounts already created keep the size they had, so any change needs a
+    // migration, as `migrate_favorites` is for `Favorites`
+    assert_eq!(
+        sizes,
+        read_baseline("account"),
+        "account sizes changed; run with BLESS_BUDGET=1 if that is intended"
+    );
+}
+
+// A deterministic key, as compute units depend on the bumps of its PDAs
+fn key(seed: u8) -> Keypair {
+    Keypair::new_from_array([seed; 32])
+}
+
+// A color of the longest length allowed, told apart by its last character
+fn long_color(tag: u8) -> String {
+    let mut color = "x".repeat(MAX_COLOR_LEN - 1);
+    color.push(char::from(b'!' + tag));
+    color
+}
+
+#[tokio::test]
+async fn compute_units_stay_within_the_baseline() {
+    let admin = key(1);
+    let user = key(2);
+    let delegate = key(3);
+    let new_owner = key(4);
+    let session_key = key(5);
+    let tipper = key(6);
+    let legacy = key(7);
+    let fillers: Vec<Keypair> = (0..MAX_TOP_COLORS as u8).map(|i| key(10 + i)).collect();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(favorites::ID, common::meter::program_account());
+    common::add_token_metadata(&mut program_test);
+    let mut funded = vec![
+        admin.insecure_clone(),
+        user.insecure_clone(),
+        delegate.insecure_clone(),
+        new_owner.insecure_clone(),
+        session_key.insecure_clone(),
+        tipper.insecure_clone(),
+        legacy.insecure_clone(),
+    ];
+    funded.extend(fillers.iter().map(Keypair::insecure_clone));
+    common::fund(&mut program_test, &funded);
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    common::add_legacy_favorites(
+        &mut program_test,
+        &legacy.pubkey(),
+        u64::MAX,
+        &long_color(24),
+        Some(delegate.pubkey()),
+    );
+
+    let gate_mint = common::add_mint(&mut program_test, 1_000);
+    let fee_mint = common::add_mint(&mut program_test, 1_000);
+    let vote_mint = common::add_mint(&mut program_test, 1_000);
+    let nft_mint = common::add_mint(&mut program_test, 1);
+    let premium_mint = common::add_mint(&mut program_test, 1_000);
+    let treasury = common::treasury_pda();
+    let user_gate = common::add_token_account(&mut program_test, &user.pubkey(), &gate_mint, 1);
+    let delegate_gate =
+        common::add_token_account(&mut program_test, &delegate.pubkey(), &gate_mint, 1);
+    let session_gate =
+        common::add_token_account(&mut program_test, &session_key.pubkey(), &gate_mint, 1);
+    let user_fees = common::add_token_account(&mut program_test, &user.pubkey(), &fee_mint, 100);
+    let delegate_fees =
+        common::add_token_account(&mut program_test, &delegate.pubkey(), &fee_mint, 100);
+    let session_fees =
+        common::add_token_account(&mut program_test, &session_key.pubkey(), &fee_mint, 100);
+    let treasury_fees = common::add_token_account(&mut program_test, &treasury, &fee_mint, 0);
+    let admin_fees = common::add_token_account(&mut program_test, &admin.pubkey(), &fee_mint, 0);
+    let user_votes = common::add_token_account(&mut program_test, &user.pubkey(), &vote_mint, 100);
+    let user_nft = common::add_token_account(&mut program_test, &user.pubkey(), &nft_mint, 1);
+    let user_premium =
+        common::add_token_account(&mut program_test, &user.pubkey(), &premium_mint, 100);
+    let treasury_premium =
+        common::add_token_account(&mut program_test, &treasury, &premium_mint, 0);
+    let mut meter = Meter::new(program_test.start_with_context().await);
+    let (admin_key, user_key) = (admin.pubkey(), user.pubkey());
+
+    // Fills the top colors, so that new colors evict one
+    for (tag, filler) in fillers.iter().enumerate() {
+        let ix = common::set_favorites_ix(&filler.pubkey(), 0, u64::MAX, &long_color(tag as u8));
+        meter.run("set_favorites", &[ix], &[filler]).await;
+    }
+    let ix = common::set_favorites_ix(&new_owner.pubkey(), 0, 1, &long_color(20));
+    meter.run("set_favorites", &[ix], &[&new_owner]).await;
+    let ix = common::migrate_favorites_ix(&legacy.pubkey(), 0);
+    meter.run("migrate_favorites", &[ix], &[&legacy]).await;
+
+    // Gated, and charging token fees
+    let ix = common::initialize_config_ix(&admin_key, Some(gate_mint), 1);
+    meter.run("initialize_config", &[ix], &[&admin]).await;
+    let ix = common::set_token_gate_ix(&admin_key, Some(gate_mint), 1);
+    meter.run("set_token_gate", &[ix], &[&admin]).await;
+    let ix = common::set_fee_ix(&admin_key, Some(fee_mint), 10);
+    meter.run("set_fee", &[ix], &[&admin]).await;
+    let ix = common::add_fee_exemption_ix(&admin_key, &tipper.pubkey());
+    meter.run("add_fee_exemption", &[ix], &[&admin]).await;
+    let ix = common::remove_fee_exemption_ix(&admin_key, &tipper.pubkey());
+    meter.run("remove_fee_exemption", &[ix], &[&admin]).await;
+
+    // Referred, creating the referrer's counter
+    let referrer = fillers[0].pubkey();
+    let ix =
+        common::referred_set_favorites_ix(&user_key, 0, &[referrer], u64::MAX, &long_color(21));
+    let ix = common::with_gate_token_account(ix, &user_gate);
+    let ix = common::with_fee_token_accounts(ix, &user_fees, &treasury_fees);
+    meter.run("set_favorites", &[ix], &[&user]).await;
+    let ix = common::set_authority_ix(&user_key, true, Some(delegate.pubkey()));
+    meter.run("set_authority", &[ix], &[&user]).await;
+
+    let delegate_key = delegate.pubkey();
+    let ix = common::update_favorites_ix(&user_key, &delegate_key, false, 1, &long_color(22));
+    let ix = common::with_gate_token_account(ix, &delegate_gate);
+    let ix = common::with_fee_token_accounts(ix, &delegate_fees, &treasury_fees);
+    meter.run("update_favorites", &[ix], &[&delegate]).await;
+
+    let ix = common::create_session_ix(&user_key, &session_key.pubkey(), u32::MAX, i64::MAX);
+    meter.run("create_session", &[ix], &[&user]).await;
+    let ix =
+        common::update_favorites_session_ix(&user_key, &session_key.pubkey(), 2, &long_color(23));
+    // The session key holds the gate mint and pays the fee
+    let ix = common::with_gate_token_account(ix, &session_gate);
+    let ix = common::with_fee_token_accounts(ix, &session_fees, &treasury_fees);
+    meter
+        .run("update_favorites_session", &[ix], &[&session_key])
+        .await;
+    let ix = common::revoke_session_ix(&user_key, &session_key.pubkey());
+    meter.run("revoke_session", &[ix], &[&user]).await;
+
+    let color = long_color(24);
+    let message = signed_update_message(&user_key, 3, &color, 0, i64::MAX);
+    // The user relays their own update, paying the fee
+    let ix = common::update_favorites_signed_ix(&user_key, &user_key, 3, &color, 0, i64::MAX);
+    let ix = common::with_gate_token_account(ix, &user_gate);
+    let ix = common::with_fee_token_accounts(ix, &user_fees, &treasury_fees);
+    let ixs = [common::ed25519_ix(&user, &message), ix];
+    meter.run("update_favorites_signed", &ixs, &[&user]).await;
+
+    let ix = common::withdraw_fee_tokens_ix(&admin_key, &treasury_fees, &admin_fees);
+    meter.run("withdraw_fee_tokens", &[ix], &[&admin]).await;
+    // Lamport fees, for the admin to withdraw
+    let ix = common::set_fee_ix(&admin_key, None, FEE);
+    meter.run("set_fee", &[ix], &[&admin]).await;
+    let ix = common::update_favorites_ix(&user_key, &user_key, false, 4, &long_color(25));
+    let ix = common::with_gate_token_account(ix, &user_gate);
+    meter.run("update_favorites", &[ix], &[&user]).await;
+    let ix = common::withdraw_fees_ix(&admin_key);
+    meter.run("withdraw_fees", &[ix], &[&admin]).await;
+
+    let ix = common::tip_ix(&tipper.pubkey(), &user_key, 1_000_000);
+    meter.run("tip", &[ix], &[&tipper]).await;
+    let ix = common::tip_ix(&tipper.pubkey(), &user_key, 1_000_000);
+    meter.run("tip", &[ix], &[&tipper]).await;
+    let ix = common::withdraw_tips_ix(&user_key);
+    meter.run("withdraw_tips", &[ix], &[&user]).await;
+
+    let handle = "h".repeat(MAX_HANDLE_LEN);
+    let ix = common::register_handle_ix(&user_key, &handle);
+    meter.run("register_handle", &[ix], &[&user]).await;
+    let ix = common::transfer_handle_ix(&user_key, &handle, &new_owner.pubkey());
+    meter.run("transfer_handle", &[ix], &[&user]).await;
+    let ix = common::release_handle_ix(&new_owner.pubkey(), &handle);
+    meter.run("release_handle", &[ix], &[&new_owner]).await;
+
+    let ix = common::set_nft_favorites_ix(&user_key, &nft_mint, &user_nft, 5, &long_color(26));
+    meter.run("set_nft_favorites", &[ix], &[&user]).await;
+    let ix = common::update_nft_favorites_ix(&user_key, &nft_mint, &user_nft, 6, &long_color(27));
+    meter.run("update_nft_favorites", &[ix], &[&user]).await;
+
+    let badge_mint = key(30);
+    let ix = common::mint_favorites_badge_ix(&user_key, &badge_mint.pubkey());
+    meter
+        .run("mint_favorites_badge", &[ix], &[&user, &badge_mint])
+        .await;
+
+    let ix = common::set_vote_mint_ix(&admin_key, Some(vote_mint));
+    meter.run("set_vote_mint", &[ix], &[&admin]).await;
+    let colors: Vec<String> = (0..MAX_PROPOSAL_COLORS as u8)
+        .map(|tag| long_color(40 + tag))
+        .collect();
+    let colors: Vec<&str> = colors.iter().map(String::as_str).collect();
+    let ix = common::create_proposal_ix(&user_key, &vote_mint, u64::MAX, &colors, VOTING_PERIOD);
+    meter.run("create_proposal", &[ix], &[&user]).await;
+    let proposal = common::proposal_pda(&user_key, u64::MAX);
+    let last_choice = MAX_PROPOSAL_COLORS as u8 - 1;
+    let ix = common::vote_ix(&user_key, &proposal, &user_votes, last_choice);
+    meter.run("vote", &[ix], &[&user]).await;
+    meter.warp(VOTING_PERIOD).await;
+    let ix = common::finalize_proposal_ix(&admin_key, &proposal);
+    meter.run("finalize_proposal", &[ix], &[&admin]).await;
+    let ix = common::reclaim_vote_ix(&user_key, &proposal, &user_votes);
+    meter.run("reclaim_vote", &[ix], &[&user]).await;
+
+    let ix = common::set_premium_ix(&admin_key, Some(premium_mint), 10);
+    meter.run("set_premium", &[ix], &[&admin]).await;
+    let ix = common::renew_premium_ix(&user_key, &user_premium, &treasury_premium, 2);
+    meter.run("renew_premium", &[ix], &[&user]).await;
+    // Growing the account for the longest premium color
+    let color = "x".repeat(MAX_PREMIUM_COLOR_LEN);
+    let ix = common::update_favorites_ix(&user_key, &user_key, false, 7, &color);
+    let ix = common::with_gate_token_account(ix, &user_gate);
+    meter.run("update_favorites", &[ix], &[&user]).await;
+    let ix = common::set_profile_ix(&user_key, MAX_PROFILES - 1, 8, &color);
+    meter.run("set_profile", &[ix], &[&user]).await;
+    let ix = common::close_profile_ix(&user_key, MAX_PROFILES - 1);
+    meter.run("close_profile", &[ix], &[&user]).await;
+    let ix = common::cancel_premium_ix(&user_key);
+    meter.run("cancel_premium", &[ix], &[&user]).await;
+
+    // Filled up, so that removing the first item shifts all the others
+    let mut ixs = common::create_full_collection_ixs(&user_key);
+    meter.run("create_collection", &ixs[..1], &[&user]).await;
+    for ix in ixs.drain(1..) {
+        meter.run("grow_collection", &[ix], &[&user]).await;
+    }
+    for number in 0..COLLECTION_CAPACITY as u64 {
+        let ix = common::append_collection_item_ix(&user_key, number, &long_color(50));
+        meter.run("append_collection_item", &[ix], &[&user]).await;
+    }
+    let last = COLLECTION_CAPACITY as u32 - 1;
+    let ix = common::update_collection_item_ix(&user_key, last, 9, &long_color(51));
+    meter.run("update_collection_item", &[ix], &[&user]).await;
+    let ix = common::remove_collection_item_ix(&user_key, 0);
+    meter.run("remove_collection_item", &[ix], &[&user]).await;
+    let ix = common::close_collection_ix(&user_key);
+    meter.run("close_collection", &[ix], &[&user]).await;
+
+    // The new owner's favorites go first, to make room
+    let ix = common::close_favorites_ix(&new_owner.pubkey(), 0, true);
+    meter.run("close_favorites", &[ix], &[&new_owner]).await;
+    let ix = common::transfer_favorites_ix(&user_key, &new_owner.pubkey(), 0, true);
+    let ix = common::with_referral_chain(ix, &[referrer]);
+    meter
+        .run("transfer_favorites", &[ix], &[&user, &new_owner])
+        .await;
+
+    // The delegate recovers them for the user, once cancelled
+    let owner_key = new_owner.pubkey();
+    let ix = common::set_recovery_key_ix(&owner_key, &delegate_key);
+    meter.run("set_recovery_key", &[ix], &[&new_owner]).await;
+    let ix = common::start_recovery_ix(&delegate_key, &owner_key, &user_key);
+    meter.run("start_recovery", &[ix], &[&delegate]).await;
+    let ix = common::cancel_recovery_ix(&owner_key);
+    meter.run("cancel_recovery", &[ix], &[&new_owner]).await;
+    let ix = common::start_recovery_ix(&delegate_key, &owner_key, &user_key);
+    meter.run("start_recovery", &[ix], &[&delegate]).await;
+    meter.warp(RECOVERY_TIMELOCK).await;
+    let ix = common::complete_recovery_ix(&owner_key, &user_key, 0);
+    let ix = common::with_referral_chain(ix, &[referrer]);
+    meter.run("complete_recovery", &[ix], &[&user]).await;
+    let ix = common::set_recovery_key_ix(&user_key, &delegate_key);
+    meter.run("set_recovery_key", &[ix], &[&user]).await;
+    let ix = common::remove_recovery_key_ix(&user_key);
+    meter.run("remove_recovery_key", &[ix], &[&user]).await;
+    let ix = common::referred_close_favorites_ix(&user_key, 0, Some(&referrer), true);
+    meter.run("close_favorites", &[ix], &[&user]).await;
+
+    let baseline = read_baseline("instruction");
+    println!(
+        "{:<26} {:>8} {:>8} {:>7}",
+        "instruction", "units", "baseline", "change"
+    );
+    let mut regressions = Vec::new();
+    for (name, units) in &meter.used {
+        match baseline.get(name) {
+            Some(&allowed) => {
+                let change = *units as i64 - allowed as i64;
+                println!("{name:<26} {units:>8} {allowed:>8} {change:>+7}");
+                if *units > allowed {
+                    regressions.push(format!("{name} uses {units}, {change} over its baseline"));
+                }
+            }
+            None => {
+                println!("{name:<26} {units:>8} {:>8} {:>7}", "-", "-");
+                regressions.push(format!("{name} has no baseline"));
+            }
+        }
+    }
+    for name in baseline
+        .keys()
+        .filter(|name| !meter.used.contains_key(*name))
+    {
+        println!("{name:<26} {:>8} not run", "-");
+    }
+
+    if blessing() {
+        // Keeps the baseline of instructions not run this time
+        let mut blessed = baseline;
+        blessed.extend(meter.used);
+        write_baseline("instruction", &blessed);
+        return;
+    }
+    assert!(
+        regressions.is_empty(),
+        "{}; run with BLESS_BUDGET=1 if that is intended",
+        regressions.join(", ")
+    );
+}
@@ -0,0 +1,163 @@
+// The zero-copy collection of favorites a user keeps beside their own.
+mod common;
+
+use favorites::{CustomError, COLLECTION_CAPACITY, COLLECTION_SPACE, MAX_COLOR_LEN};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+async fn items(context: &mut ProgramTestContext, user: &Keypair) -> Vec<(u64, String)> {
+    common::fetch_collection_items(context, &user.pubkey())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn items_are_appended_updated_and_removed_in_place() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, stranger) = (&users[0], &users[1]);
+    let user_key = user.pubkey();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    // Only users with favorites have a collection
+    let ix = common::create_collection_ix(&user_key);
+    assert!(common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .is_err());
+    let ix = common::set_favorites_ix(&user_key, 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::create_collection_ix(&user_key);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    // It takes items once grown to its full size, 10 KiB at a time
+    let ix = common::append_collection_item_ix(&user_key, 0, "green");
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::CollectionNotGrown);
+    let mut grows = 0;
+    loop {
+        let ix = common::grow_collection_ix(&user_key);
+        match common::send_fresh(&mut context, &[ix], &[user]).await {
+            Ok(()) => grows += 1,
+            result => {
+                common::assert_custom_error(result, 0, CustomError::CollectionGrown);
+                break;
+            }
+        }
+    }
+    assert_eq!(grows, COLLECTION_SPACE.div_ceil(10 * 1024) - 1);
+    let account = context
+        .banks_client
+        .get_account(common::collection_pda(&user_key))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), COLLECTION_SPACE);
+    assert!(items(&mut context, user).await.is_empty());
+
+    let appends: Vec<_> = ["green", "blue", "teal"]
+        .iter()
+        .enumerate()
+        .map(|(number, color)| common::append_collection_item_ix(&user_key, number as u64, color))
+        .collect();
+    common::send_fresh(&mut context, &appends, &[user])
+        .await
+        .unwrap();
+    let ix = common::append_collection_item_ix(&user_key, 3, &"x".repeat(MAX_COLOR_LEN + 1));
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::ColorTooLong);
+
+    let ix = common::update_collection_item_ix(&user_key, 1, 11, &"y".repeat(MAX_COLOR_LEN));
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::update_collection_item_ix(&user_key, 3, 11, "gray");
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidCollectionIndex);
+    assert_eq!(
+        items(&mut context, user).await,
+        [
+            (0, "green".to_string()),
+            (11, "y".repeat(MAX_COLOR_LEN)),
+            (2, "teal".to_string())
+        ]
+    );
+
+    // The items after a removed one keep their order
+    let ix = common::remove_collection_item_ix(&user_key, 0);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::remove_collection_item_ix(&user_key, 2);
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidCollectionIndex);
+    assert_eq!(
+        items(&mut context, user).await,
+        [(11, "y".repeat(MAX_COLOR_LEN)), (2, "teal".to_string())]
+    );
+
+    // Others sign for their own collection, which they do not have
+    let mut ix = common::remove_collection_item_ix(&user_key, 0);
+    ix.accounts[0].pubkey = stranger.pubkey();
+    assert!(common::send_fresh(&mut context, &[ix], &[stranger])
+        .await
+        .is_err());
+
+    let ix = common::close_collection_ix(&user_key);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    assert!(common::fetch_collection_items(&mut context, &user_key)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn a_full_collection_takes_no_more_items() {
+    let users = [Keypair::new()];
+    let user = &users[0];
+    let user_key = user.pubkey();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+    let ix = common::set_favorites_ix(&user_key, 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    // Created and grown in one transaction
+    let ixs = common::create_full_collection_ixs(&user_key);
+    common::send_fresh(&mut context, &ixs, &[user])
+        .await
+        .unwrap();
+
+    let color = "z".repeat(MAX_COLOR_LEN);
+    let appends: Vec<_> = (0..COLLECTION_CAPACITY as u64)
+        .map(|number| common::append_collection_item_ix(&user_key, number, &color))
+        .collect();
+    // As many per transaction as fit in its size limit
+    for chunk in appends.chunks(8) {
+        common::send_fresh(&mut context, chunk, &[user])
+            .await
+            .unwrap();
+    }
+    let ix = common::append_collection_item_ix(&user_key, 0, "red");
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::CollectionFull);
+
+    let ix = common::remove_collection_item_ix(&user_key, 0);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::append_collection_item_ix(&user_key, 1_000, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let items = items(&mut context, user).await;
+    assert_eq!(items.len(), COLLECTION_CAPACITY);
+    assert_eq!(items[0], (1, color));
+    assert_eq!(items[COLLECTION_CAPACITY - 1], (1_000, "red".to_string()));
+}
@@ -0,0 +1,197 @@
+// Compute units of the zero-copy collection against the same collection kept
+// as a Borsh `Vec`, which Anchor deserializes and serializes whole on every
+// call. The Borsh instructions only exist with the `bench` feature, so both
+// the SBF build and this test need it:
+//
+//     anchor build -- --features bench
+//     cargo test --features bench --test collection_bench -- --ignored --nocapture
+//
+// It prints the most units each instruction took while creating a collection,
+// growing it to full size, filling it to capacity, updating its last item and
+// removing its first one, and writes that table to target/collection_bench.txt.
+// CI runs it and uploads the table as an artifact.
+#![cfg(feature = "bench")]
+mod common;
+
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::meter::Meter;
+use favorites::{BORSH_COLLECTION_SPACE, COLLECTION_CAPACITY, MAX_COLOR_LEN};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::{fmt::Write, fs, path::PathBuf};
+
+fn borsh_collection_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"borsh_collection", user.as_ref()], &favorites::ID).0
+}
+
+fn create_borsh_collection_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CreateBorshCollection {
+            user: *user,
+            collection: borsh_collection_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CreateBorshCollection {}.data(),
+    }
+}
+
+fn grow_borsh_collection_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::GrowBorshCollection {
+            user: *user,
+            collection: borsh_collection_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::GrowBorshCollection {}.data(),
+    }
+}
+
+fn modify_borsh_collection_accounts(user: &Pubkey) -> Vec<AccountMeta> {
+    favorites::accounts::ModifyBorshCollection {
+        user: *user,
+        collection: borsh_collection_pda(user),
+    }
+    .to_account_metas(None)
+}
+
+fn append_borsh_collection_item_ix(user: &Pubkey, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_borsh_collection_accounts(user),
+        data: favorites::instruction::AppendBorshCollectionItem {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+fn update_borsh_collection_item_ix(
+    user: &Pubkey,
+    index: u32,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_borsh_collection_accounts(user),
+        data: favorites::instruction::UpdateBorshCollectionItem {
+            index,
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+fn remove_borsh_collection_item_ix(user: &Pubkey, index: u32) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_borsh_collection_accounts(user),
+        data: favorites::instruction::RemoveBorshCollectionItem { index }.data(),
+    }
+}
+
+// What each layout does, as (zero-copy instruction, Borsh instruction)
+const OPERATIONS: [(&str, &str); 5] = [
+    ("create_collection", "create_borsh_collection"),
+    ("grow_collection", "grow_borsh_collection"),
+    ("append_collection_item", "append_borsh_collection_item"),
+    ("update_collection_item", "update_borsh_collection_item"),
+    ("remove_collection_item", "remove_borsh_collection_item"),
+];
+
+#[tokio::test]
+#[ignore = "needs `anchor build -- --features bench`, see the top of this file"]
+async fn zero_copy_uses_fewer_compute_units_than_borsh() {
+    let user = Keypair::new_from_array([2; 32]);
+    let user_key = user.pubkey();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(favorites::ID, common::meter::program_account());
+    common::fund(&mut program_test, &[user.insecure_clone()]);
+    let mut meter = Meter::new(program_test.start_with_context().await);
+
+    // Only users with favorites have a zero-copy collection
+    let ix = common::set_favorites_ix(&user_key, 0, 7, "red");
+    meter.run("set_favorites", &[ix], &[&user]).await;
+    let mut ixs = common::create_full_collection_ixs(&user_key);
+    meter.run("create_collection", &ixs[..1], &[&user]).await;
+    for ix in ixs.drain(1..) {
+        meter.run("grow_collection", &[ix], &[&user]).await;
+    }
+    let ix = create_borsh_collection_ix(&user_key);
+    meter.run("create_borsh_collection", &[ix], &[&user]).await;
+    let grows = BORSH_COLLECTION_SPACE.div_ceil(MAX_PERMITTED_DATA_INCREASE) - 1;
+    for _ in 0..grows {
+        let ix = grow_borsh_collection_ix(&user_key);
+        meter.run("grow_borsh_collection", &[ix], &[&user]).await;
+    }
+
+    let color = "x".repeat(MAX_COLOR_LEN);
+    for number in 0..COLLECTION_CAPACITY as u64 {
+        let ix = common::append_collection_item_ix(&user_key, number, &color);
+        meter.run("append_collection_item", &[ix], &[&user]).await;
+        let ix = append_borsh_collection_item_ix(&user_key, number, &color);
+        meter
+            .run("append_borsh_collection_item", &[ix], &[&user])
+            .await;
+    }
+    let last = COLLECTION_CAPACITY as u32 - 1;
+    let ix = common::update_collection_item_ix(&user_key, last, 9, &color);
+    meter.run("update_collection_item", &[ix], &[&user]).await;
+    let ix = update_borsh_collection_item_ix(&user_key, last, 9, &color);
+    meter
+        .run("update_borsh_collection_item", &[ix], &[&user])
+        .await;
+    // The first item, so that every other one moves
+    let ix = common::remove_collection_item_ix(&user_key, 0);
+    meter.run("remove_collection_item", &[ix], &[&user]).await;
+    let ix = remove_borsh_collection_item_ix(&user_key, 0);
+    meter
+        .run("remove_borsh_collection_item", &[ix], &[&user])
+        .await;
+
+    let items = common::fetch_collection_items(&mut meter.context, &user_key)
+        .await
+        .unwrap();
+    assert_eq!(items.len(), COLLECTION_CAPACITY - 1);
+    assert_eq!(items[0], (1, color.clone()));
+    assert_eq!(items[COLLECTION_CAPACITY - 2], (9, color));
+
+    let used = &meter.used;
+    let mut table = format!(
+        "# Written by tests/collection_bench.rs, {COLLECTION_CAPACITY} items\n\
+         {:<24} {:>10} {:>8} {:>8}\n",
+        "instruction", "zero-copy", "borsh", "saved"
+    );
+    for (zero_copy, borsh) in OPERATIONS {
+        let (zero_copy_units, borsh_units) = (used[zero_copy], used[borsh]);
+        let saved = borsh_units as i64 - zero_copy_units as i64;
+        writeln!(
+            table,
+            "{zero_copy:<24} {zero_copy_units:>10} {borsh_units:>8} {saved:>+8}"
+        )
+        .unwrap();
+    }
+    print!("{table}");
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/collection_bench.txt");
+    fs::write(path, table).unwrap();
+    // Creating and growing only allocate, in the same steps for both
+    for (zero_copy, borsh) in &OPERATIONS[2..] {
+        assert!(
+            used[*zero_copy] < used[*borsh],
+            "{zero_copy} uses {} units, {borsh} only {}",
+            used[*zero_copy],
+            used[*borsh]
+        );
+    }
+}
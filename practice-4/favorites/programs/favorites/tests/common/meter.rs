@@ -0,0 +1,82 @@
+// Compute units the SBF build of the program uses, for tests/budget.rs and
+// tests/collection_bench.rs.
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anchor_lang::solana_program::bpf_loader;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+pub struct Meter {
+    pub context: ProgramTestContext,
+    // The most compute units each instruction took
+    pub used: BTreeMap<String, u64>,
+}
+
+impl Meter {
+    pub fn new(context: ProgramTestContext) -> Self {
+        Meter {
+            context,
+            used: BTreeMap::new(),
+        }
+    }
+
+    // Runs `instructions`, of which the last is the favorites instruction to
+    // measure as `name`, and keeps the most it ever used
+    pub async fn run(&mut self, name: &str, instructions: &[Instruction], signers: &[&Keypair]) {
+        let context = &mut self.context;
+        context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000)];
+        all.extend_from_slice(instructions);
+        let mut all_signers = vec![&context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            &all,
+            Some(&context.payer.pubkey()),
+            &all_signers,
+            context.last_blockhash,
+        );
+        let outcome = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        let logs = outcome.metadata.unwrap().log_messages;
+        assert!(outcome.result.is_ok(), "{name} failed: {logs:#?}");
+
+        let consumed = format!("Program {} consumed ", favorites::ID);
+        let units = logs
+            .iter()
+            .rev()
+            .find_map(|log| log.strip_prefix(&consumed))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|units| units.parse().ok())
+            .unwrap_or_else(|| panic!("{name} logged no compute units: {logs:#?}"));
+        let most = self.used.entry(name.to_string()).or_default();
+        *most = (*most).max(units);
+    }
+
+    pub async fn warp(&mut self, seconds: i64) {
+        super::warp(&mut self.context, seconds).await;
+    }
+}
+
+// The SBF build of the program, loaded as `anchor build` left it
+pub fn program_account() -> Account {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/favorites.so");
+    let elf = fs::read(&path)
+        .unwrap_or_else(|err| panic!("{}: {err}; run `anchor build` first", path.display()));
+    Account {
+        lamports: Rent::default().minimum_balance(elf.len()),
+        data: elf,
+        owner: bpf_loader::ID,
+        executable: true,
+        rent_epoch: 0,
+    }
+}
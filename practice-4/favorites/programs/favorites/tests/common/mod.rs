@@ -0,0 +1,1709 @@
+// Shared helpers for running the favorites program in an in-process bank.
+#![allow(dead_code)]
+
+use anchor_lang::{
+    prelude::{AccountInfo, Clock, Pubkey},
+    solana_program::{
+        bpf_loader_upgradeable,
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+        program_option::COption,
+        program_pack::Pack,
+        sysvar,
+    },
+    system_program, AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator,
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenState, AccountState, Mint},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use favorites::{
+    Collection, CommunityColor, CustomError, Favorites, Handle, NftFavorites, Profile, Proposal,
+    Recovery, Referrals, Registry, RegistryPage, Session, Stats, Tally, TipVault,
+};
+use solana_ed25519_program::new_ed25519_instruction_with_signature;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::{
+    path::Path,
+    sync::{Once, OnceLock},
+};
+
+pub mod meter;
+
+pub const STARTING_LAMPORTS: u64 = 10_000_000_000;
+
+// `processor!` wants a plain `fn`, while Anchor's entrypoint ties the account
+// slice and the accounts to one lifetime. Leaking a copy of the slice is fine
+// for a short-lived test bank.
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    favorites::entry(program_id, accounts, instruction_data)
+}
+
+// Adds the Token Metadata program that scripts/dump-fixtures.sh dumps into
+// tests/fixtures, failing the test without it.
+pub fn add_token_metadata(program_test: &mut ProgramTest) {
+    let fixture =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mpl_token_metadata.so");
+    assert!(
+        fixture.exists(),
+        "{} is missing, run scripts/dump-fixtures.sh",
+        fixture.display()
+    );
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+}
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test =
+        ProgramTest::new("favorites", favorites::ID, processor!(process_instruction));
+    program_test.prefer_bpf(false);
+    program_test
+}
+
+// Adds a funded system account for every keypair.
+pub fn fund(program_test: &mut ProgramTest, users: &[Keypair]) {
+    for user in users {
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: STARTING_LAMPORTS,
+                owner: system_program::ID,
+                ..Account::default()
+            },
+        );
+    }
+}
+
+// Space the first release allocated for `Favorites`: a number, a color of up
+// to 50 bytes and a delegate
+pub const LEGACY_FAVORITES_SPACE: usize = 8 + 8 + 4 + 50 + 1 + 32;
+
+// Adds favorites of `user` as the first release left them, before fields were
+// appended and the version byte added.
+pub fn add_legacy_favorites(
+    program_test: &mut ProgramTest,
+    user: &Pubkey,
+    number: u64,
+    color: &str,
+    delegate: Option<Pubkey>,
+) {
+    let mut data = Favorites::DISCRIMINATOR.to_vec();
+    (number, color.to_string(), delegate)
+        .serialize(&mut data)
+        .unwrap();
+    data.resize(LEGACY_FAVORITES_SPACE, 0);
+    program_test.add_account(
+        favorites_pda(user),
+        Account {
+            lamports: Rent::default().minimum_balance(LEGACY_FAVORITES_SPACE),
+            data,
+            owner: favorites::ID,
+            ..Account::default()
+        },
+    );
+}
+
+fn add_packed<T: Pack>(program_test: &mut ProgramTest, address: Pubkey, state: T) {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::ID,
+            ..Account::default()
+        },
+    );
+}
+
+// Adds a mint without authorities and with no decimals.
+pub fn add_mint(program_test: &mut ProgramTest, supply: u64) -> Pubkey {
+    add_mint_with_authority(program_test, supply, None)
+}
+
+// Adds a mint with no decimals that `mint_authority`, if any, can mint more of.
+pub fn add_mint_with_authority(
+    program_test: &mut ProgramTest,
+    supply: u64,
+    mint_authority: Option<Pubkey>,
+) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    add_packed(
+        program_test,
+        mint,
+        Mint {
+            mint_authority: mint_authority.into(),
+            supply,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+    );
+    mint
+}
+
+pub fn add_token_account(
+    program_test: &mut ProgramTest,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let address = Pubkey::new_unique();
+    add_packed(
+        program_test,
+        address,
+        TokenState {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenState::default()
+        },
+    );
+    address
+}
+
+pub fn favorites_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"favorites", user.as_ref()], &favorites::ID).0
+}
+
+pub fn referrals_pda(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"referrals", referrer.as_ref()], &favorites::ID).0
+}
+
+pub fn registry_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"registry"], &favorites::ID).0
+}
+
+pub fn registry_page_pda(page: u32) -> Pubkey {
+    Pubkey::find_program_address(&[b"registry", &page.to_le_bytes()], &favorites::ID).0
+}
+
+pub fn stats_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"stats"], &favorites::ID).0
+}
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &favorites::ID).0
+}
+
+// Fills in the gate token account of a gated instruction built without one.
+// It always follows the config account.
+pub fn with_gate_token_account(
+    mut instruction: Instruction,
+    token_account: &Pubkey,
+) -> Instruction {
+    let index = config_index(&instruction);
+    instruction.accounts[index + 1] = AccountMeta::new_readonly(*token_account, false);
+    instruction
+}
+
+pub fn treasury_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury"], &favorites::ID).0
+}
+
+pub fn fee_exemption_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fee_exempt", user.as_ref()], &favorites::ID).0
+}
+
+// Fills in the fee exemption of `payer` in an instruction built without one.
+// It follows the gate token account.
+pub fn with_fee_exemption(mut instruction: Instruction, payer: &Pubkey) -> Instruction {
+    let index = config_index(&instruction);
+    instruction.accounts[index + 2] = AccountMeta::new_readonly(fee_exemption_pda(payer), false);
+    instruction
+}
+
+// Fills in the token accounts paying a token fee, which follow the treasury.
+pub fn with_fee_token_accounts(
+    mut instruction: Instruction,
+    fee_token_account: &Pubkey,
+    treasury_token_account: &Pubkey,
+) -> Instruction {
+    let index = config_index(&instruction);
+    instruction.accounts[index + 4] = AccountMeta::new(*fee_token_account, false);
+    instruction.accounts[index + 5] = AccountMeta::new(*treasury_token_account, false);
+    instruction.accounts[index + 6] = AccountMeta::new_readonly(spl_token::ID, false);
+    instruction
+}
+
+fn config_index(instruction: &Instruction) -> usize {
+    let config = config_pda();
+    instruction
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == config)
+        .expect("not a gated instruction")
+}
+
+pub fn program_data_pda() -> Pubkey {
+    Pubkey::find_program_address(&[favorites::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// Adds the program's data account naming `upgrade_authority`, as a deploy
+// with the upgradeable loader leaves it: the bincode `ProgramData` variant of
+// the loader state, without the program bytes following it.
+pub fn add_program_data(program_test: &mut ProgramTest, upgrade_authority: &Pubkey) {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    program_test.add_account(
+        program_data_pda(),
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::ID,
+            ..Account::default()
+        },
+    );
+}
+
+pub fn initialize_config_ix(
+    admin: &Pubkey,
+    gate_mint: Option<Pubkey>,
+    gate_min_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::InitializeConfig {
+            admin: *admin,
+            config: config_pda(),
+            program_data: program_data_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::InitializeConfig {
+            gate_mint,
+            gate_min_amount,
+        }
+        .data(),
+    }
+}
+
+pub fn set_token_gate_ix(
+    admin: &Pubkey,
+    gate_mint: Option<Pubkey>,
+    gate_min_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::UpdateConfig {
+            admin: *admin,
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetTokenGate {
+            gate_mint,
+            gate_min_amount,
+        }
+        .data(),
+    }
+}
+
+pub fn set_favorites_ix(user: &Pubkey, open_page: u32, number: u64, color: &str) -> Instruction {
+    referred_set_favorites_ix(user, open_page, &[], number, color)
+}
+
+// `set_favorites_ix` naming the wallet that brought `user`, the first of
+// `referrers`, which lists the referral chain upwards
+pub fn referred_set_favorites_ix(
+    user: &Pubkey,
+    open_page: u32,
+    referrers: &[Pubkey],
+    number: u64,
+    color: &str,
+) -> Instruction {
+    let referrer = referrers.first();
+    let instruction = Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetFavorites {
+            user: *user,
+            favorites: favorites_pda(user),
+            registry: registry_pda(),
+            registry_page: registry_page_pda(open_page),
+            stats: stats_pda(),
+            config: config_pda(),
+            gate_token_account: None,
+            fee_exemption: None,
+            treasury: treasury_pda(),
+            fee_token_account: None,
+            treasury_token_account: None,
+            token_program: None,
+            referrer: referrer.copied(),
+            referrals: referrer.map(referrals_pda),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetFavorites {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    };
+    with_referral_chain(instruction, referrers)
+}
+
+// Appends the favorites of each of `referrers`, the referral chain the
+// program follows for instructions naming or moving a referrer
+pub fn with_referral_chain(mut instruction: Instruction, referrers: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(
+        referrers
+            .iter()
+            .map(|referrer| AccountMeta::new_readonly(favorites_pda(referrer), false)),
+    );
+    instruction
+}
+
+pub fn migrate_favorites_ix(user: &Pubkey, open_page: u32) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::MigrateFavorites {
+            user: *user,
+            favorites: favorites_pda(user),
+            registry: registry_pda(),
+            registry_page: registry_page_pda(open_page),
+            stats: stats_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::MigrateFavorites {}.data(),
+    }
+}
+
+// `user_signs` adds the owner as a signer next to `signer`.
+pub fn update_favorites_ix(
+    user: &Pubkey,
+    signer: &Pubkey,
+    user_signs: bool,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: update_accounts(user, signer, user_signs),
+        data: favorites::instruction::UpdateFavorites {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+// Only the owner may change the delegate; `user_signs` lets tests try without.
+pub fn set_authority_ix(user: &Pubkey, user_signs: bool, delegate: Option<Pubkey>) -> Instruction {
+    let mut accounts = favorites::accounts::SetAuthority {
+        user: *user,
+        favorites: favorites_pda(user),
+    }
+    .to_account_metas(None);
+    accounts[0].is_signer = user_signs;
+    Instruction {
+        program_id: favorites::ID,
+        accounts,
+        data: favorites::instruction::SetAuthority { delegate }.data(),
+    }
+}
+
+pub fn session_pda(user: &Pubkey, session_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"session", user.as_ref(), session_key.as_ref()],
+        &favorites::ID,
+    )
+    .0
+}
+
+pub fn create_session_ix(
+    user: &Pubkey,
+    session_key: &Pubkey,
+    max_uses: u32,
+    expiry: i64,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CreateSession {
+            user: *user,
+            favorites: favorites_pda(user),
+            session: session_pda(user, session_key),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CreateSession {
+            session_key: *session_key,
+            max_uses,
+            expiry,
+        }
+        .data(),
+    }
+}
+
+pub fn revoke_session_ix(user: &Pubkey, session_key: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::RevokeSession {
+            user: *user,
+            session: session_pda(user, session_key),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::RevokeSession {}.data(),
+    }
+}
+
+// `update_favorites` signed by `session_key` on behalf of `user`.
+pub fn update_favorites_session_ix(
+    user: &Pubkey,
+    session_key: &Pubkey,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::UpdateFavorites {
+            user: *user,
+            signer: *session_key,
+            favorites: favorites_pda(user),
+            stats: stats_pda(),
+            session: Some(session_pda(user, session_key)),
+            config: config_pda(),
+            gate_token_account: None,
+            fee_exemption: None,
+            treasury: treasury_pda(),
+            fee_token_account: None,
+            treasury_token_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::UpdateFavorites {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+// `update_favorites_signed` of `user`, submitted by `relayer`, who pays the fee.
+pub fn update_favorites_signed_ix(
+    user: &Pubkey,
+    relayer: &Pubkey,
+    number: u64,
+    color: &str,
+    nonce: u64,
+    expiry: i64,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::UpdateFavoritesSigned {
+            user: *user,
+            relayer: *relayer,
+            favorites: favorites_pda(user),
+            stats: stats_pda(),
+            instructions: sysvar::instructions::ID,
+            config: config_pda(),
+            gate_token_account: None,
+            fee_exemption: None,
+            treasury: treasury_pda(),
+            fee_token_account: None,
+            treasury_token_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::UpdateFavoritesSigned {
+            number,
+            color: color.to_string(),
+            nonce,
+            expiry,
+        }
+        .data(),
+    }
+}
+
+// Ed25519 program instruction verifying `signer`'s signature of `message`.
+pub fn ed25519_ix(signer: &Keypair, message: &[u8]) -> Instruction {
+    let signature: [u8; 64] = signer.sign_message(message).into();
+    new_ed25519_instruction_with_signature(message, &signature, &signer.pubkey().to_bytes())
+}
+
+pub fn close_favorites_ix(user: &Pubkey, registry_page: u32, user_signs: bool) -> Instruction {
+    referred_close_favorites_ix(user, registry_page, None, user_signs)
+}
+
+// `close_favorites_ix` for favorites naming `referrer`
+pub fn referred_close_favorites_ix(
+    user: &Pubkey,
+    registry_page: u32,
+    referrer: Option<&Pubkey>,
+    user_signs: bool,
+) -> Instruction {
+    let mut accounts = favorites::accounts::CloseFavorites {
+        user: *user,
+        favorites: favorites_pda(user),
+        registry: registry_pda(),
+        registry_page: registry_page_pda(registry_page),
+        stats: stats_pda(),
+        referrals: referrer.map(referrals_pda),
+        recovery: recovery_pda(user),
+    }
+    .to_account_metas(None);
+    accounts[0].is_signer = user_signs;
+    Instruction {
+        program_id: favorites::ID,
+        accounts,
+        data: favorites::instruction::CloseFavorites {}.data(),
+    }
+}
+
+pub fn recovery_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"recovery", user.as_ref()], &favorites::ID).0
+}
+
+pub fn set_recovery_key_ix(user: &Pubkey, recovery_key: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetRecoveryKey {
+            user: *user,
+            favorites: favorites_pda(user),
+            recovery: recovery_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetRecoveryKey {
+            recovery_key: *recovery_key,
+        }
+        .data(),
+    }
+}
+
+pub fn remove_recovery_key_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::RemoveRecoveryKey {
+            user: *user,
+            recovery: recovery_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::RemoveRecoveryKey {}.data(),
+    }
+}
+
+pub fn start_recovery_ix(recovery_key: &Pubkey, user: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::StartRecovery {
+            recovery_key: *recovery_key,
+            user: *user,
+            favorites: favorites_pda(user),
+            recovery: recovery_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::StartRecovery {
+            new_owner: *new_owner,
+        }
+        .data(),
+    }
+}
+
+pub fn cancel_recovery_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CancelRecovery {
+            user: *user,
+            recovery: recovery_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CancelRecovery {}.data(),
+    }
+}
+
+pub fn complete_recovery_ix(user: &Pubkey, new_owner: &Pubkey, registry_page: u32) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CompleteRecovery {
+            user: *user,
+            new_owner: *new_owner,
+            favorites: favorites_pda(user),
+            new_favorites: favorites_pda(new_owner),
+            recovery: recovery_pda(user),
+            registry_page: registry_page_pda(registry_page),
+            stats: stats_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CompleteRecovery {}.data(),
+    }
+}
+
+// `new_owner_signs` lets tests try without the new owner's signature.
+pub fn transfer_favorites_ix(
+    user: &Pubkey,
+    new_owner: &Pubkey,
+    registry_page: u32,
+    new_owner_signs: bool,
+) -> Instruction {
+    let mut accounts = favorites::accounts::TransferFavorites {
+        user: *user,
+        new_owner: *new_owner,
+        favorites: favorites_pda(user),
+        new_favorites: favorites_pda(new_owner),
+        recovery: recovery_pda(user),
+        registry_page: registry_page_pda(registry_page),
+        stats: stats_pda(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts[1].is_signer = new_owner_signs;
+    Instruction {
+        program_id: favorites::ID,
+        accounts,
+        data: favorites::instruction::TransferFavorites {}.data(),
+    }
+}
+
+fn update_accounts(user: &Pubkey, signer: &Pubkey, user_signs: bool) -> Vec<AccountMeta> {
+    let mut accounts = favorites::accounts::UpdateFavorites {
+        user: *user,
+        signer: *signer,
+        favorites: favorites_pda(user),
+        stats: stats_pda(),
+        session: None,
+        config: config_pda(),
+        gate_token_account: None,
+        fee_exemption: None,
+        treasury: treasury_pda(),
+        fee_token_account: None,
+        treasury_token_account: None,
+        token_program: None,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts[0].is_signer = user_signs;
+    accounts
+}
+
+pub fn nft_favorites_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"nft_favorites", mint.as_ref()], &favorites::ID).0
+}
+
+pub fn set_nft_favorites_ix(
+    holder: &Pubkey,
+    mint: &Pubkey,
+    holder_token_account: &Pubkey,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetNftFavorites {
+            holder: *holder,
+            mint: *mint,
+            holder_token_account: *holder_token_account,
+            nft_favorites: nft_favorites_pda(mint),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetNftFavorites {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn update_nft_favorites_ix(
+    holder: &Pubkey,
+    mint: &Pubkey,
+    holder_token_account: &Pubkey,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::UpdateNftFavorites {
+            holder: *holder,
+            mint: *mint,
+            holder_token_account: *holder_token_account,
+            nft_favorites: nft_favorites_pda(mint),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::UpdateNftFavorites {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn badge_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"badge_authority"], &favorites::ID).0
+}
+
+pub fn mint_favorites_badge_ix(user: &Pubkey, badge_mint: &Pubkey) -> Instruction {
+    let metadata_program = mpl_token_metadata::ID;
+    let metadata = Pubkey::find_program_address(
+        &[b"metadata", metadata_program.as_ref(), badge_mint.as_ref()],
+        &metadata_program,
+    )
+    .0;
+    let master_edition = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            metadata_program.as_ref(),
+            badge_mint.as_ref(),
+            b"edition",
+        ],
+        &metadata_program,
+    )
+    .0;
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::MintFavoritesBadge {
+            user: *user,
+            favorites: favorites_pda(user),
+            badge_mint: *badge_mint,
+            badge_token_account: get_associated_token_address(user, badge_mint),
+            badge_authority: badge_authority_pda(),
+            metadata,
+            master_edition,
+            token_program: spl_token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: metadata_program,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::MintFavoritesBadge {}.data(),
+    }
+}
+
+pub fn tip_vault_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tip_vault", user.as_ref()], &favorites::ID).0
+}
+
+pub fn tip_ix(tipper: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::Tip {
+            tipper: *tipper,
+            user: *user,
+            favorites: favorites_pda(user),
+            tip_vault: tip_vault_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::Tip { amount }.data(),
+    }
+}
+
+pub fn withdraw_tips_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::WithdrawTips {
+            user: *user,
+            tip_vault: tip_vault_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::WithdrawTips {}.data(),
+    }
+}
+
+pub fn handle_pda(handle: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"handle", handle.as_bytes()], &favorites::ID).0
+}
+
+pub fn register_handle_ix(owner: &Pubkey, handle: &str) -> Instruction {
+    // Too long handles are cut to fit a seed, for the program to reject them
+    let seed = &handle.as_bytes()[..handle.len().min(favorites::MAX_HANDLE_LEN)];
+    let handle_account = Pubkey::find_program_address(&[b"handle", seed], &favorites::ID).0;
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::RegisterHandle {
+            owner: *owner,
+            favorites: favorites_pda(owner),
+            handle_account,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::RegisterHandle {
+            handle: handle.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn transfer_handle_ix(owner: &Pubkey, handle: &str, new_owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::TransferHandle {
+            owner: *owner,
+            handle_account: handle_pda(handle),
+            new_owner: *new_owner,
+            new_owner_favorites: favorites_pda(new_owner),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::TransferHandle {}.data(),
+    }
+}
+
+pub fn release_handle_ix(owner: &Pubkey, handle: &str) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::ReleaseHandle {
+            owner: *owner,
+            handle_account: handle_pda(handle),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::ReleaseHandle {}.data(),
+    }
+}
+
+pub fn set_vote_mint_ix(admin: &Pubkey, vote_mint: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::UpdateConfig {
+            admin: *admin,
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetVoteMint { vote_mint }.data(),
+    }
+}
+
+pub fn set_fee_ix(admin: &Pubkey, fee_mint: Option<Pubkey>, fee_amount: u64) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetFee {
+            admin: *admin,
+            config: config_pda(),
+            treasury: treasury_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetFee {
+            fee_mint,
+            fee_amount,
+        }
+        .data(),
+    }
+}
+
+pub fn add_fee_exemption_ix(admin: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::AddFeeExemption {
+            admin: *admin,
+            config: config_pda(),
+            user: *user,
+            fee_exemption: fee_exemption_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::AddFeeExemption {}.data(),
+    }
+}
+
+pub fn remove_fee_exemption_ix(admin: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::RemoveFeeExemption {
+            admin: *admin,
+            config: config_pda(),
+            user: *user,
+            fee_exemption: fee_exemption_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::RemoveFeeExemption {}.data(),
+    }
+}
+
+pub fn withdraw_fees_ix(admin: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::WithdrawFees {
+            admin: *admin,
+            config: config_pda(),
+            treasury: treasury_pda(),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::WithdrawFees {}.data(),
+    }
+}
+
+pub fn withdraw_fee_tokens_ix(
+    admin: &Pubkey,
+    treasury_token_account: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::WithdrawFeeTokens {
+            admin: *admin,
+            config: config_pda(),
+            treasury: treasury_pda(),
+            treasury_token_account: *treasury_token_account,
+            destination: *destination,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::WithdrawFeeTokens {}.data(),
+    }
+}
+
+pub fn set_premium_ix(
+    admin: &Pubkey,
+    premium_mint: Option<Pubkey>,
+    premium_price: u64,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetFee {
+            admin: *admin,
+            config: config_pda(),
+            treasury: treasury_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetPremium {
+            premium_mint,
+            premium_price,
+        }
+        .data(),
+    }
+}
+
+pub fn renew_premium_ix(
+    user: &Pubkey,
+    payment_token_account: &Pubkey,
+    treasury_token_account: &Pubkey,
+    months: u8,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::RenewPremium {
+            user: *user,
+            favorites: favorites_pda(user),
+            config: config_pda(),
+            treasury: treasury_pda(),
+            payment_token_account: *payment_token_account,
+            treasury_token_account: *treasury_token_account,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::RenewPremium { months }.data(),
+    }
+}
+
+pub fn cancel_premium_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CancelPremium {
+            user: *user,
+            favorites: favorites_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CancelPremium {}.data(),
+    }
+}
+
+pub fn profile_pda(user: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", user.as_ref(), &[index]], &favorites::ID).0
+}
+
+pub fn set_profile_ix(user: &Pubkey, index: u8, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::SetProfile {
+            user: *user,
+            favorites: favorites_pda(user),
+            profile: profile_pda(user, index),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::SetProfile {
+            index,
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn close_profile_ix(user: &Pubkey, index: u8) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CloseProfile {
+            user: *user,
+            profile: profile_pda(user, index),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CloseProfile { index }.data(),
+    }
+}
+
+pub fn collection_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"collection", user.as_ref()], &favorites::ID).0
+}
+
+pub fn create_collection_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CreateCollection {
+            user: *user,
+            favorites: favorites_pda(user),
+            collection: collection_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CreateCollection {}.data(),
+    }
+}
+
+pub fn grow_collection_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::GrowCollection {
+            user: *user,
+            collection: collection_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::GrowCollection {}.data(),
+    }
+}
+
+// `create_collection_ix` and as many `grow_collection_ix` as it takes for the
+// collection to take items
+pub fn create_full_collection_ixs(user: &Pubkey) -> Vec<Instruction> {
+    let grows = favorites::COLLECTION_SPACE.div_ceil(MAX_PERMITTED_DATA_INCREASE) - 1;
+    std::iter::once(create_collection_ix(user))
+        .chain(std::iter::repeat_with(|| grow_collection_ix(user)).take(grows))
+        .collect()
+}
+
+fn modify_collection_accounts(user: &Pubkey) -> Vec<AccountMeta> {
+    favorites::accounts::ModifyCollection {
+        user: *user,
+        collection: collection_pda(user),
+    }
+    .to_account_metas(None)
+}
+
+pub fn append_collection_item_ix(user: &Pubkey, number: u64, color: &str) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_collection_accounts(user),
+        data: favorites::instruction::AppendCollectionItem {
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn update_collection_item_ix(
+    user: &Pubkey,
+    index: u32,
+    number: u64,
+    color: &str,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_collection_accounts(user),
+        data: favorites::instruction::UpdateCollectionItem {
+            index,
+            number,
+            color: color.to_string(),
+        }
+        .data(),
+    }
+}
+
+pub fn remove_collection_item_ix(user: &Pubkey, index: u32) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: modify_collection_accounts(user),
+        data: favorites::instruction::RemoveCollectionItem { index }.data(),
+    }
+}
+
+pub fn close_collection_ix(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CloseCollection {
+            user: *user,
+            collection: collection_pda(user),
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CloseCollection {}.data(),
+    }
+}
+
+pub fn proposal_pda(proposer: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"proposal", proposer.as_ref(), &id.to_le_bytes()],
+        &favorites::ID,
+    )
+    .0
+}
+
+pub fn tally_pda(proposal: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tally", proposal.as_ref()], &favorites::ID).0
+}
+
+pub fn vote_escrow_pda(proposal: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vote_escrow", proposal.as_ref()], &favorites::ID).0
+}
+
+pub fn vote_receipt_pda(proposal: &Pubkey, voter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"vote", proposal.as_ref(), voter.as_ref()],
+        &favorites::ID,
+    )
+    .0
+}
+
+pub fn community_color_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"community_color"], &favorites::ID).0
+}
+
+pub fn create_proposal_ix(
+    proposer: &Pubkey,
+    vote_mint: &Pubkey,
+    id: u64,
+    colors: &[&str],
+    voting_period: i64,
+) -> Instruction {
+    let proposal = proposal_pda(proposer, id);
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::CreateProposal {
+            proposer: *proposer,
+            config: config_pda(),
+            vote_mint: *vote_mint,
+            proposal,
+            tally: tally_pda(&proposal),
+            vote_escrow: vote_escrow_pda(&proposal),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::CreateProposal {
+            id,
+            colors: colors.iter().map(|color| color.to_string()).collect(),
+            voting_period,
+        }
+        .data(),
+    }
+}
+
+pub fn vote_ix(
+    voter: &Pubkey,
+    proposal: &Pubkey,
+    voter_token_account: &Pubkey,
+    choice: u8,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::Vote {
+            voter: *voter,
+            proposal: *proposal,
+            tally: tally_pda(proposal),
+            vote_receipt: vote_receipt_pda(proposal, voter),
+            voter_token_account: *voter_token_account,
+            vote_escrow: vote_escrow_pda(proposal),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::Vote { choice }.data(),
+    }
+}
+
+pub fn finalize_proposal_ix(payer: &Pubkey, proposal: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::FinalizeProposal {
+            payer: *payer,
+            proposal: *proposal,
+            tally: tally_pda(proposal),
+            community_color: community_color_pda(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::FinalizeProposal {}.data(),
+    }
+}
+
+pub fn reclaim_vote_ix(
+    voter: &Pubkey,
+    proposal: &Pubkey,
+    voter_token_account: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: favorites::ID,
+        accounts: favorites::accounts::ReclaimVote {
+            voter: *voter,
+            proposal: *proposal,
+            vote_receipt: vote_receipt_pda(proposal, voter),
+            voter_token_account: *voter_token_account,
+            vote_escrow: vote_escrow_pda(proposal),
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: favorites::instruction::ReclaimVote {}.data(),
+    }
+}
+
+// Submits `instructions` paid for by the context payer and signed by `signers`.
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Starts `program_test` with `users` funded, of which the first
+// `with_favorites` set favorites of 7 and "red".
+pub async fn start_with_favorites(
+    mut program_test: ProgramTest,
+    users: &[Keypair],
+    with_favorites: usize,
+) -> ProgramTestContext {
+    fund(&mut program_test, users);
+    let mut context = program_test.start_with_context().await;
+    for user in &users[..with_favorites] {
+        let open_page = open_page(&mut context).await;
+        let ix = set_favorites_ix(&user.pubkey(), open_page, 7, "red");
+        send_fresh(&mut context, &[ix], &[user]).await.unwrap();
+    }
+    context
+}
+
+// `start_with_favorites` with the favorites program alone.
+pub async fn setup(users: &[Keypair], with_favorites: usize) -> ProgramTestContext {
+    start_with_favorites(program_test(), users, with_favorites).await
+}
+
+pub async fn balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*address).await.unwrap()
+}
+
+// The bank's clock, in seconds
+pub async fn now(context: &mut ProgramTestContext) -> i64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp
+}
+
+// Moves the clock `seconds` forward.
+pub async fn warp(context: &mut ProgramTestContext, seconds: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += seconds;
+    context.set_sysvar(&clock);
+}
+
+// `send` with a new blockhash, so that a transaction repeating an earlier one,
+// such as a retry of a failed one, is processed again instead of deduplicated.
+pub async fn send_fresh(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    send(context, instructions, signers).await
+}
+
+// The stubs solana-program-test installs for natively run programs.
+static PROGRAM_TEST_STUBS: OnceLock<Box<dyn SyscallStubs>> = OnceLock::new();
+
+// solana-program-test leaves `sol_log_data` to the default stub, which prints
+// to stdout, so `emit!` never reaches the transaction logs of a native run.
+// These stubs forward everything to solana-program-test's, except that event
+// data is logged like any message, as "Program log: Program data: <base64>".
+struct LogDataStubs;
+
+impl LogDataStubs {
+    fn inner(&self) -> &dyn SyscallStubs {
+        // Only empty for the instant between swapping the stubs and storing
+        // the previous ones
+        loop {
+            if let Some(stubs) = PROGRAM_TEST_STUBS.get() {
+                return stubs.as_ref();
+            }
+            std::hint::spin_loop();
+        }
+    }
+}
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner().sol_log(message)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        for field in fields {
+            self.inner()
+                .sol_log(&format!("Program data: {}", STANDARD.encode(field)));
+        }
+    }
+    fn sol_log_compute_units(&self) {
+        self.inner().sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.inner().sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.inner()
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_sysvar(
+        &self,
+        sysvar_id_addr: *const u8,
+        var_addr: *mut u8,
+        offset: u64,
+        length: u64,
+    ) -> u64 {
+        self.inner()
+            .sol_get_sysvar(sysvar_id_addr, var_addr, offset, length)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.inner().sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_epoch_stake(&self, vote_address: *const u8) -> u64 {
+        self.inner().sol_get_epoch_stake(vote_address)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.inner().sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.inner().sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.inner().sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.inner().sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner().sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner().sol_set_return_data(data)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.inner().sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.inner().sol_get_stack_height()
+    }
+}
+
+// Like `send`, also returning the transaction's log messages, events included.
+pub async fn send_with_logs(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> (Result<(), BanksClientError>, Vec<String>) {
+    // Starting the context installed solana-program-test's stubs already
+    static LOG_DATA: Once = Once::new();
+    LOG_DATA.call_once(|| {
+        let previous = set_syscall_stubs(Box::new(LogDataStubs));
+        let _ = PROGRAM_TEST_STUBS.set(previous);
+    });
+
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    // `process_transaction` hands transactions to the bank's own thread and
+    // returns once they are committed, which can be just before the bank
+    // unlocks their accounts. Processed right after, this one may find them
+    // still in use.
+    let outcome = loop {
+        let outcome = context
+            .banks_client
+            .process_transaction_with_metadata(transaction.clone())
+            .await
+            .unwrap();
+        if outcome.result != Err(TransactionError::AccountInUse) {
+            break outcome;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    let logs = outcome
+        .metadata
+        .map(|metadata| metadata.log_messages)
+        .unwrap_or_default();
+    (
+        outcome.result.map_err(BanksClientError::TransactionError),
+        logs,
+    )
+}
+
+// Every event `E` that `emit!` logged, in order.
+pub fn events<E: AnchorDeserialize + Discriminator>(logs: &[String]) -> Vec<E> {
+    logs.iter()
+        .filter_map(|log| log.split_once("Program data: ").map(|(_, data)| data))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter_map(|data| {
+            let body = data.strip_prefix(E::DISCRIMINATOR)?;
+            Some(E::deserialize(&mut &body[..]).unwrap())
+        })
+        .collect()
+}
+
+// Asserts that instruction `index` failed with the program's `error`.
+pub fn assert_custom_error(result: Result<(), BanksClientError>, index: u8, error: CustomError) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            failed,
+            InstructionError::Custom(code),
+        ))) => {
+            assert_eq!(failed, index);
+            assert_eq!(code, u32::from(error), "expected {error:?}");
+        }
+        other => panic!("expected {error:?}, got {other:?}"),
+    }
+}
+
+pub async fn fetch_favorites(context: &mut ProgramTestContext, user: &Pubkey) -> Option<Favorites> {
+    let account = context
+        .banks_client
+        .get_account(favorites_pda(user))
+        .await
+        .unwrap()?;
+    Some(Favorites::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_stats(context: &mut ProgramTestContext) -> Option<Stats> {
+    let account = context
+        .banks_client
+        .get_account(stats_pda())
+        .await
+        .unwrap()?;
+    Some(Stats::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_registry(context: &mut ProgramTestContext) -> Option<Registry> {
+    let account = context
+        .banks_client
+        .get_account(registry_pda())
+        .await
+        .unwrap()?;
+    Some(Registry::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_registry_page(
+    context: &mut ProgramTestContext,
+    page: u32,
+) -> Option<RegistryPage> {
+    let account = context
+        .banks_client
+        .get_account(registry_page_pda(page))
+        .await
+        .unwrap()?;
+    Some(RegistryPage::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+// The page `set_favorites` appends to next.
+pub async fn open_page(context: &mut ProgramTestContext) -> u32 {
+    fetch_registry(context)
+        .await
+        .map_or(0, |registry| registry.open_page)
+}
+
+// Every user listed in the registry, in page order.
+pub async fn registered_users(context: &mut ProgramTestContext) -> Vec<Pubkey> {
+    let Some(registry) = fetch_registry(context).await else {
+        return Vec::new();
+    };
+    let mut users = Vec::new();
+    for page in 0..=registry.open_page {
+        if let Some(page) = fetch_registry_page(context, page).await {
+            users.extend(page.users);
+        }
+    }
+    users
+}
+
+pub async fn fetch_session(
+    context: &mut ProgramTestContext,
+    user: &Pubkey,
+    session_key: &Pubkey,
+) -> Option<Session> {
+    let account = context
+        .banks_client
+        .get_account(session_pda(user, session_key))
+        .await
+        .unwrap()?;
+    Some(Session::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_nft_favorites(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+) -> Option<NftFavorites> {
+    let account = context
+        .banks_client
+        .get_account(nft_favorites_pda(mint))
+        .await
+        .unwrap()?;
+    Some(NftFavorites::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_tip_vault(context: &mut ProgramTestContext, user: &Pubkey) -> Option<TipVault> {
+    let account = context
+        .banks_client
+        .get_account(tip_vault_pda(user))
+        .await
+        .unwrap()?;
+    Some(TipVault::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_proposal(
+    context: &mut ProgramTestContext,
+    proposal: &Pubkey,
+) -> Option<Proposal> {
+    let account = context.banks_client.get_account(*proposal).await.unwrap()?;
+    Some(Proposal::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_tally(context: &mut ProgramTestContext, proposal: &Pubkey) -> Option<Tally> {
+    let account = context
+        .banks_client
+        .get_account(tally_pda(proposal))
+        .await
+        .unwrap()?;
+    Some(Tally::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_community_color(context: &mut ProgramTestContext) -> Option<CommunityColor> {
+    let account = context
+        .banks_client
+        .get_account(community_color_pda())
+        .await
+        .unwrap()?;
+    Some(CommunityColor::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, token_account: &Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(*token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    TokenState::unpack(&account.data).unwrap().amount
+}
+
+pub async fn fetch_handle(context: &mut ProgramTestContext, handle: &str) -> Option<Handle> {
+    let account = context
+        .banks_client
+        .get_account(handle_pda(handle))
+        .await
+        .unwrap()?;
+    Some(Handle::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_profile(
+    context: &mut ProgramTestContext,
+    user: &Pubkey,
+    index: u8,
+) -> Option<Profile> {
+    let account = context
+        .banks_client
+        .get_account(profile_pda(user, index))
+        .await
+        .unwrap()?;
+    Some(Profile::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+pub async fn fetch_referrals(
+    context: &mut ProgramTestContext,
+    referrer: &Pubkey,
+) -> Option<Referrals> {
+    let account = context
+        .banks_client
+        .get_account(referrals_pda(referrer))
+        .await
+        .unwrap()?;
+    Some(Referrals::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+// The number and color of every item in `user`'s collection, in order
+pub async fn fetch_collection_items(
+    context: &mut ProgramTestContext,
+    user: &Pubkey,
+) -> Option<Vec<(u64, String)>> {
+    let account = context
+        .banks_client
+        .get_account(collection_pda(user))
+        .await
+        .unwrap()?;
+    let (discriminator, data) = account.data.split_at(Collection::DISCRIMINATOR.len());
+    assert_eq!(discriminator, Collection::DISCRIMINATOR);
+    let collection: Box<Collection> = Box::new(bytemuck::pod_read_unaligned(data));
+    let items = collection.items[..collection.len as usize]
+        .iter()
+        .map(|item| {
+            let color = &item.color[..item.color_len as usize];
+            (item.number, String::from_utf8(color.to_vec()).unwrap())
+        })
+        .collect();
+    Some(items)
+}
+
+pub async fn fetch_recovery(context: &mut ProgramTestContext, user: &Pubkey) -> Option<Recovery> {
+    let account = context
+        .banks_client
+        .get_account(recovery_pda(user))
+        .await
+        .unwrap()?;
+    Some(Recovery::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
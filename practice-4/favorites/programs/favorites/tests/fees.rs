@@ -0,0 +1,236 @@
+// Fees on setting and updating favorites, paid into the treasury PDA.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use favorites::{signed_update_message, CustomError};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    rent::Rent,
+    signature::{Keypair, Signer},
+};
+
+const FEE: u64 = 1_000;
+
+// What the treasury holds on top of its rent.
+async fn treasury_fees(context: &mut ProgramTestContext) -> u64 {
+    let rent_exempt = Rent::default().minimum_balance(8);
+    common::balance(context, &common::treasury_pda()).await - rent_exempt
+}
+
+// A config charging `fee_amount` of `fee_mint`, with `admin` as its admin.
+async fn charge(
+    context: &mut ProgramTestContext,
+    admin: &Keypair,
+    fee_mint: Option<Pubkey>,
+    fee_amount: u64,
+) {
+    let ix = common::initialize_config_ix(&admin.pubkey(), None, 0);
+    common::send_fresh(context, &[ix], &[admin]).await.unwrap();
+    let ix = common::set_fee_ix(&admin.pubkey(), fee_mint, fee_amount);
+    common::send_fresh(context, &[ix], &[admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn lamport_fees_go_to_the_treasury_until_the_admin_withdraws() {
+    let (admin, user) = (Keypair::new(), Keypair::new());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mut context = program_test.start_with_context().await;
+    charge(&mut context, &admin, None, FEE).await;
+
+    let ix = common::set_fee_ix(&user.pubkey(), None, 0);
+    let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(treasury_fees(&mut context).await, FEE);
+    let ix = common::update_favorites_ix(&user.pubkey(), &user.pubkey(), false, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(treasury_fees(&mut context).await, 2 * FEE);
+
+    let ix = common::withdraw_fees_ix(&user.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    let before = common::balance(&mut context, &admin.pubkey()).await;
+    let ix = common::withdraw_fees_ix(&admin.pubkey());
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    // The context payer pays the transaction fee
+    assert_eq!(
+        common::balance(&mut context, &admin.pubkey()).await,
+        before + 2 * FEE
+    );
+    assert_eq!(treasury_fees(&mut context).await, 0);
+
+    let ix = common::withdraw_fees_ix(&admin.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[&admin]).await;
+    common::assert_custom_error(result, 0, CustomError::NoFeesToWithdraw);
+}
+
+#[tokio::test]
+async fn relayers_pay_the_fee_on_signed_updates() {
+    let (admin, user, relayer) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[
+            admin.insecure_clone(),
+            user.insecure_clone(),
+            relayer.insecure_clone(),
+        ],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mut context = program_test.start_with_context().await;
+    charge(&mut context, &admin, None, FEE).await;
+    let user_key = user.pubkey();
+    let ix = common::set_favorites_ix(&user_key, 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+
+    // The owner relaying their own update pays like any other update. The
+    // context payer pays the transaction fees.
+    let before = common::balance(&mut context, &user_key).await;
+    let message = signed_update_message(&user_key, 8, "blue", 0, i64::MAX);
+    let update = [
+        common::ed25519_ix(&user, &message),
+        common::update_favorites_signed_ix(&user_key, &user_key, 8, "blue", 0, i64::MAX),
+    ];
+    common::send_fresh(&mut context, &update, &[&user])
+        .await
+        .unwrap();
+    assert_eq!(common::balance(&mut context, &user_key).await, before - FEE);
+    assert_eq!(treasury_fees(&mut context).await, 2 * FEE);
+
+    let before = common::balance(&mut context, &relayer.pubkey()).await;
+    let message = signed_update_message(&user_key, 9, "green", 1, i64::MAX);
+    let update = [
+        common::ed25519_ix(&user, &message),
+        common::update_favorites_signed_ix(&user_key, &relayer.pubkey(), 9, "green", 1, i64::MAX),
+    ];
+    common::send_fresh(&mut context, &update, &[&relayer])
+        .await
+        .unwrap();
+    assert_eq!(
+        common::balance(&mut context, &relayer.pubkey()).await,
+        before - FEE
+    );
+    assert_eq!(treasury_fees(&mut context).await, 3 * FEE);
+}
+
+#[tokio::test]
+async fn exempt_users_pay_nothing() {
+    let (admin, user) = (Keypair::new(), Keypair::new());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mut context = program_test.start_with_context().await;
+    charge(&mut context, &admin, None, FEE).await;
+
+    let ix = common::add_fee_exemption_ix(&user.pubkey(), &user.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+    let ix = common::add_fee_exemption_ix(&admin.pubkey(), &user.pubkey());
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+
+    let user_key = user.pubkey();
+    let ix = common::set_favorites_ix(&user_key, 0, 7, "red");
+    let ix = common::with_fee_exemption(ix, &user_key);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    let ix = common::update_favorites_ix(&user_key, &user_key, false, 8, "blue");
+    let ix = common::with_fee_exemption(ix, &user_key);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(treasury_fees(&mut context).await, 0);
+
+    // Once removed, the exemption can no longer be passed
+    let ix = common::remove_fee_exemption_ix(&admin.pubkey(), &user_key);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ix = common::update_favorites_ix(&user_key, &user_key, false, 9, "green");
+    let ix = common::with_fee_exemption(ix, &user_key);
+    assert!(common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .is_err());
+    let ix = common::update_favorites_ix(&user_key, &user_key, false, 9, "green");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(treasury_fees(&mut context).await, FEE);
+}
+
+#[tokio::test]
+async fn token_fees_go_to_a_treasury_token_account() {
+    let (admin, user) = (Keypair::new(), Keypair::new());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, 1_000);
+    let other_mint = common::add_mint(&mut program_test, 1_000);
+    let user_tokens = common::add_token_account(&mut program_test, &user.pubkey(), &mint, 100);
+    let treasury = common::treasury_pda();
+    let treasury_tokens = common::add_token_account(&mut program_test, &treasury, &mint, 0);
+    let not_the_treasury = common::add_token_account(&mut program_test, &admin.pubkey(), &mint, 0);
+    let wrong_mint = common::add_token_account(&mut program_test, &treasury, &other_mint, 0);
+    let admin_tokens = common::add_token_account(&mut program_test, &admin.pubkey(), &mint, 0);
+    let mut context = program_test.start_with_context().await;
+    charge(&mut context, &admin, Some(mint), 10).await;
+
+    let set = || common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    let result = common::send_fresh(&mut context, &[set()], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidFeeAccounts);
+    for destination in [not_the_treasury, wrong_mint] {
+        let ix = common::with_fee_token_accounts(set(), &user_tokens, &destination);
+        let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+        common::assert_custom_error(result, 0, CustomError::InvalidFeeAccounts);
+    }
+
+    let ix = common::with_fee_token_accounts(set(), &user_tokens, &treasury_tokens);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(common::token_balance(&mut context, &user_tokens).await, 90);
+    assert_eq!(
+        common::token_balance(&mut context, &treasury_tokens).await,
+        10
+    );
+    // No lamports are charged on top
+    assert_eq!(treasury_fees(&mut context).await, 0);
+
+    let ix = common::withdraw_fee_tokens_ix(&admin.pubkey(), &treasury_tokens, &admin_tokens);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(common::token_balance(&mut context, &admin_tokens).await, 10);
+    assert_eq!(
+        common::token_balance(&mut context, &treasury_tokens).await,
+        0
+    );
+
+    let ix = common::withdraw_fee_tokens_ix(&admin.pubkey(), &treasury_tokens, &admin_tokens);
+    let result = common::send_fresh(&mut context, &[ix], &[&admin]).await;
+    common::assert_custom_error(result, 0, CustomError::NoFeesToWithdraw);
+}
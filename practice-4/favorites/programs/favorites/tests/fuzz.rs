@@ -0,0 +1,360 @@
+// Property-based harness: random sequences of favorites instructions with
+// random signers and strings, checking invariants after every step.
+//
+// Runs offline with `cargo test -p favorites --test fuzz`. Set
+// `FAVORITES_FUZZ_CASES` to run more sequences than the default.
+mod common;
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use favorites::{Favorites, MAX_COLOR_LEN};
+use proptest::prelude::*;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    rent::Rent,
+    signature::{Keypair, Signer},
+};
+
+const USERS: usize = 3;
+const DEFAULT_CASES: u32 = 32;
+
+#[derive(Clone, Debug)]
+enum Op {
+    SetFavorites {
+        user: usize,
+        number: u64,
+        color: String,
+    },
+    UpdateFavorites {
+        user: usize,
+        signer: usize,
+        user_signs: bool,
+        number: u64,
+        color: String,
+    },
+    SetAuthority {
+        user: usize,
+        user_signs: bool,
+        delegate: Option<usize>,
+    },
+    CloseFavorites {
+        user: usize,
+        user_signs: bool,
+    },
+}
+
+// Mostly ASCII around the color bound, plus multi-byte strings whose byte
+// length differs from their char count.
+fn color() -> impl Strategy<Value = String> {
+    prop_oneof!["[a-z ]{0,60}", "\\PC{0,20}"]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..USERS, any::<u64>(), color()).prop_map(|(user, number, color)| Op::SetFavorites {
+            user,
+            number,
+            color
+        }),
+        (0..USERS, 0..USERS, any::<bool>(), any::<u64>(), color()).prop_map(
+            |(user, signer, user_signs, number, color)| Op::UpdateFavorites {
+                user,
+                signer,
+                user_signs,
+                number,
+                color,
+            }
+        ),
+        (0..USERS, any::<bool>(), proptest::option::of(0..USERS)).prop_map(
+            |(user, user_signs, delegate)| Op::SetAuthority {
+                user,
+                user_signs,
+                delegate,
+            }
+        ),
+        (0..USERS, any::<bool>())
+            .prop_map(|(user, user_signs)| Op::CloseFavorites { user, user_signs }),
+    ]
+}
+
+fn cases() -> u32 {
+    std::env::var("FAVORITES_FUZZ_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(DEFAULT_CASES)
+}
+
+async fn snapshot(context: &mut ProgramTestContext, users: &[Keypair]) -> Vec<Option<Account>> {
+    let mut accounts = Vec::with_capacity(users.len());
+    for user in users {
+        let pda = common::favorites_pda(&user.pubkey());
+        accounts.push(context.banks_client.get_account(pda).await.unwrap());
+    }
+    accounts
+}
+
+fn decode(account: &Account) -> Favorites {
+    Favorites::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// Closing an account that does not exist fails either way; any page will do.
+fn registry_page(account: &Option<Account>) -> u32 {
+    account
+        .as_ref()
+        .map_or(0, |account| decode(account).registry_page)
+}
+
+// The registry lists exactly the users that have a favorites account, each on
+// the page their account records.
+async fn check_registry(
+    step: usize,
+    op: &Op,
+    context: &mut ProgramTestContext,
+    users: &[Keypair],
+    after: &[Option<Account>],
+) {
+    let mut registered = common::registered_users(context).await;
+    registered.sort();
+    let mut expected: Vec<Pubkey> = users
+        .iter()
+        .zip(after)
+        .filter(|(_, account)| account.is_some())
+        .map(|(user, _)| user.pubkey())
+        .collect();
+    expected.sort();
+    assert_eq!(registered, expected, "step {step} ({op:?}): registry users");
+
+    let user_count = common::fetch_registry(context)
+        .await
+        .map_or(0, |registry| registry.user_count);
+    assert_eq!(
+        user_count,
+        expected.len() as u64,
+        "step {step} ({op:?}): registry user count"
+    );
+
+    for (user, account) in users.iter().zip(after) {
+        if let Some(account) = account {
+            let page = common::fetch_registry_page(context, decode(account).registry_page)
+                .await
+                .unwrap();
+            assert!(
+                page.users.contains(&user.pubkey()),
+                "step {step} ({op:?}): {} is not on its registry page",
+                user.pubkey()
+            );
+        }
+    }
+}
+
+// With fewer users than `MAX_TOP_COLORS` the stats are exact: they count the
+// live accounts, their numbers and every color in use.
+async fn check_stats(
+    step: usize,
+    op: &Op,
+    context: &mut ProgramTestContext,
+    after: &[Option<Account>],
+) {
+    let live: Vec<Favorites> = after.iter().flatten().map(decode).collect();
+    let Some(stats) = common::fetch_stats(context).await else {
+        assert!(live.is_empty(), "step {step} ({op:?}): stats are missing");
+        return;
+    };
+    assert_eq!(
+        stats.user_count,
+        live.len() as u64,
+        "step {step} ({op:?}): stats user count"
+    );
+    assert_eq!(
+        stats.number_sum,
+        live.iter().map(|favorites| favorites.number as u128).sum(),
+        "step {step} ({op:?}): stats number sum"
+    );
+    for favorites in &live {
+        assert!(
+            stats.min_number.unwrap() <= favorites.number
+                && favorites.number <= stats.max_number.unwrap(),
+            "step {step} ({op:?}): {} is outside the stats min and max",
+            favorites.number
+        );
+    }
+
+    let mut counted: Vec<(String, u64)> = stats
+        .top_colors
+        .into_iter()
+        .map(|entry| (entry.color, entry.count))
+        .collect();
+    counted.sort();
+    let mut expected: Vec<(String, u64)> = Vec::new();
+    for favorites in &live {
+        match expected
+            .iter_mut()
+            .find(|(color, _)| *color == favorites.color)
+        {
+            Some((_, count)) => *count += 1,
+            None => expected.push((favorites.color.clone(), 1)),
+        }
+    }
+    expected.sort();
+    assert_eq!(counted, expected, "step {step} ({op:?}): stats colors");
+}
+
+fn check_invariants(
+    step: usize,
+    op: &Op,
+    users: &[Keypair],
+    signers: &[Pubkey],
+    rent: &Rent,
+    before: &[Option<Account>],
+    after: &[Option<Account>],
+) {
+    for (index, user) in users.iter().enumerate() {
+        let owner = user.pubkey();
+
+        if before[index] != after[index] {
+            let delegate = before[index]
+                .as_ref()
+                .and_then(|account| decode(account).delegate);
+            let authorized = signers
+                .iter()
+                .any(|signer| *signer == owner || Some(*signer) == delegate);
+            assert!(
+                authorized,
+                "step {step} ({op:?}): favorites of user {index} changed without the owner or delegate signing"
+            );
+        }
+
+        if let Some(account) = &after[index] {
+            let favorites = decode(account);
+            assert!(
+                favorites.color.len() <= MAX_COLOR_LEN,
+                "step {step} ({op:?}): color of user {index} is {} bytes",
+                favorites.color.len()
+            );
+            assert!(
+                rent.is_exempt(account.lamports, account.data.len()),
+                "step {step} ({op:?}): favorites of user {index} is not rent exempt"
+            );
+        }
+    }
+}
+
+async fn run(ops: Vec<Op>) {
+    let users: Vec<Keypair> = (0..USERS).map(|_| Keypair::new()).collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    for (step, op) in ops.iter().enumerate() {
+        let before = snapshot(&mut context, &users).await;
+        let (instruction, signer_indexes) = match op {
+            Op::SetFavorites {
+                user,
+                number,
+                color,
+            } => (
+                common::set_favorites_ix(
+                    &users[*user].pubkey(),
+                    common::open_page(&mut context).await,
+                    *number,
+                    color,
+                ),
+                vec![*user],
+            ),
+            Op::UpdateFavorites {
+                user,
+                signer,
+                user_signs,
+                number,
+                color,
+            } => (
+                common::update_favorites_ix(
+                    &users[*user].pubkey(),
+                    &users[*signer].pubkey(),
+                    *user_signs,
+                    *number,
+                    color,
+                ),
+                if *user_signs {
+                    vec![*signer, *user]
+                } else {
+                    vec![*signer]
+                },
+            ),
+            Op::SetAuthority {
+                user,
+                user_signs,
+                delegate,
+            } => (
+                common::set_authority_ix(
+                    &users[*user].pubkey(),
+                    *user_signs,
+                    delegate.map(|delegate| users[delegate].pubkey()),
+                ),
+                if *user_signs { vec![*user] } else { vec![] },
+            ),
+            Op::CloseFavorites { user, user_signs } => (
+                common::close_favorites_ix(
+                    &users[*user].pubkey(),
+                    registry_page(&before[*user]),
+                    *user_signs,
+                ),
+                if *user_signs { vec![*user] } else { vec![] },
+            ),
+        };
+        let mut signer_indexes = signer_indexes;
+        signer_indexes.dedup();
+        let signers: Vec<&Keypair> = signer_indexes.iter().map(|index| &users[*index]).collect();
+        let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+
+        // Repeating an operation would otherwise produce an identical,
+        // already-processed transaction.
+        let unique = ComputeBudgetInstruction::set_compute_unit_limit(200_000 + step as u32);
+
+        let result = common::send(&mut context, &[unique, instruction], &signers).await;
+        let after = snapshot(&mut context, &users).await;
+
+        check_invariants(step, op, &users, &signer_keys, &rent, &before, &after);
+        check_registry(step, op, &mut context, &users, &after).await;
+        check_stats(step, op, &mut context, &after).await;
+
+        if result.is_ok() {
+            match op {
+                Op::SetFavorites {
+                    user,
+                    number,
+                    color,
+                }
+                | Op::UpdateFavorites {
+                    user,
+                    number,
+                    color,
+                    ..
+                } => {
+                    let favorites = decode(after[*user].as_ref().unwrap());
+                    assert_eq!(favorites.number, *number, "step {step} ({op:?})");
+                    assert_eq!(&favorites.color, color, "step {step} ({op:?})");
+                }
+                Op::SetAuthority { user, delegate, .. } => {
+                    let favorites = decode(after[*user].as_ref().unwrap());
+                    let expected = delegate.map(|delegate| users[delegate].pubkey());
+                    assert_eq!(favorites.delegate, expected, "step {step} ({op:?})");
+                }
+                Op::CloseFavorites { user, .. } => {
+                    assert!(after[*user].is_none(), "step {step} ({op:?})");
+                }
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(cases()))]
+
+    #[test]
+    fn random_instruction_sequences_keep_invariants(ops in prop::collection::vec(op(), 1..24)) {
+        tokio::runtime::Runtime::new().unwrap().block_on(run(ops));
+    }
+}
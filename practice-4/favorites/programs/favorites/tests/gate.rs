@@ -0,0 +1,180 @@
+// Token gating through the config PDA.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use favorites::{signed_update_message, CustomError};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+const MIN_AMOUNT: u64 = 10;
+
+// A config gating on a fresh mint, with `admin` as its admin.
+async fn gate(context: &mut ProgramTestContext, admin: &Keypair, mint: &Pubkey) {
+    let ix = common::initialize_config_ix(&admin.pubkey(), Some(*mint), MIN_AMOUNT);
+    common::send_fresh(context, &[ix], &[admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn set_favorites_requires_enough_of_the_gate_mint() {
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, 1_000);
+    let other_mint = common::add_mint(&mut program_test, 1_000);
+    let enough = common::add_token_account(&mut program_test, &user.pubkey(), &mint, MIN_AMOUNT);
+    let too_little =
+        common::add_token_account(&mut program_test, &user.pubkey(), &mint, MIN_AMOUNT - 1);
+    let wrong_mint = common::add_token_account(&mut program_test, &user.pubkey(), &other_mint, 100);
+    let not_theirs = common::add_token_account(&mut program_test, &admin.pubkey(), &mint, 100);
+    let mut context = program_test.start_with_context().await;
+    gate(&mut context, &admin, &mint).await;
+
+    let set = || common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+
+    let result = common::send_fresh(&mut context, &[set()], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::TokenGateNotMet);
+    for token_account in [too_little, wrong_mint, not_theirs] {
+        let ix = common::with_gate_token_account(set(), &token_account);
+        let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+        common::assert_custom_error(result, 0, CustomError::TokenGateNotMet);
+    }
+
+    let ix = common::with_gate_token_account(set(), &enough);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    assert!(common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn updates_require_the_signer_to_hold_the_gate_mint() {
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let delegate = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, 1_000);
+    let users_tokens =
+        common::add_token_account(&mut program_test, &user.pubkey(), &mint, MIN_AMOUNT);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 1, "red");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    let ix = common::set_authority_ix(&user.pubkey(), true, Some(delegate.pubkey()));
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    gate(&mut context, &admin, &mint).await;
+
+    // The delegate holds nothing, and the owner's tokens do not count for them
+    let ix = common::with_gate_token_account(
+        common::update_favorites_ix(&user.pubkey(), &delegate.pubkey(), false, 2, "blue"),
+        &users_tokens,
+    );
+    let result = common::send_fresh(&mut context, &[ix], &[&delegate]).await;
+    common::assert_custom_error(result, 0, CustomError::TokenGateNotMet);
+
+    let ix = common::with_gate_token_account(
+        common::update_favorites_ix(&user.pubkey(), &user.pubkey(), false, 3, "green"),
+        &users_tokens,
+    );
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+
+    // A relayed update is gated on the owner who signed it
+    let message = signed_update_message(&user.pubkey(), 4, "black", 0, i64::MAX);
+    let update = [
+        common::ed25519_ix(&user, &message),
+        common::update_favorites_signed_ix(
+            &user.pubkey(),
+            &context.payer.pubkey(),
+            4,
+            "black",
+            0,
+            i64::MAX,
+        ),
+    ];
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+    common::assert_custom_error(result, 1, CustomError::TokenGateNotMet);
+    let update = [
+        update[0].clone(),
+        common::with_gate_token_account(update[1].clone(), &users_tokens),
+    ];
+    common::send(&mut context, &update, &[]).await.unwrap();
+
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!((favorites.number, favorites.color.as_str()), (4, "black"));
+}
+
+#[tokio::test]
+async fn only_the_upgrade_authority_initializes_the_config() {
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    // Racing the deploy does not make anybody else the admin
+    let ix = common::initialize_config_ix(&user.pubkey(), None, 0);
+    let result = common::send(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    let ix = common::initialize_config_ix(&admin.pubkey(), None, 0);
+    common::send(&mut context, &[ix], &[&admin]).await.unwrap();
+    let ix = common::set_token_gate_ix(&admin.pubkey(), None, 0);
+    common::send(&mut context, &[ix], &[&admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn only_the_admin_changes_the_gate() {
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[admin.insecure_clone(), user.insecure_clone()],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, 1_000);
+    let mut context = program_test.start_with_context().await;
+    gate(&mut context, &admin, &mint).await;
+
+    let ix = common::set_token_gate_ix(&user.pubkey(), None, 0);
+    let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    // The config exists once, so nobody can take it over by initializing again
+    let ix = common::initialize_config_ix(&user.pubkey(), None, 0);
+    assert!(common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .is_err());
+
+    let ix = common::set_token_gate_ix(&admin.pubkey(), None, 0);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+}
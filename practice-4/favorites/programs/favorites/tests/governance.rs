@@ -0,0 +1,301 @@
+// Community color votes weighed by the vote mint.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use favorites::{CustomError, MIN_VOTING_PERIOD};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+const VOTING_PERIOD: i64 = MIN_VOTING_PERIOD;
+
+struct Setup {
+    context: ProgramTestContext,
+    admin: Keypair,
+    voters: Vec<(Keypair, Pubkey)>,
+    mint: Pubkey,
+}
+
+// A config voting with a fresh mint, and a voter holding each of `balances`.
+async fn setup(balances: &[u64]) -> Setup {
+    let admin = Keypair::new();
+    let voters: Vec<Keypair> = balances.iter().map(|_| Keypair::new()).collect();
+    let mut program_test = common::program_test();
+    let mut funded = vec![admin.insecure_clone()];
+    funded.extend(voters.iter().map(Keypair::insecure_clone));
+    common::fund(&mut program_test, &funded);
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, balances.iter().sum());
+    let voters = voters
+        .into_iter()
+        .zip(balances)
+        .map(|(voter, balance)| {
+            let tokens =
+                common::add_token_account(&mut program_test, &voter.pubkey(), &mint, *balance);
+            (voter, tokens)
+        })
+        .collect();
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::initialize_config_ix(&admin.pubkey(), None, 0);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ix = common::set_vote_mint_ix(&admin.pubkey(), Some(mint));
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    Setup {
+        context,
+        admin,
+        voters,
+        mint,
+    }
+}
+
+#[tokio::test]
+async fn heaviest_color_becomes_the_community_color() {
+    let Setup {
+        mut context,
+        admin,
+        voters,
+        mint,
+    } = setup(&[5, 3, 4, 1]).await;
+    let ix = common::create_proposal_ix(
+        &admin.pubkey(),
+        &mint,
+        1,
+        &["red", "blue", "green"],
+        VOTING_PERIOD,
+    );
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let proposal = common::proposal_pda(&admin.pubkey(), 1);
+
+    // 5 for red against 3 + 4 for blue
+    for ((voter, tokens), choice) in voters.iter().zip([0, 1, 1]) {
+        let ix = common::vote_ix(&voter.pubkey(), &proposal, tokens, choice);
+        common::send_fresh(&mut context, &[ix], &[voter])
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut context, tokens).await, 0);
+    }
+    let tally = common::fetch_tally(&mut context, &proposal).await.unwrap();
+    assert_eq!(tally.votes, [5, 7, 0]);
+
+    let (voter, tokens) = &voters[0];
+    let ix = common::finalize_proposal_ix(&voter.pubkey(), &proposal);
+    let result = common::send_fresh(&mut context, &[ix], &[voter]).await;
+    common::assert_custom_error(result, 0, CustomError::VotingNotOver);
+    let ix = common::reclaim_vote_ix(&voter.pubkey(), &proposal, tokens);
+    let result = common::send_fresh(&mut context, &[ix], &[voter]).await;
+    common::assert_custom_error(result, 0, CustomError::VotingNotOver);
+
+    common::warp(&mut context, VOTING_PERIOD).await;
+    let (late_voter, late_tokens) = &voters[3];
+    let ix = common::vote_ix(&late_voter.pubkey(), &proposal, late_tokens, 0);
+    let result = common::send_fresh(&mut context, &[ix], &[late_voter]).await;
+    common::assert_custom_error(result, 0, CustomError::VotingClosed);
+
+    let ix = common::finalize_proposal_ix(&voter.pubkey(), &proposal);
+    common::send_fresh(&mut context, &[ix], &[voter])
+        .await
+        .unwrap();
+    let community_color = common::fetch_community_color(&mut context).await.unwrap();
+    assert_eq!(community_color.color, "blue");
+    assert_eq!(community_color.proposal, proposal);
+    assert!(
+        common::fetch_proposal(&mut context, &proposal)
+            .await
+            .unwrap()
+            .finalized
+    );
+
+    let ix = common::finalize_proposal_ix(&voter.pubkey(), &proposal);
+    let result = common::send_fresh(&mut context, &[ix], &[voter]).await;
+    common::assert_custom_error(result, 0, CustomError::AlreadyFinalized);
+
+    // Every voter gets their tokens back, once
+    for ((voter, tokens), balance) in voters.iter().zip([5, 3, 4]) {
+        let ix = common::reclaim_vote_ix(&voter.pubkey(), &proposal, tokens);
+        common::send_fresh(&mut context, &[ix], &[voter])
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut context, tokens).await, balance);
+        let ix = common::reclaim_vote_ix(&voter.pubkey(), &proposal, tokens);
+        assert!(common::send_fresh(&mut context, &[ix], &[voter])
+            .await
+            .is_err());
+    }
+}
+
+#[tokio::test]
+async fn votes_need_a_valid_choice_and_a_balance_and_count_once() {
+    let Setup {
+        mut context,
+        admin,
+        voters,
+        mint,
+    } = setup(&[5, 0]).await;
+    let ix = common::create_proposal_ix(&admin.pubkey(), &mint, 1, &["red", "blue"], VOTING_PERIOD);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let proposal = common::proposal_pda(&admin.pubkey(), 1);
+
+    let (voter, tokens) = &voters[0];
+    let ix = common::vote_ix(&voter.pubkey(), &proposal, tokens, 2);
+    let result = common::send_fresh(&mut context, &[ix], &[voter]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidChoice);
+    let ix = common::vote_ix(&voter.pubkey(), &proposal, tokens, 1);
+    common::send_fresh(&mut context, &[ix], &[voter])
+        .await
+        .unwrap();
+    let ix = common::vote_ix(&voter.pubkey(), &proposal, tokens, 0);
+    assert!(common::send_fresh(&mut context, &[ix], &[voter])
+        .await
+        .is_err());
+
+    let (empty_voter, empty_tokens) = &voters[1];
+    let ix = common::vote_ix(&empty_voter.pubkey(), &proposal, empty_tokens, 0);
+    let result = common::send_fresh(&mut context, &[ix], &[empty_voter]).await;
+    common::assert_custom_error(result, 0, CustomError::NoVotingPower);
+
+    let tally = common::fetch_tally(&mut context, &proposal).await.unwrap();
+    assert_eq!(tally.votes, [0, 5]);
+}
+
+#[tokio::test]
+async fn proposal_without_votes_keeps_the_community_color() {
+    let Setup {
+        mut context,
+        admin,
+        voters,
+        mint,
+    } = setup(&[5]).await;
+    let (voter, tokens) = &voters[0];
+    for id in [1, 2] {
+        let ix =
+            common::create_proposal_ix(&admin.pubkey(), &mint, id, &["red", "blue"], VOTING_PERIOD);
+        common::send_fresh(&mut context, &[ix], &[&admin])
+            .await
+            .unwrap();
+    }
+    let decided = common::proposal_pda(&admin.pubkey(), 1);
+    let ix = common::vote_ix(&voter.pubkey(), &decided, tokens, 0);
+    common::send_fresh(&mut context, &[ix], &[voter])
+        .await
+        .unwrap();
+
+    common::warp(&mut context, VOTING_PERIOD).await;
+    let ix = common::finalize_proposal_ix(&admin.pubkey(), &decided);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ignored = common::proposal_pda(&admin.pubkey(), 2);
+    let ix = common::finalize_proposal_ix(&admin.pubkey(), &ignored);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+
+    assert!(
+        common::fetch_proposal(&mut context, &ignored)
+            .await
+            .unwrap()
+            .finalized
+    );
+    let community_color = common::fetch_community_color(&mut context).await.unwrap();
+    assert_eq!(community_color.color, "red");
+    assert_eq!(community_color.proposal, decided);
+}
+
+#[tokio::test]
+async fn older_proposals_cannot_override_a_newer_decision() {
+    let Setup {
+        mut context,
+        admin,
+        voters,
+        mint,
+    } = setup(&[5, 3]).await;
+    let older = common::proposal_pda(&admin.pubkey(), 1);
+    let newer = common::proposal_pda(&admin.pubkey(), 2);
+    // The older proposal runs longer, so it ends after the newer one
+    let ix = common::create_proposal_ix(
+        &admin.pubkey(),
+        &mint,
+        1,
+        &["red", "blue"],
+        3 * VOTING_PERIOD,
+    );
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    common::warp(&mut context, 60).await;
+    let ix = common::create_proposal_ix(&admin.pubkey(), &mint, 2, &["red", "blue"], VOTING_PERIOD);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    for ((voter, tokens), (proposal, choice)) in voters.iter().zip([(older, 0), (newer, 1)]) {
+        let ix = common::vote_ix(&voter.pubkey(), &proposal, tokens, choice);
+        common::send_fresh(&mut context, &[ix], &[voter])
+            .await
+            .unwrap();
+    }
+
+    common::warp(&mut context, VOTING_PERIOD).await;
+    let ix = common::finalize_proposal_ix(&admin.pubkey(), &newer);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    common::warp(&mut context, 2 * VOTING_PERIOD).await;
+    let ix = common::finalize_proposal_ix(&admin.pubkey(), &older);
+    let result = common::send_fresh(&mut context, &[ix], &[&admin]).await;
+    common::assert_custom_error(result, 0, CustomError::StaleProposal);
+
+    let community_color = common::fetch_community_color(&mut context).await.unwrap();
+    assert_eq!(community_color.color, "blue");
+    assert_eq!(community_color.proposal, newer);
+    // Its voters still get their tokens back
+    let (voter, tokens) = &voters[0];
+    let ix = common::reclaim_vote_ix(&voter.pubkey(), &older, tokens);
+    common::send_fresh(&mut context, &[ix], &[voter])
+        .await
+        .unwrap();
+    assert_eq!(common::token_balance(&mut context, tokens).await, 5);
+}
+
+#[tokio::test]
+async fn proposals_need_a_vote_mint_and_sensible_colors() {
+    let Setup {
+        mut context,
+        admin,
+        mint,
+        ..
+    } = setup(&[]).await;
+
+    let too_long = "a".repeat(favorites::MAX_COLOR_LEN + 1);
+    for (colors, period) in [
+        (vec!["red"], VOTING_PERIOD),
+        (
+            vec!["red", "blue", "green", "black", "white"],
+            VOTING_PERIOD,
+        ),
+        (vec!["red", too_long.as_str()], VOTING_PERIOD),
+        (vec!["red", "blue"], 0),
+        (vec!["red", "blue"], MIN_VOTING_PERIOD - 1),
+        // Would end past the last timestamp there is
+        (vec!["red", "blue"], i64::MAX),
+    ] {
+        let ix = common::create_proposal_ix(&admin.pubkey(), &mint, 1, &colors, period);
+        let result = common::send_fresh(&mut context, &[ix], &[&admin]).await;
+        common::assert_custom_error(result, 0, CustomError::InvalidProposal);
+    }
+
+    let ix = common::set_vote_mint_ix(&admin.pubkey(), None);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ix = common::create_proposal_ix(&admin.pubkey(), &mint, 1, &["red", "blue"], VOTING_PERIOD);
+    let result = common::send_fresh(&mut context, &[ix], &[&admin]).await;
+    common::assert_custom_error(result, 0, CustomError::VotingDisabled);
+}
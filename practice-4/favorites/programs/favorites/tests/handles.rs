@@ -0,0 +1,109 @@
+// Handles: unique names pointing at an owner and their favorites.
+mod common;
+
+use favorites::{is_valid_handle, CustomError};
+use solana_sdk::signature::{Keypair, Signer};
+
+#[test]
+fn handle_format() {
+    for handle in ["abc", "slava", "user_42", &"a".repeat(32)] {
+        assert!(is_valid_handle(handle), "{handle}");
+    }
+    for handle in [
+        "ab",
+        "@slava",
+        "Slava",
+        "sla va",
+        "slava!",
+        "ѕlava",
+        &"a".repeat(33),
+    ] {
+        assert!(!is_valid_handle(handle), "{handle}");
+    }
+}
+
+#[tokio::test]
+async fn handle_is_unique_until_released() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let [alice, bob, carol] = &users;
+    let mut context = common::setup(&users, users.len() - 1).await;
+
+    let ix = common::register_handle_ix(&alice.pubkey(), "slava");
+    common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .unwrap();
+    let handle = common::fetch_handle(&mut context, "slava").await.unwrap();
+    assert_eq!(handle.owner, alice.pubkey());
+    assert_eq!(handle.favorites, common::favorites_pda(&alice.pubkey()));
+    assert_eq!(handle.handle, "slava");
+
+    let ix = common::register_handle_ix(&bob.pubkey(), "slava");
+    assert!(common::send_fresh(&mut context, &[ix], &[bob])
+        .await
+        .is_err());
+    // Without favorites there is nothing to point at
+    let ix = common::register_handle_ix(&carol.pubkey(), "carol");
+    assert!(common::send_fresh(&mut context, &[ix], &[carol])
+        .await
+        .is_err());
+
+    let ix = common::release_handle_ix(&bob.pubkey(), "slava");
+    let result = common::send_fresh(&mut context, &[ix], &[bob]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+    let ix = common::release_handle_ix(&alice.pubkey(), "slava");
+    common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .unwrap();
+    assert!(common::fetch_handle(&mut context, "slava").await.is_none());
+
+    let ix = common::register_handle_ix(&bob.pubkey(), "slava");
+    common::send_fresh(&mut context, &[ix], &[bob])
+        .await
+        .unwrap();
+    let handle = common::fetch_handle(&mut context, "slava").await.unwrap();
+    assert_eq!(handle.owner, bob.pubkey());
+}
+
+#[tokio::test]
+async fn invalid_handles_are_rejected() {
+    let users = [Keypair::new(), Keypair::new()];
+    let alice = &users[0];
+    let mut context = common::setup(&users, users.len() - 1).await;
+
+    for handle in ["ab", "Slava", "@slava", &"a".repeat(33)] {
+        let ix = common::register_handle_ix(&alice.pubkey(), handle);
+        let result = common::send_fresh(&mut context, &[ix], &[alice]).await;
+        common::assert_custom_error(result, 0, CustomError::InvalidHandle);
+    }
+}
+
+#[tokio::test]
+async fn owner_transfers_handle_to_a_user_with_favorites() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let [alice, bob, carol] = &users;
+    let mut context = common::setup(&users, users.len() - 1).await;
+    let ix = common::register_handle_ix(&alice.pubkey(), "slava");
+    common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .unwrap();
+
+    let ix = common::transfer_handle_ix(&bob.pubkey(), "slava", &bob.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[bob]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+    let ix = common::transfer_handle_ix(&alice.pubkey(), "slava", &carol.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .is_err());
+
+    let ix = common::transfer_handle_ix(&alice.pubkey(), "slava", &bob.pubkey());
+    common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .unwrap();
+    let handle = common::fetch_handle(&mut context, "slava").await.unwrap();
+    assert_eq!(handle.owner, bob.pubkey());
+    assert_eq!(handle.favorites, common::favorites_pda(&bob.pubkey()));
+
+    let ix = common::release_handle_ix(&alice.pubkey(), "slava");
+    let result = common::send_fresh(&mut context, &[ix], &[alice]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+}
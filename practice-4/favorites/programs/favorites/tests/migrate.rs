@@ -0,0 +1,89 @@
+// Favorites written by the first release, before fields were appended and the
+// version byte added, are migrated in place.
+mod common;
+
+use favorites::{CustomError, Favorites, FAVORITES_VERSION};
+use solana_sdk::{
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn legacy_favorites_are_migrated() {
+    let (user, other, delegate) = (Keypair::new(), Keypair::new(), Pubkey::new_unique());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[user.insecure_clone(), other.insecure_clone()],
+    );
+    common::add_legacy_favorites(&mut program_test, &user.pubkey(), 7, "red", Some(delegate));
+    let mut context = program_test.start_with_context().await;
+    let ix = common::set_favorites_ix(&other.pubkey(), 0, 1, "green");
+    common::send(&mut context, &[ix], &[&other]).await.unwrap();
+
+    // Nothing reads the old layout
+    let ix = common::update_favorites_ix(&user.pubkey(), &user.pubkey(), false, 9, "black");
+    let result = common::send(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::NotMigrated);
+
+    let ix = common::migrate_favorites_ix(&user.pubkey(), 0);
+    common::send(&mut context, &[ix], &[&user]).await.unwrap();
+
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            favorites.number,
+            favorites.color.as_str(),
+            favorites.delegate
+        ),
+        (7, "red", Some(delegate))
+    );
+    assert_eq!(favorites.version, FAVORITES_VERSION);
+    assert_eq!(favorites.registry_page, 0);
+    assert_eq!((favorites.nonce, favorites.premium_until), (0, 0));
+    assert_eq!(favorites.referrer, None);
+    let account = context
+        .banks_client
+        .get_account(common::favorites_pda(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), Favorites::space(0));
+    assert!(account.lamports >= Rent::default().minimum_balance(account.data.len()));
+
+    // Migrated users count like new ones
+    let mut registered = common::registered_users(&mut context).await;
+    registered.sort();
+    let mut expected = vec![user.pubkey(), other.pubkey()];
+    expected.sort();
+    assert_eq!(registered, expected);
+    assert_eq!(
+        common::fetch_stats(&mut context).await.unwrap().user_count,
+        2
+    );
+
+    let ix = common::update_favorites_ix(&user.pubkey(), &user.pubkey(), false, 8, "blue");
+    common::send(&mut context, &[ix], &[&user]).await.unwrap();
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!((favorites.number, favorites.color.as_str()), (8, "blue"));
+}
+
+#[tokio::test]
+async fn current_favorites_are_not_migrated() {
+    let user = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &[user.insecure_clone()]);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send(&mut context, &[ix], &[&user]).await.unwrap();
+
+    let ix = common::migrate_favorites_ix(&user.pubkey(), 0);
+    let result = common::send(&mut context, &[ix], &[&user]).await;
+    common::assert_custom_error(result, 0, CustomError::AlreadyMigrated);
+}
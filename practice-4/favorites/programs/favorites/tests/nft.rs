@@ -0,0 +1,99 @@
+// Favorites bound to an NFT follow whoever holds it.
+mod common;
+
+use anchor_spl::token::spl_token;
+use favorites::CustomError;
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn favorites_change_hands_with_the_nft() {
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[alice.insecure_clone(), bob.insecure_clone()],
+    );
+    let mint = common::add_mint(&mut program_test, 1);
+    let alices_tokens = common::add_token_account(&mut program_test, &alice.pubkey(), &mint, 1);
+    let bobs_tokens = common::add_token_account(&mut program_test, &bob.pubkey(), &mint, 0);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_nft_favorites_ix(&alice.pubkey(), &mint, &alices_tokens, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[&alice])
+        .await
+        .unwrap();
+
+    // Bob neither holds the NFT nor can pass off Alice's token account as his
+    for token_account in [bobs_tokens, alices_tokens] {
+        let ix = common::update_nft_favorites_ix(&bob.pubkey(), &mint, &token_account, 8, "blue");
+        let result = common::send_fresh(&mut context, &[ix], &[&bob]).await;
+        common::assert_custom_error(result, 0, CustomError::NotNftHolder);
+    }
+
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &alices_tokens,
+        &bobs_tokens,
+        &alice.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    common::send_fresh(&mut context, &[transfer], &[&alice])
+        .await
+        .unwrap();
+
+    let ix = common::update_nft_favorites_ix(&alice.pubkey(), &mint, &alices_tokens, 9, "green");
+    let result = common::send_fresh(&mut context, &[ix], &[&alice]).await;
+    common::assert_custom_error(result, 0, CustomError::NotNftHolder);
+
+    let ix = common::update_nft_favorites_ix(&bob.pubkey(), &mint, &bobs_tokens, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[&bob])
+        .await
+        .unwrap();
+    let nft_favorites = common::fetch_nft_favorites(&mut context, &mint)
+        .await
+        .unwrap();
+    assert_eq!(nft_favorites.mint, mint);
+    assert_eq!(
+        (nft_favorites.number, nft_favorites.color.as_str()),
+        (8, "blue")
+    );
+}
+
+#[tokio::test]
+async fn mint_that_can_mint_more_is_not_an_nft() {
+    let alice = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, std::slice::from_ref(&alice));
+    let mint = common::add_mint_with_authority(&mut program_test, 1, Some(alice.pubkey()));
+    let alices_tokens = common::add_token_account(&mut program_test, &alice.pubkey(), &mint, 1);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_nft_favorites_ix(&alice.pubkey(), &mint, &alices_tokens, 7, "red");
+    let result = common::send_fresh(&mut context, &[ix], &[&alice]).await;
+
+    common::assert_custom_error(result, 0, CustomError::NotAnNft);
+    assert!(common::fetch_nft_favorites(&mut context, &mint)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn mint_with_more_than_one_token_is_not_an_nft() {
+    let alice = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, std::slice::from_ref(&alice));
+    let mint = common::add_mint(&mut program_test, 2);
+    let alices_tokens = common::add_token_account(&mut program_test, &alice.pubkey(), &mint, 2);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_nft_favorites_ix(&alice.pubkey(), &mint, &alices_tokens, 7, "red");
+    let result = common::send_fresh(&mut context, &[ix], &[&alice]).await;
+
+    common::assert_custom_error(result, 0, CustomError::NotAnNft);
+    assert!(common::fetch_nft_favorites(&mut context, &mint)
+        .await
+        .is_none());
+}
@@ -0,0 +1,258 @@
+// Premium bought with the premium mint: longer colors and more profiles.
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use favorites::{
+    CustomError, Favorites, FREE_PROFILES, MAX_COLOR_LEN, MAX_PREMIUM_COLOR_LEN, MAX_PROFILES,
+    PREMIUM_PERIOD,
+};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+
+const PRICE: u64 = 10;
+
+async fn favorites_size(context: &mut ProgramTestContext, user: &Pubkey) -> usize {
+    let account = context
+        .banks_client
+        .get_account(common::favorites_pda(user))
+        .await
+        .unwrap()
+        .unwrap();
+    account.data.len()
+}
+
+struct Setup {
+    context: ProgramTestContext,
+    admin: Keypair,
+    user: Keypair,
+    // Another funded wallet, for the user to move to
+    new_wallet: Keypair,
+    mint: Pubkey,
+    user_tokens: Pubkey,
+    treasury_tokens: Pubkey,
+}
+
+// A config without premium for sale, and a user with favorites holding 100
+// of the mint it will be sold for.
+async fn setup() -> Setup {
+    let (admin, user, new_wallet) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let mut program_test = common::program_test();
+    common::fund(
+        &mut program_test,
+        &[
+            admin.insecure_clone(),
+            user.insecure_clone(),
+            new_wallet.insecure_clone(),
+        ],
+    );
+    common::add_program_data(&mut program_test, &admin.pubkey());
+    let mint = common::add_mint(&mut program_test, 1_000);
+    let user_tokens = common::add_token_account(&mut program_test, &user.pubkey(), &mint, 100);
+    let treasury_tokens =
+        common::add_token_account(&mut program_test, &common::treasury_pda(), &mint, 0);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::initialize_config_ix(&admin.pubkey(), None, 0);
+    common::send_fresh(&mut context, &[ix], &[&admin])
+        .await
+        .unwrap();
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    Setup {
+        context,
+        admin,
+        user,
+        new_wallet,
+        mint,
+        user_tokens,
+        treasury_tokens,
+    }
+}
+
+async fn sell_premium(setup: &mut Setup) {
+    let ix = common::set_premium_ix(&setup.admin.pubkey(), Some(setup.mint), PRICE);
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.admin])
+        .await
+        .unwrap();
+}
+
+async fn renew(setup: &mut Setup, months: u8) -> Result<(), BanksClientError> {
+    let ix = common::renew_premium_ix(
+        &setup.user.pubkey(),
+        &setup.user_tokens,
+        &setup.treasury_tokens,
+        months,
+    );
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await
+}
+
+async fn premium_until(setup: &mut Setup) -> i64 {
+    common::fetch_favorites(&mut setup.context, &setup.user.pubkey())
+        .await
+        .unwrap()
+        .premium_until
+}
+
+#[tokio::test]
+async fn renewing_extends_premium_until_it_lapses_or_is_cancelled() {
+    let mut setup = setup().await;
+    let result = renew(&mut setup, 1).await;
+    common::assert_custom_error(result, 0, CustomError::PremiumUnavailable);
+
+    let ix = common::set_premium_ix(&setup.user.pubkey(), Some(setup.mint), PRICE);
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+    sell_premium(&mut setup).await;
+
+    let result = renew(&mut setup, 0).await;
+    common::assert_custom_error(result, 0, CustomError::ZeroPremiumMonths);
+    let user = setup.user.pubkey();
+    let ix = common::renew_premium_ix(&user, &setup.user_tokens, &setup.user_tokens, 1);
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidPremiumAccounts);
+
+    let start = common::now(&mut setup.context).await;
+    renew(&mut setup, 2).await.unwrap();
+    assert_eq!(premium_until(&mut setup).await, start + 2 * PREMIUM_PERIOD);
+    let context = &mut setup.context;
+    assert_eq!(common::token_balance(context, &setup.user_tokens).await, 80);
+    assert_eq!(
+        common::token_balance(context, &setup.treasury_tokens).await,
+        2 * PRICE
+    );
+
+    // Renewing early adds to the months left
+    renew(&mut setup, 1).await.unwrap();
+    assert_eq!(premium_until(&mut setup).await, start + 3 * PREMIUM_PERIOD);
+
+    // After premium lapsed, the new month starts now
+    common::warp(&mut setup.context, 4 * PREMIUM_PERIOD).await;
+    let ix = common::cancel_premium_ix(&user);
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::PremiumRequired);
+    renew(&mut setup, 1).await.unwrap();
+    let restart = common::now(&mut setup.context).await;
+    assert_eq!(premium_until(&mut setup).await, restart + PREMIUM_PERIOD);
+
+    let ix = common::cancel_premium_ix(&user);
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.user])
+        .await
+        .unwrap();
+    assert_eq!(premium_until(&mut setup).await, restart);
+    assert_eq!(
+        common::token_balance(&mut setup.context, &setup.user_tokens).await,
+        60
+    );
+}
+
+#[tokio::test]
+async fn premium_colors_grow_the_account_and_lapse_with_premium() {
+    let mut setup = setup().await;
+    sell_premium(&mut setup).await;
+    let user = setup.user.pubkey();
+    let longest = "x".repeat(MAX_PREMIUM_COLOR_LEN);
+    let update = |color: &str| common::update_favorites_ix(&user, &user, false, 8, color);
+
+    let ix = update(&longest[..MAX_COLOR_LEN + 1]);
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::ColorTooLong);
+
+    renew(&mut setup, 1).await.unwrap();
+    let ix = update(&format!("{longest}x"));
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::ColorTooLong);
+    common::send_fresh(&mut setup.context, &[update(&longest)], &[&setup.user])
+        .await
+        .unwrap();
+    let grown = Favorites::space(MAX_PREMIUM_COLOR_LEN);
+    assert_eq!(favorites_size(&mut setup.context, &user).await, grown);
+    let favorites = common::fetch_favorites(&mut setup.context, &user)
+        .await
+        .unwrap();
+    assert_eq!(favorites.color, longest);
+    // Too long to rank among the top colors
+    let stats = common::fetch_stats(&mut setup.context).await.unwrap();
+    assert!(stats.top_colors.iter().all(|entry| entry.color != longest));
+
+    // Shorter colors leave the space in place
+    common::send_fresh(&mut setup.context, &[update("blue")], &[&setup.user])
+        .await
+        .unwrap();
+    assert_eq!(favorites_size(&mut setup.context, &user).await, grown);
+
+    common::warp(&mut setup.context, PREMIUM_PERIOD).await;
+    let result = common::send_fresh(&mut setup.context, &[update(&longest)], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::ColorTooLong);
+
+    // Moving favorites keeps both their size and premium
+    let new_owner = setup.new_wallet.insecure_clone();
+    let ix = common::transfer_favorites_ix(&user, &new_owner.pubkey(), 0, true);
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.user, &new_owner])
+        .await
+        .unwrap();
+    assert_eq!(
+        favorites_size(&mut setup.context, &new_owner.pubkey()).await,
+        grown
+    );
+    let moved = common::fetch_favorites(&mut setup.context, &new_owner.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(moved.premium_until, favorites.premium_until);
+}
+
+#[tokio::test]
+async fn profiles_beyond_the_free_ones_need_premium() {
+    let mut setup = setup().await;
+    sell_premium(&mut setup).await;
+    let user = setup.user.pubkey();
+
+    for index in 0..FREE_PROFILES {
+        let ix = common::set_profile_ix(&user, index, 1, "red");
+        common::send_fresh(&mut setup.context, &[ix], &[&setup.user])
+            .await
+            .unwrap();
+    }
+    let ix = common::set_profile_ix(&user, FREE_PROFILES, 2, "blue");
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::PremiumRequired);
+    let long_color = "x".repeat(MAX_COLOR_LEN + 1);
+    let ix = common::set_profile_ix(&user, 0, 1, &long_color);
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::ColorTooLong);
+
+    renew(&mut setup, 1).await.unwrap();
+    for index in FREE_PROFILES..MAX_PROFILES {
+        let ix = common::set_profile_ix(&user, index, index as u64, &long_color);
+        common::send_fresh(&mut setup.context, &[ix], &[&setup.user])
+            .await
+            .unwrap();
+    }
+    let ix = common::set_profile_ix(&user, MAX_PROFILES, 2, "blue");
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidProfile);
+    let profile = common::fetch_profile(&mut setup.context, &user, MAX_PROFILES - 1)
+        .await
+        .unwrap();
+    assert_eq!(profile.number, (MAX_PROFILES - 1) as u64);
+    assert_eq!(profile.color, long_color);
+
+    // Without premium, the extra profiles can only be closed
+    let ix = common::cancel_premium_ix(&user);
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.user])
+        .await
+        .unwrap();
+    let ix = common::set_profile_ix(&user, FREE_PROFILES, 3, "green");
+    let result = common::send_fresh(&mut setup.context, &[ix], &[&setup.user]).await;
+    common::assert_custom_error(result, 0, CustomError::PremiumRequired);
+    let ix = common::close_profile_ix(&user, FREE_PROFILES);
+    common::send_fresh(&mut setup.context, &[ix], &[&setup.user])
+        .await
+        .unwrap();
+    assert!(
+        common::fetch_profile(&mut setup.context, &user, FREE_PROFILES)
+            .await
+            .is_none()
+    );
+}
@@ -0,0 +1,277 @@
+// Moving favorites to a new wallet with the owner's recovery key.
+mod common;
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use favorites::{
+    CustomError, RecoveryCancelled, RecoveryCompleted, RecoveryKeyRemoved, RecoveryKeySet,
+    RecoveryStarted, RECOVERY_TIMELOCK,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+};
+
+// Sends `instruction` and returns the one event `E` it emitted.
+async fn send_for_event<E: AnchorDeserialize + Discriminator>(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> E {
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let (result, logs) = common::send_with_logs(context, &[instruction], signers).await;
+    result.unwrap();
+    let mut events: Vec<E> = common::events(&logs);
+    assert_eq!(events.len(), 1);
+    events.remove(0)
+}
+
+// `users[0]` sets favorites and makes `users[1]` their recovery key.
+async fn setup(users: &[Keypair]) -> ProgramTestContext {
+    let mut context = common::setup(users, 1).await;
+    let (user, recovery_key) = (&users[0], &users[1]);
+    let ix = common::set_recovery_key_ix(&user.pubkey(), &user.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidRecoveryKey);
+    let ix = common::set_recovery_key_ix(&user.pubkey(), &recovery_key.pubkey());
+    let event: RecoveryKeySet = send_for_event(&mut context, ix, &[user]).await;
+    assert_eq!(
+        (event.user, event.recovery_key),
+        (user.pubkey(), recovery_key.pubkey())
+    );
+    context
+}
+
+#[tokio::test]
+async fn recovery_moves_favorites_once_the_timelock_passed() {
+    let users = [
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let (user, recovery_key, new_owner, stranger) = (&users[0], &users[1], &users[2], &users[3]);
+    let mut context = setup(&users).await;
+    let user_key = user.pubkey();
+
+    let ix = common::start_recovery_ix(&stranger.pubkey(), &user_key, &stranger.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[stranger]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    let start = common::now(&mut context).await;
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &new_owner.pubkey());
+    let event: RecoveryStarted = send_for_event(&mut context, ix, &[recovery_key]).await;
+    assert_eq!(
+        (
+            event.user,
+            event.recovery_key,
+            event.new_owner,
+            event.unlocks_at
+        ),
+        (
+            user_key,
+            recovery_key.pubkey(),
+            new_owner.pubkey(),
+            start + RECOVERY_TIMELOCK
+        )
+    );
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &stranger.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[recovery_key]).await;
+    common::assert_custom_error(result, 0, CustomError::RecoveryInProgress);
+    let ix = common::set_recovery_key_ix(&user_key, &stranger.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::RecoveryInProgress);
+
+    let ix = common::complete_recovery_ix(&user_key, &new_owner.pubkey(), 0);
+    let result = common::send_fresh(&mut context, &[ix], &[new_owner]).await;
+    common::assert_custom_error(result, 0, CustomError::RecoveryLocked);
+
+    common::warp(&mut context, RECOVERY_TIMELOCK).await;
+    let ix = common::complete_recovery_ix(&user_key, &stranger.pubkey(), 0);
+    let result = common::send_fresh(&mut context, &[ix], &[stranger]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+    let ix = common::complete_recovery_ix(&user_key, &new_owner.pubkey(), 0);
+    let event: RecoveryCompleted = send_for_event(&mut context, ix, &[new_owner]).await;
+    assert_eq!(
+        (event.user, event.new_owner),
+        (user_key, new_owner.pubkey())
+    );
+
+    assert!(common::fetch_favorites(&mut context, &user_key)
+        .await
+        .is_none());
+    assert!(common::fetch_recovery(&mut context, &user_key)
+        .await
+        .is_none());
+    let moved = common::fetch_favorites(&mut context, &new_owner.pubkey())
+        .await
+        .unwrap();
+    assert_eq!((moved.number, moved.color.as_str()), (7, "red"));
+    assert_eq!(
+        common::registered_users(&mut context).await,
+        vec![new_owner.pubkey()]
+    );
+}
+
+#[tokio::test]
+async fn the_owner_can_cancel_during_the_timelock() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let (user, recovery_key, new_owner) = (&users[0], &users[1], &users[2]);
+    let mut context = setup(&users).await;
+    let user_key = user.pubkey();
+
+    let ix = common::cancel_recovery_ix(&user_key);
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::NoRecoveryInProgress);
+
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &new_owner.pubkey());
+    common::send_fresh(&mut context, &[ix], &[recovery_key])
+        .await
+        .unwrap();
+    let ix = common::remove_recovery_key_ix(&user_key);
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::RecoveryInProgress);
+
+    common::warp(&mut context, RECOVERY_TIMELOCK - 1).await;
+    let ix = common::cancel_recovery_ix(&user_key);
+    let event: RecoveryCancelled = send_for_event(&mut context, ix, &[user]).await;
+    assert_eq!(
+        (event.user, event.new_owner),
+        (user_key, new_owner.pubkey())
+    );
+    common::warp(&mut context, 1).await;
+    let ix = common::complete_recovery_ix(&user_key, &new_owner.pubkey(), 0);
+    let result = common::send_fresh(&mut context, &[ix], &[new_owner]).await;
+    common::assert_custom_error(result, 0, CustomError::NoRecoveryInProgress);
+
+    // Without a recovery key, nobody can start one
+    let ix = common::remove_recovery_key_ix(&user_key);
+    let event: RecoveryKeyRemoved = send_for_event(&mut context, ix, &[user]).await;
+    assert_eq!(
+        (event.user, event.recovery_key),
+        (user_key, recovery_key.pubkey())
+    );
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &new_owner.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[recovery_key])
+        .await
+        .is_err());
+    let favorites = common::fetch_favorites(&mut context, &user_key)
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 7);
+}
+
+#[tokio::test]
+async fn closing_favorites_drops_the_recovery_key_and_sessions() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let (user, recovery_key, session_key) = (&users[0], &users[1], &users[2]);
+    let mut context = setup(&users).await;
+    let user_key = user.pubkey();
+    let expiry = common::now(&mut context).await + 3600;
+    let ix = common::create_session_ix(&user_key, &session_key.pubkey(), 5, expiry);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    let mut ix = common::close_favorites_ix(&user_key, 0, true);
+    let session = common::session_pda(&user_key, &session_key.pubkey());
+    ix.accounts.push(AccountMeta::new(session, false));
+    let (result, logs) = common::send_with_logs(&mut context, &[ix], &[user]).await;
+    result.unwrap();
+    let removed: Vec<RecoveryKeyRemoved> = common::events(&logs);
+    assert_eq!(
+        (removed[0].user, removed[0].recovery_key),
+        (user_key, recovery_key.pubkey())
+    );
+    assert!(common::fetch_recovery(&mut context, &user_key)
+        .await
+        .is_none());
+    assert!(
+        common::fetch_session(&mut context, &user_key, &session_key.pubkey())
+            .await
+            .is_none()
+    );
+
+    // Favorites created again start without either
+    let ix = common::set_favorites_ix(&user_key, 0, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &recovery_key.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[recovery_key])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn moving_favorites_drops_the_old_owners_recovery_key_and_sessions() {
+    let users = [
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let (user, recovery_key, new_owner, session_key) = (&users[0], &users[1], &users[2], &users[3]);
+    let mut context = setup(&users).await;
+    let user_key = user.pubkey();
+
+    let ix = common::start_recovery_ix(&recovery_key.pubkey(), &user_key, &recovery_key.pubkey());
+    common::send_fresh(&mut context, &[ix], &[recovery_key])
+        .await
+        .unwrap();
+    let expiry = common::now(&mut context).await + 3600;
+    let ix = common::create_session_ix(&user_key, &session_key.pubkey(), 5, expiry);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    // Only sessions of the old owner can be passed along
+    let mut ix = common::transfer_favorites_ix(&user_key, &new_owner.pubkey(), 0, true);
+    let stranger_session = common::session_pda(&new_owner.pubkey(), &session_key.pubkey());
+    ix.accounts.push(AccountMeta::new(stranger_session, false));
+    assert!(common::send_fresh(&mut context, &[ix], &[user, new_owner])
+        .await
+        .is_err());
+
+    let mut ix = common::transfer_favorites_ix(&user_key, &new_owner.pubkey(), 0, true);
+    let session = common::session_pda(&user_key, &session_key.pubkey());
+    ix.accounts.push(AccountMeta::new(session, false));
+    let (result, logs) = common::send_with_logs(&mut context, &[ix], &[user, new_owner]).await;
+    result.unwrap();
+    let cancelled: Vec<RecoveryCancelled> = common::events(&logs);
+    let removed: Vec<RecoveryKeyRemoved> = common::events(&logs);
+    assert_eq!(
+        (cancelled[0].user, cancelled[0].new_owner),
+        (user_key, recovery_key.pubkey())
+    );
+    assert_eq!(
+        (removed[0].user, removed[0].recovery_key),
+        (user_key, recovery_key.pubkey())
+    );
+    assert!(common::fetch_recovery(&mut context, &user_key)
+        .await
+        .is_none());
+    assert!(
+        common::fetch_session(&mut context, &user_key, &session_key.pubkey())
+            .await
+            .is_none()
+    );
+
+    // Neither applies to favorites the old owner creates again
+    let ix = common::set_favorites_ix(&user_key, 0, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::complete_recovery_ix(&user_key, &recovery_key.pubkey(), 0);
+    assert!(common::send_fresh(&mut context, &[ix], &[recovery_key])
+        .await
+        .is_err());
+    let ix = common::update_favorites_session_ix(&user_key, &session_key.pubkey(), 9, "green");
+    assert!(common::send_fresh(&mut context, &[ix], &[session_key])
+        .await
+        .is_err());
+    let favorites = common::fetch_favorites(&mut context, &user_key)
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 8);
+}
@@ -0,0 +1,280 @@
+// Referrers named at signup, their counters and the rules against cycles.
+mod common;
+
+use favorites::CustomError;
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    instruction::AccountMeta,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+async fn referral_count(context: &mut ProgramTestContext, referrer: &Keypair) -> Option<u64> {
+    common::fetch_referrals(context, &referrer.pubkey())
+        .await
+        .map(|referrals| referrals.count)
+}
+
+// Signs `user` up naming the first of `referrers`, with the rest of its chain
+async fn sign_up(
+    context: &mut ProgramTestContext,
+    user: &Keypair,
+    referrers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let referrers: Vec<Pubkey> = referrers.iter().map(|referrer| referrer.pubkey()).collect();
+    let ix = common::referred_set_favorites_ix(&user.pubkey(), 0, &referrers, 1, "red");
+    common::send_fresh(context, &[ix], &[user]).await
+}
+
+#[tokio::test]
+async fn signups_count_towards_their_referrer() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let (referrer, alice, bob) = (&users[0], &users[1], &users[2]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_favorites_ix(&referrer.pubkey(), 0, 1, "red");
+    common::send_fresh(&mut context, &[ix], &[referrer])
+        .await
+        .unwrap();
+    assert_eq!(referral_count(&mut context, referrer).await, None);
+
+    let ix = common::referred_set_favorites_ix(&alice.pubkey(), 0, &[alice.pubkey()], 2, "blue");
+    let result = common::send_fresh(&mut context, &[ix], &[alice]).await;
+    common::assert_custom_error(result, 0, CustomError::SelfReferral);
+
+    // The referrer's counter is required along with them
+    let mut ix =
+        common::referred_set_favorites_ix(&alice.pubkey(), 0, &[referrer.pubkey()], 2, "blue");
+    let referrals = common::referrals_pda(&referrer.pubkey());
+    let index = ix
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == referrals)
+        .unwrap();
+    ix.accounts[index] = AccountMeta::new_readonly(favorites::ID, false);
+    let result = common::send_fresh(&mut context, &[ix], &[alice]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+
+    // So is their favorites account, the start of the referral chain
+    let mut ix =
+        common::referred_set_favorites_ix(&alice.pubkey(), 0, &[referrer.pubkey()], 2, "blue");
+    ix.accounts.pop();
+    let result = common::send_fresh(&mut context, &[ix], &[alice]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+
+    for (user, number) in [(alice, 2), (bob, 3)] {
+        let ix = common::referred_set_favorites_ix(
+            &user.pubkey(),
+            0,
+            &[referrer.pubkey()],
+            number,
+            "blue",
+        );
+        common::send_fresh(&mut context, &[ix], &[user])
+            .await
+            .unwrap();
+    }
+    assert_eq!(referral_count(&mut context, referrer).await, Some(2));
+    let favorites = common::fetch_favorites(&mut context, &alice.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.referrer, Some(referrer.pubkey()));
+    let favorites = common::fetch_favorites(&mut context, &referrer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.referrer, None);
+}
+
+#[tokio::test]
+async fn referrers_must_have_favorites() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (referrer, user) = (&users[0], &users[1]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    let result = sign_up(&mut context, user, &[referrer]).await;
+    common::assert_custom_error(result, 0, CustomError::ReferrerWithoutFavorites);
+    assert_eq!(referral_count(&mut context, referrer).await, None);
+
+    // Closing their favorites stops them from bringing more users
+    sign_up(&mut context, referrer, &[]).await.unwrap();
+    let ix = common::close_favorites_ix(&referrer.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[referrer])
+        .await
+        .unwrap();
+    let result = sign_up(&mut context, user, &[referrer]).await;
+    common::assert_custom_error(result, 0, CustomError::ReferrerWithoutFavorites);
+}
+
+#[tokio::test]
+async fn referrals_cannot_close_a_cycle() {
+    let users = [
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let (first, second, third, new_wallet) = (&users[0], &users[1], &users[2], &users[3]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    // first brought second, who brought third
+    sign_up(&mut context, first, &[]).await.unwrap();
+    sign_up(&mut context, second, &[first]).await.unwrap();
+    sign_up(&mut context, third, &[second, first])
+        .await
+        .unwrap();
+
+    // Signing up again, referred by anyone down their chain, closes a cycle
+    let ix = common::close_favorites_ix(&first.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[first])
+        .await
+        .unwrap();
+    for referrers in [&[second, first][..], &[third, second, first]] {
+        let result = sign_up(&mut context, first, referrers).await;
+        common::assert_custom_error(result, 0, CustomError::ReferralCycle);
+    }
+
+    // The whole chain must be passed for the program to follow it
+    let result = sign_up(&mut context, first, &[third]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+    let result = sign_up(&mut context, first, &[third, first]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+    assert_eq!(referral_count(&mut context, third).await, None);
+
+    // Moving referred favorites to a wallet up their chain closes one too
+    let chain = [second.pubkey(), first.pubkey()];
+    let ix = common::transfer_favorites_ix(&third.pubkey(), &first.pubkey(), 0, true);
+    let ix = common::with_referral_chain(ix, &chain);
+    let result = common::send_fresh(&mut context, &[ix], &[third, first]).await;
+    common::assert_custom_error(result, 0, CustomError::ReferralCycle);
+
+    let ix = common::transfer_favorites_ix(&third.pubkey(), &new_wallet.pubkey(), 0, true);
+    let ix = common::with_referral_chain(ix, &chain);
+    common::send_fresh(&mut context, &[ix], &[third, new_wallet])
+        .await
+        .unwrap();
+    let moved = common::fetch_favorites(&mut context, &new_wallet.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(moved.referrer, Some(second.pubkey()));
+}
+
+#[tokio::test]
+async fn only_the_nearest_referrers_are_followed() {
+    let users: Vec<Keypair> = (0..favorites::MAX_REFERRAL_DEPTH + 2)
+        .map(|_| Keypair::new())
+        .collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    // Each user brought the next. Past the deepest the program follows, the
+    // chain is left out.
+    sign_up(&mut context, &users[0], &[]).await.unwrap();
+    for (index, user) in users.iter().enumerate().skip(1) {
+        let chain: Vec<&Keypair> = users[..index]
+            .iter()
+            .rev()
+            .take(favorites::MAX_REFERRAL_DEPTH)
+            .collect();
+        if chain.len() == favorites::MAX_REFERRAL_DEPTH {
+            let result = sign_up(&mut context, user, &chain[..chain.len() - 1]).await;
+            common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+        }
+        sign_up(&mut context, user, &chain).await.unwrap();
+    }
+
+    // So a cycle through more wallets goes unnoticed
+    let first = &users[0];
+    let ix = common::close_favorites_ix(&first.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[first])
+        .await
+        .unwrap();
+    let chain: Vec<&Keypair> = users
+        .iter()
+        .rev()
+        .take(favorites::MAX_REFERRAL_DEPTH)
+        .collect();
+    sign_up(&mut context, first, &chain).await.unwrap();
+    let last = users.last().unwrap();
+    assert_eq!(referral_count(&mut context, last).await, Some(1));
+}
+
+#[tokio::test]
+async fn wallets_that_referred_users_can_be_referred_outside_their_chain() {
+    let users = [
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let (alice, bob, carol, dave, eve) = (&users[0], &users[1], &users[2], &users[3], &users[4]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    // alice brought bob, then carol brings alice back once alice closed
+    sign_up(&mut context, alice, &[]).await.unwrap();
+    sign_up(&mut context, bob, &[alice]).await.unwrap();
+    sign_up(&mut context, carol, &[]).await.unwrap();
+    let ix = common::close_favorites_ix(&alice.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[alice])
+        .await
+        .unwrap();
+    sign_up(&mut context, alice, &[carol]).await.unwrap();
+    assert_eq!(referral_count(&mut context, alice).await, Some(1));
+    assert_eq!(referral_count(&mut context, carol).await, Some(1));
+
+    // The chain runs through alice's new referrer
+    sign_up(&mut context, eve, &[bob, alice, carol])
+        .await
+        .unwrap();
+    assert_eq!(referral_count(&mut context, bob).await, Some(1));
+
+    // Referred favorites move to a wallet that referred others just as well
+    sign_up(&mut context, dave, &[]).await.unwrap();
+    let ix = common::close_favorites_ix(&dave.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[dave])
+        .await
+        .unwrap();
+    let chain = [carol.pubkey()];
+    let ix = common::transfer_favorites_ix(&alice.pubkey(), &dave.pubkey(), 0, true);
+    let ix = common::with_referral_chain(ix, &chain);
+    common::send_fresh(&mut context, &[ix], &[alice, dave])
+        .await
+        .unwrap();
+    let moved = common::fetch_favorites(&mut context, &dave.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(moved.referrer, Some(carol.pubkey()));
+}
+
+#[tokio::test]
+async fn closing_referred_favorites_counts_down_the_referrer() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (referrer, user) = (&users[0], &users[1]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    sign_up(&mut context, referrer, &[]).await.unwrap();
+    sign_up(&mut context, user, &[referrer]).await.unwrap();
+    assert_eq!(referral_count(&mut context, referrer).await, Some(1));
+
+    // The referrer's counter is required
+    let ix = common::close_favorites_ix(&user.pubkey(), 0, true);
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::InvalidReferralAccounts);
+
+    let ix = common::referred_close_favorites_ix(&user.pubkey(), 0, Some(&referrer.pubkey()), true);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    assert_eq!(referral_count(&mut context, referrer).await, Some(0));
+}
@@ -0,0 +1,70 @@
+// Registry paging: filling a page opens the next one, and closing removes the
+// user from the page their favorites record.
+mod common;
+
+use favorites::REGISTRY_PAGE_CAPACITY;
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn full_page_opens_the_next_one() {
+    let users: Vec<Keypair> = (0..=REGISTRY_PAGE_CAPACITY)
+        .map(|_| Keypair::new())
+        .collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    for (index, user) in users.iter().enumerate() {
+        let open_page = common::open_page(&mut context).await;
+        let ix = common::set_favorites_ix(&user.pubkey(), open_page, index as u64, "red");
+        common::send(&mut context, &[ix], &[user]).await.unwrap();
+    }
+
+    let registry = common::fetch_registry(&mut context).await.unwrap();
+    assert_eq!(registry.open_page, 1);
+    assert_eq!(registry.user_count, users.len() as u64);
+    let first = common::fetch_registry_page(&mut context, 0).await.unwrap();
+    assert_eq!(first.users.len(), REGISTRY_PAGE_CAPACITY);
+    let second = common::fetch_registry_page(&mut context, 1).await.unwrap();
+    assert_eq!(second.users, vec![users[REGISTRY_PAGE_CAPACITY].pubkey()]);
+    let last = common::fetch_favorites(&mut context, &users[REGISTRY_PAGE_CAPACITY].pubkey())
+        .await
+        .unwrap();
+    assert_eq!(last.registry_page, 1);
+
+    let mut registered = common::registered_users(&mut context).await;
+    registered.sort();
+    let mut expected: Vec<_> = users.iter().map(|user| user.pubkey()).collect();
+    expected.sort();
+    assert_eq!(registered, expected);
+}
+
+#[tokio::test]
+async fn close_removes_the_user_from_their_page() {
+    let users: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    for user in &users {
+        let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "blue");
+        common::send(&mut context, &[ix], &[user]).await.unwrap();
+    }
+
+    // The page has to be the one the favorites account records
+    let wrong_page = common::close_favorites_ix(&users[0].pubkey(), 1, true);
+    assert!(common::send(&mut context, &[wrong_page], &[&users[0]])
+        .await
+        .is_err());
+
+    let ix = common::close_favorites_ix(&users[0].pubkey(), 0, true);
+    common::send(&mut context, &[ix], &[&users[0]])
+        .await
+        .unwrap();
+
+    let page = common::fetch_registry_page(&mut context, 0).await.unwrap();
+    assert_eq!(page.users, vec![users[2].pubkey(), users[1].pubkey()]);
+    let registry = common::fetch_registry(&mut context).await.unwrap();
+    assert_eq!(registry.user_count, 2);
+    assert_eq!(registry.open_page, 0);
+}
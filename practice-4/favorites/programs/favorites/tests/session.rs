@@ -0,0 +1,207 @@
+// Session keys: limited uses, expiry and revocation.
+mod common;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use favorites::CustomError;
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+
+const EXPIRY: i64 = i64::MAX;
+
+async fn update_as(
+    context: &mut ProgramTestContext,
+    user: &Keypair,
+    session_key: &Keypair,
+    number: u64,
+) -> Result<(), BanksClientError> {
+    let ix =
+        common::update_favorites_session_ix(&user.pubkey(), &session_key.pubkey(), number, "blue");
+    common::send_fresh(context, &[ix], &[session_key]).await
+}
+
+#[tokio::test]
+async fn session_key_updates_until_its_uses_run_out() {
+    let user = Keypair::new();
+    let session_key = Keypair::new();
+    let mut context = common::setup(std::slice::from_ref(&user), 1).await;
+    let ix = common::create_session_ix(&user.pubkey(), &session_key.pubkey(), 2, EXPIRY);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+
+    update_as(&mut context, &user, &session_key, 2)
+        .await
+        .unwrap();
+    update_as(&mut context, &user, &session_key, 3)
+        .await
+        .unwrap();
+    let result = update_as(&mut context, &user, &session_key, 4).await;
+
+    common::assert_custom_error(result, 0, CustomError::SessionExhausted);
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 3);
+    assert_eq!(favorites.color, "blue");
+    let session = common::fetch_session(&mut context, &user.pubkey(), &session_key.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(session.uses_left, 0);
+}
+
+#[tokio::test]
+async fn expired_session_key_is_rejected() {
+    let user = Keypair::new();
+    let session_key = Keypair::new();
+    let mut context = common::setup(std::slice::from_ref(&user), 1).await;
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expiry = clock.unix_timestamp + 60;
+    let ix = common::create_session_ix(&user.pubkey(), &session_key.pubkey(), 5, expiry);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    update_as(&mut context, &user, &session_key, 2)
+        .await
+        .unwrap();
+
+    clock.unix_timestamp = expiry + 1;
+    context.set_sysvar(&clock);
+    let result = update_as(&mut context, &user, &session_key, 3).await;
+
+    common::assert_custom_error(result, 0, CustomError::SessionExpired);
+}
+
+#[tokio::test]
+async fn session_cannot_be_created_already_expired() {
+    let user = Keypair::new();
+    let mut context = common::setup(std::slice::from_ref(&user), 1).await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+
+    let ix = common::create_session_ix(
+        &user.pubkey(),
+        &Pubkey::new_unique(),
+        5,
+        clock.unix_timestamp - 1,
+    );
+    let result = common::send_fresh(&mut context, &[ix], &[&user]).await;
+
+    common::assert_custom_error(result, 0, CustomError::SessionExpired);
+}
+
+#[tokio::test]
+async fn revoked_session_key_is_rejected() {
+    let user = Keypair::new();
+    let session_key = Keypair::new();
+    let mut context = common::setup(std::slice::from_ref(&user), 1).await;
+    let ix = common::create_session_ix(&user.pubkey(), &session_key.pubkey(), 5, EXPIRY);
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+    let balance = context
+        .banks_client
+        .get_balance(user.pubkey())
+        .await
+        .unwrap();
+
+    let ix = common::revoke_session_ix(&user.pubkey(), &session_key.pubkey());
+    common::send_fresh(&mut context, &[ix], &[&user])
+        .await
+        .unwrap();
+
+    assert!(
+        common::fetch_session(&mut context, &user.pubkey(), &session_key.pubkey())
+            .await
+            .is_none()
+    );
+    // The context payer pays the fees, so the owner gets the whole rent back
+    assert!(
+        context
+            .banks_client
+            .get_balance(user.pubkey())
+            .await
+            .unwrap()
+            > balance
+    );
+    assert!(update_as(&mut context, &user, &session_key, 2)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn session_key_cannot_update_another_owner() {
+    let users = [Keypair::new(), Keypair::new()];
+    let session_key = Keypair::new();
+    let mut context = common::setup(&users, users.len()).await;
+    let ix = common::create_session_ix(&users[0].pubkey(), &session_key.pubkey(), 5, EXPIRY);
+    common::send_fresh(&mut context, &[ix], &[&users[0]])
+        .await
+        .unwrap();
+
+    // Point the update of the second owner at the first owner's session
+    let mut ix =
+        common::update_favorites_session_ix(&users[1].pubkey(), &session_key.pubkey(), 2, "blue");
+    ix.accounts[3].pubkey = common::session_pda(&users[0].pubkey(), &session_key.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[&session_key])
+        .await
+        .is_err());
+
+    // And without any session at all
+    let ix =
+        common::update_favorites_ix(&users[1].pubkey(), &session_key.pubkey(), false, 2, "blue");
+    let result = common::send_fresh(&mut context, &[ix], &[&session_key]).await;
+    common::assert_custom_error(result, 0, CustomError::Unauthorized);
+
+    let favorites = common::fetch_favorites(&mut context, &users[1].pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 7);
+}
+
+#[tokio::test]
+async fn sessions_end_with_the_favorites_they_were_opened_for() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, new_owner) = (&users[0], &users[1]);
+    let session_keys = [Keypair::new(), Keypair::new()];
+    let mut context = common::setup(&users, 1).await;
+    let ix = common::create_session_ix(&user.pubkey(), &session_keys[0].pubkey(), 5, EXPIRY);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    // Moved without passing the session, which stays open but stops applying
+    // to the favorites the old owner creates again
+    let ix = common::transfer_favorites_ix(&user.pubkey(), &new_owner.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[user, new_owner])
+        .await
+        .unwrap();
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 8, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let result = update_as(&mut context, user, &session_keys[0], 2).await;
+    common::assert_custom_error(result, 0, CustomError::SessionOutdated);
+
+    // The same goes for closing
+    let ix = common::create_session_ix(&user.pubkey(), &session_keys[1].pubkey(), 5, EXPIRY);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    update_as(&mut context, user, &session_keys[1], 3)
+        .await
+        .unwrap();
+    let ix = common::close_favorites_ix(&user.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 8, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let result = update_as(&mut context, user, &session_keys[1], 4).await;
+    common::assert_custom_error(result, 0, CustomError::SessionOutdated);
+
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 8);
+}
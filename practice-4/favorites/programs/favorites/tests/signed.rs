@@ -0,0 +1,157 @@
+// `update_favorites_signed`: a relayer (the context payer) submits updates
+// the owner signed off-chain. Fees on them are in tests/fees.rs.
+mod common;
+
+use anchor_lang::prelude::Clock;
+use favorites::{signed_update_message, CustomError};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const EXPIRY: i64 = i64::MAX;
+
+async fn setup() -> (ProgramTestContext, Keypair) {
+    let user = Keypair::new();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, std::slice::from_ref(&user));
+    let mut context = program_test.start_with_context().await;
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 1, "red");
+    common::send(&mut context, &[ix], &[&user]).await.unwrap();
+    (context, user)
+}
+
+// The Ed25519 check followed by the update relayed by the context payer, both
+// signed over by `signer`
+fn signed_update(
+    context: &ProgramTestContext,
+    signer: &Keypair,
+    user: &Keypair,
+    color: &str,
+    nonce: u64,
+) -> Vec<Instruction> {
+    let message = signed_update_message(&user.pubkey(), 7, color, nonce, EXPIRY);
+    let relayer = context.payer.pubkey();
+    vec![
+        common::ed25519_ix(signer, &message),
+        common::update_favorites_signed_ix(&user.pubkey(), &relayer, 7, color, nonce, EXPIRY),
+    ]
+}
+
+#[tokio::test]
+async fn relayer_applies_signed_update() {
+    let (mut context, user) = setup().await;
+
+    let update = signed_update(&context, &user, &user, "blue", 0);
+    common::send_fresh(&mut context, &update, &[])
+        .await
+        .unwrap();
+
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.number, 7);
+    assert_eq!(favorites.color, "blue");
+    assert_eq!(favorites.nonce, 1);
+
+    let update = signed_update(&context, &user, &user, "green", 1);
+    common::send_fresh(&mut context, &update, &[])
+        .await
+        .unwrap();
+    let favorites = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(favorites.color, "green");
+    assert_eq!(favorites.nonce, 2);
+}
+
+#[tokio::test]
+async fn replayed_update_is_rejected() {
+    let (mut context, user) = setup().await;
+    let update = signed_update(&context, &user, &user, "blue", 0);
+
+    common::send_fresh(&mut context, &update, &[])
+        .await
+        .unwrap();
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+
+    common::assert_custom_error(result, 1, CustomError::NonceMismatch);
+}
+
+#[tokio::test]
+async fn signature_of_someone_else_is_rejected() {
+    let (mut context, user) = setup().await;
+    let stranger = Keypair::new();
+
+    let update = signed_update(&context, &stranger, &user, "blue", 0);
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+
+    common::assert_custom_error(result, 1, CustomError::InvalidSignature);
+}
+
+#[tokio::test]
+async fn update_differing_from_the_signed_message_is_rejected() {
+    let (mut context, user) = setup().await;
+    let mut update = signed_update(&context, &user, &user, "blue", 0);
+    let relayer = context.payer.pubkey();
+    update[1] = common::update_favorites_signed_ix(&user.pubkey(), &relayer, 7, "black", 0, EXPIRY);
+
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+
+    common::assert_custom_error(result, 1, CustomError::InvalidSignature);
+}
+
+#[tokio::test]
+async fn update_without_ed25519_instruction_is_rejected() {
+    let (mut context, user) = setup().await;
+    let update = signed_update(&context, &user, &user, "blue", 0);
+
+    let result = common::send_fresh(&mut context, &update[1..], &[]).await;
+
+    common::assert_custom_error(result, 0, CustomError::InvalidSignature);
+}
+
+#[tokio::test]
+async fn forged_signature_fails_verification() {
+    let (mut context, user) = setup().await;
+    let mut update = signed_update(&context, &Keypair::new(), &user, "blue", 0);
+    // Claim the stranger's signature is the owner's
+    update[0].data[16..48].copy_from_slice(user.pubkey().as_ref());
+
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+
+    assert!(
+        matches!(
+            result,
+            Err(BanksClientError::TransactionError(
+                TransactionError::InstructionError(0, _)
+            ))
+        ),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn expired_update_is_rejected() {
+    let (mut context, user) = setup().await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expiry = clock.unix_timestamp - 1;
+    let message = signed_update_message(&user.pubkey(), 7, "blue", 0, expiry);
+    let update = [
+        common::ed25519_ix(&user, &message),
+        common::update_favorites_signed_ix(
+            &user.pubkey(),
+            &context.payer.pubkey(),
+            7,
+            "blue",
+            0,
+            expiry,
+        ),
+    ];
+
+    let result = common::send_fresh(&mut context, &update, &[]).await;
+
+    common::assert_custom_error(result, 1, CustomError::SignedUpdateExpired);
+}
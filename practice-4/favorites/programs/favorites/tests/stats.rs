@@ -0,0 +1,83 @@
+// The global stats follow every create, update and close.
+mod common;
+
+use favorites::{ColorCount, MAX_TOP_COLORS};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+async fn set(context: &mut ProgramTestContext, user: &Keypair, number: u64, color: &str) {
+    let open_page = common::open_page(context).await;
+    let ix = common::set_favorites_ix(&user.pubkey(), open_page, number, color);
+    common::send_fresh(context, &[ix], &[user]).await.unwrap();
+}
+
+fn counts(entries: &[(&str, u64)]) -> Vec<ColorCount> {
+    let mut counts: Vec<ColorCount> = entries
+        .iter()
+        .map(|(color, count)| ColorCount {
+            color: color.to_string(),
+            count: *count,
+        })
+        .collect();
+    counts.sort_by(|a, b| a.color.cmp(&b.color));
+    counts
+}
+
+#[tokio::test]
+async fn stats_track_totals_and_colors() {
+    let users: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+    assert!(common::fetch_stats(&mut context).await.is_none());
+
+    set(&mut context, &users[0], 7, "red").await;
+    set(&mut context, &users[1], 3, "red").await;
+    set(&mut context, &users[2], 20, "blue").await;
+    let ix = common::update_favorites_ix(&users[1].pubkey(), &users[1].pubkey(), true, 5, "green");
+    common::send_fresh(&mut context, &[ix], &[&users[1]])
+        .await
+        .unwrap();
+    let ix = common::close_favorites_ix(&users[2].pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[&users[2]])
+        .await
+        .unwrap();
+
+    let mut stats = common::fetch_stats(&mut context).await.unwrap();
+    assert_eq!(stats.user_count, 2);
+    assert_eq!(stats.number_sum, 7 + 5);
+    // Extremes are all-time: the closed 20 and the replaced 3 still count
+    assert_eq!((stats.min_number, stats.max_number), (Some(3), Some(20)));
+    stats.top_colors.sort_by(|a, b| a.color.cmp(&b.color));
+    assert_eq!(stats.top_colors, counts(&[("red", 1), ("green", 1)]));
+}
+
+#[tokio::test]
+async fn new_color_replaces_the_least_used_once_full() {
+    let users: Vec<Keypair> = (0..MAX_TOP_COLORS + 2).map(|_| Keypair::new()).collect();
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    // "c0" is used twice, "c1" to "c7" once each
+    set(&mut context, &users[0], 0, "c0").await;
+    for (index, user) in users[1..MAX_TOP_COLORS + 1].iter().enumerate() {
+        set(&mut context, user, 0, &format!("c{index}")).await;
+    }
+    let stats = common::fetch_stats(&mut context).await.unwrap();
+    assert_eq!(stats.top_colors.len(), MAX_TOP_COLORS);
+
+    // A ninth color takes the place of a least used one, inheriting its count
+    set(&mut context, &users[MAX_TOP_COLORS + 1], 0, "new").await;
+    let stats = common::fetch_stats(&mut context).await.unwrap();
+    assert_eq!(stats.top_colors.len(), MAX_TOP_COLORS);
+    let entry = |color: &str| stats.top_colors.iter().find(|entry| entry.color == color);
+    assert_eq!(entry("c0").unwrap().count, 2);
+    assert_eq!(entry("new").unwrap().count, 2);
+    let ones = stats
+        .top_colors
+        .iter()
+        .filter(|entry| entry.count == 1)
+        .count();
+    assert_eq!(ones, MAX_TOP_COLORS - 2);
+}
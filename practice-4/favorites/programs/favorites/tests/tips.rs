@@ -0,0 +1,178 @@
+// Tipping a user's favorites and withdrawing the tips.
+mod common;
+
+use anchor_lang::AccountSerialize;
+use favorites::{CustomError, TipReceived, TipVault};
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    instruction::InstructionError,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+#[tokio::test]
+async fn tips_add_up_and_emit_an_event() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, tipper) = (&users[0], &users[1]);
+    let mut context = common::setup(&users, 1).await;
+
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 1_000);
+    let (result, logs) = common::send_with_logs(&mut context, &[ix], &[tipper]).await;
+    result.unwrap();
+    let events: Vec<TipReceived> = common::events(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        (
+            events[0].user,
+            events[0].tipper,
+            events[0].amount,
+            events[0].tips_total
+        ),
+        (user.pubkey(), tipper.pubkey(), 1_000, 1_000)
+    );
+
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 500);
+    common::send_fresh(&mut context, &[ix], &[tipper])
+        .await
+        .unwrap();
+
+    let tip_vault = common::fetch_tip_vault(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(tip_vault.tips_total, 1_500);
+    // The first tipper paid the rent on top of the tips
+    let vault = common::tip_vault_pda(&user.pubkey());
+    let vault_size = context
+        .banks_client
+        .get_account(vault)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+        .len();
+    let rent_exempt = Rent::default().minimum_balance(vault_size);
+    assert_eq!(
+        common::balance(&mut context, &vault).await,
+        rent_exempt + 1_500
+    );
+}
+
+#[tokio::test]
+async fn owner_withdraws_everything_above_rent() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, tipper) = (&users[0], &users[1]);
+    let mut context = common::setup(&users, 1).await;
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 2_000);
+    common::send_fresh(&mut context, &[ix], &[tipper])
+        .await
+        .unwrap();
+    let vault = common::tip_vault_pda(&user.pubkey());
+    let vault_before = common::balance(&mut context, &vault).await;
+    let user_before = common::balance(&mut context, &user.pubkey()).await;
+
+    let ix = common::withdraw_tips_ix(&user.pubkey());
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    // The context payer pays the fees
+    assert_eq!(
+        common::balance(&mut context, &user.pubkey()).await,
+        user_before + 2_000
+    );
+    assert_eq!(
+        common::balance(&mut context, &vault).await,
+        vault_before - 2_000
+    );
+    let tip_vault = common::fetch_tip_vault(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(tip_vault.tips_total, 2_000);
+
+    let ix = common::withdraw_tips_ix(&user.pubkey());
+    let result = common::send_fresh(&mut context, &[ix], &[user]).await;
+    common::assert_custom_error(result, 0, CustomError::NoTipsToWithdraw);
+}
+
+#[tokio::test]
+async fn only_the_owner_reaches_their_vault() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, stranger) = (&users[0], &users[1]);
+    let mut context = common::setup(&users, 1).await;
+    let ix = common::tip_ix(&stranger.pubkey(), &user.pubkey(), 2_000);
+    common::send_fresh(&mut context, &[ix], &[stranger])
+        .await
+        .unwrap();
+
+    // The vault is derived from the signer, so a stranger only finds their own
+    let ix = common::withdraw_tips_ix(&stranger.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[stranger])
+        .await
+        .is_err());
+    let mut ix = common::withdraw_tips_ix(&stranger.pubkey());
+    ix.accounts[1].pubkey = common::tip_vault_pda(&user.pubkey());
+    assert!(common::send_fresh(&mut context, &[ix], &[stranger])
+        .await
+        .is_err());
+
+    let tip_vault = common::tip_vault_pda(&user.pubkey());
+    let rent_exempt = Rent::default().minimum_balance(8 + 8);
+    assert_eq!(
+        common::balance(&mut context, &tip_vault).await,
+        rent_exempt + 2_000
+    );
+}
+
+#[tokio::test]
+async fn tips_total_does_not_overflow() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, tipper) = (&users[0], &users[1]);
+    let mut context = common::setup(&users, 1).await;
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 1_000);
+    common::send(&mut context, &[ix], &[tipper]).await.unwrap();
+
+    let vault = common::tip_vault_pda(&user.pubkey());
+    let mut account = context
+        .banks_client
+        .get_account(vault)
+        .await
+        .unwrap()
+        .unwrap();
+    account.data.clear();
+    TipVault {
+        tips_total: u64::MAX,
+    }
+    .try_serialize(&mut account.data)
+    .unwrap();
+    context.set_account(&vault, &account.into());
+
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 1);
+    let result = common::send_fresh(&mut context, &[ix], &[tipper]).await;
+    assert!(matches!(
+        result,
+        Err(BanksClientError::TransactionError(
+            TransactionError::InstructionError(0, InstructionError::ArithmeticOverflow)
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn tips_need_an_amount_and_favorites() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, tipper) = (&users[0], &users[1]);
+    let mut context = common::setup(&users, 1).await;
+
+    let ix = common::tip_ix(&tipper.pubkey(), &user.pubkey(), 0);
+    let result = common::send_fresh(&mut context, &[ix], &[tipper]).await;
+    common::assert_custom_error(result, 0, CustomError::ZeroTip);
+
+    // The tipper never set favorites
+    let ix = common::tip_ix(&user.pubkey(), &tipper.pubkey(), 1_000);
+    assert!(common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .is_err());
+    assert!(common::fetch_tip_vault(&mut context, &tipper.pubkey())
+        .await
+        .is_none());
+}
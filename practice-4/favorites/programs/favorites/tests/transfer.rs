@@ -0,0 +1,97 @@
+// Moving favorites to a new owner wallet.
+mod common;
+
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn transfer_moves_everything_to_the_new_owner() {
+    let users = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let (user, new_owner, delegate) = (&users[0], &users[1], &users[2]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let ix = common::set_authority_ix(&user.pubkey(), true, Some(delegate.pubkey()));
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+    let stats_before = common::fetch_stats(&mut context).await.unwrap();
+
+    let ix = common::transfer_favorites_ix(&user.pubkey(), &new_owner.pubkey(), 0, true);
+    common::send_fresh(&mut context, &[ix], &[user, new_owner])
+        .await
+        .unwrap();
+
+    assert!(common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .is_none());
+    let moved = common::fetch_favorites(&mut context, &new_owner.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            moved.number,
+            moved.color.as_str(),
+            moved.delegate,
+            moved.registry_page,
+            moved.nonce
+        ),
+        (7, "red", Some(delegate.pubkey()), 0, 0)
+    );
+    assert_eq!(
+        common::registered_users(&mut context).await,
+        vec![new_owner.pubkey()]
+    );
+    let stats = common::fetch_stats(&mut context).await.unwrap();
+    assert_eq!(
+        (stats.user_count, stats.number_sum, stats.top_colors),
+        (
+            stats_before.user_count,
+            stats_before.number_sum,
+            stats_before.top_colors
+        )
+    );
+
+    // The delegate carries over
+    let ix = common::update_favorites_ix(&new_owner.pubkey(), &delegate.pubkey(), false, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[delegate])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn transfer_needs_both_owners_and_a_free_destination() {
+    let users = [Keypair::new(), Keypair::new()];
+    let (user, new_owner) = (&users[0], &users[1]);
+    let mut program_test = common::program_test();
+    common::fund(&mut program_test, &users);
+    let mut context = program_test.start_with_context().await;
+
+    let ix = common::set_favorites_ix(&user.pubkey(), 0, 7, "red");
+    common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .unwrap();
+
+    let ix = common::transfer_favorites_ix(&user.pubkey(), &new_owner.pubkey(), 0, false);
+    assert!(common::send_fresh(&mut context, &[ix], &[user])
+        .await
+        .is_err());
+
+    let ix = common::set_favorites_ix(&new_owner.pubkey(), 0, 8, "blue");
+    common::send_fresh(&mut context, &[ix], &[new_owner])
+        .await
+        .unwrap();
+    let ix = common::transfer_favorites_ix(&user.pubkey(), &new_owner.pubkey(), 0, true);
+    assert!(common::send_fresh(&mut context, &[ix], &[user, new_owner])
+        .await
+        .is_err());
+
+    let kept = common::fetch_favorites(&mut context, &user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!((kept.number, kept.color.as_str()), (7, "red"));
+}
@@ -0,0 +1,24 @@
+#!/usr/bin/env bash
+# Dumps the mainnet programs the tests run next to favorites into
+# programs/favorites/tests/fixtures, where solana-program-test looks for them.
+# Programs already dumped are kept; pass --force to dump them again.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+fixtures=programs/favorites/tests/fixtures
+cluster=${CLUSTER:-m}
+force=0
+[[ ${1:-} == --force ]] && force=1
+mkdir -p "$fixtures"
+
+dump() {
+    local address=$1 name=$2
+    if [[ -f $fixtures/$name.so && $force != 1 ]]; then
+        echo "$fixtures/$name.so already dumped"
+        return
+    fi
+    solana program dump -u "$cluster" "$address" "$fixtures/$name.so"
+}
+
+# Token Metadata, which mints the favorites badges
+dump metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s mpl_token_metadata